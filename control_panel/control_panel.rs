@@ -78,6 +78,16 @@ impl ControlPanel {
                 .service(endpoints::stats)
                 .service(endpoints::get_config)
                 .service(endpoints::set_config)
+                .service(endpoints::strategies)
+                .service(endpoints::start_strategy)
+                .service(endpoints::stop_strategy)
+                .service(endpoints::kill_switch)
+                .service(endpoints::kill_switch_report)
+                .service(endpoints::orders)
+                .service(endpoints::balances)
+                .service(endpoints::reservations)
+                .service(endpoints::positions)
+                .service(endpoints::pnl)
                 .service(
                     actix_files::Files::new("/", webui_dir)
                         .use_last_modified(true)
@@ -1,6 +1,8 @@
 use actix_web::{get, post, web, HttpResponse, Responder};
 use futures::FutureExt;
 
+use mmb_rpc::rest_api::{PnlFilter, TradePlaceFilter};
+
 use crate::control_panel::{send_request, WebMmbRpcClient};
 
 // New endpoints have to be added as a service for actix server and webui control page. Look at super::control_panel::start() and webui/README.md
@@ -22,15 +24,9 @@ pub(super) async fn get_config(client: WebMmbRpcClient) -> impl Responder {
 
 #[post("/config")]
 pub(super) async fn set_config(body: web::Bytes, client: WebMmbRpcClient) -> impl Responder {
-    let settings = match String::from_utf8((&body).to_vec()) {
+    let settings = match body_to_string(&body, "settings") {
         Ok(settings) => settings,
-        Err(err) => {
-            return HttpResponse::BadRequest().body(format!(
-                "Failed to convert input settings({:?}) to utf8 string: {}",
-                body,
-                err.to_string(),
-            ))
-        }
+        Err(response) => return response,
     };
 
     send_request(client, move |client| {
@@ -43,3 +39,119 @@ pub(super) async fn set_config(body: web::Bytes, client: WebMmbRpcClient) -> imp
 pub(super) async fn stats(client: WebMmbRpcClient) -> impl Responder {
     send_request(client, |client| client.stats().boxed()).await
 }
+
+#[get("/strategies")]
+pub(super) async fn strategies(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.strategies().boxed()).await
+}
+
+#[post("/strategies/start")]
+pub(super) async fn start_strategy(body: web::Bytes, client: WebMmbRpcClient) -> impl Responder {
+    let strategy_name = match body_to_string(&body, "strategy name") {
+        Ok(strategy_name) => strategy_name,
+        Err(response) => return response,
+    };
+
+    send_request(client, move |client| {
+        client.start_strategy(strategy_name.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/strategies/stop")]
+pub(super) async fn stop_strategy(body: web::Bytes, client: WebMmbRpcClient) -> impl Responder {
+    let strategy_name = match body_to_string(&body, "strategy name") {
+        Ok(strategy_name) => strategy_name,
+        Err(response) => return response,
+    };
+
+    send_request(client, move |client| {
+        client.stop_strategy(strategy_name.clone()).boxed()
+    })
+    .await
+}
+
+#[post("/kill_switch")]
+pub(super) async fn kill_switch(body: web::Bytes, client: WebMmbRpcClient) -> impl Responder {
+    let flatten = match body_to_string(&body, "flatten flag") {
+        Ok(flatten) => flatten,
+        Err(response) => return response,
+    };
+    let flatten = match flatten.trim() {
+        "" | "false" => false,
+        "true" => true,
+        _ => {
+            return HttpResponse::BadRequest().body(format!(
+                "Flatten flag should be 'true' or 'false', but received '{}'",
+                flatten
+            ))
+        }
+    };
+
+    send_request(client, move |client| client.kill_switch(flatten).boxed()).await
+}
+
+#[get("/kill_switch")]
+pub(super) async fn kill_switch_report(client: WebMmbRpcClient) -> impl Responder {
+    send_request(client, |client| client.kill_switch_report().boxed()).await
+}
+
+#[get("/orders")]
+pub(super) async fn orders(
+    filter: web::Query<TradePlaceFilter>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let filter = filter.into_inner();
+    send_request(client, move |client| client.orders(filter.clone()).boxed()).await
+}
+
+#[get("/balances")]
+pub(super) async fn balances(
+    filter: web::Query<TradePlaceFilter>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let filter = filter.into_inner();
+    send_request(client, move |client| {
+        client.balances(filter.clone()).boxed()
+    })
+    .await
+}
+
+#[get("/reservations")]
+pub(super) async fn reservations(
+    filter: web::Query<TradePlaceFilter>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let filter = filter.into_inner();
+    send_request(client, move |client| {
+        client.reservations(filter.clone()).boxed()
+    })
+    .await
+}
+
+#[get("/positions")]
+pub(super) async fn positions(
+    filter: web::Query<TradePlaceFilter>,
+    client: WebMmbRpcClient,
+) -> impl Responder {
+    let filter = filter.into_inner();
+    send_request(client, move |client| {
+        client.positions(filter.clone()).boxed()
+    })
+    .await
+}
+
+#[get("/pnl")]
+pub(super) async fn pnl(filter: web::Query<PnlFilter>, client: WebMmbRpcClient) -> impl Responder {
+    let filter = filter.into_inner();
+    send_request(client, move |client| client.pnl(filter.clone()).boxed()).await
+}
+
+fn body_to_string(body: &web::Bytes, body_description: &str) -> Result<String, HttpResponse> {
+    String::from_utf8(body.to_vec()).map_err(|err| {
+        HttpResponse::BadRequest().body(format!(
+            "Failed to convert input {}({:?}) to utf8 string: {}",
+            body_description, body, err,
+        ))
+    })
+}
@@ -0,0 +1,53 @@
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mmb_core::exchanges::common::SortedOrderData;
+use mmb_core::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use mmb_core::orders::order::OrderSide;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+const LEVELS_COUNT: i64 = 1000;
+
+fn make_snapshot() -> LocalOrderBookSnapshot {
+    let middle_price = dec!(10000);
+    let price_step = dec!(0.5);
+
+    let make_side = |direction: Decimal| -> SortedOrderData {
+        (1..=LEVELS_COUNT)
+            .map(|level| {
+                let level = Decimal::from(level);
+                (
+                    middle_price + direction * price_step * level,
+                    level * dec!(0.01),
+                )
+            })
+            .collect()
+    };
+
+    LocalOrderBookSnapshot::new(make_side(dec!(1)), make_side(dec!(-1)), Utc::now())
+}
+
+fn order_book_analytics(c: &mut Criterion) {
+    let snapshot = make_snapshot();
+
+    c.bench_function("calculate_vwap_for_amount", |b| {
+        b.iter(|| {
+            snapshot.calculate_vwap_for_amount(black_box(OrderSide::Sell), black_box(dec!(500)))
+        })
+    });
+    c.bench_function("calculate_cost_to_sweep", |b| {
+        b.iter(|| snapshot.calculate_cost_to_sweep(black_box(OrderSide::Buy), black_box(dec!(500))))
+    });
+    c.bench_function("calculate_depth_within", |b| {
+        b.iter(|| snapshot.calculate_depth_within(black_box(OrderSide::Sell), black_box(dec!(1))))
+    });
+    c.bench_function("calculate_imbalance", |b| {
+        b.iter(|| snapshot.calculate_imbalance(black_box(20)))
+    });
+    c.bench_function("calculate_microprice", |b| {
+        b.iter(|| snapshot.calculate_microprice())
+    });
+}
+
+criterion_group!(benches, order_book_analytics);
+criterion_main!(benches);
@@ -0,0 +1,331 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::join_all;
+use futures::FutureExt;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::alert_sinks::{create_alert_sink, AlertSink, AlertSinkSettings};
+use crate::infrastructure::spawn_future;
+use crate::misc::time::get_current_time;
+
+const FLUSH_CHECK_PERIOD: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub severity: AlertSeverity,
+    /// Component which raised the alert, e.g. `ExchangeBlocker`
+    pub source: String,
+    pub message: String,
+    pub time: DateTime,
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}] {}: {}", self.severity, self.source, self.message)
+    }
+}
+
+fn default_min_severity() -> AlertSeverity {
+    AlertSeverity::Warning
+}
+
+fn default_dedup_period_secs() -> u64 {
+    300
+}
+
+fn default_max_alerts_per_minute() -> usize {
+    10
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct AlertsSettings {
+    /// Alerts with lower severity are only logged
+    #[serde(default = "default_min_severity")]
+    pub min_severity: AlertSeverity,
+    /// Alert with the same source and message is sent to sinks only once per the period
+    #[serde(default = "default_dedup_period_secs")]
+    pub dedup_period_secs: u64,
+    /// Alerts exceeding the limit are only logged. Critical alerts aren't limited and aren't counted
+    #[serde(default = "default_max_alerts_per_minute")]
+    pub max_alerts_per_minute: usize,
+    pub sinks: Vec<AlertSinkSettings>,
+}
+
+/// Decides which alerts are sent to sinks: deduplicates the same alerts and limits rate of sending
+struct AlertThrottle {
+    dedup_period: chrono::Duration,
+    max_alerts_per_minute: usize,
+    last_sent_by_key: HashMap<(String, String), DateTime>,
+    sent_times: VecDeque<DateTime>,
+}
+
+impl AlertThrottle {
+    fn new(settings: &AlertsSettings) -> Self {
+        Self {
+            dedup_period: chrono::Duration::seconds(settings.dedup_period_secs as i64),
+            max_alerts_per_minute: settings.max_alerts_per_minute,
+            last_sent_by_key: HashMap::new(),
+            sent_times: VecDeque::new(),
+        }
+    }
+
+    fn try_pass(&mut self, alert: &Alert) -> bool {
+        let now = alert.time;
+
+        let key = (alert.source.clone(), alert.message.clone());
+        if let Some(last_sent) = self.last_sent_by_key.get(&key) {
+            if now - *last_sent < self.dedup_period {
+                return false;
+            }
+        }
+
+        // critical alerts shouldn't be lost because of a burst of less important ones
+        if alert.severity != AlertSeverity::Critical {
+            let minute_ago = now - chrono::Duration::minutes(1);
+            while matches!(self.sent_times.front(), Some(time) if *time <= minute_ago) {
+                let _ = self.sent_times.pop_front();
+            }
+            if self.sent_times.len() >= self.max_alerts_per_minute {
+                return false;
+            }
+
+            self.sent_times.push_back(now);
+        }
+
+        let _ = self.last_sent_by_key.insert(key, now);
+        let dedup_period = self.dedup_period;
+        self.last_sent_by_key
+            .retain(|_, last_sent| now - *last_sent < dedup_period);
+
+        true
+    }
+}
+
+/// Engine-wide bus of alerts about critical conditions (exchange blocks, websocket disconnects, graceful shutdown).
+/// All alerts are logged, alerts with severity not lower than `AlertsSettings::min_severity` are also sent to sinks
+/// with deduplication and rate limiting
+pub struct AlertBus {
+    min_severity: AlertSeverity,
+    sinks: Vec<Arc<dyn AlertSink>>,
+    throttle: Option<Mutex<AlertThrottle>>,
+    pending_deliveries: Arc<AtomicUsize>,
+}
+
+impl AlertBus {
+    /// If settings aren't specified, alerts are only logged
+    pub fn new(settings: Option<&AlertsSettings>) -> Arc<Self> {
+        match settings {
+            Some(settings) => {
+                let sinks = settings.sinks.iter().map(create_alert_sink).collect();
+                Self::with_sinks(settings, sinks)
+            }
+            None => Arc::new(Self {
+                min_severity: AlertSeverity::Critical,
+                sinks: Vec::new(),
+                throttle: None,
+                pending_deliveries: Default::default(),
+            }),
+        }
+    }
+
+    pub fn with_sinks(settings: &AlertsSettings, sinks: Vec<Arc<dyn AlertSink>>) -> Arc<Self> {
+        Arc::new(Self {
+            min_severity: settings.min_severity,
+            sinks,
+            throttle: Some(Mutex::new(AlertThrottle::new(settings))),
+            pending_deliveries: Default::default(),
+        })
+    }
+
+    /// Log alert and send it to sinks in background.
+    /// Returns `true` if alert is sent to sinks, `false` if it's filtered by severity, deduplication or rate limit
+    pub fn publish(&self, severity: AlertSeverity, source: &str, message: String) -> bool {
+        let alert = Alert {
+            severity,
+            source: source.to_owned(),
+            message,
+            time: get_current_time(),
+        };
+
+        match alert.severity {
+            AlertSeverity::Info => log::info!("Alert {}", alert),
+            AlertSeverity::Warning => log::warn!("Alert {}", alert),
+            AlertSeverity::Critical => log::error!("Alert {}", alert),
+        }
+
+        let throttle = match &self.throttle {
+            Some(throttle) if alert.severity >= self.min_severity && !self.sinks.is_empty() => {
+                throttle
+            }
+            _ => return false,
+        };
+
+        if !throttle.lock().try_pass(&alert) {
+            log::info!(
+                "Alert {} isn't sent because of deduplication or rate limit",
+                alert
+            );
+            return false;
+        }
+
+        let sinks = self.sinks.clone();
+        let pending_deliveries = self.pending_deliveries.clone();
+        let _ = pending_deliveries.fetch_add(1, Ordering::SeqCst);
+        let action = async move {
+            send_to_sinks(&sinks, &alert).await;
+            let _ = pending_deliveries.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        };
+        let _ = spawn_future("Send alert to sinks", false, action.boxed());
+
+        true
+    }
+
+    /// Wait until published alerts are sent to sinks, but not longer than `timeout`
+    pub async fn flush(&self, timeout: Duration) {
+        let wait_deliveries = async {
+            while self.pending_deliveries.load(Ordering::SeqCst) > 0 {
+                tokio::time::sleep(FLUSH_CHECK_PERIOD).await;
+            }
+        };
+
+        if tokio::time::timeout(timeout, wait_deliveries)
+            .await
+            .is_err()
+        {
+            log::error!(
+                "Timeout {} secs is exceeded: not all alerts are sent to sinks",
+                timeout.as_secs()
+            );
+        }
+    }
+}
+
+async fn send_to_sinks(sinks: &[Arc<dyn AlertSink>], alert: &Alert) {
+    let results = join_all(sinks.iter().map(|sink| sink.send(alert))).await;
+    for (sink, result) in sinks.iter().zip(results) {
+        if let Err(error) = result {
+            log::error!(
+                "Unable to send alert {} to sink {}: {:?}",
+                alert,
+                sink.name(),
+                error
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use chrono::{TimeZone, Utc};
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn settings() -> AlertsSettings {
+        AlertsSettings {
+            min_severity: AlertSeverity::Warning,
+            dedup_period_secs: 60,
+            max_alerts_per_minute: 2,
+            sinks: Vec::new(),
+        }
+    }
+
+    fn alert(message: &str, seconds: i64) -> Alert {
+        Alert {
+            severity: AlertSeverity::Critical,
+            source: "test".to_owned(),
+            message: message.to_owned(),
+            time: Utc
+                .with_ymd_and_hms(2021, 11, 1, 0, 0, 0)
+                .single()
+                .expect("in test")
+                + chrono::Duration::seconds(seconds),
+        }
+    }
+
+    fn warning(message: &str, seconds: i64) -> Alert {
+        Alert {
+            severity: AlertSeverity::Warning,
+            ..alert(message, seconds)
+        }
+    }
+
+    struct ChannelSink {
+        sender: mpsc::UnboundedSender<Alert>,
+    }
+
+    #[async_trait]
+    impl AlertSink for ChannelSink {
+        fn name(&self) -> &str {
+            "ChannelSink"
+        }
+
+        async fn send(&self, alert: &Alert) -> Result<()> {
+            self.sender.send(alert.clone())?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn throttle_deduplicates_same_alerts() {
+        let mut throttle = AlertThrottle::new(&settings());
+
+        assert!(throttle.try_pass(&alert("blocked", 0)));
+        assert!(!throttle.try_pass(&alert("blocked", 30)));
+        assert!(throttle.try_pass(&alert("blocked", 61)));
+    }
+
+    #[test]
+    fn throttle_limits_alerts_per_minute() {
+        let mut throttle = AlertThrottle::new(&settings());
+
+        assert!(throttle.try_pass(&warning("first", 0)));
+        assert!(throttle.try_pass(&warning("second", 10)));
+        assert!(!throttle.try_pass(&warning("third", 20)));
+        assert!(throttle.try_pass(&warning("fourth", 61)));
+    }
+
+    #[test]
+    fn throttle_doesnt_limit_critical_alerts() {
+        let mut throttle = AlertThrottle::new(&settings());
+
+        assert!(throttle.try_pass(&warning("first", 0)));
+        assert!(throttle.try_pass(&warning("second", 10)));
+        assert!(!throttle.try_pass(&warning("third", 20)));
+        assert!(throttle.try_pass(&alert("stopped", 30)));
+        assert!(!throttle.try_pass(&alert("stopped", 40)));
+        assert!(throttle.try_pass(&alert("blocked", 50)));
+    }
+
+    #[tokio::test]
+    async fn publish_sends_alerts_to_sinks() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let alert_bus = AlertBus::with_sinks(&settings(), vec![Arc::new(ChannelSink { sender })]);
+
+        assert!(!alert_bus.publish(AlertSeverity::Info, "test", "info".to_owned()));
+        assert!(alert_bus.publish(AlertSeverity::Critical, "test", "stopped".to_owned()));
+        assert!(!alert_bus.publish(AlertSeverity::Critical, "test", "stopped".to_owned()));
+        alert_bus.flush(Duration::from_secs(1)).await;
+
+        let sent_alert = receiver.try_recv().expect("in test");
+        assert_eq!(sent_alert.severity, AlertSeverity::Critical);
+        assert_eq!(sent_alert.message, "stopped");
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,496 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use hyper::client::HttpConnector;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::alerts::alert_bus::Alert;
+
+pub const DEFAULT_TELEGRAM_API_URL: &str = "https://api.telegram.org";
+/// Sending to unresponsive sink shouldn't delay graceful shutdown for long
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_telegram_api_url() -> String {
+    DEFAULT_TELEGRAM_API_URL.to_owned()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertSinkSettings {
+    /// Alert is sent in JSON with POST request to the URL
+    Webhook { url: String },
+    /// Slack incoming webhook or any other service accepting `{"text": "..."}` JSON
+    Slack { url: String },
+    /// Message is sent by Telegram Bot API
+    Telegram {
+        bot_token: String,
+        chat_id: String,
+        #[serde(default = "default_telegram_api_url")]
+        api_url: String,
+    },
+    /// Email is sent by SMTP server, connection is encrypted by STARTTLS by default
+    Smtp {
+        /// Host of SMTP server, it's also used to verify TLS certificate of the server
+        host: String,
+        /// Default port of `encryption` is used if it isn't specified
+        port: Option<u16>,
+        #[serde(default)]
+        encryption: SmtpEncryption,
+        /// Email is sent without authentication if credentials aren't specified
+        credentials: Option<SmtpCredentials>,
+        from: String,
+        to: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpEncryption {
+    /// Connection is upgraded by STARTTLS, port 587 by default
+    #[default]
+    StartTls,
+    /// Connection is encrypted from the start, port 465 by default
+    Tls,
+    /// Plain connection for local mail relay only, port 25 by default
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SmtpCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[async_trait]
+pub trait AlertSink: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn send(&self, alert: &Alert) -> Result<()>;
+}
+
+pub fn create_alert_sink(settings: &AlertSinkSettings) -> Arc<dyn AlertSink> {
+    match settings {
+        AlertSinkSettings::Webhook { url } => Arc::new(HttpAlertSink::webhook(url)),
+        AlertSinkSettings::Slack { url } => Arc::new(HttpAlertSink::slack(url)),
+        AlertSinkSettings::Telegram {
+            bot_token,
+            chat_id,
+            api_url,
+        } => Arc::new(HttpAlertSink::telegram(api_url, bot_token, chat_id)),
+        AlertSinkSettings::Smtp {
+            host,
+            port,
+            encryption,
+            credentials,
+            from,
+            to,
+        } => Arc::new(SmtpAlertSink::new(
+            host,
+            *port,
+            *encryption,
+            credentials.clone(),
+            from,
+            to.clone(),
+        )),
+    }
+}
+
+enum HttpAlertFormat {
+    Json,
+    Text,
+    Telegram { chat_id: String },
+}
+
+/// Sink sending alerts with POST request in JSON
+pub struct HttpAlertSink {
+    name: &'static str,
+    url: String,
+    format: HttpAlertFormat,
+    client: Client<HttpsConnector<HttpConnector>>,
+    timeout: Duration,
+}
+
+impl HttpAlertSink {
+    fn new(name: &'static str, url: String, format: HttpAlertFormat) -> Self {
+        Self {
+            name,
+            url,
+            format,
+            client: Client::builder().build::<_, Body>(HttpsConnector::new()),
+            timeout: SEND_TIMEOUT,
+        }
+    }
+
+    pub fn webhook(url: &str) -> Self {
+        Self::new("Webhook", url.to_owned(), HttpAlertFormat::Json)
+    }
+
+    pub fn slack(url: &str) -> Self {
+        Self::new("Slack", url.to_owned(), HttpAlertFormat::Text)
+    }
+
+    pub fn telegram(api_url: &str, bot_token: &str, chat_id: &str) -> Self {
+        Self::new(
+            "Telegram",
+            format!("{}/bot{}/sendMessage", api_url, bot_token),
+            HttpAlertFormat::Telegram {
+                chat_id: chat_id.to_owned(),
+            },
+        )
+    }
+
+    fn create_body(&self, alert: &Alert) -> Result<String> {
+        let body = match &self.format {
+            HttpAlertFormat::Json => serde_json::to_string(alert)?,
+            HttpAlertFormat::Text => json!({ "text": alert.to_string() }).to_string(),
+            HttpAlertFormat::Telegram { chat_id } => {
+                json!({ "chat_id": chat_id, "text": alert.to_string() }).to_string()
+            }
+        };
+
+        Ok(body)
+    }
+
+    async fn send_request(&self, alert: &Alert) -> Result<()> {
+        let request = Request::post(self.url.as_str())
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(self.create_body(alert)?))
+            .context("Unable to create alert request")?;
+
+        let response = self
+            .client
+            .request(request)
+            .await
+            .context("Unable to send alert request")?;
+
+        if !response.status().is_success() {
+            bail!("Alert request failed with status {}", response.status());
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AlertSink for HttpAlertSink {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        tokio::time::timeout(self.timeout, self.send_request(alert))
+            .await
+            .with_context(|| format!("Alert request timed out after {:?}", self.timeout))?
+    }
+}
+
+/// Sink sending alerts by email over SMTP
+pub struct SmtpAlertSink {
+    host: String,
+    port: Option<u16>,
+    encryption: SmtpEncryption,
+    credentials: Option<SmtpCredentials>,
+    from: String,
+    to: Vec<String>,
+    timeout: Duration,
+}
+
+impl SmtpAlertSink {
+    pub fn new(
+        host: &str,
+        port: Option<u16>,
+        encryption: SmtpEncryption,
+        credentials: Option<SmtpCredentials>,
+        from: &str,
+        to: Vec<String>,
+    ) -> Self {
+        Self {
+            host: host.to_owned(),
+            port,
+            encryption,
+            credentials,
+            from: from.to_owned(),
+            to,
+            timeout: SEND_TIMEOUT,
+        }
+    }
+
+    fn create_transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let mut builder = match self.encryption {
+            SmtpEncryption::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?
+            }
+            SmtpEncryption::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?,
+            SmtpEncryption::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(self.host.as_str())
+            }
+        };
+
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let Some(credentials) = &self.credentials {
+            builder = builder.credentials(Credentials::new(
+                credentials.username.clone(),
+                credentials.password.clone(),
+            ));
+        }
+
+        Ok(builder.timeout(Some(self.timeout)).build())
+    }
+
+    /// Header values are encoded and body is dot-stuffed by `lettre`,
+    /// so alert text can't inject headers or end message data early
+    fn create_message(&self, alert: &Alert) -> Result<Message> {
+        let parse_mailbox = |address: &str| {
+            address
+                .parse::<Mailbox>()
+                .with_context(|| format!("Invalid email address '{}'", address))
+        };
+
+        let mut builder = Message::builder()
+            .from(parse_mailbox(&self.from)?)
+            .subject(format!("[{:?}] {}", alert.severity, alert.source))
+            .date(alert.time.into());
+        for to in &self.to {
+            builder = builder.to(parse_mailbox(to)?);
+        }
+
+        builder
+            .body(alert.message.clone())
+            .context("Unable to create alert email")
+    }
+
+    async fn send_email(&self, alert: &Alert) -> Result<()> {
+        let message = self.create_message(alert)?;
+        self.create_transport()
+            .context("Unable to create SMTP transport")?
+            .send(message)
+            .await
+            .with_context(|| format!("Unable to send alert email by {}", self.host))?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AlertSink for SmtpAlertSink {
+    fn name(&self) -> &str {
+        "Smtp"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<()> {
+        tokio::time::timeout(self.timeout, self.send_email(alert))
+            .await
+            .with_context(|| format!("Sending alert email timed out after {:?}", self.timeout))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use chrono::{TimeZone, Utc};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Response, Server, StatusCode};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::alerts::alert_bus::AlertSeverity;
+
+    fn alert() -> Alert {
+        Alert {
+            severity: AlertSeverity::Critical,
+            source: "ExchangeBlocker".to_owned(),
+            message: "Binance_0 is blocked by ProfitLossExceeded".to_owned(),
+            time: Utc
+                .with_ymd_and_hms(2021, 11, 1, 0, 0, 0)
+                .single()
+                .expect("in test"),
+        }
+    }
+
+    /// Stub HTTP server sending path and body of received requests to the channel
+    fn start_http_stub_server(status: StatusCode) -> (String, mpsc::UnboundedReceiver<String>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let make_service = make_service_fn(move |_| {
+            let sender = sender.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let sender = sender.clone();
+                    async move {
+                        let path = request.uri().path().to_owned();
+                        let body = hyper::body::to_bytes(request.into_body())
+                            .await
+                            .expect("in test");
+                        let body = String::from_utf8(body.to_vec()).expect("in test");
+                        sender.send(format!("{} {}", path, body)).expect("in test");
+
+                        Response::builder().status(status).body(Body::empty())
+                    }
+                }))
+            }
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let base_url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        (base_url, receiver)
+    }
+
+    #[tokio::test]
+    async fn webhook_sends_alert_in_json() {
+        let (base_url, mut receiver) = start_http_stub_server(StatusCode::OK);
+        let sink = HttpAlertSink::webhook(&format!("{}/alerts", base_url));
+
+        sink.send(&alert()).await.expect("in test");
+
+        let request = receiver.recv().await.expect("in test");
+        assert_eq!(
+            request,
+            r#"/alerts {"severity":"Critical","source":"ExchangeBlocker","message":"Binance_0 is blocked by ProfitLossExceeded","time":"2021-11-01T00:00:00Z"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn telegram_sends_message_to_chat() {
+        let (base_url, mut receiver) = start_http_stub_server(StatusCode::OK);
+        let sink = HttpAlertSink::telegram(&base_url, "token", "42");
+
+        sink.send(&alert()).await.expect("in test");
+
+        let request = receiver.recv().await.expect("in test");
+        assert_eq!(
+            request,
+            r#"/bottoken/sendMessage {"chat_id":"42","text":"[Critical] ExchangeBlocker: Binance_0 is blocked by ProfitLossExceeded"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn http_sink_fails_on_error_status() {
+        let (base_url, _receiver) = start_http_stub_server(StatusCode::INTERNAL_SERVER_ERROR);
+        let sink = HttpAlertSink::slack(&base_url);
+
+        assert!(sink.send(&alert()).await.is_err());
+    }
+
+    /// Stub SMTP server returning received lines after client closed connection
+    async fn start_smtp_stub_server() -> (u16, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("in test");
+        let port = listener.local_addr().expect("in test").port();
+
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("in test");
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            let mut received = Vec::new();
+            let mut is_data = false;
+
+            writer.write_all(b"220 stub\r\n").await.expect("in test");
+            while let Some(line) = lines.next_line().await.expect("in test") {
+                let reply: &[u8] = match line.as_str() {
+                    "." if is_data => {
+                        is_data = false;
+                        b"250 Queued\r\n"
+                    }
+                    _ if is_data => b"",
+                    "DATA" => {
+                        is_data = true;
+                        b"354 Go ahead\r\n"
+                    }
+                    "QUIT" => b"221 Bye\r\n",
+                    line if line.starts_with("EHLO") => b"250-stub\r\n250 OK\r\n",
+                    line if line.starts_with("MAIL FROM") || line.starts_with("RCPT TO") => {
+                        b"250 OK\r\n"
+                    }
+                    _ => b"500 Unknown command\r\n",
+                };
+                writer.write_all(reply).await.expect("in test");
+                received.push(line);
+            }
+
+            received
+        });
+
+        (port, server)
+    }
+
+    fn plain_smtp_sink(port: u16) -> SmtpAlertSink {
+        SmtpAlertSink::new(
+            "127.0.0.1",
+            Some(port),
+            SmtpEncryption::None,
+            None,
+            "bot@mmb",
+            vec!["oncall@mmb".to_owned()],
+        )
+    }
+
+    #[tokio::test]
+    async fn smtp_sends_email() {
+        let (port, server) = start_smtp_stub_server().await;
+
+        plain_smtp_sink(port).send(&alert()).await.expect("in test");
+
+        let received = server.await.expect("in test");
+        assert!(received.contains(&"MAIL FROM:<bot@mmb>".to_owned()));
+        assert!(received.contains(&"RCPT TO:<oncall@mmb>".to_owned()));
+        assert!(received.contains(&"Subject: [Critical] ExchangeBlocker".to_owned()));
+        assert!(received.contains(&"Binance_0 is blocked by ProfitLossExceeded".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn smtp_alert_text_cant_inject_headers_or_end_data() {
+        let (port, server) = start_smtp_stub_server().await;
+
+        let alert = Alert {
+            source: "ExchangeBlocker\r\nBcc: attacker@mmb".to_owned(),
+            message: "first line\r\n.\r\nRCPT TO:<attacker@mmb>".to_owned(),
+            ..alert()
+        };
+        plain_smtp_sink(port).send(&alert).await.expect("in test");
+
+        let received = server.await.expect("in test");
+        assert!(!received.iter().any(|line| line.starts_with("Bcc:")));
+        let data_start = received
+            .iter()
+            .position(|line| line == "DATA")
+            .expect("in test");
+        let recipients = received[..data_start]
+            .iter()
+            .filter(|line| line.starts_with("RCPT TO"))
+            .collect::<Vec<_>>();
+        assert_eq!(recipients, vec!["RCPT TO:<oncall@mmb>"]);
+        // single dot line of the message is escaped by dot-stuffing
+        assert!(received.contains(&"..".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn smtp_send_times_out_on_unresponsive_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("in test");
+        let port = listener.local_addr().expect("in test").port();
+
+        // server accepts connection, but never replies
+        let server = tokio::spawn(async move { listener.accept().await.expect("in test") });
+
+        let mut sink = plain_smtp_sink(port);
+        sink.timeout = Duration::from_millis(100);
+        let result = sink.send(&alert()).await;
+
+        assert!(result.is_err());
+        drop(server.await.expect("in test"));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod alert_bus;
+pub mod alert_sinks;
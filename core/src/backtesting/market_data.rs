@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::data_recorder::recorded_event::RecordedEvent;
+pub use crate::data_recorder::recorded_event::{
+    RecordedOrderBookEvent, RecordedTrade, RecordedTradesEvent,
+};
+use crate::data_recorder::storage::RecordedFileReader;
+use crate::exchanges::events::{ExchangeEvent, TickDirection, Trade, TradesEvent};
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::order_book::order_book_data::OrderBookData;
+
+/// Public market event which can be stored and replayed later.
+/// Market data file contains one serialized event per line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RecordedMarketEvent {
+    OrderBook(RecordedOrderBookEvent),
+    Trades(RecordedTradesEvent),
+}
+
+impl RecordedMarketEvent {
+    pub fn time(&self) -> DateTime {
+        match self {
+            RecordedMarketEvent::OrderBook(event) => event.time,
+            RecordedMarketEvent::Trades(event) => event.time,
+        }
+    }
+
+    /// Returns `None` for events without public market data
+    pub fn from_exchange_event(event: &ExchangeEvent) -> Option<Self> {
+        match event {
+            ExchangeEvent::OrderBookEvent(event) => Some(RecordedMarketEvent::OrderBook(
+                RecordedOrderBookEvent::from_order_book_event(event),
+            )),
+            ExchangeEvent::Trades(event) => Some(RecordedMarketEvent::Trades(
+                RecordedTradesEvent::from_trades_event(event),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns `None` for recorded events without public market data
+    pub fn from_recorded_event(event: RecordedEvent) -> Option<Self> {
+        match event {
+            RecordedEvent::OrderBook(event) => Some(RecordedMarketEvent::OrderBook(event)),
+            RecordedEvent::Trades(event) => Some(RecordedMarketEvent::Trades(event)),
+            _ => None,
+        }
+    }
+
+    pub fn to_exchange_event(&self) -> ExchangeEvent {
+        match self {
+            RecordedMarketEvent::OrderBook(event) => {
+                let event_type = match event.is_snapshot {
+                    true => EventType::Snapshot,
+                    false => EventType::Update,
+                };
+
+                ExchangeEvent::OrderBookEvent(OrderBookEvent::new(
+                    event.time,
+                    event.exchange_account_id,
+                    event.currency_pair,
+                    "".to_string(),
+                    event_type,
+                    Arc::new(OrderBookData::new(
+                        event.asks.iter().cloned().collect(),
+                        event.bids.iter().cloned().collect(),
+                    )),
+                ))
+            }
+            RecordedMarketEvent::Trades(event) => ExchangeEvent::Trades(TradesEvent {
+                exchange_account_id: event.exchange_account_id,
+                currency_pair: event.currency_pair,
+                trades: event
+                    .trades
+                    .iter()
+                    .map(|trade| Trade {
+                        trade_id: trade.trade_id.clone(),
+                        price: trade.price,
+                        quantity: trade.amount,
+                        side: trade.side,
+                        transaction_time: trade.transaction_time,
+                        tick_direction: TickDirection::None,
+                    })
+                    .collect_vec(),
+                receipt_time: event.time,
+            }),
+        }
+    }
+}
+
+/// Writes market events to the file, one event per line
+pub fn write_market_data(path: &Path, events: &[RecordedMarketEvent]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Unable to create market data file {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    for event in events {
+        serde_json::to_writer(&mut writer, event).context("Unable to serialize market event")?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush().context("Unable to flush market data file")
+}
+
+/// Lazily reads market events from files in the specified order.
+/// Both plain market data files and files written by `DataRecorder` are supported,
+/// recorded events without public market data are skipped.
+/// Files should be chronologically ordered, so events are never sorted in memory
+pub struct MarketDataReader {
+    paths: VecDeque<PathBuf>,
+    current_file: Option<(PathBuf, RecordedFileReader)>,
+    last_event_time: Option<DateTime>,
+}
+
+impl MarketDataReader {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        Self {
+            paths: paths.into(),
+            current_file: None,
+            last_event_time: None,
+        }
+    }
+
+    fn read_next(&mut self) -> Result<Option<RecordedMarketEvent>> {
+        loop {
+            if self.current_file.is_none() {
+                let path = match self.paths.pop_front() {
+                    Some(path) => path,
+                    None => return Ok(None),
+                };
+
+                let reader = RecordedFileReader::open(&path)?;
+                self.current_file = Some((path, reader));
+            }
+
+            let (path, reader) = self.current_file.as_mut().expect("checked above");
+            let event = match reader.next().transpose()? {
+                Some(recorded_event) => {
+                    match RecordedMarketEvent::from_recorded_event(recorded_event) {
+                        Some(event) => event,
+                        None => continue,
+                    }
+                }
+                None => {
+                    self.current_file = None;
+                    continue;
+                }
+            };
+
+            if let Some(last_event_time) = self.last_event_time {
+                if event.time() < last_event_time {
+                    bail!(
+                        "Market events should be ordered by time, but event at {} goes after event at {} in {}",
+                        event.time(),
+                        last_event_time,
+                        path.display()
+                    );
+                }
+            }
+            self.last_event_time = Some(event.time());
+
+            return Ok(Some(event));
+        }
+    }
+}
+
+impl Iterator for MarketDataReader {
+    type Item = Result<RecordedMarketEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::exchanges::events::TradeId;
+    use crate::orders::order::OrderSide;
+    use chrono::{Duration, TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    fn order_book_event(time: DateTime) -> RecordedMarketEvent {
+        RecordedMarketEvent::OrderBook(RecordedOrderBookEvent {
+            time,
+            exchange_account_id: ExchangeAccountId::new("Binance".into(), 0),
+            currency_pair: CurrencyPair::from_codes("base".into(), "quote".into()),
+            is_snapshot: true,
+            asks: vec![(dec!(1.1), dec!(2)), (dec!(1.2), dec!(3))],
+            bids: vec![(dec!(0.9), dec!(4))],
+        })
+    }
+
+    fn trades_event(time: DateTime) -> RecordedMarketEvent {
+        RecordedMarketEvent::Trades(RecordedTradesEvent {
+            time,
+            exchange_account_id: ExchangeAccountId::new("Binance".into(), 0),
+            currency_pair: CurrencyPair::from_codes("base".into(), "quote".into()),
+            trades: vec![RecordedTrade {
+                trade_id: TradeId::Number(1),
+                price: dec!(1.1),
+                amount: dec!(0.5),
+                side: OrderSide::Buy,
+                transaction_time: time,
+            }],
+        })
+    }
+
+    fn market_data_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "mmb_market_data_{}_{}.jsonl",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn exchange_event_round_trip() {
+        let time = Utc
+            .with_ymd_and_hms(2021, 9, 20, 0, 0, 0)
+            .single()
+            .expect("in test");
+        for recorded_event in [order_book_event(time), trades_event(time)] {
+            let exchange_event = recorded_event.to_exchange_event();
+            let restored_event =
+                RecordedMarketEvent::from_exchange_event(&exchange_event).expect("in test");
+
+            assert_eq!(restored_event, recorded_event);
+        }
+    }
+
+    #[test]
+    fn read_events_from_several_files() {
+        let time = Utc
+            .with_ymd_and_hms(2021, 9, 20, 0, 0, 0)
+            .single()
+            .expect("in test");
+        let first_events = vec![order_book_event(time), trades_event(time)];
+        let second_events = vec![order_book_event(time + Duration::seconds(1))];
+
+        let first_path = market_data_path("first");
+        let second_path = market_data_path("second");
+        write_market_data(&first_path, &first_events).expect("in test");
+        write_market_data(&second_path, &second_events).expect("in test");
+
+        let events: Vec<_> = MarketDataReader::new(vec![first_path.clone(), second_path.clone()])
+            .try_collect()
+            .expect("in test");
+
+        let _ = std::fs::remove_file(first_path);
+        let _ = std::fs::remove_file(second_path);
+
+        assert_eq!(events, [first_events, second_events].concat());
+    }
+
+    #[test]
+    fn fail_when_events_are_not_ordered_by_time() {
+        let time = Utc
+            .with_ymd_and_hms(2021, 9, 20, 0, 0, 0)
+            .single()
+            .expect("in test");
+        let events = vec![
+            order_book_event(time + Duration::seconds(1)),
+            order_book_event(time),
+        ];
+
+        let path = market_data_path("unordered");
+        write_market_data(&path, &events).expect("in test");
+
+        let result: Result<Vec<_>> = MarketDataReader::new(vec![path.clone()]).try_collect();
+
+        let _ = std::fs::remove_file(path);
+
+        let error = result.expect_err("in test");
+        assert!(error.to_string().contains("ordered by time"));
+    }
+}
@@ -0,0 +1,17 @@
+pub mod market_data;
+pub mod report;
+
+use crate::exchanges::events::TradesEvent;
+use crate::order_book::event::OrderBookEvent;
+
+/// Exchange side of a backtest: executes orders of the simulated exchange against replayed market data.
+/// Implementation should report order events through exchange client callbacks before returning
+pub trait BacktestMarket: Send + Sync {
+    fn handle_order_book_event(&self, event: &OrderBookEvent);
+
+    fn handle_trades_event(&self, event: &TradesEvent);
+
+    /// Returns true while some requests of the engine aren't answered yet, e.g. they wait for simulated latency.
+    /// Next market event isn't replayed until all requests are answered
+    fn has_requests_in_flight(&self) -> bool;
+}
@@ -0,0 +1,321 @@
+use std::sync::Arc;
+
+use chrono::Duration;
+use mmb_utils::DateTime;
+use mockall_double::double;
+use serde::Serialize;
+
+#[double]
+use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+
+use crate::balance_changes::balance_changes_calculator::BalanceChangesCalculator;
+use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, Price, TradePlaceAccount};
+use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::symbol::Symbol;
+use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::event::OrderEventType;
+use crate::orders::order::{ClientOrderId, OrderRole, OrderSide};
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestFill {
+    pub time: DateTime,
+    pub client_order_id: ClientOrderId,
+    pub side: OrderSide,
+    pub role: OrderRole,
+    pub price: Price,
+    pub amount: Amount,
+}
+
+/// State of strategy inventory at some moment of replay.
+/// Balance changes are counted from the start of replay
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestSample {
+    pub time: DateTime,
+    /// Middle price of the replayed order book
+    pub price: Option<Price>,
+    /// Change of base currency balance
+    pub position: Amount,
+    /// Change of quote currency balance
+    pub quote_balance_change: Amount,
+    /// Quote balance change plus position valued by middle price
+    pub pnl: Option<Amount>,
+}
+
+/// Fills and inventory samples of one strategy on one trade place
+#[derive(Debug, Clone, Serialize)]
+pub struct TradePlaceReport {
+    pub strategy_name: String,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub fills: Vec<BacktestFill>,
+    pub samples: Vec<BacktestSample>,
+}
+
+impl TradePlaceReport {
+    pub fn fills_count(&self) -> usize {
+        self.fills.len()
+    }
+
+    pub fn fills_count_by_side(&self, side: OrderSide) -> usize {
+        self.fills.iter().filter(|fill| fill.side == side).count()
+    }
+
+    pub fn final_sample(&self) -> Option<&BacktestSample> {
+        self.samples.last()
+    }
+
+    pub fn final_pnl(&self) -> Option<Amount> {
+        self.final_sample().and_then(|sample| sample.pnl)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    pub started_at: DateTime,
+    pub finished_at: DateTime,
+    pub market_events_count: u64,
+    /// Reports of hosted strategies on their trade places and of engine services like `Hedger`
+    /// on trade places where their orders were filled
+    pub trade_places: Vec<TradePlaceReport>,
+}
+
+impl BacktestReport {
+    pub fn fills_count(&self) -> usize {
+        self.trade_places.iter().map(|x| x.fills_count()).sum()
+    }
+
+    pub fn fills_count_by_side(&self, side: OrderSide) -> usize {
+        self.trade_places
+            .iter()
+            .map(|x| x.fills_count_by_side(side))
+            .sum()
+    }
+
+    pub fn trade_place(
+        &self,
+        strategy_name: &str,
+        trade_place: TradePlaceAccount,
+    ) -> Option<&TradePlaceReport> {
+        self.trade_places.iter().find(|x| {
+            x.strategy_name == strategy_name
+                && x.exchange_account_id == trade_place.exchange_account_id
+                && x.currency_pair == trade_place.currency_pair
+        })
+    }
+}
+
+/// Collects fills and inventory of one strategy on one trade place
+struct TradePlaceReportBuilder {
+    strategy_name: String,
+    trade_place: TradePlaceAccount,
+    symbol: Arc<Symbol>,
+    position: Amount,
+    quote_balance_change: Amount,
+    fills: Vec<BacktestFill>,
+    samples: Vec<BacktestSample>,
+}
+
+impl TradePlaceReportBuilder {
+    fn new(strategy_name: String, trade_place: TradePlaceAccount, symbol: Arc<Symbol>) -> Self {
+        Self {
+            strategy_name,
+            trade_place,
+            symbol,
+            position: Amount::ZERO,
+            quote_balance_change: Amount::ZERO,
+            fills: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn add_sample(&mut self, now: DateTime, price: Option<Price>) {
+        self.samples.push(BacktestSample {
+            time: now,
+            price,
+            position: self.position,
+            quote_balance_change: self.quote_balance_change,
+            pnl: price.map(|price| {
+                self.quote_balance_change
+                    + self.symbol.convert_amount_from_amount_currency_code(
+                        self.symbol.quote_currency_code(),
+                        self.position,
+                        price,
+                    )
+            }),
+        });
+    }
+
+    fn build(self) -> TradePlaceReport {
+        TradePlaceReport {
+            strategy_name: self.strategy_name,
+            exchange_account_id: self.trade_place.exchange_account_id,
+            currency_pair: self.trade_place.currency_pair,
+            fills: self.fills,
+            samples: self.samples,
+        }
+    }
+}
+
+/// Collects fills and inventory samples for every strategy and trade place from events observed during replay.
+/// Balance changes of fills are calculated the same way as by `BalanceChangesService`
+pub(crate) struct BacktestReportBuilder {
+    currency_pair_to_symbol_converter: Arc<CurrencyPairToSymbolConverter>,
+    balance_changes_calculator: BalanceChangesCalculator,
+    sample_interval: Duration,
+    local_snapshots_service: LocalSnapshotsService,
+    started_at: DateTime,
+    next_sample_time: DateTime,
+    market_events_count: u64,
+    trade_places: Vec<TradePlaceReportBuilder>,
+}
+
+impl BacktestReportBuilder {
+    pub fn new(
+        currency_pair_to_symbol_converter: Arc<CurrencyPairToSymbolConverter>,
+        sample_interval: Duration,
+        started_at: DateTime,
+    ) -> Self {
+        Self {
+            currency_pair_to_symbol_converter: currency_pair_to_symbol_converter.clone(),
+            balance_changes_calculator: BalanceChangesCalculator::new(
+                currency_pair_to_symbol_converter,
+            ),
+            sample_interval,
+            local_snapshots_service: LocalSnapshotsService::default(),
+            started_at,
+            next_sample_time: started_at,
+            market_events_count: 0,
+            trade_places: Vec::new(),
+        }
+    }
+
+    /// Registers trade place of hosted strategy, so it is reported even if strategy has no fills on it
+    pub fn add_trade_place(&mut self, strategy_name: &str, trade_place: TradePlaceAccount) {
+        let _ = self.get_or_add_trade_place(strategy_name, trade_place);
+    }
+
+    fn get_or_add_trade_place(
+        &mut self,
+        strategy_name: &str,
+        trade_place: TradePlaceAccount,
+    ) -> &mut TradePlaceReportBuilder {
+        let position = self
+            .trade_places
+            .iter()
+            .position(|x| x.strategy_name == strategy_name && x.trade_place == trade_place);
+
+        match position {
+            Some(index) => &mut self.trade_places[index],
+            None => {
+                let symbol = self
+                    .currency_pair_to_symbol_converter
+                    .get_symbol(trade_place.exchange_account_id, trade_place.currency_pair);
+                self.trade_places.push(TradePlaceReportBuilder::new(
+                    strategy_name.to_owned(),
+                    trade_place,
+                    symbol,
+                ));
+                self.trade_places.last_mut().expect("Trade place was added")
+            }
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &ExchangeEvent, now: DateTime) {
+        match event {
+            ExchangeEvent::OrderBookEvent(order_book_event) => {
+                let _ = self
+                    .local_snapshots_service
+                    .update(order_book_event.clone());
+            }
+            ExchangeEvent::OrderEvent(order_event) => {
+                let cloned_order = match &order_event.event_type {
+                    OrderEventType::OrderFilled { cloned_order } => cloned_order,
+                    _ => return,
+                };
+
+                if order_event.order.is_external_order() {
+                    return;
+                }
+
+                let fill = match cloned_order.fills.fills.last() {
+                    Some(fill) => fill,
+                    None => return,
+                };
+
+                let header = &cloned_order.header;
+                let trade_place =
+                    TradePlaceAccount::new(header.exchange_account_id, header.currency_pair);
+                let configuration_descriptor = ConfigurationDescriptor::new(
+                    header.strategy_name.as_str().into(),
+                    format!(
+                        "{};{}",
+                        trade_place.exchange_account_id, trade_place.currency_pair
+                    )
+                    .as_str()
+                    .into(),
+                );
+                let balance_changes = self.balance_changes_calculator.get_balance_changes(
+                    configuration_descriptor,
+                    cloned_order,
+                    fill,
+                );
+
+                let report = self.get_or_add_trade_place(&header.strategy_name, trade_place);
+                for (request, balance_change) in balance_changes.get_changes().get_as_balances() {
+                    if request.currency_code == report.symbol.base_currency_code() {
+                        report.position += balance_change;
+                    } else if request.currency_code == report.symbol.quote_currency_code() {
+                        report.quote_balance_change += balance_change;
+                    }
+                }
+
+                report.fills.push(BacktestFill {
+                    time: now,
+                    client_order_id: header.client_order_id.clone(),
+                    side: header.side,
+                    role: fill.role().into(),
+                    price: fill.price(),
+                    amount: fill.amount(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// Should be called after all events caused by a replayed market event were handled
+    pub fn market_event_handled(&mut self, now: DateTime) {
+        self.market_events_count += 1;
+
+        if now >= self.next_sample_time {
+            self.add_samples(now);
+            self.next_sample_time = now + self.sample_interval;
+        }
+    }
+
+    fn add_samples(&mut self, now: DateTime) {
+        for report in &mut self.trade_places {
+            if report.samples.last().map(|x| x.time) == Some(now) {
+                continue;
+            }
+
+            let trade_place = report.trade_place.trade_place();
+            let price = self
+                .local_snapshots_service
+                .get_snapshot(trade_place)
+                .and_then(|snapshot| snapshot.calculate_middle_price(trade_place));
+            report.add_sample(now, price);
+        }
+    }
+
+    pub fn build(mut self, finished_at: DateTime) -> BacktestReport {
+        self.add_samples(finished_at);
+
+        BacktestReport {
+            started_at: self.started_at,
+            finished_at,
+            market_events_count: self.market_events_count,
+            trade_places: self.trade_places.into_iter().map(|x| x.build()).collect(),
+        }
+    }
+}
@@ -18,7 +18,7 @@ use crate::{
         balance_changes_accumulator::BalanceChangeAccumulator, profit_balance_changes_calculator,
         profit_loss_balance_change::ProfitLossBalanceChange,
     },
-    exchanges::common::{Amount, TradePlaceAccount},
+    exchanges::common::{Amount, CurrencyCode, TradePlaceAccount},
 };
 
 use super::balance_change_period_selector::BalanceChangePeriodSelector;
@@ -51,7 +51,35 @@ impl BalanceChangeUsdPeriodicCalculator {
         cancellation_token: CancellationToken,
     ) -> Amount {
         let items = self.balance_change_period_selector.lock().get_items();
+        Self::calculate_over_market(items, usd_converter, cancellation_token).await
+    }
+
+    /// Over market USD change of balance changes in the specified currency only
+    pub async fn calculate_over_market_usd_change_by_currency(
+        &self,
+        usd_converter: &UsdConverter,
+        currency_code: CurrencyCode,
+        cancellation_token: CancellationToken,
+    ) -> Amount {
+        let items = self
+            .balance_change_period_selector
+            .lock()
+            .get_items()
+            .into_iter()
+            .map(|x| {
+                x.into_iter()
+                    .filter(|balance_change| balance_change.currency_code == currency_code)
+                    .collect_vec()
+            })
+            .collect_vec();
+        Self::calculate_over_market(items, usd_converter, cancellation_token).await
+    }
 
+    async fn calculate_over_market(
+        items: Vec<Vec<ProfitLossBalanceChange>>,
+        usd_converter: &UsdConverter,
+        cancellation_token: CancellationToken,
+    ) -> Amount {
         let actions = items
             .iter()
             .map(|x| {
@@ -7,6 +7,7 @@ use mmb_utils::{
     DateTime,
 };
 use mockall_double::double;
+use parking_lot::Mutex;
 use tokio::sync::mpsc;
 
 #[double]
@@ -62,11 +63,11 @@ impl BalanceChange {
 }
 
 pub struct BalanceChangesService {
-    usd_converter: UsdConverter,
+    usd_converter: Arc<UsdConverter>,
     // TODO: fix me when DatabaseManager/DataRecorder will be implemented
     // private readonly IDatabaseManager _databaseManager;
     // private readonly IDataRecorder _dataRecorder;
-    rx_event: mpsc::Receiver<BalanceChangeServiceEvent>,
+    rx_event: Mutex<Option<mpsc::Receiver<BalanceChangeServiceEvent>>>,
     tx_event: mpsc::Sender<BalanceChangeServiceEvent>,
     balance_changes_accumulators: Vec<Arc<dyn BalanceChangeAccumulator + Send + Sync>>,
     profit_loss_stopper_service: Arc<ProfitLossStopperService>,
@@ -78,7 +79,7 @@ impl BalanceChangesService {
     pub fn new(
         currency_pair_to_symbol_converter: Arc<CurrencyPairToSymbolConverter>,
         profit_loss_stopper_service: Arc<ProfitLossStopperService>,
-        usd_converter: UsdConverter,
+        usd_converter: Arc<UsdConverter>,
         application_manager: Arc<ApplicationManager>,
         // IDatabaseManager databaseManager,
         // IDataRecorder dataRecorder,
@@ -92,7 +93,7 @@ impl BalanceChangesService {
             usd_converter,
             // _databaseManager = databaseManager;
             // _dataRecorder = dataRecorder;
-            rx_event,
+            rx_event: Mutex::new(Some(rx_event)),
             tx_event,
             balance_changes_accumulators,
             profit_loss_stopper_service,
@@ -130,7 +131,7 @@ impl BalanceChangesService {
         this
     }
 
-    pub async fn run(&mut self, cancellation_token: CancellationToken) {
+    pub async fn run(self: Arc<Self>, cancellation_token: CancellationToken) {
         // TODO: fix me when DatabaseManager/DataRecorder will be implemented
         //             if (_databaseManager != null)
         //             {
@@ -138,9 +139,14 @@ impl BalanceChangesService {
         //                 await _profitLossStopperService.CheckForLimit(_usdConverter, cancellationToken);
         //             }
 
+        let mut rx_event = self
+            .rx_event
+            .lock()
+            .take()
+            .expect("BalanceChangesService::run() should be called only once");
         loop {
             let new_event = tokio::select! {
-                event = self.rx_event.recv() => event,
+                event = rx_event.recv() => event,
                 _ = cancellation_token.when_cancelled() => return,
             }.expect("BalanceChangesService::run() the event channel is closed but cancellation hasn't been requested");
 
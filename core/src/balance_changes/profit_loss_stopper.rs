@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
-use mmb_utils::cancellation_token::CancellationToken;
+use chrono::Duration;
+use futures::FutureExt;
+use mmb_utils::{cancellation_token::CancellationToken, nothing_to_do, DateTime};
 use mockall_double::double;
 use parking_lot::Mutex;
 
@@ -11,6 +13,8 @@ use crate::exchanges::exchange_blocker::ExchangeBlocker;
 #[double]
 use crate::exchanges::general::engine_api::EngineApi;
 #[double]
+use crate::misc::time::time_manager;
+#[double]
 use crate::services::usd_converter::usd_converter::UsdConverter;
 
 use crate::{
@@ -18,38 +22,54 @@ use crate::{
         common::{Amount, TradePlaceAccount},
         exchange_blocker::{BlockReason, BlockType},
     },
+    infrastructure::spawn_future,
     misc::position_helper,
+    risk::pre_trade_risk::PreTradeRiskEngine,
+    settings::{StopperAction, StopperCondition, StopperLimitKind, UnblockPolicy},
 };
 
 use super::balance_change_usd_periodic_calculator::BalanceChangeUsdPeriodicCalculator;
 
 static BLOCK_REASON: BlockReason = BlockReason::new("ProfitLossExceeded");
 
+#[derive(Default)]
+struct StopperState {
+    /// Max USD change over the period observed by stopper
+    high_water_mark: Amount,
+    /// Time when actions of the condition were taken, `None` if they are reverted
+    triggered_at: Option<DateTime>,
+}
+
 pub(crate) struct ProfitLossStopper {
-    limit: Amount,
+    condition: StopperCondition,
     target_trade_place: TradePlaceAccount,
     usd_periodic_calculator: Arc<BalanceChangeUsdPeriodicCalculator>,
     exchange_blocker: Arc<ExchangeBlocker>,
     balance_manager: Option<Arc<Mutex<BalanceManager>>>,
     engine_api: Arc<EngineApi>,
+    pre_trade_risk: Arc<PreTradeRiskEngine>,
+    state: Mutex<StopperState>,
 }
 
 impl ProfitLossStopper {
     pub fn new(
-        limit: Amount,
+        condition: StopperCondition,
         target_trade_place: TradePlaceAccount,
         usd_periodic_calculator: Arc<BalanceChangeUsdPeriodicCalculator>,
         exchange_blocker: Arc<ExchangeBlocker>,
         balance_manager: Option<Arc<Mutex<BalanceManager>>>,
         engine_api: Arc<EngineApi>,
+        pre_trade_risk: Arc<PreTradeRiskEngine>,
     ) -> Self {
         Self {
-            limit,
+            condition,
             target_trade_place,
             usd_periodic_calculator,
             exchange_blocker,
             balance_manager,
             engine_api,
+            pre_trade_risk,
+            state: Default::default(),
         }
     }
 
@@ -58,57 +78,62 @@ impl ProfitLossStopper {
         usd_converter: &UsdConverter,
         cancellation_token: CancellationToken,
     ) {
-        let over_market = self
-            .usd_periodic_calculator
-            .calculate_over_market_usd_change(usd_converter, cancellation_token.clone())
-            .await;
+        let over_market = match self.condition.currency_code {
+            Some(currency_code) => {
+                self.usd_periodic_calculator
+                    .calculate_over_market_usd_change_by_currency(
+                        usd_converter,
+                        currency_code,
+                        cancellation_token.clone(),
+                    )
+                    .await
+            }
+            None => {
+                self.usd_periodic_calculator
+                    .calculate_over_market_usd_change(usd_converter, cancellation_token.clone())
+                    .await
+            }
+        };
         self.check(over_market, cancellation_token).await;
     }
 
     async fn check(&self, usd_change: Amount, cancellation_token: CancellationToken) {
         let period = self.usd_periodic_calculator.period();
+        let limit = self.condition.limit;
 
         log::info!(
             "ProfitLossStopper::check() {}: {} (limit {})",
             period,
             usd_change,
-            self.limit
+            limit
         );
 
-        let target_exchange_account_id = self.target_trade_place.exchange_account_id;
-
-        if usd_change <= -self.limit {
-            let _ = position_helper::close_position_if_needed(
-                &self.target_trade_place,
-                self.balance_manager.clone(),
-                self.engine_api.clone(),
-                cancellation_token,
-            );
+        if self.is_limit_exceeded(usd_change) {
+            if self.has_action(StopperAction::ClosePositions) {
+                let _ = position_helper::close_position_if_needed(
+                    &self.target_trade_place,
+                    self.balance_manager.clone(),
+                    self.engine_api.clone(),
+                    cancellation_token.clone(),
+                );
+            }
 
-            if self
-                .exchange_blocker
-                .is_blocked_by_reason(target_exchange_account_id, BLOCK_REASON)
-            {
+            if self.is_triggered() {
+                self.block_again_if_unblocked();
                 return;
             }
 
             log::warn!(
-                "Usd change for {}: {} exceeded {}",
+                "Usd change for {}: {} exceeded {:?} limit {}",
                 period,
                 usd_change,
-                self.limit
+                self.condition.limit_kind,
+                limit
             );
 
-            self.exchange_blocker.block(
-                target_exchange_account_id,
-                BLOCK_REASON,
-                BlockType::Manual,
-            );
+            self.trigger(cancellation_token);
         } else {
-            if !self
-                .exchange_blocker
-                .is_blocked_by_reason(target_exchange_account_id, BLOCK_REASON)
-            {
+            if !self.is_triggered() || !self.can_be_released() {
                 return;
             }
 
@@ -116,11 +141,115 @@ impl ProfitLossStopper {
                 "Usd change not {}: {} exceeded {}",
                 period,
                 usd_change,
-                self.limit
+                limit
             );
 
-            self.exchange_blocker
-                .unblock(target_exchange_account_id, BLOCK_REASON);
+            self.release();
+        }
+    }
+
+    fn is_limit_exceeded(&self, usd_change: Amount) -> bool {
+        match self.condition.limit_kind {
+            StopperLimitKind::Loss => usd_change <= -self.condition.limit,
+            StopperLimitKind::Drawdown => {
+                let mut state = self.state.lock();
+                state.high_water_mark = state.high_water_mark.max(usd_change);
+                state.high_water_mark - usd_change >= self.condition.limit
+            }
+        }
+    }
+
+    fn has_action(&self, action: StopperAction) -> bool {
+        self.condition.actions.contains(&action)
+    }
+
+    fn is_triggered(&self) -> bool {
+        self.state.lock().triggered_at.is_some()
+    }
+
+    /// Exchange can be unblocked manually, so stopper blocks it again while the condition is exceeded
+    fn block_again_if_unblocked(&self) {
+        let exchange_account_id = self.target_trade_place.exchange_account_id;
+        if !self.has_action(StopperAction::Block)
+            || self
+                .exchange_blocker
+                .is_blocked_by_reason(exchange_account_id, BLOCK_REASON)
+        {
+            return;
+        }
+
+        log::warn!(
+            "Exchange {} was unblocked while profit loss limit is exceeded, so it is blocked again",
+            exchange_account_id
+        );
+        self.exchange_blocker
+            .block(exchange_account_id, BLOCK_REASON, BlockType::Manual);
+    }
+
+    fn can_be_released(&self) -> bool {
+        match self.condition.unblock_policy {
+            UnblockPolicy::WhenRecovered => true,
+            UnblockPolicy::AfterCooldown { minutes } => {
+                self.state.lock().triggered_at.map_or(true, |triggered_at| {
+                    time_manager::now() - triggered_at >= Duration::minutes(minutes)
+                })
+            }
+            UnblockPolicy::Never => false,
+        }
+    }
+
+    fn trigger(&self, cancellation_token: CancellationToken) {
+        let exchange_account_id = self.target_trade_place.exchange_account_id;
+        self.state.lock().triggered_at = Some(time_manager::now());
+
+        for action in &self.condition.actions {
+            match action {
+                StopperAction::Block => self.exchange_blocker.block(
+                    exchange_account_id,
+                    BLOCK_REASON,
+                    BlockType::Manual,
+                ),
+                StopperAction::CancelOrders => {
+                    let engine_api = self.engine_api.clone();
+                    let cancellation_token = cancellation_token.clone();
+                    let action = async move {
+                        engine_api.cancel_opened_orders(cancellation_token).await;
+                        Ok(())
+                    };
+                    let _ = spawn_future(
+                        "Cancel opened orders by ProfitLossStopper",
+                        true,
+                        action.boxed(),
+                    );
+                }
+                StopperAction::ReduceOnly => {
+                    self.pre_trade_risk.enable_reduce_only(exchange_account_id)
+                }
+                // positions are closed on every check while limit is exceeded
+                StopperAction::ClosePositions => nothing_to_do(),
+                // exceeding is already logged
+                StopperAction::NotifyOnly => nothing_to_do(),
+            }
+        }
+    }
+
+    fn release(&self) {
+        let exchange_account_id = self.target_trade_place.exchange_account_id;
+        self.state.lock().triggered_at = None;
+
+        for action in &self.condition.actions {
+            match action {
+                StopperAction::Block => self
+                    .exchange_blocker
+                    .unblock(exchange_account_id, BLOCK_REASON),
+                // other conditions can still require reduce only mode
+                StopperAction::ReduceOnly => {
+                    self.pre_trade_risk.disable_reduce_only(exchange_account_id)
+                }
+                StopperAction::CancelOrders
+                | StopperAction::ClosePositions
+                | StopperAction::NotifyOnly => nothing_to_do(),
+            }
         }
     }
 }
@@ -151,6 +280,8 @@ mod test {
         },
         misc::time,
         orders::order::ClientOrderFillId,
+        risk::pre_trade_risk::RiskLimitsSettings,
+        settings::{default_stopper_actions, TimePeriodKind},
     };
 
     use super::ProfitLossStopper;
@@ -181,6 +312,18 @@ mod test {
         Duration::hours(1)
     }
 
+    fn condition(limit_kind: StopperLimitKind, actions: Vec<StopperAction>) -> StopperCondition {
+        StopperCondition {
+            period_kind: TimePeriodKind::Hour,
+            period_value: 1,
+            limit: LIMIT,
+            limit_kind,
+            currency_code: None,
+            actions,
+            unblock_policy: UnblockPolicy::WhenRecovered,
+        }
+    }
+
     fn client_order_fill_id() -> ClientOrderFillId {
         ClientOrderFillId::new("client_order_id_test".into())
     }
@@ -190,6 +333,7 @@ mod test {
         pub profit_loss_stopper: ProfitLossStopper,
         pub usd_converter: UsdConverter,
         pub balance_manager: Arc<Mutex<BalanceManager>>,
+        pub pre_trade_risk: Arc<PreTradeRiskEngine>,
 
         _exchange_blocker: Arc<ExchangeBlocker>,
         _time_manager_mock: time_manager::__now::Context,
@@ -204,6 +348,7 @@ mod test {
             profit_loss_stopper: ProfitLossStopper,
             usd_converter: UsdConverter,
             balance_manager: Arc<Mutex<BalanceManager>>,
+            pre_trade_risk: Arc<PreTradeRiskEngine>,
             _time_manager_mock: time_manager::__now::Context,
             seconds_offset_in_mock: Arc<Mutex<u32>>,
             _mock_lockers: Vec<ReentrantMutexGuard<'static, ()>>,
@@ -213,6 +358,7 @@ mod test {
                 profit_loss_stopper,
                 usd_converter,
                 balance_manager,
+                pre_trade_risk,
                 _exchange_blocker,
                 _time_manager_mock,
                 seconds_offset_in_mock,
@@ -236,6 +382,22 @@ mod test {
         exchange_blocker: Arc<ExchangeBlocker>,
         exchange_blocker_locker: ReentrantMutexGuard<'static, ()>,
         get_last_position_change_calling_times: usize,
+    ) -> TestContext {
+        init_with_condition(
+            max_period,
+            condition(StopperLimitKind::Loss, default_stopper_actions()),
+            exchange_blocker,
+            exchange_blocker_locker,
+            get_last_position_change_calling_times,
+        )
+    }
+
+    fn init_with_condition(
+        max_period: Duration,
+        condition: StopperCondition,
+        exchange_blocker: Arc<ExchangeBlocker>,
+        exchange_blocker_locker: ReentrantMutexGuard<'static, ()>,
+        get_last_position_change_calling_times: usize,
     ) -> TestContext {
         let seconds_offset_in_mock = Arc::new(Mutex::new(0u32));
         let mut mock_lockers = vec![exchange_blocker_locker];
@@ -263,13 +425,16 @@ mod test {
         mock_lockers.push(exchange_locker);
         let exchange = Arc::new(exchange);
 
+        let pre_trade_risk = PreTradeRiskEngine::new(RiskLimitsSettings::default());
+
         let profit_loss_stopper = ProfitLossStopper::new(
-            LIMIT,
+            condition,
             trade_place(),
             balance_change_usd_periodic_calculator.clone(),
             exchange_blocker.clone(),
             Some(balance_manager.clone()),
             exchange,
+            pre_trade_risk.clone(),
         );
 
         let (mut usd_converter, usd_converter_locker) = UsdConverter::init_mock();
@@ -284,6 +449,7 @@ mod test {
             profit_loss_stopper,
             usd_converter,
             balance_manager,
+            pre_trade_risk,
             time_manager_mock,
             seconds_offset_in_mock,
             mock_lockers,
@@ -381,10 +547,6 @@ mod test {
             .returning(|_, _, _| ())
             .times(1);
 
-        exchange_blocker
-            .expect_is_blocked_by_reason()
-            .returning(|_, _| false);
-
         let context = init_with_exchange_blocker(
             max_period(),
             Arc::new(exchange_blocker),
@@ -439,16 +601,6 @@ mod test {
             .returning(|_, _| ())
             .times(1);
 
-        exchange_blocker
-            .expect_is_blocked_by_reason()
-            .returning(|_, _| false)
-            .times(2);
-
-        exchange_blocker
-            .expect_is_blocked_by_reason()
-            .returning(|_, _| true)
-            .times(1);
-
         let context = init_with_exchange_blocker(
             max_period(),
             Arc::new(exchange_blocker),
@@ -516,16 +668,6 @@ mod test {
             .returning(|_, _| ())
             .times(1);
 
-        exchange_blocker
-            .expect_is_blocked_by_reason()
-            .returning(|_, _| false)
-            .times(2);
-
-        exchange_blocker
-            .expect_is_blocked_by_reason()
-            .returning(|_, _| true)
-            .times(1);
-
         let context = init_with_exchange_blocker(
             Duration::seconds(3),
             Arc::new(exchange_blocker),
@@ -601,16 +743,6 @@ mod test {
             .returning(|_, _| ())
             .times(1);
 
-        exchange_blocker
-            .expect_is_blocked_by_reason()
-            .returning(|_, _| false)
-            .times(2);
-
-        exchange_blocker
-            .expect_is_blocked_by_reason()
-            .returning(|_, _| true)
-            .times(1);
-
         let context = init_with_exchange_blocker(
             Duration::seconds(3),
             Arc::new(exchange_blocker),
@@ -665,4 +797,254 @@ mod test {
             .check_for_limit(&context.usd_converter, CancellationToken::default())
             .await;
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn check_for_limit_should_stop_transaction_on_drawdown_from_peak() {
+        init_logger();
+        let (mut exchange_blocker, exchange_blocker_locker) = ExchangeBlocker::init_mock();
+        exchange_blocker
+            .expect_block()
+            .returning(|_, _, _| ())
+            .times(1);
+
+        let context = init_with_condition(
+            max_period(),
+            condition(StopperLimitKind::Drawdown, vec![StopperAction::Block]),
+            Arc::new(exchange_blocker),
+            exchange_blocker_locker,
+            4,
+        );
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(8),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+
+        // total change is -3 and doesn't exceed loss limit, but it's 11 below the peak
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(-11),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn reduce_only_should_be_reverted_after_recovery() {
+        init_logger();
+        let (exchange_blocker, exchange_blocker_locker) = ExchangeBlocker::init_mock();
+        let context = init_with_condition(
+            max_period(),
+            condition(StopperLimitKind::Loss, vec![StopperAction::ReduceOnly]),
+            Arc::new(exchange_blocker),
+            exchange_blocker_locker,
+            4,
+        );
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(-11),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+        assert!(context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(5),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+        assert!(!context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn reduce_only_of_other_condition_should_be_kept_after_recovery() {
+        init_logger();
+        let (exchange_blocker, exchange_blocker_locker) = ExchangeBlocker::init_mock();
+        let context = init_with_condition(
+            max_period(),
+            condition(StopperLimitKind::Loss, vec![StopperAction::ReduceOnly]),
+            Arc::new(exchange_blocker),
+            exchange_blocker_locker,
+            5,
+        );
+        // other condition is still triggered
+        context
+            .pre_trade_risk
+            .enable_reduce_only(exchange_account_id());
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(-11),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        // repeated check while limit is exceeded shouldn't enable reduce only mode again
+        for _ in 0..2 {
+            context
+                .profit_loss_stopper
+                .check_for_limit(&context.usd_converter, CancellationToken::default())
+                .await;
+        }
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(5),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+        assert!(context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+
+        context
+            .pre_trade_risk
+            .disable_reduce_only(exchange_account_id());
+        assert!(!context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn after_cooldown_policy_should_keep_reduce_only_until_cooldown_passed() {
+        init_logger();
+        let mut condition = condition(StopperLimitKind::Loss, vec![StopperAction::ReduceOnly]);
+        condition.unblock_policy = UnblockPolicy::AfterCooldown { minutes: 1 };
+
+        let (exchange_blocker, exchange_blocker_locker) = ExchangeBlocker::init_mock();
+        let context = init_with_condition(
+            max_period(),
+            condition,
+            Arc::new(exchange_blocker),
+            exchange_blocker_locker,
+            5,
+        );
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(-11),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(5),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+        assert!(context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+
+        *context.seconds_offset_in_mock.lock() = 61;
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+        assert!(!context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    pub async fn should_be_released_after_manual_unblock_and_recovery() {
+        init_logger();
+        let (mut exchange_blocker, exchange_blocker_locker) = ExchangeBlocker::init_mock();
+        // blocked on trigger and again after manual unblock while limit is still exceeded
+        exchange_blocker
+            .expect_block()
+            .returning(|_, _, _| ())
+            .times(2);
+        // exchange was unblocked manually
+        exchange_blocker
+            .expect_is_blocked_by_reason()
+            .returning(|_, _| false)
+            .times(1);
+        exchange_blocker
+            .expect_unblock()
+            .returning(|_, _| ())
+            .times(1);
+
+        let context = init_with_condition(
+            max_period(),
+            condition(
+                StopperLimitKind::Loss,
+                vec![StopperAction::Block, StopperAction::ReduceOnly],
+            ),
+            Arc::new(exchange_blocker),
+            exchange_blocker_locker,
+            5,
+        );
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(-11),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        for _ in 0..2 {
+            context
+                .profit_loss_stopper
+                .check_for_limit(&context.usd_converter, CancellationToken::default())
+                .await;
+        }
+        assert!(context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+
+        context
+            .balance_change_usd_periodic_calculator
+            .add_balance_change(&create_balance_change(
+                dec!(5),
+                time_manager::now(),
+                client_order_fill_id(),
+            ));
+
+        context
+            .profit_loss_stopper
+            .check_for_limit(&context.usd_converter, CancellationToken::default())
+            .await;
+        assert!(!context.pre_trade_risk.is_reduce_only(exchange_account_id()));
+    }
 }
@@ -19,6 +19,7 @@ use crate::services::usd_converter::usd_converter::UsdConverter;
 use crate::{
     balance_changes::balance_changes_accumulator::BalanceChangeAccumulator,
     exchanges::common::TradePlaceAccount,
+    risk::pre_trade_risk::PreTradeRiskEngine,
     settings::{ProfitLossStopperSettings, TimePeriodKind},
 };
 
@@ -31,6 +32,7 @@ pub struct ProfitLossStopperService {
     target_trade_place: TradePlaceAccount,
     exchange_blocker: Arc<ExchangeBlocker>,
     engine_api: Arc<EngineApi>,
+    pre_trade_risk: Arc<PreTradeRiskEngine>,
     profit_loss_stoppers: Vec<ProfitLossStopper>,
     usd_periodic_calculators: Vec<Arc<BalanceChangeUsdPeriodicCalculator>>,
 }
@@ -42,11 +44,13 @@ impl ProfitLossStopperService {
         exchange_blocker: Arc<ExchangeBlocker>,
         balance_manager: Option<Arc<Mutex<BalanceManager>>>,
         engine_api: Arc<EngineApi>,
+        pre_trade_risk: Arc<PreTradeRiskEngine>,
     ) -> Self {
         let mut this = Self {
             target_trade_place,
             exchange_blocker,
             engine_api,
+            pre_trade_risk,
             profit_loss_stoppers: Vec::new(),
             usd_periodic_calculators: Vec::new(),
         };
@@ -64,18 +68,20 @@ impl ProfitLossStopperService {
     ) {
         for stopper_condition in stopper_settings.conditions.iter() {
             let period = match stopper_condition.period_kind {
+                TimePeriodKind::Minute => Duration::minutes(stopper_condition.period_value),
                 TimePeriodKind::Hour => Duration::hours(stopper_condition.period_value),
                 TimePeriodKind::Day => Duration::days(stopper_condition.period_value),
             };
             let usd_periodic_calculator =
                 BalanceChangeUsdPeriodicCalculator::new(period, balance_manager.clone());
             let profit_loss_stopper = ProfitLossStopper::new(
-                stopper_condition.limit,
+                stopper_condition.clone(),
                 self.target_trade_place.clone(),
                 usd_periodic_calculator.clone(),
                 self.exchange_blocker.clone(),
                 balance_manager.clone(),
                 self.engine_api.clone(),
+                self.pre_trade_risk.clone(),
             );
 
             self.usd_periodic_calculators.push(usd_periodic_calculator);
@@ -138,7 +144,8 @@ mod test {
 
     use crate::{
         exchanges::common::{CurrencyPair, ExchangeAccountId, TradePlaceAccount},
-        settings::StopperCondition,
+        risk::pre_trade_risk::RiskLimitsSettings,
+        settings::{StopperAction, StopperCondition, StopperLimitKind, UnblockPolicy},
     };
 
     use super::*;
@@ -157,10 +164,16 @@ mod test {
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     pub async fn settings_loading_test_empty_settings_should_not_throw() {
         let stopper_settings = ProfitLossStopperSettings {
+            exchange_account_id: exchange_account_id(),
+            currency_pair: CurrencyPair::from_codes("BTC".into(), "ETH".into()),
             conditions: vec![StopperCondition {
                 period_kind: TimePeriodKind::Day,
                 period_value: 1,
                 limit: dec!(50),
+                limit_kind: StopperLimitKind::Loss,
+                currency_code: None,
+                actions: vec![StopperAction::Block, StopperAction::ClosePositions],
+                unblock_policy: UnblockPolicy::WhenRecovered,
             }],
         };
 
@@ -170,6 +183,7 @@ mod test {
             Arc::new(ExchangeBlocker::default()),
             None,
             Arc::new(EngineApi::default()),
+            PreTradeRiskEngine::new(RiskLimitsSettings::default()),
         );
     }
 }
@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::balance_changes::balance_changes_service::BalanceChangesService;
+use crate::balance_manager::balance_request::BalanceRequest;
 use crate::balance_manager::balance_reservation::BalanceReservation;
 use crate::balance_manager::position_change::PositionChange;
 use crate::balances::balance_reservation_manager::BalanceReservationManager;
@@ -16,7 +17,7 @@ use crate::misc::reserve_parameters::ReserveParameters;
 use crate::misc::service_value_tree::ServiceValueTree;
 use crate::orders::fill::OrderFill;
 use crate::orders::order::{
-    ClientOrderId, OrderSide, OrderSnapshot, OrderStatus, OrderType, ReservationId,
+    ClientOrderId, OrderHeader, OrderSide, OrderSnapshot, OrderStatus, OrderType, ReservationId,
 };
 use crate::orders::pool::OrderRef;
 use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
@@ -447,6 +448,28 @@ impl BalanceManager {
         Ok(balances_dict)
     }
 
+    /// Balances which should be on the exchange according to the engine state: the last received
+    /// exchange balances changed by fills and reservations approved for orders after that
+    pub fn get_expected_exchange_balances(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<HashMap<CurrencyCode, Amount>> {
+        let mut balances = self
+            .calculate_whole_balances()?
+            .remove(&exchange_account_id)
+            .unwrap_or_default();
+
+        let balance_diffs = self
+            .balance_reservation_manager
+            .virtual_balance_holder
+            .get_balance_diffs(exchange_account_id);
+        for (currency_code, diff) in balance_diffs {
+            *balances.entry(currency_code).or_default() += diff;
+        }
+
+        Ok(balances)
+    }
+
     pub fn custom_clone(this: Arc<Mutex<Self>>) -> Arc<Mutex<BalanceManager>> {
         let this_locked = this.lock();
         let balances = this_locked.get_balances();
@@ -773,6 +796,63 @@ impl BalanceManager {
         self.save_balances();
     }
 
+    /// Registers reservation of the order restored after restart under its original id,
+    /// so unfilled amount of the order is taken into account by reservation limits
+    pub fn restore_order_reservation(
+        &mut self,
+        configuration_descriptor: ConfigurationDescriptor,
+        order: &OrderSnapshot,
+    ) -> Result<()> {
+        let reservation_id = match order.header.reservation_id {
+            Some(reservation_id) => reservation_id,
+            None => return Ok(()),
+        };
+
+        let exchange_account_id = order.header.exchange_account_id;
+        let symbol = self
+            .balance_reservation_manager
+            .currency_pair_to_symbol_converter
+            .get_symbol(exchange_account_id, order.header.currency_pair);
+        let reserve_parameters = ReserveParameters::new(
+            configuration_descriptor,
+            exchange_account_id,
+            symbol,
+            order.header.side,
+            order.price(),
+            order.header.amount - order.fills.filled_amount,
+        );
+        self.balance_reservation_manager
+            .restore_approved_reservation(
+                reservation_id,
+                &reserve_parameters,
+                &order.header.client_order_id,
+            )?;
+
+        self.save_balances();
+        Ok(())
+    }
+
+    /// Releases the rest of reservation registered by `restore_order_reservation` when the order is finished
+    pub fn release_restored_order_reservation(
+        &mut self,
+        reservation_id: ReservationId,
+        client_order_id: &ClientOrderId,
+    ) -> Result<()> {
+        let unreserved_amount = match self
+            .get_reservation(reservation_id)
+            .and_then(|reservation| reservation.approved_parts.get(client_order_id))
+        {
+            Some(approved_part) => approved_part.unreserved_amount,
+            None => return Ok(()),
+        };
+
+        self.unreserve_by_client_order_id(
+            reservation_id,
+            client_order_id.clone(),
+            unreserved_amount,
+        )
+    }
+
     pub fn try_transfer_reservation(
         &mut self,
         src_reservation_id: ReservationId,
@@ -792,6 +872,98 @@ impl BalanceManager {
         true
     }
 
+    /// Transfer reservation of the replaced order unfilled amount to reservation of the order replacing it.
+    /// Transferred amount becomes not approved in the new order reservation, so it can be approved by
+    /// the new order. Amount reserved for filled part of the replaced order is unreserved.
+    /// After that the new order reservation is adjusted to the new order amount.
+    /// Returns false if there is not enough balance to reserve the new order amount
+    pub fn try_transfer_order_reservation(
+        &mut self,
+        replaced_order: &OrderSnapshot,
+        new_order: &OrderHeader,
+    ) -> bool {
+        let (src_reservation_id, dst_reservation_id) = match (
+            replaced_order.header.reservation_id,
+            new_order.reservation_id,
+        ) {
+            (Some(src), Some(dst)) => (src, dst),
+            _ => return true,
+        };
+        let client_order_id = &replaced_order.header.client_order_id;
+
+        if let Some(approved_amount) = self.get_approved_amount(src_reservation_id, client_order_id)
+        {
+            let unfilled_amount = (replaced_order.header.amount - replaced_order.filled_amount())
+                .min(approved_amount);
+            if unfilled_amount > dec!(0)
+                && self.try_transfer_reservation(
+                    src_reservation_id,
+                    dst_reservation_id,
+                    unfilled_amount,
+                    &Some(client_order_id.clone()),
+                )
+            {
+                let dst_reservation = self.get_mut_reservation_expected(dst_reservation_id);
+                if let Some(approved_part) = dst_reservation.approved_parts.remove(client_order_id)
+                {
+                    dst_reservation.not_approved_amount += approved_part.unreserved_amount;
+                }
+            }
+        }
+
+        if let Some(filled_part) = self.get_approved_amount(src_reservation_id, client_order_id) {
+            self.unreserve_by_client_order_id(
+                src_reservation_id,
+                client_order_id.clone(),
+                filled_part,
+            )
+            .with_expect(|| {
+                format!(
+                    "Failed to unreserve filled part of replaced order {}",
+                    client_order_id
+                )
+            });
+        }
+
+        let dst_reservation = self.get_reservation_expected(dst_reservation_id);
+        let lack_amount = new_order.amount - dst_reservation.not_approved_amount;
+        if lack_amount < dec!(0) {
+            self.unreserve(dst_reservation_id, -lack_amount)
+                .with_expect(|| format!("Failed to unreserve {}", dst_reservation_id));
+        } else if lack_amount > dec!(0) {
+            let reserve_parameters =
+                ReserveParameters::from_reservation(dst_reservation, lack_amount);
+            let additional_reservation_id = match self.try_reserve(&reserve_parameters, &mut None) {
+                Some(reservation_id) => reservation_id,
+                None => return false,
+            };
+
+            if !self.try_transfer_reservation(
+                additional_reservation_id,
+                dst_reservation_id,
+                lack_amount,
+                &None,
+            ) {
+                self.unreserve_rest(additional_reservation_id)
+                    .with_expect(|| format!("Failed to unreserve {}", additional_reservation_id));
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn get_approved_amount(
+        &self,
+        reservation_id: ReservationId,
+        client_order_id: &ClientOrderId,
+    ) -> Option<Amount> {
+        self.get_reservation(reservation_id)?
+            .approved_parts
+            .get(client_order_id)
+            .map(|approved_part| approved_part.unreserved_amount)
+    }
+
     pub fn try_update_reservation(
         &mut self,
         reservation_id: ReservationId,
@@ -874,6 +1046,51 @@ impl BalanceManager {
             .get_exchange_balance(exchange_account_id, symbol.clone(), currency_code, None)
     }
 
+    /// Balance of the strategy on exchange including amounts reserved for its orders
+    /// and changes by its fills which aren't received from exchange yet
+    pub fn get_balance_with_reserved_amounts(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+        exchange_account_id: ExchangeAccountId,
+        symbol: Arc<Symbol>,
+        currency_code: CurrencyCode,
+        price: Price,
+    ) -> Option<Amount> {
+        let request = BalanceRequest::new(
+            configuration_descriptor,
+            exchange_account_id,
+            symbol.currency_pair(),
+            currency_code,
+        );
+        let virtual_balance = self
+            .balance_reservation_manager
+            .virtual_balance_holder
+            .get_virtual_balance(&request, symbol.clone(), Some(price), &mut None)?;
+
+        // `unreserved_amount` is amount which is still reserved in amount currency code
+        let reserved_amount: Amount = self
+            .balance_reservation_manager
+            .balance_reservation_storage
+            .get_all_raw_reservations()
+            .values()
+            .filter(|reservation| {
+                reservation.configuration_descriptor == configuration_descriptor
+                    && reservation.exchange_account_id == exchange_account_id
+                    && reservation.symbol.currency_pair() == symbol.currency_pair()
+                    && reservation.reservation_currency_code == currency_code
+            })
+            .map(|reservation| {
+                symbol.convert_amount_from_amount_currency_code(
+                    currency_code,
+                    reservation.unreserved_amount,
+                    reservation.price,
+                )
+            })
+            .sum();
+
+        Some(virtual_balance + reserved_amount)
+    }
+
     pub fn get_all_virtual_balance_diffs(&self) -> &ServiceValueTree {
         self.balance_reservation_manager
             .virtual_balance_holder
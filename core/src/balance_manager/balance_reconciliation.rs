@@ -0,0 +1,454 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::alerts::alert_bus::AlertSeverity;
+use crate::balance_manager::balance_manager::BalanceManager;
+use crate::exchanges::block_reasons;
+use crate::exchanges::common::{Amount, CurrencyCode, ExchangeAccountId};
+use crate::exchanges::exchange_blocker::BlockType;
+use crate::exchanges::general::exchange::Exchange;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::orders::order::OrderStatus;
+
+static BALANCE_RECONCILIATION: &str = "BalanceReconciliation";
+
+fn default_period_secs() -> u64 {
+    60
+}
+
+fn default_max_drift_ratio() -> Amount {
+    dec!(0.01)
+}
+
+fn default_confirmations_count() -> u32 {
+    2
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BalanceReconciliationSettings {
+    /// Period of requesting balances from exchanges
+    #[serde(default = "default_period_secs")]
+    pub period_secs: u64,
+    /// Max absolute drift by currency, e.g. `{ btc = 0.001 }`
+    #[serde(default)]
+    pub max_drifts: HashMap<CurrencyCode, Amount>,
+    /// Max drift relative to expected balance for currencies without value in `max_drifts`
+    #[serde(default = "default_max_drift_ratio")]
+    pub max_drift_ratio: Amount,
+    /// Drift is reported only if it's exceeded on the count of checks in a row,
+    /// so balance changes which are still in flight aren't reported
+    #[serde(default = "default_confirmations_count")]
+    pub confirmations_count: u32,
+    /// Exchange is blocked while drift is exceeded
+    #[serde(default)]
+    pub block_exchange: bool,
+}
+
+/// Exchange balance minus expected balance by currency except `skipped_currencies`
+pub(crate) fn calculate_drifts(
+    exchange_balances: &HashMap<CurrencyCode, Amount>,
+    expected_balances: &HashMap<CurrencyCode, Amount>,
+    skipped_currencies: &HashSet<CurrencyCode>,
+) -> HashMap<CurrencyCode, Amount> {
+    exchange_balances
+        .keys()
+        .chain(expected_balances.keys())
+        .unique()
+        .filter(|currency_code| !skipped_currencies.contains(currency_code))
+        .map(|currency_code| {
+            let exchange_balance = exchange_balances
+                .get(currency_code)
+                .copied()
+                .unwrap_or_default();
+            let expected_balance = expected_balances
+                .get(currency_code)
+                .copied()
+                .unwrap_or_default();
+            (*currency_code, exchange_balance - expected_balance)
+        })
+        .collect()
+}
+
+pub(crate) struct BalanceCheck {
+    /// Exchange balance minus expected balance by currency
+    pub drifts: HashMap<CurrencyCode, Amount>,
+    /// Currencies with drift exceeded on `confirmations_count` checks in a row
+    pub confirmed_drifts: Vec<CurrencyCode>,
+    /// Exchange and expected balances of confirmed drifts
+    pub confirmed_drifts_message: String,
+}
+
+/// Counts checks in a row where drift of currency exceeded max drift
+pub(crate) struct DriftTracker {
+    settings: BalanceReconciliationSettings,
+    exceeded_checks_in_row: HashMap<(ExchangeAccountId, CurrencyCode), u32>,
+}
+
+impl DriftTracker {
+    pub(crate) fn new(settings: BalanceReconciliationSettings) -> Self {
+        Self {
+            settings,
+            exceeded_checks_in_row: HashMap::new(),
+        }
+    }
+
+    fn max_drift(&self, currency_code: CurrencyCode, expected_balance: Amount) -> Amount {
+        self.settings
+            .max_drifts
+            .get(&currency_code)
+            .copied()
+            .unwrap_or_else(|| expected_balance.abs() * self.settings.max_drift_ratio)
+    }
+
+    /// Compare balances received from exchange with balances expected by `balance_manager`.
+    /// Drifts of `skipped_currencies` aren't checked and their exceeded checks in a row are kept
+    pub(crate) fn check_balances(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        exchange_balances: &HashMap<CurrencyCode, Amount>,
+        balance_manager: &BalanceManager,
+        skipped_currencies: &HashSet<CurrencyCode>,
+    ) -> Result<BalanceCheck> {
+        let expected_balances =
+            balance_manager.get_expected_exchange_balances(exchange_account_id)?;
+        let drifts = calculate_drifts(exchange_balances, &expected_balances, skipped_currencies);
+        let confirmed_drifts = self.update(exchange_account_id, &drifts, &expected_balances);
+
+        let confirmed_drifts_message = confirmed_drifts
+            .iter()
+            .map(|currency_code| {
+                format!(
+                    "{} exchange {} expected {}",
+                    currency_code,
+                    exchange_balances
+                        .get(currency_code)
+                        .copied()
+                        .unwrap_or_default(),
+                    expected_balances
+                        .get(currency_code)
+                        .copied()
+                        .unwrap_or_default()
+                )
+            })
+            .join(", ");
+
+        Ok(BalanceCheck {
+            drifts,
+            confirmed_drifts,
+            confirmed_drifts_message,
+        })
+    }
+
+    /// Returns currencies with drift exceeded on `confirmations_count` checks in a row
+    fn update(
+        &mut self,
+        exchange_account_id: ExchangeAccountId,
+        drifts: &HashMap<CurrencyCode, Amount>,
+        expected_balances: &HashMap<CurrencyCode, Amount>,
+    ) -> Vec<CurrencyCode> {
+        let mut confirmed_drifts = Vec::new();
+        for (&currency_code, drift) in drifts {
+            let expected_balance = expected_balances
+                .get(&currency_code)
+                .copied()
+                .unwrap_or_default();
+            let key = (exchange_account_id, currency_code);
+
+            if drift.abs() <= self.max_drift(currency_code, expected_balance) {
+                let _ = self.exceeded_checks_in_row.remove(&key);
+                continue;
+            }
+
+            let exceeded_checks = self.exceeded_checks_in_row.entry(key).or_default();
+            *exceeded_checks += 1;
+            if *exceeded_checks >= self.settings.confirmations_count {
+                confirmed_drifts.push(currency_code);
+            }
+        }
+
+        confirmed_drifts.sort_by_key(|x| x.as_str().to_owned());
+        confirmed_drifts
+    }
+}
+
+/// Periodically compares balances received from exchanges with balances expected by `BalanceManager`,
+/// so missed fills and balance changes made outside of the engine are found
+pub struct BalanceReconciliationService {
+    settings: BalanceReconciliationSettings,
+    engine_context: Arc<EngineContext>,
+    drift_tracker: Mutex<DriftTracker>,
+}
+
+impl BalanceReconciliationService {
+    pub fn start(
+        settings: BalanceReconciliationSettings,
+        engine_context: Arc<EngineContext>,
+    ) -> Arc<Self> {
+        let service = Arc::new(Self {
+            drift_tracker: Mutex::new(DriftTracker::new(settings.clone())),
+            settings,
+            engine_context,
+        });
+
+        let cancellation_token = service.engine_context.application_manager.stop_token();
+        let action = service.clone().run(cancellation_token);
+        let _ = spawn_future(BALANCE_RECONCILIATION, true, action.boxed());
+
+        service
+    }
+
+    async fn run(self: Arc<Self>, cancellation_token: CancellationToken) -> Result<()> {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.settings.period_secs));
+        // balances are just received on engine start, so the first check is postponed
+        let _ = interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.reconcile(cancellation_token.clone()).await,
+                _ = cancellation_token.when_cancelled() => return Ok(()),
+            }
+        }
+    }
+
+    async fn reconcile(&self, cancellation_token: CancellationToken) {
+        let exchanges = self
+            .engine_context
+            .exchanges
+            .iter()
+            .map(|x| x.value().clone())
+            .collect_vec();
+
+        for exchange in exchanges {
+            self.reconcile_exchange(&exchange, cancellation_token.clone())
+                .await;
+        }
+    }
+
+    async fn reconcile_exchange(&self, exchange: &Exchange, cancellation_token: CancellationToken) {
+        let exchange_account_id = exchange.exchange_account_id;
+        let statistic_service = &self.engine_context.statistic_service;
+
+        let mut skipped_currencies = get_currencies_in_flight(exchange);
+
+        // Balances are requested without `BalanceUpdate` event, otherwise `BalanceManager` would
+        // overwrite its balances by them and drift would disappear before it's confirmed
+        let balances_and_positions = match exchange
+            .get_balance_and_positions(cancellation_token)
+            .await
+        {
+            Ok(balances_and_positions) => balances_and_positions,
+            Err(error) => {
+                log::warn!(
+                    "Balance reconciliation for {} is skipped because balances weren't received: {:?}",
+                    exchange_account_id,
+                    error
+                );
+                return;
+            }
+        };
+
+        // orders could be created or cancelled while balances were requested
+        skipped_currencies.extend(get_currencies_in_flight(exchange));
+        if !skipped_currencies.is_empty() {
+            log::info!(
+                "Balance reconciliation for {} skips {} because there are orders in creating or cancelling state",
+                exchange_account_id,
+                skipped_currencies.iter().join(", ")
+            );
+            statistic_service.register_skipped_balance_reconciliation(exchange_account_id);
+        }
+
+        let exchange_balances = balances_and_positions
+            .balances
+            .iter()
+            .map(|x| (x.currency_code, x.balance))
+            .collect();
+        let balance_check = match self.drift_tracker.lock().check_balances(
+            exchange_account_id,
+            &exchange_balances,
+            &self.engine_context.balance_manager.lock(),
+            &skipped_currencies,
+        ) {
+            Ok(balance_check) => balance_check,
+            Err(error) => {
+                log::error!(
+                    "Unable to check balances for {}: {:?}",
+                    exchange_account_id,
+                    error
+                );
+                return;
+            }
+        };
+
+        if balance_check.confirmed_drifts.is_empty() {
+            log::info!("Balances on {} are reconciled", exchange_account_id);
+        }
+
+        statistic_service.register_balance_reconciliation(
+            exchange_account_id,
+            balance_check.drifts,
+            !balance_check.confirmed_drifts.is_empty(),
+        );
+
+        self.handle_confirmed_drifts(exchange_account_id, balance_check.confirmed_drifts_message);
+    }
+
+    fn handle_confirmed_drifts(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        confirmed_drifts_message: String,
+    ) {
+        let exchange_blocker = &self.engine_context.exchange_blocker;
+        let is_blocked = exchange_blocker
+            .is_blocked_by_reason(exchange_account_id, block_reasons::BALANCE_DRIFT);
+
+        if confirmed_drifts_message.is_empty() {
+            if is_blocked {
+                exchange_blocker.unblock(exchange_account_id, block_reasons::BALANCE_DRIFT);
+            }
+            return;
+        }
+
+        let _ = self.engine_context.alert_bus.publish(
+            AlertSeverity::Critical,
+            BALANCE_RECONCILIATION,
+            format!(
+                "Balances on {} drifted: {}",
+                exchange_account_id, confirmed_drifts_message
+            ),
+        );
+
+        if self.settings.block_exchange && !is_blocked {
+            exchange_blocker.block(
+                exchange_account_id,
+                block_reasons::BALANCE_DRIFT,
+                BlockType::Manual,
+            );
+        }
+    }
+}
+
+/// Currencies of orders which are creating or cancelling. Their balances can be already changed on exchange,
+/// but not in the engine
+fn get_currencies_in_flight(exchange: &Exchange) -> HashSet<CurrencyCode> {
+    exchange
+        .orders
+        .not_finished
+        .iter()
+        .filter(|order| {
+            matches!(
+                order.status(),
+                OrderStatus::Creating | OrderStatus::Canceling
+            )
+        })
+        .filter_map(|order| exchange.symbols.get(&order.currency_pair()))
+        .flat_map(|symbol| {
+            [
+                Some(symbol.base_currency_code()),
+                Some(symbol.quote_currency_code()),
+                symbol.balance_currency_code,
+            ]
+        })
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use mmb_utils::hashmap;
+
+    use super::*;
+
+    fn settings() -> BalanceReconciliationSettings {
+        BalanceReconciliationSettings {
+            period_secs: 60,
+            max_drifts: hashmap!["btc".into() => dec!(0.001)],
+            max_drift_ratio: dec!(0.01),
+            confirmations_count: 2,
+            block_exchange: false,
+        }
+    }
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance".into(), 0)
+    }
+
+    #[test]
+    fn drifts_for_all_currencies() {
+        let exchange_balances = hashmap!["btc".into() => dec!(1), "usdt".into() => dec!(100)];
+        let expected_balances = hashmap!["btc".into() => dec!(1.5), "eth".into() => dec!(2)];
+
+        let drifts = calculate_drifts(&exchange_balances, &expected_balances, &HashSet::new());
+
+        assert_eq!(
+            drifts,
+            hashmap![
+                "btc".into() => dec!(-0.5),
+                "usdt".into() => dec!(100),
+                "eth".into() => dec!(-2)
+            ]
+        );
+    }
+
+    #[test]
+    fn drifts_skip_currencies_in_flight() {
+        let exchange_balances = hashmap!["btc".into() => dec!(1), "usdt".into() => dec!(100)];
+        let expected_balances = hashmap!["btc".into() => dec!(1.5), "eth".into() => dec!(2)];
+        let skipped_currencies = HashSet::from(["btc".into(), "usdt".into()]);
+
+        let drifts = calculate_drifts(&exchange_balances, &expected_balances, &skipped_currencies);
+
+        assert_eq!(drifts, hashmap!["eth".into() => dec!(-2)]);
+    }
+
+    #[test]
+    fn drift_is_confirmed_after_checks_in_row() {
+        let mut drift_tracker = DriftTracker::new(settings());
+        let expected_balances = hashmap!["btc".into() => dec!(1), "usdt".into() => dec!(1000)];
+        let drifts = hashmap!["btc".into() => dec!(0.01), "usdt".into() => dec!(5)];
+
+        let confirmed = drift_tracker.update(exchange_account_id(), &drifts, &expected_balances);
+        assert!(confirmed.is_empty());
+
+        // usdt drift 5 doesn't exceed 1% of expected balance
+        let confirmed = drift_tracker.update(exchange_account_id(), &drifts, &expected_balances);
+        assert_eq!(confirmed, vec!["btc".into()]);
+    }
+
+    #[test]
+    fn drift_in_flight_is_not_confirmed() {
+        let mut drift_tracker = DriftTracker::new(settings());
+        let expected_balances = hashmap!["btc".into() => dec!(1)];
+
+        let confirmed = drift_tracker.update(
+            exchange_account_id(),
+            &hashmap!["btc".into() => dec!(0.01)],
+            &expected_balances,
+        );
+        assert!(confirmed.is_empty());
+
+        let confirmed = drift_tracker.update(
+            exchange_account_id(),
+            &hashmap!["btc".into() => dec!(0)],
+            &expected_balances,
+        );
+        assert!(confirmed.is_empty());
+
+        let confirmed = drift_tracker.update(
+            exchange_account_id(),
+            &hashmap!["btc".into() => dec!(0.01)],
+            &expected_balances,
+        );
+        assert!(confirmed.is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 pub(crate) mod approved_part;
 pub mod balance_manager;
+pub mod balance_reconciliation;
 pub(crate) mod balance_position_by_fill_amount;
 pub mod balance_request;
 pub(crate) mod balance_reservation;
@@ -144,7 +144,7 @@ impl BalanceManagerOrdinal {
 }
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::Arc;
     use std::time::Duration;
 
@@ -157,6 +157,9 @@ mod tests {
     use rust_decimal_macros::dec;
 
     use crate::balance_manager::balance_manager::BalanceManager;
+    use crate::balance_manager::balance_reconciliation::{
+        BalanceReconciliationSettings, DriftTracker,
+    };
     use crate::balance_manager::position_change::PositionChange;
     use crate::exchanges::common::{Amount, CurrencyCode, Price, TradePlaceAccount};
     use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
@@ -610,6 +613,187 @@ mod tests {
         assert!(reservation.approved_parts.is_empty());
     }
 
+    #[test]
+    pub fn expected_exchange_balances_change_only_after_reservation_approval() {
+        init_logger();
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::btc(), dec!(1));
+        let exchange_account_id = test_object.balance_manager_base.exchange_account_id_1;
+
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Buy,
+            dec!(0.2),
+            dec!(5),
+        );
+        let reservation_id = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters, &mut None)
+            .expect("in test");
+
+        // reserved amount is still free on the exchange while order isn't created
+        let expected_balances = test_object
+            .balance_manager()
+            .get_expected_exchange_balances(exchange_account_id)
+            .expect("in test");
+        assert_eq!(expected_balances[&BalanceManagerBase::btc()], dec!(1));
+
+        let order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Buy, reservation_id);
+        test_object.balance_manager().approve_reservation(
+            reservation_id,
+            &order.header.client_order_id,
+            dec!(5),
+        );
+
+        let expected_balances = test_object
+            .balance_manager()
+            .get_expected_exchange_balances(exchange_account_id)
+            .expect("in test");
+        assert_eq!(expected_balances[&BalanceManagerBase::btc()], dec!(0));
+    }
+
+    #[test]
+    pub fn restored_order_reservation_is_approved_and_released_when_order_is_finished() {
+        init_logger();
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::btc(), dec!(1));
+        let configuration_descriptor = test_object.balance_manager_base.configuration_descriptor;
+
+        let reservation_id = ReservationId::generate();
+        let order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Buy, reservation_id);
+        test_object
+            .balance_manager()
+            .restore_order_reservation(configuration_descriptor, &order)
+            .expect("in test");
+
+        {
+            let balance_manager = test_object.balance_manager();
+            let reservation = balance_manager.get_reservation_expected(reservation_id);
+            assert_eq!(reservation.amount, dec!(5));
+            assert_eq!(reservation.not_approved_amount, dec!(0));
+            assert_eq!(
+                reservation.approved_parts[&order.header.client_order_id].amount,
+                dec!(5)
+            );
+        }
+
+        test_object
+            .balance_manager()
+            .release_restored_order_reservation(reservation_id, &order.header.client_order_id)
+            .expect("in test");
+
+        assert!(test_object
+            .balance_manager()
+            .get_reservation(reservation_id)
+            .is_none());
+    }
+
+    #[test]
+    pub fn new_reservation_id_does_not_collide_with_restored_one() {
+        init_logger();
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::btc(), dec!(2));
+        let configuration_descriptor = test_object.balance_manager_base.configuration_descriptor;
+
+        // id journaled before restart can be ahead of generator reseeded by current time
+        let last_generated_id: u64 = ReservationId::generate()
+            .to_string()
+            .parse()
+            .expect("in test");
+        let restored_reservation_id: ReservationId =
+            serde_json::from_value(serde_json::json!(last_generated_id + 1)).expect("in test");
+        let order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Buy, restored_reservation_id);
+        test_object
+            .balance_manager()
+            .restore_order_reservation(configuration_descriptor, &order)
+            .expect("in test");
+
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Buy,
+            dec!(0.2),
+            dec!(5),
+        );
+        let reservation_id = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters, &mut None)
+            .expect("in test");
+
+        assert_ne!(reservation_id, restored_reservation_id);
+        let balance_manager = test_object.balance_manager();
+        let restored_reservation =
+            balance_manager.get_reservation_expected(restored_reservation_id);
+        assert_eq!(
+            restored_reservation.approved_parts[&order.header.client_order_id].amount,
+            dec!(5)
+        );
+    }
+
+    #[test]
+    pub fn balance_drift_of_missed_fill_is_confirmed_on_second_check() {
+        init_logger();
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::btc(), dec!(1));
+        let exchange_account_id = test_object.balance_manager_base.exchange_account_id_1;
+
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Buy,
+            dec!(0.2),
+            dec!(5),
+        );
+        let reservation_id = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters, &mut None)
+            .expect("in test");
+        let order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Buy, reservation_id);
+        test_object.balance_manager().approve_reservation(
+            reservation_id,
+            &order.header.client_order_id,
+            dec!(5),
+        );
+
+        // order is filled on exchange, but the fill isn't received by the engine
+        let exchange_balances =
+            hashmap![BalanceManagerBase::btc() => dec!(0), BalanceManagerBase::eth() => dec!(5)];
+        let mut drift_tracker = DriftTracker::new(BalanceReconciliationSettings {
+            period_secs: 60,
+            max_drifts: HashMap::new(),
+            max_drift_ratio: dec!(0.01),
+            confirmations_count: 2,
+            block_exchange: false,
+        });
+
+        let first_check = drift_tracker
+            .check_balances(
+                exchange_account_id,
+                &exchange_balances,
+                &test_object.balance_manager(),
+                &HashSet::new(),
+            )
+            .expect("in test");
+        assert_eq!(first_check.drifts[&BalanceManagerBase::eth()], dec!(5));
+        assert!(first_check.confirmed_drifts.is_empty());
+
+        let second_check = drift_tracker
+            .check_balances(
+                exchange_account_id,
+                &exchange_balances,
+                &test_object.balance_manager(),
+                &HashSet::new(),
+            )
+            .expect("in test");
+        assert_eq!(
+            second_check.confirmed_drifts,
+            vec![BalanceManagerBase::eth()]
+        );
+        assert_eq!(
+            second_check.confirmed_drifts_message,
+            "eth exchange 5 expected 0"
+        );
+    }
+
     #[test]
     pub fn try_reserve_sell_not_enough_balance() {
         init_logger();
@@ -2203,6 +2387,110 @@ mod tests {
         );
     }
 
+    fn prepare_order_replacement(
+        test_object: &mut BalanceManagerOrdinal,
+        new_amount: Amount,
+    ) -> (
+        ReserveParameters,
+        ReservationId,
+        OrderSnapshot,
+        OrderSnapshot,
+    ) {
+        let reserve_parameters = test_object.balance_manager_base.create_reserve_parameters(
+            OrderSide::Sell,
+            dec!(0.2),
+            dec!(5),
+        );
+        let reservation_id_1 = test_object
+            .balance_manager()
+            .try_reserve(&reserve_parameters, &mut None)
+            .expect("in test");
+        let replaced_order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Sell, reservation_id_1);
+        test_object.balance_manager().approve_reservation(
+            reservation_id_1,
+            &replaced_order.header.client_order_id,
+            dec!(5),
+        );
+
+        let reservation_id_2 = test_object
+            .balance_manager()
+            .try_reserve(
+                &test_object.balance_manager_base.create_reserve_parameters(
+                    OrderSide::Sell,
+                    dec!(0.2),
+                    dec!(0),
+                ),
+                &mut None,
+            )
+            .expect("in test");
+        let new_order = test_object.balance_manager_base.create_order_by_amount(
+            OrderSide::Sell,
+            new_amount,
+            reservation_id_2,
+        );
+
+        (
+            reserve_parameters,
+            reservation_id_1,
+            replaced_order,
+            new_order,
+        )
+    }
+
+    #[rstest]
+    #[case::same_amount(dec!(5), dec!(0))]
+    #[case::greater_amount(dec!(7), dec!(0))]
+    #[case::less_amount(dec!(3), dec!(0))]
+    #[case::partially_filled(dec!(5), dec!(2))]
+    pub fn transfer_order_reservation(#[case] new_amount: Amount, #[case] filled_amount: Amount) {
+        init_logger();
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::eth(), dec!(10));
+
+        let (reserve_parameters, reservation_id_1, mut replaced_order, new_order) =
+            prepare_order_replacement(&mut test_object, new_amount);
+        replaced_order.fills.filled_amount = filled_amount;
+
+        let mut balance_manager = test_object.balance_manager();
+        assert!(balance_manager.try_transfer_order_reservation(&replaced_order, &new_order.header));
+
+        assert!(balance_manager.get_reservation(reservation_id_1).is_none());
+
+        let reservation = balance_manager
+            .get_reservation_expected(new_order.header.reservation_id.expect("in test"));
+        assert_eq!(reservation.not_approved_amount, new_amount);
+        assert_eq!(reservation.unreserved_amount, new_amount);
+        assert!(reservation.approved_parts.is_empty());
+
+        assert_eq!(
+            balance_manager.get_balance_by_reserve_parameters(&reserve_parameters),
+            Some(dec!(10) - new_amount)
+        );
+    }
+
+    #[test]
+    pub fn transfer_order_reservation_not_enough_balance() {
+        init_logger();
+        let mut test_object = create_test_obj_by_currency_code(BalanceManagerBase::eth(), dec!(10));
+
+        let (reserve_parameters, reservation_id_1, replaced_order, new_order) =
+            prepare_order_replacement(&mut test_object, dec!(12));
+
+        let mut balance_manager = test_object.balance_manager();
+        assert!(!balance_manager.try_transfer_order_reservation(&replaced_order, &new_order.header));
+
+        // replaced order amount stays reserved for the new order
+        assert!(balance_manager.get_reservation(reservation_id_1).is_none());
+        let reservation = balance_manager
+            .get_reservation_expected(new_order.header.reservation_id.expect("in test"));
+        assert_eq!(reservation.not_approved_amount, dec!(5));
+        assert_eq!(
+            balance_manager.get_balance_by_reserve_parameters(&reserve_parameters),
+            Some(dec!(5))
+        );
+    }
+
     #[test]
     pub fn transfer_reservations_amount_partial_approve_with_multiple_orders() {
         init_logger();
@@ -2767,6 +3055,64 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn get_balance_with_reserved_amounts_includes_fills_and_reservations() {
+        init_logger();
+        let mut test_object = create_test_obj_with_multiple_currencies(
+            vec![
+                BalanceManagerBase::btc(),
+                BalanceManagerBase::eth(),
+                BalanceManagerBase::bnb(),
+            ],
+            vec![dec!(2), dec!(5), dec!(0.2)],
+        );
+
+        let price = dec!(0.2);
+        for (side, amount) in [(OrderSide::Sell, dec!(1)), (OrderSide::Buy, dec!(2))] {
+            let reserve_parameters = test_object
+                .balance_manager_base
+                .create_reserve_parameters(side, price, amount);
+            let _ = test_object
+                .balance_manager()
+                .try_reserve(&reserve_parameters, &mut None)
+                .expect("in test");
+        }
+
+        let mut order = test_object
+            .balance_manager_base
+            .create_order(OrderSide::Buy, ReservationId::generate());
+        order.add_fill(BalanceManagerOrdinal::create_order_fill(
+            price,
+            dec!(5),
+            dec!(2.5),
+        ));
+        let configuration_descriptor = test_object
+            .balance_manager_base
+            .configuration_descriptor
+            .clone();
+        test_object
+            .balance_manager()
+            .order_was_filled(configuration_descriptor, &order);
+
+        let get_balance = |currency_code| {
+            test_object
+                .balance_manager()
+                .get_balance_with_reserved_amounts(
+                    configuration_descriptor,
+                    test_object.balance_manager_base.exchange_account_id_1,
+                    test_object.balance_manager_base.symbol(),
+                    currency_code,
+                    price,
+                )
+                .expect("in test")
+        };
+        assert_eq!(get_balance(BalanceManagerBase::eth()), dec!(5) + dec!(5));
+        assert_eq!(
+            get_balance(BalanceManagerBase::btc()),
+            dec!(2) - price * dec!(5)
+        );
+    }
+
     #[test]
     pub fn order_was_filled_last_fill_by_default_sell() {
         init_logger();
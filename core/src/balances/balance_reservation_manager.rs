@@ -1526,6 +1526,44 @@ impl BalanceReservationManager {
         Some(self.reservation_id)
     }
 
+    /// Registers approved reservation of the order restored after restart under its original id.
+    /// Balance isn't changed because unfilled amount of the order is already locked on the exchange
+    pub fn restore_approved_reservation(
+        &mut self,
+        reservation_id: ReservationId,
+        reserve_parameters: &ReserveParameters,
+        client_order_id: &ClientOrderId,
+    ) -> Result<()> {
+        if self.get_reservation(reservation_id).is_some() {
+            bail!("Reservation {} is already registered", reservation_id);
+        }
+        reservation_id.mark_as_used();
+
+        let preset = self.get_currency_code_and_reservation_amount(reserve_parameters, &mut None);
+        let request = BalanceRequest::new(
+            reserve_parameters.configuration_descriptor.clone(),
+            reserve_parameters.exchange_account_id,
+            reserve_parameters.symbol.currency_pair(),
+            preset.reservation_currency_code,
+        );
+        let reservation = BalanceReservation::new(
+            reserve_parameters.configuration_descriptor.clone(),
+            reserve_parameters.exchange_account_id,
+            reserve_parameters.symbol.clone(),
+            reserve_parameters.order_side,
+            reserve_parameters.price,
+            reserve_parameters.amount,
+            preset.taken_free_amount_in_amount_currency_code,
+            preset.cost_in_amount_currency_code,
+            preset.reservation_currency_code,
+        );
+
+        self.balance_reservation_storage
+            .add(reservation_id, reservation);
+        self.add_reserved_amount(&request, reservation_id, reserve_parameters.amount, false)?;
+        self.approve_reservation(reservation_id, client_order_id, reserve_parameters.amount)
+    }
+
     fn can_reserve_core(
         &self,
         reserve_parameters: &ReserveParameters,
@@ -63,6 +63,23 @@ impl VirtualBalanceHolder {
         }
     }
 
+    /// Sum of balance diffs by currency since the last balance update for the exchange
+    pub fn get_balance_diffs(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> HashMap<CurrencyCode, Amount> {
+        let mut balance_diffs = HashMap::new();
+        for (balance_request, diff) in self.balance_diff.get_as_balances() {
+            if balance_request.exchange_account_id == exchange_account_id {
+                *balance_diffs
+                    .entry(balance_request.currency_code)
+                    .or_default() += diff;
+            }
+        }
+
+        balance_diffs
+    }
+
     pub fn add_balance(&mut self, balance_request: &BalanceRequest, balance_to_add: Amount) {
         let current_diff_value = self
             .balance_diff
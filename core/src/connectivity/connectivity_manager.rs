@@ -10,17 +10,21 @@ use futures::Future;
 use log::log;
 use mmb_utils::{cancellation_token::CancellationToken, send_expected::SendExpectedByRef};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 use std::{
     borrow::Borrow,
     ops::DerefMut,
-    sync::{Arc, Weak},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Weak,
+    },
 };
 use tokio::sync::broadcast;
 
 pub const MAX_RETRY_CONNECT_COUNT: u32 = 3;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum WebSocketRole {
     Main,
     Secondary,
@@ -67,6 +71,21 @@ impl WebSockets {
     }
 }
 
+#[derive(Default)]
+struct ConnectionAttempts {
+    main: AtomicU64,
+    secondary: AtomicU64,
+}
+
+impl ConnectionAttempts {
+    fn get(&self, role: WebSocketRole) -> &AtomicU64 {
+        match role {
+            WebSocketRole::Main => &self.main,
+            WebSocketRole::Secondary => &self.secondary,
+        }
+    }
+}
+
 // TODO Find more clear names in the future
 type Callback0 = Box<dyn Fn() + Send>;
 type Callback1<T, U> = Box<dyn Fn(T) -> U + Send>;
@@ -81,6 +100,7 @@ pub struct ConnectivityManager {
     exchange_account_id: ExchangeAccountId,
     callback_get_ws_params: Mutex<GetWSParamsCallback>,
     websockets: WebSockets,
+    connection_attempts: ConnectionAttempts,
 
     callback_connecting: Mutex<Callback0>,
     callback_connected: Mutex<Callback0>,
@@ -96,6 +116,7 @@ impl ConnectivityManager {
                 main: tokio::sync::Mutex::new(WebSocketConnectivity::new()),
                 secondary: tokio::sync::Mutex::new(WebSocketConnectivity::new()),
             },
+            connection_attempts: Default::default(),
 
             callback_connecting: Mutex::new(Box::new(|| {})),
             callback_connected: Mutex::new(Box::new(|| {})),
@@ -126,6 +147,14 @@ impl ConnectivityManager {
         *self.callback_msg_received.lock() = msg_received;
     }
 
+    /// Count of attempts to open websocket connection after the first one
+    pub fn reconnects_count(&self, role: WebSocketRole) -> u64 {
+        self.connection_attempts
+            .get(role)
+            .load(Ordering::Relaxed)
+            .saturating_sub(1)
+    }
+
     fn set_callback_ws_params(&self, get_websocket_params: GetWSParamsCallback) {
         *self.callback_get_ws_params.lock() = get_websocket_params;
     }
@@ -283,6 +312,10 @@ impl ConnectivityManager {
                     }
 
                     let notifier = ConnectivityManagerNotifier::new(role, Arc::downgrade(self));
+                    let _ = self
+                        .connection_attempts
+                        .get(role)
+                        .fetch_add(1, Ordering::Relaxed);
 
                     let websocket = WebSocketConnection::open_connection(
                         self.exchange_account_id,
@@ -1,2 +1,3 @@
 pub mod connectivity_manager;
+pub mod traffic_fixtures;
 pub mod websocket_connection;
@@ -0,0 +1,587 @@
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use futures::{FutureExt, SinkExt, StreamExt};
+use hyper::{StatusCode, Uri};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+
+use crate::connectivity::connectivity_manager::WebSocketRole;
+use crate::exchanges::common::RestRequestOutcome;
+use crate::exchanges::rest_client::HttpParams;
+use crate::infrastructure::spawn_future;
+
+/// Parameters which differ from request to request (authentication data, timestamps)
+/// so they aren't saved to fixtures and aren't used for requests matching
+pub const DEFAULT_IGNORED_PARAMS: &[&str] = &[
+    "timestamp",
+    "signature",
+    "sign",
+    "recvWindow",
+    "api_key",
+    "expires",
+];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TrafficMode {
+    /// Real requests are sent and traffic is saved to fixture file
+    Record,
+    /// Responses are taken from fixture file without network access
+    Replay,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecordedHttpRequest {
+    pub method: String,
+    pub path: String,
+    /// Query and body parameters sorted by name
+    pub params: HttpParams,
+    /// Parameters generated for each run (e.g. client order ids) which aren't used for matching
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generated_params: HttpParams,
+}
+
+impl RecordedHttpRequest {
+    fn is_matched(&self, other: &RecordedHttpRequest) -> bool {
+        self.method == other.method && self.path == other.path && self.params == other.params
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecordedHttpExchange {
+    pub request: RecordedHttpRequest,
+    pub status: u16,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecordedWebSocketMessage {
+    /// Count of HTTP requests sent before the message was received.
+    /// On replay the message is sent only after the same count of requests
+    pub after_http_requests: usize,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecordedWebSocketSession {
+    pub role: WebSocketRole,
+    pub path: String,
+    pub sent: Vec<String>,
+    pub received: Vec<RecordedWebSocketMessage>,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TrafficFixture {
+    pub http: Vec<RecordedHttpExchange>,
+    pub websockets: Vec<RecordedWebSocketSession>,
+}
+
+#[derive(Default)]
+struct ReplayState {
+    consumed_http: Vec<bool>,
+    next_websocket_by_role: HashMap<WebSocketRole, usize>,
+}
+
+/// Recorded values of generated parameters mapped to the actual ones
+type Aliases = Arc<Mutex<HashMap<String, String>>>;
+
+/// Fixture file with HTTP and WebSocket traffic of exchange connector.
+/// In `Record` mode traffic passed through `RestClient` and `WebSocketConnection` is saved to file on drop,
+/// in `Replay` mode HTTP requests are answered from the file by method, path and parameters
+/// and WebSocket connections are redirected to local server which sends recorded messages.
+pub struct TrafficFixtures {
+    mode: TrafficMode,
+    path: PathBuf,
+    ignored_params: Vec<String>,
+    generated_params: Vec<String>,
+    fixture: Mutex<TrafficFixture>,
+    replay_state: Mutex<ReplayState>,
+    aliases: Aliases,
+    // Count of sent (recorded) or replayed HTTP requests
+    http_requests_count: watch::Sender<usize>,
+}
+
+impl TrafficFixtures {
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self::new(TrafficMode::Record, path.into(), TrafficFixture::default())
+    }
+
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read traffic fixture {}", path.display()))?;
+        let fixture: TrafficFixture = serde_json::from_str(&content)
+            .with_context(|| format!("Unable to parse traffic fixture {}", path.display()))?;
+
+        Ok(Self::new(TrafficMode::Replay, path, fixture))
+    }
+
+    fn new(mode: TrafficMode, path: PathBuf, fixture: TrafficFixture) -> Self {
+        let replay_state = ReplayState {
+            consumed_http: vec![false; fixture.http.len()],
+            ..Default::default()
+        };
+        let (http_requests_count, _) = watch::channel(0);
+
+        Self {
+            mode,
+            path,
+            ignored_params: DEFAULT_IGNORED_PARAMS
+                .iter()
+                .map(|param| param.to_string())
+                .collect(),
+            generated_params: Vec::new(),
+            fixture: Mutex::new(fixture),
+            replay_state: Mutex::new(replay_state),
+            aliases: Default::default(),
+            http_requests_count,
+        }
+    }
+
+    /// Parameters which are generated randomly for each run, e.g. client order ids.
+    /// They aren't used for matching and their recorded values are replaced
+    /// with the actual ones in replayed responses and websocket messages
+    pub fn with_generated_params(mut self, params: &[&str]) -> Self {
+        self.generated_params
+            .extend(params.iter().map(|param| param.to_string()));
+        self
+    }
+
+    pub fn mode(&self) -> TrafficMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)
+                .with_context(|| format!("Unable to create directory {}", directory.display()))?;
+        }
+
+        let content = serde_json::to_string_pretty(&*self.fixture.lock())
+            .context("Unable to serialize traffic fixture")?;
+        fs::write(&self.path, content)
+            .with_context(|| format!("Unable to write traffic fixture {}", self.path.display()))
+    }
+
+    pub(crate) fn make_http_request(
+        &self,
+        method: &str,
+        uri: &Uri,
+        body_params: &HttpParams,
+    ) -> RecordedHttpRequest {
+        let query_params = uri
+            .query()
+            .map(|query| {
+                form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect::<HttpParams>()
+            })
+            .unwrap_or_default();
+
+        let (mut generated_params, mut params): (HttpParams, HttpParams) = query_params
+            .into_iter()
+            .chain(body_params.iter().cloned())
+            .filter(|(key, _)| !self.ignored_params.contains(key))
+            .partition(|(key, _)| self.generated_params.contains(key));
+        params.sort();
+        generated_params.sort();
+
+        RecordedHttpRequest {
+            method: method.to_owned(),
+            path: uri.path().to_owned(),
+            params,
+            generated_params,
+        }
+    }
+
+    pub(crate) fn notify_http_request_sent(&self) {
+        // Fixture lock prevents lost increments from concurrent requests
+        let _fixture = self.fixture.lock();
+        let count = *self.http_requests_count.borrow();
+        let _ = self.http_requests_count.send_replace(count + 1);
+    }
+
+    pub(crate) fn record_http(&self, request: RecordedHttpRequest, outcome: &RestRequestOutcome) {
+        self.fixture.lock().http.push(RecordedHttpExchange {
+            request,
+            status: outcome.status.as_u16(),
+            content: outcome.content.clone(),
+        });
+    }
+
+    /// Recorded responses are returned in recording order for equal requests.
+    /// When all of them are consumed the last one is repeated, so polling requests keep working
+    pub(crate) fn replay_http(&self, request: &RecordedHttpRequest) -> Result<RestRequestOutcome> {
+        let outcome = {
+            let fixture = self.fixture.lock();
+            let mut replay_state = self.replay_state.lock();
+
+            let matched_indexes = fixture
+                .http
+                .iter()
+                .enumerate()
+                .filter(|(_, recorded)| recorded.request.is_matched(request))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+
+            let last_matched = match matched_indexes.last() {
+                Some(last_matched) => *last_matched,
+                None => bail!(
+                    "There is no recorded response for {} {} {:?} in {}",
+                    request.method,
+                    request.path,
+                    request.params,
+                    self.path.display()
+                ),
+            };
+
+            let index = matched_indexes
+                .into_iter()
+                .find(|index| !replay_state.consumed_http[*index])
+                .unwrap_or(last_matched);
+            replay_state.consumed_http[index] = true;
+
+            let recorded = &fixture.http[index];
+            self.add_aliases(
+                &recorded.request.generated_params,
+                &request.generated_params,
+            );
+
+            let status = StatusCode::from_u16(recorded.status)
+                .with_context(|| format!("Wrong recorded status code {}", recorded.status))?;
+
+            RestRequestOutcome::new(substitute_aliases(&self.aliases, &recorded.content), status)
+        };
+
+        self.notify_http_request_sent();
+
+        Ok(outcome)
+    }
+
+    fn add_aliases(&self, recorded_params: &HttpParams, actual_params: &HttpParams) {
+        let mut aliases = self.aliases.lock();
+        for (key, recorded_value) in recorded_params {
+            let actual_value = actual_params
+                .iter()
+                .find(|(actual_key, _)| actual_key == key)
+                .map(|(_, actual_value)| actual_value);
+
+            if let Some(actual_value) = actual_value {
+                if actual_value != recorded_value {
+                    let _ = aliases.insert(recorded_value.clone(), actual_value.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns index of session which is used for recording messages
+    pub(crate) fn start_websocket_session(&self, role: WebSocketRole, url: &Url) -> usize {
+        let mut fixture = self.fixture.lock();
+        fixture.websockets.push(RecordedWebSocketSession {
+            role,
+            path: url.path().to_owned(),
+            sent: Vec::new(),
+            received: Vec::new(),
+        });
+
+        fixture.websockets.len() - 1
+    }
+
+    pub(crate) fn record_websocket_sent(&self, session_index: usize, message: &str) {
+        self.fixture.lock().websockets[session_index]
+            .sent
+            .push(message.to_owned());
+    }
+
+    pub(crate) fn record_websocket_received(&self, session_index: usize, message: &str) {
+        let after_http_requests = *self.http_requests_count.borrow();
+        self.fixture.lock().websockets[session_index]
+            .received
+            .push(RecordedWebSocketMessage {
+                after_http_requests,
+                content: message.to_owned(),
+            });
+    }
+
+    /// Starts local websocket server which sends messages of next recorded session for the role
+    /// and returns url for connection to it
+    pub(crate) async fn replay_websocket(&self, role: WebSocketRole, url: &Url) -> Result<Url> {
+        let messages = self.next_websocket_messages(role);
+        let aliases = self.aliases.clone();
+        let mut http_requests_count = self.http_requests_count.subscribe();
+
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("Unable to bind websocket replay server")?;
+        let address = listener
+            .local_addr()
+            .context("Unable to get address of websocket replay server")?;
+
+        let action = async move {
+            let (stream, _) = listener
+                .accept()
+                .await
+                .context("Unable to accept websocket replay connection")?;
+            let mut ws_stream = tokio_tungstenite::accept_async(stream)
+                .await
+                .context("Unable to accept websocket handshake")?;
+
+            let mut messages = messages.into_iter().peekable();
+            let mut is_replay_finished = false;
+            loop {
+                while let Some(message) = messages
+                    .next_if(|message| *http_requests_count.borrow() >= message.after_http_requests)
+                {
+                    let content = substitute_aliases(&aliases, &message.content);
+                    ws_stream
+                        .send(Message::Text(content))
+                        .await
+                        .context("Unable to send recorded websocket message")?;
+                }
+
+                // Reading is needed for answering on pings until client closes the connection
+                tokio::select! {
+                    changed = http_requests_count.changed(), if !is_replay_finished => {
+                        is_replay_finished = changed.is_err();
+                    }
+                    message = ws_stream.next() => {
+                        if !matches!(message, Some(Ok(_))) {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            Ok(())
+        };
+        let _ = spawn_future("Replay recorded websocket messages", false, action.boxed());
+
+        let mut replay_url = Url::parse(&format!("ws://{}", address))
+            .context("Unable to build websocket replay url")?;
+        replay_url.set_path(url.path());
+        replay_url.set_query(url.query());
+
+        Ok(replay_url)
+    }
+
+    fn next_websocket_messages(&self, role: WebSocketRole) -> Vec<RecordedWebSocketMessage> {
+        let fixture = self.fixture.lock();
+        let mut replay_state = self.replay_state.lock();
+
+        let next_index = replay_state.next_websocket_by_role.entry(role).or_insert(0);
+        let session = fixture
+            .websockets
+            .iter()
+            .filter(|session| session.role == role)
+            .nth(*next_index);
+        *next_index += 1;
+
+        match session {
+            Some(session) => session.received.clone(),
+            // Connection is kept opened without messages like a quiet stream
+            None => Vec::new(),
+        }
+    }
+}
+
+fn substitute_aliases(aliases: &Aliases, content: &str) -> String {
+    aliases
+        .lock()
+        .iter()
+        .fold(content.to_owned(), |content, (recorded, actual)| {
+            content.replace(recorded.as_str(), actual)
+        })
+}
+
+impl Debug for TrafficFixtures {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrafficFixtures")
+            .field("mode", &self.mode)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl Drop for TrafficFixtures {
+    fn drop(&mut self) {
+        if self.mode == TrafficMode::Record {
+            if let Err(error) = self.save() {
+                log::error!("Unable to save traffic fixture: {:?}", error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn uri(value: &str) -> Uri {
+        value.try_into().expect("in test")
+    }
+
+    fn fixtures_with(recorded: Vec<(&str, &str, &str)>) -> TrafficFixtures {
+        let fixtures = TrafficFixtures::new(
+            TrafficMode::Replay,
+            PathBuf::from("in_memory.json"),
+            TrafficFixture::default(),
+        )
+        .with_generated_params(&["newClientOrderId"]);
+
+        for (method, url, content) in recorded {
+            let request = fixtures.make_http_request(method, &uri(url), &HttpParams::new());
+            fixtures.record_http(
+                request,
+                &RestRequestOutcome::new(content.to_owned(), StatusCode::OK),
+            );
+        }
+        let http_len = fixtures.fixture.lock().http.len();
+        fixtures.replay_state.lock().consumed_http = vec![false; http_len];
+
+        fixtures
+    }
+
+    #[test]
+    fn params_are_sorted_and_split() {
+        let fixtures = fixtures_with(Vec::new());
+
+        let request = fixtures.make_http_request(
+            "POST",
+            &uri("https://host.com/api/order?symbol=CNDBTC&timestamp=1&signature=abc"),
+            &vec![
+                ("side".to_owned(), "BUY".to_owned()),
+                ("newClientOrderId".to_owned(), "random".to_owned()),
+            ],
+        );
+
+        assert_eq!(
+            request,
+            RecordedHttpRequest {
+                method: "POST".to_owned(),
+                path: "/api/order".to_owned(),
+                params: vec![
+                    ("side".to_owned(), "BUY".to_owned()),
+                    ("symbol".to_owned(), "CNDBTC".to_owned()),
+                ],
+                generated_params: vec![("newClientOrderId".to_owned(), "random".to_owned())],
+            }
+        );
+    }
+
+    #[test]
+    fn replay_by_method_path_and_params() {
+        let fixtures = fixtures_with(vec![
+            ("GET", "https://host.com/api/order?symbol=A", "first_a"),
+            ("GET", "https://host.com/api/order?symbol=B", "first_b"),
+            ("DELETE", "https://host.com/api/order?symbol=A", "deleted_a"),
+            ("GET", "https://host.com/api/order?symbol=A", "second_a"),
+        ]);
+
+        let replay = |method: &str, url: &str| {
+            let request = fixtures.make_http_request(method, &uri(url), &HttpParams::new());
+            fixtures.replay_http(&request).expect("in test").content
+        };
+
+        assert_eq!(
+            replay("GET", "https://host.com/api/order?symbol=B"),
+            "first_b"
+        );
+        assert_eq!(
+            replay("GET", "https://host.com/api/order?symbol=A"),
+            "first_a"
+        );
+        assert_eq!(
+            replay("DELETE", "https://host.com/api/order?symbol=A&timestamp=2"),
+            "deleted_a"
+        );
+        assert_eq!(
+            replay("GET", "https://host.com/api/order?symbol=A"),
+            "second_a"
+        );
+        // last recorded response is repeated when all of them are consumed
+        assert_eq!(
+            replay("GET", "https://host.com/api/order?symbol=A"),
+            "second_a"
+        );
+        assert_eq!(*fixtures.http_requests_count.borrow(), 5);
+    }
+
+    #[test]
+    fn replay_unknown_request() {
+        let fixtures = fixtures_with(vec![("GET", "https://host.com/api/order", "order")]);
+
+        let request = fixtures.make_http_request(
+            "GET",
+            &uri("https://host.com/api/time"),
+            &HttpParams::new(),
+        );
+
+        assert!(fixtures.replay_http(&request).is_err());
+    }
+
+    #[test]
+    fn generated_params_are_substituted() {
+        let fixtures = fixtures_with(vec![(
+            "POST",
+            "https://host.com/api/order?symbol=A&newClientOrderId=recorded_id",
+            r#"{"clientOrderId":"recorded_id"}"#,
+        )]);
+
+        let request = fixtures.make_http_request(
+            "POST",
+            &uri("https://host.com/api/order?symbol=A&newClientOrderId=actual_id"),
+            &HttpParams::new(),
+        );
+        let outcome = fixtures.replay_http(&request).expect("in test");
+
+        assert_eq!(outcome.content, r#"{"clientOrderId":"actual_id"}"#);
+        assert_eq!(
+            substitute_aliases(&fixtures.aliases, r#"{"c":"recorded_id"}"#),
+            r#"{"c":"actual_id"}"#
+        );
+    }
+
+    #[test]
+    fn websocket_sessions_are_replayed_by_role() {
+        let fixtures = fixtures_with(Vec::new());
+        let url = Url::parse("wss://host.com/ws").expect("in test");
+
+        let main = fixtures.start_websocket_session(WebSocketRole::Main, &url);
+        fixtures.record_websocket_received(main, "main_message");
+        let secondary = fixtures.start_websocket_session(WebSocketRole::Secondary, &url);
+        fixtures.record_websocket_sent(secondary, "subscribe");
+        fixtures.notify_http_request_sent();
+        fixtures.record_websocket_received(secondary, "secondary_message");
+
+        assert_eq!(
+            fixtures.next_websocket_messages(WebSocketRole::Secondary),
+            vec![RecordedWebSocketMessage {
+                after_http_requests: 1,
+                content: "secondary_message".to_owned()
+            }]
+        );
+        assert_eq!(
+            fixtures.next_websocket_messages(WebSocketRole::Main),
+            vec![RecordedWebSocketMessage {
+                after_http_requests: 0,
+                content: "main_message".to_owned()
+            }]
+        );
+        assert!(fixtures
+            .next_websocket_messages(WebSocketRole::Main)
+            .is_empty());
+    }
+}
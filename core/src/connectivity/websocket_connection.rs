@@ -1,4 +1,5 @@
 use crate::connectivity::connectivity_manager::{ConnectivityManagerNotifier, WebSocketRole};
+use crate::connectivity::traffic_fixtures::{TrafficFixtures, TrafficMode};
 use crate::exchanges::common::ExchangeAccountId;
 
 use crate::infrastructure::spawn_future;
@@ -25,11 +26,28 @@ const PING_MESSAGE: &'static [u8; 9] = b"heartbeat";
 #[derive(Debug, Clone)]
 pub struct WebSocketParams {
     url: Url,
+    // Messages which should be sent right after connection is opened, e.g. subscriptions to channels
+    subscription_messages: Vec<String>,
+    traffic_fixtures: Option<Arc<TrafficFixtures>>,
 }
 
 impl WebSocketParams {
     pub fn new(url: Url) -> Self {
-        WebSocketParams { url }
+        WebSocketParams {
+            url,
+            subscription_messages: Vec::new(),
+            traffic_fixtures: None,
+        }
+    }
+
+    pub fn with_subscription_messages(mut self, subscription_messages: Vec<String>) -> Self {
+        self.subscription_messages = subscription_messages;
+        self
+    }
+
+    pub fn with_traffic_fixtures(mut self, traffic_fixtures: Option<Arc<TrafficFixtures>>) -> Self {
+        self.traffic_fixtures = traffic_fixtures;
+        self
     }
 }
 
@@ -43,6 +61,8 @@ pub struct WebSocketConnection {
     last_heartbeat_time: Mutex<Instant>,
     connectivity_manager_notifier: ConnectivityManagerNotifier,
     is_connected: Mutex<bool>,
+    // Fixtures and index of recorded session if traffic is being recorded
+    traffic_recording: Option<(Arc<TrafficFixtures>, usize)>,
 }
 
 impl WebSocketConnection {
@@ -52,7 +72,21 @@ impl WebSocketConnection {
         params: WebSocketParams,
         connectivity_manager_notifier: ConnectivityManagerNotifier,
     ) -> Result<Arc<Self>> {
-        let (ws_stream, response) = connect_async(params.url)
+        let (url, traffic_recording) = match params.traffic_fixtures {
+            Some(traffic_fixtures) => match traffic_fixtures.mode() {
+                TrafficMode::Replay => (
+                    traffic_fixtures.replay_websocket(role, &params.url).await?,
+                    None,
+                ),
+                TrafficMode::Record => {
+                    let session_index = traffic_fixtures.start_websocket_session(role, &params.url);
+                    (params.url, Some((traffic_fixtures, session_index)))
+                }
+            },
+            None => (params.url, None),
+        };
+
+        let (ws_stream, response) = connect_async(url)
             .await
             .context("Error occurred during websocket connect")?;
 
@@ -71,6 +105,7 @@ impl WebSocketConnection {
             writer,
             connectivity_manager_notifier,
             true,
+            traffic_recording,
         ));
 
         spawn_future(
@@ -85,6 +120,12 @@ impl WebSocketConnection {
             Self::heartbeat(ws.clone()).boxed(),
         );
 
+        for message in params.subscription_messages {
+            ws.send_string(message)
+                .await
+                .context("Unable to send subscription message to websocket")?;
+        }
+
         Ok(ws)
     }
 
@@ -95,6 +136,9 @@ impl WebSocketConnection {
             self.role,
             text
         );
+        if let Some((traffic_fixtures, session_index)) = &self.traffic_recording {
+            traffic_fixtures.record_websocket_sent(*session_index, &text);
+        }
         self.send(Message::Text(text)).await
     }
 
@@ -118,6 +162,7 @@ impl WebSocketConnection {
         writer: WebSocketWriter,
         connectivity_manager_notifier: ConnectivityManagerNotifier,
         is_connected: bool,
+        traffic_recording: Option<(Arc<TrafficFixtures>, usize)>,
     ) -> Self {
         Self {
             exchange_account_id,
@@ -126,6 +171,7 @@ impl WebSocketConnection {
             last_heartbeat_time: Mutex::new(Instant::now()),
             connectivity_manager_notifier,
             is_connected: Mutex::new(is_connected),
+            traffic_recording,
         }
     }
 
@@ -198,7 +244,12 @@ impl WebSocketConnection {
 
     async fn handle_websocket_message(&self, msg: Message) {
         match msg {
-            Message::Text(ref text) => self.connectivity_manager_notifier.message_received(text),
+            Message::Text(ref text) => {
+                if let Some((traffic_fixtures, session_index)) = &self.traffic_recording {
+                    traffic_fixtures.record_websocket_received(*session_index, text);
+                }
+                self.connectivity_manager_notifier.message_received(text)
+            }
             Message::Binary(bytes) => log::trace!(
                 "Websocket {} {:?} got binary message: {:x?}",
                 self.exchange_account_id,
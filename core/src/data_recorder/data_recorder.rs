@@ -0,0 +1,244 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::FutureExt;
+use mmb_utils::cancellation_token::CancellationToken;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use crate::data_recorder::recorded_event::RecordedEvent;
+use crate::data_recorder::storage::RecordedFileWriter;
+use crate::exchanges::events::ExchangeEvent;
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::Service;
+use crate::misc::time::time_manager;
+
+static DATA_RECORDER: &str = "DataRecorder";
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+fn default_rotation_period_minutes() -> u32 {
+    60
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DataRecorderSettings {
+    /// Directory for recorded files. Will be created if doesn't exist
+    pub directory: PathBuf,
+    /// New file is started every `rotation_period_minutes`
+    #[serde(default = "default_rotation_period_minutes")]
+    pub rotation_period_minutes: u32,
+}
+
+/// Records order book, trades, order and balance events from the engine events channel
+/// and any events passed to `save` into rotating compressed files in `DataRecorderSettings::directory`
+pub struct DataRecorder {
+    directory: PathBuf,
+    events_sender: mpsc::UnboundedSender<RecordedEvent>,
+    work_finished_receiver: Mutex<Option<oneshot::Receiver<Result<()>>>>,
+}
+
+impl DataRecorder {
+    pub fn start(
+        settings: &DataRecorderSettings,
+        events_receiver: broadcast::Receiver<ExchangeEvent>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Arc<Self>> {
+        let writer = RecordedFileWriter::new(
+            settings.directory.clone(),
+            chrono::Duration::minutes(settings.rotation_period_minutes.into()),
+        )?;
+
+        let (events_sender, saved_events_receiver) = mpsc::unbounded_channel();
+        let (work_finished_sender, work_finished_receiver) = oneshot::channel();
+
+        let action = record_events(
+            writer,
+            events_receiver,
+            saved_events_receiver,
+            work_finished_sender,
+            cancellation_token,
+        );
+        spawn_future("Start data recorder", true, action.boxed());
+
+        Ok(Arc::new(Self {
+            directory: settings.directory.clone(),
+            events_sender,
+            work_finished_receiver: Mutex::new(Some(work_finished_receiver)),
+        }))
+    }
+
+    pub fn directory(&self) -> &PathBuf {
+        &self.directory
+    }
+
+    /// Record event which doesn't go through the engine events channel
+    pub fn save(&self, event: RecordedEvent) {
+        if self.events_sender.send(event).is_err() {
+            log::warn!("Unable to save event because DataRecorder is already stopped");
+        }
+    }
+}
+
+impl Service for DataRecorder {
+    fn name(&self) -> &str {
+        DATA_RECORDER
+    }
+
+    fn graceful_shutdown(self: Arc<Self>) -> Option<oneshot::Receiver<Result<()>>> {
+        let work_finished_receiver = self.work_finished_receiver.lock().take();
+        if work_finished_receiver.is_none() {
+            log::warn!("'work_finished_receiver' wasn't created when started graceful shutdown in DataRecorder");
+        }
+
+        work_finished_receiver
+    }
+}
+
+async fn record_events(
+    mut writer: RecordedFileWriter,
+    mut events_receiver: broadcast::Receiver<ExchangeEvent>,
+    mut saved_events_receiver: mpsc::UnboundedReceiver<RecordedEvent>,
+    work_finished_sender: oneshot::Sender<Result<()>>,
+    cancellation_token: CancellationToken,
+) -> Result<()> {
+    let mut flush_interval = tokio::time::interval(FLUSH_INTERVAL);
+
+    loop {
+        tokio::select! {
+            event = events_receiver.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped_count)) => {
+                        log::warn!("DataRecorder skipped {} events because it is lagging behind", skipped_count);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                record_exchange_event(&mut writer, &event);
+            }
+            Some(recorded_event) = saved_events_receiver.recv() => write_event(&mut writer, &recorded_event),
+            _ = flush_interval.tick() => {
+                if let Err(error) = writer.flush() {
+                    log::error!("Unable to flush recorded file in DataRecorder: {:?}", error);
+                }
+            }
+            _ = cancellation_token.when_cancelled() => break,
+        }
+    }
+
+    // events already sent to the recorder shouldn't be lost on shutdown
+    loop {
+        match events_receiver.try_recv() {
+            Ok(event) => record_exchange_event(&mut writer, &event),
+            Err(TryRecvError::Lagged(_)) => continue,
+            Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+        }
+    }
+    while let Ok(recorded_event) = saved_events_receiver.try_recv() {
+        write_event(&mut writer, &recorded_event);
+    }
+
+    let result = writer
+        .finish()
+        .context("Unable to finish recording in DataRecorder");
+    let _ = work_finished_sender.send(result);
+
+    Ok(())
+}
+
+fn record_exchange_event(writer: &mut RecordedFileWriter, event: &ExchangeEvent) {
+    if let Some(recorded_event) = RecordedEvent::from_exchange_event(event, time_manager::now()) {
+        write_event(writer, &recorded_event);
+    }
+}
+
+/// Failed write loses only the event, so recording goes on with next events
+fn write_event(writer: &mut RecordedFileWriter, recorded_event: &RecordedEvent) {
+    if let Err(error) = writer.write(recorded_event) {
+        log::error!("Unable to write event in DataRecorder: {:?}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_recorder::storage::tests::{price_source, test_directory};
+    use crate::data_recorder::storage::{list_recorded_files, RecordedFileReader};
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId};
+    use crate::exchanges::events::{BalanceUpdateEvent, ExchangeBalancesAndPositions, TradesEvent};
+    use itertools::Itertools;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn record_events_until_graceful_shutdown() {
+        let directory = test_directory("service");
+        let settings = DataRecorderSettings {
+            directory: directory.clone(),
+            rotation_period_minutes: default_rotation_period_minutes(),
+        };
+        let (events_sender, events_receiver) = broadcast::channel(10);
+        let cancellation_token = CancellationToken::new();
+
+        let data_recorder =
+            DataRecorder::start(&settings, events_receiver, cancellation_token.clone())
+                .expect("in test");
+
+        let exchange_account_id = ExchangeAccountId::new("Binance".into(), 0);
+        let now = time_manager::now();
+        events_sender
+            .send(ExchangeEvent::Trades(TradesEvent {
+                exchange_account_id,
+                currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                trades: vec![],
+                receipt_time: now,
+            }))
+            .expect("in test");
+        events_sender
+            .send(ExchangeEvent::BalanceUpdate(BalanceUpdateEvent {
+                exchange_account_id,
+                balances_and_positions: ExchangeBalancesAndPositions {
+                    balances: vec![],
+                    positions: None,
+                },
+            }))
+            .expect("in test");
+        data_recorder.save(price_source(now, dec!(1)));
+
+        // events sent before cancellation are recorded on graceful shutdown
+        cancellation_token.cancel();
+        data_recorder
+            .graceful_shutdown()
+            .expect("in test")
+            .await
+            .expect("in test")
+            .expect("in test");
+
+        let recorded_events: Vec<_> = list_recorded_files(&directory)
+            .expect("in test")
+            .iter()
+            .flat_map(|path| RecordedFileReader::open(path).expect("in test"))
+            .try_collect()
+            .expect("in test");
+        let _ = std::fs::remove_dir_all(&directory);
+
+        let recorded_types = recorded_events
+            .iter()
+            .map(|event| match event {
+                RecordedEvent::Trades(_) => "Trades",
+                RecordedEvent::BalanceUpdate(_) => "BalanceUpdate",
+                RecordedEvent::PriceSource(_) => "PriceSource",
+                _ => "Other",
+            })
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            recorded_types,
+            vec!["BalanceUpdate", "PriceSource", "Trades"]
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod data_recorder;
+pub mod recorded_event;
+pub mod storage;
@@ -0,0 +1,203 @@
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::events::{ExchangeEvent, TradeId, TradesEvent};
+use crate::misc::derivative_position::DerivativePosition;
+use crate::misc::price_source_model::PriceSourceModel;
+use crate::order_book::event::{EventType, OrderBookEvent};
+use crate::orders::event::OrderEventType;
+use crate::orders::order::{OrderSide, OrderSnapshot};
+
+/// Version of recorded events format. Should be incremented on every incompatible change of
+/// recorded structures, so old files can be recognized
+pub const RECORDED_SCHEMA_VERSION: u32 = 1;
+
+/// First line of every recorded chunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFileHeader {
+    pub schema_version: u32,
+}
+
+impl Default for RecordedFileHeader {
+    fn default() -> Self {
+        Self {
+            schema_version: RECORDED_SCHEMA_VERSION,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedOrderBookEvent {
+    pub time: DateTime,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub is_snapshot: bool,
+    pub asks: Vec<(Price, Amount)>,
+    pub bids: Vec<(Price, Amount)>,
+}
+
+impl RecordedOrderBookEvent {
+    pub fn from_order_book_event(event: &OrderBookEvent) -> Self {
+        Self {
+            time: event.creation_time,
+            exchange_account_id: event.exchange_account_id,
+            currency_pair: event.currency_pair,
+            is_snapshot: matches!(event.event_type, EventType::Snapshot),
+            asks: event.data.asks.iter().map(|(&p, &a)| (p, a)).collect_vec(),
+            bids: event.data.bids.iter().map(|(&p, &a)| (p, a)).collect_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTrade {
+    pub trade_id: TradeId,
+    pub price: Price,
+    pub amount: Amount,
+    /// Side of the taker
+    pub side: OrderSide,
+    pub transaction_time: DateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedTradesEvent {
+    pub time: DateTime,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub trades: Vec<RecordedTrade>,
+}
+
+impl RecordedTradesEvent {
+    pub fn from_trades_event(event: &TradesEvent) -> Self {
+        Self {
+            time: event.receipt_time,
+            exchange_account_id: event.exchange_account_id,
+            currency_pair: event.currency_pair,
+            trades: event
+                .trades
+                .iter()
+                .map(|trade| RecordedTrade {
+                    trade_id: trade.trade_id.clone(),
+                    price: trade.price,
+                    amount: trade.quantity,
+                    side: trade.side,
+                    transaction_time: trade.transaction_time,
+                })
+                .collect_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecordedOrderEventType {
+    CreateOrderSucceeded,
+    CreateOrderFailed,
+    OrderFilled,
+    OrderCompleted,
+    CancelOrderSucceeded,
+    CancelOrderFailed,
+}
+
+/// Order event with the order state at the moment of the event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedOrderEvent {
+    pub time: DateTime,
+    pub event_type: RecordedOrderEventType,
+    pub order: OrderSnapshot,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedBalanceUpdate {
+    pub time: DateTime,
+    pub exchange_account_id: ExchangeAccountId,
+    pub balances: Vec<(CurrencyCode, Amount)>,
+    pub positions: Option<Vec<DerivativePosition>>,
+}
+
+/// Single line of recorded file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum RecordedEvent {
+    OrderBook(RecordedOrderBookEvent),
+    Trades(RecordedTradesEvent),
+    Order(Box<RecordedOrderEvent>),
+    BalanceUpdate(RecordedBalanceUpdate),
+    PriceSource(PriceSourceModel),
+}
+
+impl RecordedEvent {
+    pub fn time(&self) -> DateTime {
+        match self {
+            RecordedEvent::OrderBook(event) => event.time,
+            RecordedEvent::Trades(event) => event.time,
+            RecordedEvent::Order(event) => event.time,
+            RecordedEvent::BalanceUpdate(event) => event.time,
+            RecordedEvent::PriceSource(price_source) => price_source.init_time,
+        }
+    }
+
+    /// Returns `None` for events which are not recorded.
+    /// `now` is used as time of events without own creation time
+    pub fn from_exchange_event(event: &ExchangeEvent, now: DateTime) -> Option<Self> {
+        let recorded_event = match event {
+            ExchangeEvent::OrderBookEvent(event) => {
+                RecordedEvent::OrderBook(RecordedOrderBookEvent::from_order_book_event(event))
+            }
+            ExchangeEvent::Trades(event) => {
+                RecordedEvent::Trades(RecordedTradesEvent::from_trades_event(event))
+            }
+            ExchangeEvent::OrderEvent(event) => {
+                let (event_type, order) = match &event.event_type {
+                    OrderEventType::CreateOrderSucceeded => (
+                        RecordedOrderEventType::CreateOrderSucceeded,
+                        event.order.deep_clone(),
+                    ),
+                    OrderEventType::CreateOrderFailed => (
+                        RecordedOrderEventType::CreateOrderFailed,
+                        event.order.deep_clone(),
+                    ),
+                    OrderEventType::OrderFilled { cloned_order } => (
+                        RecordedOrderEventType::OrderFilled,
+                        cloned_order.as_ref().clone(),
+                    ),
+                    OrderEventType::OrderCompleted { cloned_order } => (
+                        RecordedOrderEventType::OrderCompleted,
+                        cloned_order.as_ref().clone(),
+                    ),
+                    OrderEventType::CancelOrderSucceeded => (
+                        RecordedOrderEventType::CancelOrderSucceeded,
+                        event.order.deep_clone(),
+                    ),
+                    OrderEventType::CancelOrderFailed => (
+                        RecordedOrderEventType::CancelOrderFailed,
+                        event.order.deep_clone(),
+                    ),
+                };
+
+                RecordedEvent::Order(Box::new(RecordedOrderEvent {
+                    time: now,
+                    event_type,
+                    order,
+                }))
+            }
+            ExchangeEvent::BalanceUpdate(event) => {
+                RecordedEvent::BalanceUpdate(RecordedBalanceUpdate {
+                    time: now,
+                    exchange_account_id: event.exchange_account_id,
+                    balances: event
+                        .balances_and_positions
+                        .balances
+                        .iter()
+                        .map(|x| (x.currency_code, x.balance))
+                        .collect_vec(),
+                    positions: event.balances_and_positions.positions.clone(),
+                })
+            }
+            ExchangeEvent::LiquidationPrice(_) => return None,
+        };
+
+        Some(recorded_event)
+    }
+}
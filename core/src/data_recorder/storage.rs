@@ -0,0 +1,351 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Lines, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use itertools::Itertools;
+use mmb_utils::DateTime;
+
+use super::recorded_event::{RecordedEvent, RecordedFileHeader, RECORDED_SCHEMA_VERSION};
+
+const FILE_NAME_PREFIX: &str = "events_";
+const COMPRESSED_FILE_EXTENSION: &str = ".jsonl.gz";
+const FILE_NAME_TIME_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+/// Path of the file for events starting from `period_start`
+fn recorded_file_path(directory: &Path, period_start: DateTime) -> PathBuf {
+    directory.join(format!(
+        "{}{}{}",
+        FILE_NAME_PREFIX,
+        period_start.format(FILE_NAME_TIME_FORMAT),
+        COMPRESSED_FILE_EXTENSION
+    ))
+}
+
+/// Start time of the period recorded in the file, if the file was created by `RecordedFileWriter`
+pub fn recorded_file_start_time(path: &Path) -> Option<DateTime> {
+    let file_name = path.file_name()?.to_str()?;
+    let time = file_name
+        .strip_prefix(FILE_NAME_PREFIX)?
+        .strip_suffix(COMPRESSED_FILE_EXTENSION)?;
+
+    NaiveDateTime::parse_from_str(time, FILE_NAME_TIME_FORMAT)
+        .ok()
+        .map(|time| Utc.from_utc_datetime(&time))
+}
+
+/// Recorded files in the directory in chronological order
+pub fn list_recorded_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(directory)
+        .with_context(|| format!("Unable to read directory {}", directory.display()))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if let Some(start_time) = recorded_file_start_time(&path) {
+            files.push((start_time, path));
+        }
+    }
+
+    Ok(files
+        .into_iter()
+        .sorted_by_key(|(start_time, _)| *start_time)
+        .map(|(_, path)| path)
+        .collect_vec())
+}
+
+/// Writes recorded events to gzip compressed files in JSON lines format.
+/// New file is started for every rotation period. Existing files are never rewritten:
+/// if the file of the current period already exists, a new gzip member is appended to it
+pub struct RecordedFileWriter {
+    directory: PathBuf,
+    rotation_period: Duration,
+    current_file: Option<(DateTime, GzEncoder<File>)>,
+}
+
+impl RecordedFileWriter {
+    pub fn new(directory: PathBuf, rotation_period: Duration) -> Result<Self> {
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("Unable to create directory {}", directory.display()))?;
+
+        Ok(Self {
+            directory,
+            rotation_period,
+            current_file: None,
+        })
+    }
+
+    pub fn write(&mut self, event: &RecordedEvent) -> Result<()> {
+        let encoder = self.get_file_for(event.time())?;
+        serde_json::to_writer(&mut *encoder, event)
+            .context("Unable to serialize recorded event")?;
+        encoder
+            .write_all(b"\n")
+            .context("Unable to write recorded event")
+    }
+
+    /// Makes all written events readable from the current file
+    pub fn flush(&mut self) -> Result<()> {
+        match &mut self.current_file {
+            Some((_, encoder)) => encoder.flush().context("Unable to flush recorded file"),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes gzip trailer of the current file
+    pub fn finish(&mut self) -> Result<()> {
+        match self.current_file.take() {
+            Some((_, encoder)) => {
+                let _ = encoder.finish().context("Unable to finish recorded file")?;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn get_file_for(&mut self, time: DateTime) -> Result<&mut GzEncoder<File>> {
+        let need_rotation = match &self.current_file {
+            Some((period_start, _)) => time >= *period_start + self.rotation_period,
+            None => true,
+        };
+
+        if need_rotation {
+            self.finish()?;
+
+            let period_start = self.period_start(time);
+            let path = recorded_file_path(&self.directory, period_start);
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("Unable to open recorded file {}", path.display()))?;
+
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            serde_json::to_writer(&mut encoder, &RecordedFileHeader::default())?;
+            encoder.write_all(b"\n")?;
+
+            self.current_file = Some((period_start, encoder));
+        }
+
+        Ok(&mut self.current_file.as_mut().expect("file opened above").1)
+    }
+
+    fn period_start(&self, time: DateTime) -> DateTime {
+        let period_secs = self.rotation_period.num_seconds().max(1);
+        let start_secs = time.timestamp() - time.timestamp().rem_euclid(period_secs);
+        Utc.timestamp_opt(start_secs, 0)
+            .single()
+            .expect("Period start is earlier than the time, so it should be valid")
+    }
+}
+
+impl Drop for RecordedFileWriter {
+    fn drop(&mut self) {
+        if let Err(error) = self.finish() {
+            log::error!("Unable to finish recorded file: {:?}", error);
+        }
+    }
+}
+
+/// Reads recorded events from a single file.
+/// Both gzip compressed and plain JSON lines files are supported, header lines are optional
+pub struct RecordedFileReader {
+    path: PathBuf,
+    lines: Lines<BufReader<Box<dyn Read + Send>>>,
+}
+
+impl RecordedFileReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Unable to open recorded file {}", path.display()))?;
+
+        let is_compressed = path
+            .extension()
+            .map_or(false, |extension| extension == "gz");
+        let reader: Box<dyn Read + Send> = match is_compressed {
+            true => Box::new(MultiGzDecoder::new(file)),
+            false => Box::new(file),
+        };
+
+        Ok(Self {
+            path: path.to_owned(),
+            lines: BufReader::new(reader).lines(),
+        })
+    }
+
+    fn read_next(&mut self) -> Result<Option<RecordedEvent>> {
+        for line in &mut self.lines {
+            let line = line
+                .with_context(|| format!("Unable to read recorded file {}", self.path.display()))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if line.starts_with(r#"{"schema_version":"#) {
+                let header: RecordedFileHeader =
+                    serde_json::from_str(&line).with_context(|| {
+                        format!("Unable to parse header in {}", self.path.display())
+                    })?;
+
+                if header.schema_version > RECORDED_SCHEMA_VERSION {
+                    bail!(
+                        "Recorded file {} has schema version {}, but only versions up to {} are supported",
+                        self.path.display(),
+                        header.schema_version,
+                        RECORDED_SCHEMA_VERSION
+                    );
+                }
+                continue;
+            }
+
+            let event = serde_json::from_str(&line).with_context(|| {
+                format!(
+                    "Unable to parse recorded event in {}: {}",
+                    self.path.display(),
+                    line
+                )
+            })?;
+            return Ok(Some(event));
+        }
+
+        Ok(None)
+    }
+}
+
+impl Iterator for RecordedFileReader {
+    type Item = Result<RecordedEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, Price};
+    use crate::misc::price_source_model::PriceSourceModel;
+    use rust_decimal_macros::dec;
+
+    pub(crate) fn test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("mmb_recorder_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    pub(crate) fn price_source(time: DateTime, bid: Price) -> RecordedEvent {
+        RecordedEvent::PriceSource(PriceSourceModel::new(
+            time,
+            "Binance".into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            Some(bid),
+            Some(bid + dec!(1)),
+        ))
+    }
+
+    fn read_all(directory: &Path) -> Vec<RecordedEvent> {
+        list_recorded_files(directory)
+            .expect("in test")
+            .iter()
+            .flat_map(|path| RecordedFileReader::open(path).expect("in test"))
+            .try_collect()
+            .expect("in test")
+    }
+
+    fn times(events: &[RecordedEvent]) -> Vec<DateTime> {
+        events.iter().map(|x| x.time()).collect_vec()
+    }
+
+    #[test]
+    fn rotate_files_by_period() {
+        let directory = test_directory("rotate");
+        let start = Utc
+            .with_ymd_and_hms(2021, 9, 20, 10, 0, 0)
+            .single()
+            .expect("in test");
+        let events = vec![
+            price_source(start, dec!(1)),
+            price_source(start + Duration::minutes(59), dec!(2)),
+            price_source(start + Duration::minutes(61), dec!(3)),
+        ];
+
+        let mut writer =
+            RecordedFileWriter::new(directory.clone(), Duration::hours(1)).expect("in test");
+        for event in &events {
+            writer.write(event).expect("in test");
+        }
+        writer.finish().expect("in test");
+
+        let files = list_recorded_files(&directory).expect("in test");
+        let recorded_events = read_all(&directory);
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(
+            files
+                .iter()
+                .map(|x| recorded_file_start_time(x).expect("in test"))
+                .collect_vec(),
+            vec![start, start + Duration::hours(1)]
+        );
+        assert_eq!(times(&recorded_events), times(&events));
+    }
+
+    #[test]
+    fn append_to_existing_file() {
+        let directory = test_directory("append");
+        let start = Utc
+            .with_ymd_and_hms(2021, 9, 20, 10, 0, 0)
+            .single()
+            .expect("in test");
+        let first_event = price_source(start, dec!(1));
+        let second_event = price_source(start + Duration::minutes(1), dec!(2));
+
+        for event in [&first_event, &second_event] {
+            let mut writer =
+                RecordedFileWriter::new(directory.clone(), Duration::hours(1)).expect("in test");
+            writer.write(event).expect("in test");
+        }
+
+        let files = list_recorded_files(&directory).expect("in test");
+        let recorded_events = read_all(&directory);
+        let _ = fs::remove_dir_all(&directory);
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            times(&recorded_events),
+            vec![first_event.time(), second_event.time()]
+        );
+    }
+
+    #[test]
+    fn fail_on_unsupported_schema_version() {
+        let directory = test_directory("schema");
+        fs::create_dir_all(&directory).expect("in test");
+        let path = directory.join("events.jsonl");
+        fs::write(
+            &path,
+            format!(
+                "{}\n",
+                serde_json::to_string(&RecordedFileHeader {
+                    schema_version: RECORDED_SCHEMA_VERSION + 1
+                })
+                .expect("in test")
+            ),
+        )
+        .expect("in test");
+
+        let result: Result<Vec<_>> = RecordedFileReader::open(&path)
+            .expect("in test")
+            .try_collect();
+        let _ = fs::remove_dir_all(&directory);
+
+        let error = result.expect_err("in test");
+        assert!(error.to_string().contains("schema version"));
+    }
+}
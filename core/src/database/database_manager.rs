@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::{Client, NoTls};
+
+use crate::infrastructure::spawn_future;
+
+/// Tables are created on connection if they don't exist yet
+const MIGRATIONS: &[&str] = &[r#"
+    CREATE TABLE IF NOT EXISTS "PriceSources" (
+        "Id" BIGSERIAL PRIMARY KEY,
+        "ExchangeName" TEXT NOT NULL,
+        "CurrencyCodePair" TEXT NOT NULL,
+        "DateTime" TIMESTAMPTZ NOT NULL,
+        "Bid" NUMERIC,
+        "Ask" NUMERIC
+    );
+    CREATE INDEX IF NOT EXISTS "IX_PriceSources_ExchangeName_CurrencyCodePair_DateTime"
+        ON "PriceSources" ("ExchangeName", "CurrencyCodePair", "DateTime");
+    "#];
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DatabaseSettings {
+    /// PostgreSQL connection string, e.g. `host=localhost user=postgres password=postgres dbname=mmb`
+    pub url: String,
+}
+
+/// Connection to PostgreSQL database for persistent engine data
+pub struct DatabaseManager {
+    client: Client,
+}
+
+impl DatabaseManager {
+    pub async fn connect(settings: &DatabaseSettings) -> Result<Arc<Self>> {
+        let (client, connection) = tokio_postgres::connect(&settings.url, NoTls)
+            .await
+            .context("Unable to connect to database")?;
+
+        let action = async move { connection.await.context("Database connection failed") };
+        let _ = spawn_future("Database connection", false, action.boxed());
+
+        let database_manager = Arc::new(Self { client });
+        database_manager.migrate().await?;
+
+        Ok(database_manager)
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        for migration in MIGRATIONS {
+            self.client
+                .batch_execute(migration)
+                .await
+                .context("Unable to apply database migration")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Database tests are ignored by default because they need a local test instance.
+    /// Run them with `MMB_TEST_DATABASE_URL=<url> cargo test -- --ignored`
+    pub(crate) fn test_database_settings() -> DatabaseSettings {
+        let url = std::env::var("MMB_TEST_DATABASE_URL")
+            .expect("MMB_TEST_DATABASE_URL should be specified for database tests");
+        DatabaseSettings { url }
+    }
+
+    #[ignore] // Requires database specified by MMB_TEST_DATABASE_URL
+    #[tokio::test]
+    async fn connect_and_migrate_twice() {
+        let settings = test_database_settings();
+
+        for _ in 0..2 {
+            let database_manager = DatabaseManager::connect(&settings).await.expect("in test");
+            let _ = database_manager
+                .client()
+                .query("SELECT 1 FROM \"PriceSources\" LIMIT 1", &[])
+                .await
+                .expect("in test");
+        }
+    }
+}
@@ -1,26 +1,28 @@
+use std::cell::RefCell;
 use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Context, Result};
-use chrono::Utc;
 use futures::FutureExt;
-use itertools::Itertools;
+use itertools::{Either, Itertools};
 use mmb_utils::infrastructure::WithExpect;
 use mmb_utils::{nothing_to_do, DateTime};
 use parking_lot::Mutex;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use tokio::sync::{broadcast, oneshot};
+use tokio::sync::{broadcast, oneshot, watch};
 
 use crate::disposition_execution::trading_context_calculation::calculate_trading_context;
-use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, Price, TradePlaceAccount};
+use crate::exchanges::common::{Amount, ExchangeAccountId, Price, TradePlaceAccount};
 use crate::exchanges::events::ExchangeEvent;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::request_type::RequestType;
 use crate::exchanges::general::symbol::Symbol;
+use crate::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
 use crate::explanation::{Explanation, WithExplanation};
 use crate::lifecycle::trading_engine::{EngineContext, Service};
 use crate::misc::reserve_parameters::ReserveParameters;
+use crate::misc::time::time_manager;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
 use crate::orders::event::OrderEventType;
 use crate::orders::order::{
@@ -28,6 +30,7 @@ use crate::orders::order::{
     OrderStatus, OrderType,
 };
 use crate::orders::pool::OrderRef;
+use crate::risk::pre_trade_risk::{OrderRiskContext, RiskLimitViolation};
 use crate::strategies::disposition_strategy::DispositionStrategy;
 use crate::{
     disposition_execution::trade_limit::is_enough_amount_and_cost, infrastructure::spawn_future,
@@ -66,10 +69,11 @@ impl DispositionExecutorService {
         engine_ctx: Arc<EngineContext>,
         events_receiver: broadcast::Receiver<ExchangeEvent>,
         local_snapshots_service: LocalSnapshotsService,
-        exchange_account_id: ExchangeAccountId,
-        currency_pair: CurrencyPair,
+        trade_places: Vec<TradePlaceAccount>,
         max_amount: Amount,
+        price_slots_count: usize,
         strategy: Box<dyn DispositionStrategy>,
+        is_enabled: watch::Receiver<bool>,
         cancellation_token: CancellationToken,
         statistics: Arc<StatisticService>,
     ) -> Arc<Self> {
@@ -80,10 +84,11 @@ impl DispositionExecutorService {
                 engine_ctx,
                 events_receiver,
                 local_snapshots_service,
-                exchange_account_id,
-                currency_pair,
+                trade_places,
                 max_amount,
+                price_slots_count,
                 strategy,
+                is_enabled,
                 work_finished_sender,
                 cancellation_token,
                 statistics,
@@ -114,18 +119,54 @@ impl Service for DispositionExecutorService {
     }
 }
 
+/// Orders of strategy on one of its trade places
+struct TradePlaceState {
+    trade_place: TradePlaceAccount,
+    symbol: Arc<Symbol>,
+    orders_state: OrdersState,
+}
+
+impl TradePlaceState {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        self.trade_place.exchange_account_id
+    }
+}
+
+enum PendingOrderRequest {
+    Create(OrderCreating),
+    Cancel {
+        order: OrderRef,
+        request_group_id: RequestGroupId,
+    },
+}
+
+impl PendingOrderRequest {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        match self {
+            PendingOrderRequest::Create(order_creating) => {
+                order_creating.header.exchange_account_id
+            }
+            PendingOrderRequest::Cancel { order, .. } => order.exchange_account_id(),
+        }
+    }
+}
+
+/// Synchronizes orders of strategy with its trading context on all strategy trade places.
+/// Balance is reserved for all trade places by the single strategy configuration descriptor
 struct DispositionExecutor {
     engine_ctx: Arc<EngineContext>,
-    exchange_account_id: ExchangeAccountId,
-    symbol: Arc<Symbol>,
+    trade_places: Vec<TradePlaceState>,
     max_amount: Amount,
     events_receiver: broadcast::Receiver<ExchangeEvent>,
     local_snapshots_service: LocalSnapshotsService,
-    orders_state: OrdersState,
     strategy: Box<dyn DispositionStrategy>,
+    /// Stopped strategy doesn't trade until it is started again through `HostedStrategies`
+    is_enabled: watch::Receiver<bool>,
     work_finished_sender: Option<oneshot::Sender<Result<()>>>,
     cancellation_token: CancellationToken,
     statistics: Arc<StatisticService>,
+    /// Order requests of all price slots collected while handling current event
+    pending_order_requests: RefCell<Vec<PendingOrderRequest>>,
 }
 
 impl DispositionExecutor {
@@ -133,33 +174,56 @@ impl DispositionExecutor {
         engine_ctx: Arc<EngineContext>,
         events_receiver: broadcast::Receiver<ExchangeEvent>,
         local_snapshots_service: LocalSnapshotsService,
-        exchange_account_id: ExchangeAccountId,
-        currency_pair: CurrencyPair,
+        trade_places: Vec<TradePlaceAccount>,
         max_amount: Amount,
+        price_slots_count: usize,
         strategy: Box<dyn DispositionStrategy>,
+        is_enabled: watch::Receiver<bool>,
         work_finished_sender: oneshot::Sender<Result<()>>,
         cancellation_token: CancellationToken,
         statistics: Arc<StatisticService>,
     ) -> Self {
-        let symbol = engine_ctx
-            .exchanges
-            .get(&exchange_account_id)
-            .expect("Target exchange should exists")
-            .get_symbol(currency_pair)
-            .expect("Currency pair symbol should exists for target trading place");
+        let trade_places = trade_places
+            .into_iter()
+            .unique()
+            .map(|trade_place| {
+                let symbol = engine_ctx
+                    .exchanges
+                    .get(&trade_place.exchange_account_id)
+                    .with_expect(|| {
+                        format!(
+                            "Target exchange {} should exists",
+                            trade_place.exchange_account_id
+                        )
+                    })
+                    .get_symbol(trade_place.currency_pair)
+                    .with_expect(|| {
+                        format!(
+                            "Currency pair symbol should exists for target trading place {:?}",
+                            trade_place
+                        )
+                    });
+
+                TradePlaceState {
+                    trade_place,
+                    symbol,
+                    orders_state: OrdersState::new(price_slots_count),
+                }
+            })
+            .collect();
 
         DispositionExecutor {
             engine_ctx,
             events_receiver,
             local_snapshots_service,
-            exchange_account_id,
-            symbol,
+            trade_places,
             max_amount,
-            orders_state: OrdersState::new(),
             strategy,
+            is_enabled,
             work_finished_sender: Some(work_finished_sender),
             cancellation_token,
             statistics,
+            pending_order_requests: Default::default(),
         }
     }
 
@@ -169,6 +233,12 @@ impl DispositionExecutor {
         loop {
             let event = tokio::select! {
                 event_res = self.events_receiver.recv() => event_res.context("Error during receiving event in DispositionExecutor::start()")?,
+                changed_res = self.is_enabled.changed() => {
+                    changed_res.context("Error during receiving strategy state in DispositionExecutor::start()")?;
+                    self.handle_strategy_state_changed(&mut trading_context);
+                    self.send_pending_order_requests();
+                    continue;
+                }
                 _ = self.cancellation_token.when_cancelled() => {
                     let _ = self.work_finished_sender.take().ok_or(anyhow!("Can't take `work_finished_sender` in DispositionExecutor"))?.send(Ok(()));
                     return Ok(());
@@ -176,6 +246,7 @@ impl DispositionExecutor {
             };
 
             self.handle_event(event, &mut trading_context)?;
+            self.send_pending_order_requests();
         }
     }
 
@@ -189,7 +260,14 @@ impl DispositionExecutor {
 
         match event {
             ExchangeEvent::OrderBookEvent(order_book_event) => {
-                let _ = self.local_snapshots_service.update(order_book_event);
+                if let Some(trade_place) = self.local_snapshots_service.update(order_book_event) {
+                    let trade_place = trade_place.trade_place();
+                    if let Some(snapshot) = self.local_snapshots_service.get_snapshot(trade_place) {
+                        self.engine_ctx
+                            .pnl_ledger
+                            .update_mark_price(trade_place, snapshot);
+                    }
+                }
             }
             ExchangeEvent::OrderEvent(order_event) => {
                 if order_event.order.is_external_order() {
@@ -228,6 +306,7 @@ impl DispositionExecutor {
                                 self.strategy.configuration_descriptor(),
                                 cloned_order,
                             );
+                            self.add_fill_to_pnl_ledger(cloned_order);
 
                             if cloned_order.status() == OrderStatus::Completed {
                                 return Ok(());
@@ -283,9 +362,16 @@ impl DispositionExecutor {
                     }
                 }
             }
+            ExchangeEvent::Trades(ref trades_event) => {
+                self.strategy.handle_trades_event(trades_event)
+            }
             _ => nothing_to_do(),
         };
 
+        if !*self.is_enabled.borrow() {
+            return Ok(());
+        }
+
         let mut new_trading_context = estimate_trading_context(
             need_recalculate_trading_context,
             self.max_amount,
@@ -304,24 +390,63 @@ impl DispositionExecutor {
         Ok(())
     }
 
+    fn handle_strategy_state_changed(&self, last_trading_context: &mut Option<TradingContext>) {
+        let configuration_descriptor = self.strategy.configuration_descriptor();
+        if *self.is_enabled.borrow() {
+            log::info!(
+                "Strategy {:?} will start trading on next order book event",
+                configuration_descriptor
+            );
+            return;
+        }
+
+        log::info!(
+            "Strategy {:?} is stopped, cancelling all its orders",
+            configuration_descriptor
+        );
+
+        let mut explanation = Explanation::default();
+        for trade_place_state in &self.trade_places {
+            for (_, state_by_side) in trade_place_state.orders_state.by_side.iter() {
+                for price_slot in &state_by_side.slots {
+                    self.start_cancelling_all_orders(
+                        "strategy is stopped",
+                        &mut price_slot.order.borrow_mut(),
+                        &mut explanation,
+                    );
+                }
+            }
+        }
+
+        // trading context should be synchronized again after strategy start
+        *last_trading_context = None;
+    }
+
     fn synchronize_price_slots_for_trading_context(
-        &mut self,
+        &self,
         trading_context: &mut Option<TradingContext>,
         now: DateTime,
     ) -> Result<()> {
-        for (side, state_by_side) in self.orders_state.by_side.iter() {
-            let trading_context_by_side =
-                match trading_context.as_mut().map(|x| &mut x.by_side[side]) {
-                    None => continue,
-                    Some(v) => v,
-                };
+        for trade_place_state in &self.trade_places {
+            let trading_context_by_trade_place = match trading_context
+                .as_mut()
+                .and_then(|x| x.by_trade_place.get_mut(&trade_place_state.trade_place))
+            {
+                None => continue,
+                Some(v) => v,
+            };
 
-            self.synchronize_price_slots_for_list(
-                &state_by_side.slots,
-                &mut trading_context_by_side.estimating[..],
-                trading_context_by_side.max_amount,
-                now,
-            )?
+            for (side, state_by_side) in trade_place_state.orders_state.by_side.iter() {
+                let trading_context_by_side = &mut trading_context_by_trade_place.by_side[side];
+
+                self.synchronize_price_slots_for_list(
+                    trade_place_state,
+                    &state_by_side.slots,
+                    &mut trading_context_by_side.estimating[..],
+                    trading_context_by_side.max_amount,
+                    now,
+                )?
+            }
         }
 
         // TODO save explanations
@@ -330,13 +455,14 @@ impl DispositionExecutor {
 
     fn synchronize_price_slots_for_list(
         &self,
+        trade_place_state: &TradePlaceState,
         slots: &[PriceSlot],
         estimating: &mut [WithExplanation<Option<TradeCycle>>],
         max_amount: Decimal,
         now: DateTime,
     ) -> Result<()> {
         if slots.len() != estimating.len() {
-            bail!("ExchangeAccountId {} slots count is different is trading context ({}) and DispositionExecutor state ({})", self.exchange_account_id, estimating.len(), slots.len());
+            bail!("ExchangeAccountId {} slots count is different is trading context ({}) and DispositionExecutor state ({})", trade_place_state.exchange_account_id(), estimating.len(), slots.len());
         }
 
         for level_index in 0..slots.len() {
@@ -345,7 +471,14 @@ impl DispositionExecutor {
 
             let (trade_cycle, explanation) = with_explanation.as_mut_all();
 
-            self.synchronize_price_slot(trade_cycle, price_slot, max_amount, now, explanation)?;
+            self.synchronize_price_slot(
+                trade_place_state,
+                trade_cycle,
+                price_slot,
+                max_amount,
+                now,
+                explanation,
+            )?;
         }
 
         Ok(())
@@ -353,6 +486,7 @@ impl DispositionExecutor {
 
     fn synchronize_price_slot(
         &self,
+        trade_place_state: &TradePlaceState,
         new_estimating: &Option<TradeCycle>,
         price_slot: &PriceSlot,
         max_amount: Decimal,
@@ -369,7 +503,7 @@ impl DispositionExecutor {
         if self
             .engine_ctx
             .exchange_blocker
-            .is_blocked(self.exchange_account_id)
+            .is_blocked(trade_place_state.exchange_account_id())
         {
             self.start_cancelling_all_orders(
                 "target exchange is locked",
@@ -400,6 +534,13 @@ impl DispositionExecutor {
             Some(v) => v,
         };
         let new_estimating_disposition = &new_estimating.disposition;
+        if new_estimating_disposition.trade_place_account() != trade_place_state.trade_place {
+            bail!(
+                "Disposition {:?} is placed in trading context of other trade place {:?}",
+                new_estimating_disposition,
+                trade_place_state.trade_place
+            );
+        }
 
         let composite_order_ref = composite_order.borrow();
         if composite_order_ref.side != new_estimating_disposition.side() {
@@ -461,6 +602,7 @@ impl DispositionExecutor {
 
             drop(composite_order_ref);
             self.try_create_order(
+                trade_place_state,
                 desired_amount - remaining_amount,
                 price_slot,
                 new_estimating,
@@ -477,6 +619,7 @@ impl DispositionExecutor {
             if composite_order_ref.orders.is_empty() {
                 drop(composite_order_ref);
                 self.try_create_order(
+                    trade_place_state,
                     desired_amount,
                     price_slot,
                     new_estimating,
@@ -484,6 +627,29 @@ impl DispositionExecutor {
                     now,
                     explanation,
                 )?;
+            } else if get_replaceable_order(&composite_order_ref).is_some() {
+                explanation.add_reason("Replacing existing order");
+
+                drop(composite_order_ref);
+                self.try_replace_order(
+                    trade_place_state,
+                    desired_amount,
+                    price_slot,
+                    new_estimating,
+                    max_amount,
+                    now,
+                    explanation,
+                )?;
+
+                // Order with outdated price shouldn't stay alive if it wasn't replaced
+                let composite_order_mut = &mut price_slot.order.borrow_mut();
+                if get_replaceable_order(composite_order_mut).is_some() {
+                    self.start_cancelling_all_orders(
+                        "order replacement failed",
+                        composite_order_mut,
+                        explanation,
+                    );
+                }
             } else {
                 explanation.add_reason("Cancelling existing orders");
 
@@ -554,12 +720,60 @@ impl DispositionExecutor {
 
         log::trace!("Begin cancel_order {}", order.client_order_id());
 
-        let client_order_id = order.client_order_id();
-        let request_group_id = order_record.request_group_id.clone();
-        let exchange = self.exchange();
+        self.pending_order_requests
+            .borrow_mut()
+            .push(PendingOrderRequest::Cancel {
+                order,
+                request_group_id: order_record.request_group_id,
+            });
+    }
+
+    /// Sends order requests collected while handling event.
+    /// Requests to exchange supporting batch orders are grouped into batches
+    fn send_pending_order_requests(&self) {
+        let pending_order_requests = self.pending_order_requests.take();
+        if pending_order_requests.is_empty() {
+            return;
+        }
+
+        let requests_by_exchange = pending_order_requests
+            .into_iter()
+            .into_group_map_by(|request| request.exchange_account_id());
+        for (exchange_account_id, requests) in requests_by_exchange {
+            let exchange = self.exchange(exchange_account_id);
+            let (orders_to_create, orders_to_cancel): (Vec<_>, Vec<_>) =
+                requests.into_iter().partition_map(|request| match request {
+                    PendingOrderRequest::Create(order_creating) => Either::Left(order_creating),
+                    PendingOrderRequest::Cancel {
+                        order,
+                        request_group_id,
+                    } => Either::Right((order, request_group_id)),
+                });
+
+            if exchange.features.order_features.supports_batch_orders {
+                self.spawn_batch_cancel_orders(exchange.clone(), orders_to_cancel);
+                self.spawn_batch_create_orders(exchange, orders_to_create);
+            } else {
+                for (order, request_group_id) in orders_to_cancel {
+                    self.spawn_cancel_order(exchange.clone(), order, request_group_id);
+                }
+                for order_creating in orders_to_create {
+                    self.spawn_create_order(exchange.clone(), order_creating);
+                }
+            }
+        }
+    }
+
+    fn spawn_cancel_order(
+        &self,
+        exchange: Arc<Exchange>,
+        order: OrderRef,
+        request_group_id: RequestGroupId,
+    ) {
         let cancellation_token = self.cancellation_token.clone();
 
         let action = async move {
+            let client_order_id = order.client_order_id();
             log::trace!("Begin wait_cancel_order {}", client_order_id);
             exchange
                 .wait_cancel_order(order, Some(request_group_id), false, cancellation_token)
@@ -575,6 +789,82 @@ impl DispositionExecutor {
         );
     }
 
+    fn spawn_create_order(&self, exchange: Arc<Exchange>, order_creating: OrderCreating) {
+        let cancellation_token = self.cancellation_token.clone();
+
+        let action = async move {
+            let client_order_id = &order_creating.header.client_order_id;
+            log::trace!("Begin create_order {}", client_order_id);
+            exchange
+                .create_order(&order_creating, None, cancellation_token)
+                .await?;
+            log::trace!("Finished create_order {}", client_order_id);
+
+            Ok(())
+        };
+        spawn_future(
+            "Start create_order from DispositionExecutor::place_order()",
+            true,
+            action.boxed(),
+        );
+    }
+
+    fn spawn_batch_cancel_orders(
+        &self,
+        exchange: Arc<Exchange>,
+        orders_to_cancel: Vec<(OrderRef, RequestGroupId)>,
+    ) {
+        if orders_to_cancel.is_empty() {
+            return;
+        }
+
+        let cancellation_token = self.cancellation_token.clone();
+
+        let action = async move {
+            let orders = orders_to_cancel
+                .into_iter()
+                .map(|(order, request_group_id)| (order, Some(request_group_id)))
+                .collect_vec();
+            log::trace!("Begin batch_cancel_orders {}", orders.len());
+            let results = exchange
+                .batch_cancel_orders(&orders, cancellation_token)
+                .await;
+            log_batch_errors("cancel", results);
+            log::trace!("Finished batch_cancel_orders {}", orders.len());
+
+            Ok(())
+        };
+        spawn_future(
+            "Start batch_cancel_orders from DispositionExecutor",
+            true,
+            action.boxed(),
+        );
+    }
+
+    fn spawn_batch_create_orders(&self, exchange: Arc<Exchange>, orders: Vec<OrderCreating>) {
+        if orders.is_empty() {
+            return;
+        }
+
+        let cancellation_token = self.cancellation_token.clone();
+
+        let action = async move {
+            log::trace!("Begin batch_create_orders {}", orders.len());
+            let results = exchange
+                .batch_create_orders(&orders, cancellation_token)
+                .await;
+            log_batch_errors("create", results);
+            log::trace!("Finished batch_create_orders {}", orders.len());
+
+            Ok(())
+        };
+        spawn_future(
+            "Start batch_create_orders from DispositionExecutor",
+            true,
+            action.boxed(),
+        );
+    }
+
     fn start_cancelling_orders_with_cause<'a>(
         &self,
         cause: &str,
@@ -590,6 +880,7 @@ impl DispositionExecutor {
 
     fn try_create_order(
         &self,
+        trade_place_state: &TradePlaceState,
         desired_amount: Decimal,
         price_slot: &PriceSlot,
         new_estimating: &TradeCycle,
@@ -599,40 +890,179 @@ impl DispositionExecutor {
     ) -> Result<()> {
         log::trace!("Begin try_create_order");
 
+        let new_order_amount = self.calculate_new_order_amount(
+            trade_place_state,
+            price_slot.order.borrow().side,
+            desired_amount,
+            max_amount,
+            explanation,
+        );
+
+        self.place_order(
+            trade_place_state,
+            price_slot,
+            new_estimating,
+            new_order_amount,
+            None,
+            now,
+            explanation,
+        )
+    }
+
+    /// Replace single order of price slot by order with new price instead of cancelling it
+    /// and creating the new order after cancellation
+    fn try_replace_order(
+        &self,
+        trade_place_state: &TradePlaceState,
+        desired_amount: Decimal,
+        price_slot: &PriceSlot,
+        new_estimating: &TradeCycle,
+        max_amount: Decimal,
+        now: DateTime,
+        explanation: &mut Explanation,
+    ) -> Result<()> {
+        log::trace!("Begin try_replace_order");
+
+        let replaced_order = match get_replaceable_order(&price_slot.order.borrow()) {
+            None => {
+                return log_trace(
+                    "Finished `try_replace_order` because there is no order to replace",
+                    explanation,
+                )
+            }
+            Some(order) => order,
+        };
+
+        // Unfilled amount of the replaced order will be transferred to the new order
+        let replaced_unfilled_amount = replaced_order.amount() - replaced_order.filled_amount();
+        let new_order_amount = self.calculate_new_order_amount(
+            trade_place_state,
+            price_slot.order.borrow().side,
+            desired_amount,
+            max_amount + replaced_unfilled_amount,
+            explanation,
+        );
+
+        self.place_order(
+            trade_place_state,
+            price_slot,
+            new_estimating,
+            new_order_amount,
+            Some(replaced_order),
+            now,
+            explanation,
+        )
+    }
+
+    fn check_risk_limits(
+        &self,
+        trade_place_state: &TradePlaceState,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        replaced_order: Option<&OrderRef>,
+        now: DateTime,
+    ) -> Result<(), RiskLimitViolation> {
+        let trade_place = trade_place_state.trade_place;
+        let exchange_account_id = trade_place.exchange_account_id;
+
+        let middle_price = self
+            .local_snapshots_service
+            .get_snapshot(trade_place.trade_place())
+            .and_then(|snapshot| snapshot.calculate_middle_price(trade_place.trade_place()));
+
+        let pending_order_requests = self.pending_order_requests.borrow();
+        let pending_orders = pending_order_requests
+            .iter()
+            .filter_map(|request| match request {
+                PendingOrderRequest::Create(order_creating) => Some(order_creating),
+                PendingOrderRequest::Cancel { .. } => None,
+            });
+        let context = OrderRiskContext::new(
+            &self.exchange(exchange_account_id),
+            &self.engine_ctx.balance_manager.lock(),
+            trade_place.currency_pair,
+            pending_orders,
+            replaced_order,
+            middle_price,
+        );
+
+        self.engine_ctx.pre_trade_risk.check_order(
+            exchange_account_id,
+            &trade_place_state.symbol,
+            side,
+            price,
+            amount,
+            &context,
+            now,
+        )
+    }
+
+    fn place_order(
+        &self,
+        trade_place_state: &TradePlaceState,
+        price_slot: &PriceSlot,
+        new_estimating: &TradeCycle,
+        new_order_amount: Amount,
+        replaced_order: Option<OrderRef>,
+        now: DateTime,
+        explanation: &mut Explanation,
+    ) -> Result<()> {
         let side = price_slot.order.borrow().side;
         let new_disposition = &new_estimating.disposition;
 
         let new_price = new_disposition.order.price;
-        let found = self.find_new_order_crossing_existing_orders(new_price, side);
+        let found =
+            self.find_new_order_crossing_existing_orders(trade_place_state, new_price, side);
         if let Some(crossed_order) = found {
-            let msg = format!("Finished `try_create_order` because there is order {} with price {} that crossing current price {}", crossed_order.client_order_id(),
+            let msg = format!("Finished `place_order` because there is order {} with price {} that crossing current price {}", crossed_order.client_order_id(),
                                  crossed_order.price(),
                                  new_price
             );
             return log_trace(msg, explanation);
         }
 
-        let new_order_amount = self.calculate_new_order_amount(
-            new_disposition.trade_place_account(),
-            side,
-            desired_amount,
-            max_amount,
-            explanation,
-        );
+        if let Err(reason) = is_enough_amount_and_cost(
+            new_disposition,
+            new_order_amount,
+            true,
+            &trade_place_state.symbol,
+        ) {
+            return log_trace(
+                format!("Finished `place_order` by reason: {}", reason),
+                explanation,
+            );
+        }
 
-        if let Err(reason) =
-            is_enough_amount_and_cost(new_disposition, new_order_amount, true, &self.symbol)
-        {
+        if let Err(violation) = self.check_risk_limits(
+            trade_place_state,
+            new_disposition.side(),
+            new_price,
+            new_order_amount,
+            replaced_order.as_ref(),
+            now,
+        ) {
+            self.statistics
+                .register_risk_rejected_order(trade_place_state.trade_place);
+            log::warn!(
+                "Order on {:?} wasn't created because it violates risk limit: {}",
+                trade_place_state.trade_place,
+                violation
+            );
             return log_trace(
-                format!("Finished `try_create_order` by reason: {}", reason),
+                format!(
+                    "Finished `place_order` because order violates risk limit: {}",
+                    violation
+                ),
                 explanation,
             );
         }
 
         let new_client_order_id = ClientOrderId::unique_id();
+        let exchange_account_id = trade_place_state.exchange_account_id();
 
         let requests_group_id = self.engine_ctx.timeout_manager.try_reserve_group(
-            self.exchange_account_id,
+            exchange_account_id,
             GROUP_REQUESTS_COUNT,
             DISPOSITION_EXECUTOR_REQUESTS_GROUP.to_string(),
         )?;
@@ -640,20 +1070,29 @@ impl DispositionExecutor {
         let requests_group_id = match requests_group_id {
             None => {
                 return log_trace(
-                    "Finished `try_create_order` because can't reserve reservation group",
+                    "Finished `place_order` because can't reserve reservation group",
                     explanation,
                 )
             }
             Some(v) => v,
         };
 
+        // Only amount exceeding unfilled amount of the replaced order should be reserved,
+        // the rest will be transferred from the replaced order reservation
+        let reserved_amount = match &replaced_order {
+            None => new_order_amount,
+            Some(replaced_order) => (new_order_amount
+                - (replaced_order.amount() - replaced_order.filled_amount()))
+            .max(dec!(0)),
+        };
+
         let target_reserve_parameters = ReserveParameters::new(
             self.strategy.configuration_descriptor(),
-            self.exchange_account_id,
-            self.symbol.clone(),
+            exchange_account_id,
+            trade_place_state.symbol.clone(),
             new_disposition.side(),
             new_disposition.price(),
-            new_order_amount,
+            reserved_amount,
         );
 
         let reservation_id;
@@ -662,7 +1101,7 @@ impl DispositionExecutor {
 
             // This expect can happened if try_reserve() sets the explanation to None
             let explanation_err_msg =
-                "DispositionExecutor::try_create_order(): Explanation should be non None here";
+                "DispositionExecutor::place_order(): Explanation should be non None here";
 
             reservation_id = match self
                 .engine_ctx
@@ -674,18 +1113,18 @@ impl DispositionExecutor {
                 None => {
                     self.engine_ctx
                         .timeout_manager
-                        .remove_group(self.exchange_account_id, requests_group_id)
+                        .remove_group(exchange_account_id, requests_group_id)
                         .with_expect(|| {
                             format!(
                                 "failed to remove_group for {} {}",
-                                self.exchange_account_id, requests_group_id,
+                                exchange_account_id, requests_group_id,
                             )
                         });
 
                     return log_trace(
                         format!(
-                            "Finished try_create_order because can't reserve balance {}",
-                            new_order_amount
+                            "Finished place_order because can't reserve balance {}",
+                            reserved_amount
                         ),
                         &mut explanation.expect(explanation_err_msg),
                     );
@@ -696,19 +1135,17 @@ impl DispositionExecutor {
         };
 
         if !self.engine_ctx.timeout_manager.try_reserve_group_instant(
-            self.exchange_account_id,
+            exchange_account_id,
             RequestType::CancelOrder,
             Some(requests_group_id),
         )? {
             self.engine_ctx
                 .balance_manager
                 .lock()
-                .unreserve_rest(
-                    reservation_id,
-                )
+                .unreserve_rest(reservation_id)
                 .with_expect(|| {
                     format!(
-                        "DispositionExecutor::try_create_order() failed to unreserve_rest for: {:?}",
+                        "DispositionExecutor::place_order() failed to unreserve_rest for: {:?}",
                         reservation_id
                     )
                 });
@@ -716,10 +1153,10 @@ impl DispositionExecutor {
             let _ = self
                 .engine_ctx
                 .timeout_manager
-                .remove_group(self.exchange_account_id, requests_group_id)?;
+                .remove_group(exchange_account_id, requests_group_id)?;
 
             return log_trace(
-                "Finished `try_create_order` because can't reserve requests",
+                "Finished `place_order` because can't reserve requests",
                 explanation,
             );
         }
@@ -729,8 +1166,8 @@ impl DispositionExecutor {
         let new_order_header = OrderHeader::new(
             new_client_order_id.clone(),
             now,
-            self.exchange_account_id,
-            self.symbol.currency_pair(),
+            exchange_account_id,
+            trade_place_state.symbol.currency_pair(),
             OrderType::Limit,
             new_disposition.side(),
             new_order_amount,
@@ -740,7 +1177,18 @@ impl DispositionExecutor {
             new_estimating.strategy_name.clone(),
         );
 
-        let exchange = self.exchange();
+        let exchange = self.exchange(exchange_account_id);
+
+        let replaced_order_group_id = replaced_order.as_ref().map(|replaced_order| {
+            let mut composite_order = price_slot.order.borrow_mut();
+            let order_record = composite_order
+                .orders
+                .get_mut(&replaced_order.client_order_id())
+                .expect("Replaced order should be in price slot");
+            order_record.is_cancellation_requested = true;
+            order_record.is_replaced = true;
+            order_record.request_group_id
+        });
 
         let new_order = exchange
             .orders
@@ -753,43 +1201,60 @@ impl DispositionExecutor {
             requests_group_id,
         );
 
-        explanation.add_reason(format!("Creating order {}", new_client_order_id));
+        match &replaced_order {
+            None => explanation.add_reason(format!("Creating order {}", new_client_order_id)),
+            Some(replaced_order) => explanation.add_reason(format!(
+                "Replacing order {} by {}",
+                replaced_order.client_order_id(),
+                new_client_order_id
+            )),
+        }
 
         self.cancellation_token.error_if_cancellation_requested()?;
 
-        {
-            let new_client_order_id = new_client_order_id.clone();
-            let cancellation_token = self.cancellation_token.clone();
-
-            let action = async move {
-                log::trace!("Begin create_order {}", new_client_order_id);
+        let order_creating = OrderCreating {
+            header: new_order_header,
+            price: new_price,
+        };
 
-                let order_creating = OrderCreating {
-                    header: new_order_header,
-                    price: new_price,
+        match replaced_order {
+            None => self
+                .pending_order_requests
+                .borrow_mut()
+                .push(PendingOrderRequest::Create(order_creating)),
+            Some(replaced_order) => {
+                let new_client_order_id = new_client_order_id.clone();
+                let cancellation_token = self.cancellation_token.clone();
+
+                let action = async move {
+                    log::trace!("Begin replace_order {}", new_client_order_id);
+                    exchange
+                        .replace_order(
+                            &replaced_order,
+                            &order_creating,
+                            replaced_order_group_id,
+                            cancellation_token,
+                        )
+                        .await?;
+                    log::trace!("Finished replace_order {}", new_client_order_id);
+
+                    Ok(())
                 };
-
-                exchange
-                    .create_order(&order_creating, None, cancellation_token)
-                    .await?;
-
-                log::trace!("Finished create_order {}", new_client_order_id);
-
-                Ok(())
-            };
-            spawn_future(
-                "wait_cancel_order in blocking cancel_order",
-                true,
-                action.boxed(),
-            );
+                spawn_future(
+                    "Start replace_order from DispositionExecutor::place_order()",
+                    true,
+                    action.boxed(),
+                );
+            }
         }
 
-        log::trace!("Begin try_create_order {}", new_client_order_id);
+        log::trace!("Finished place_order {}", new_client_order_id);
         Ok(())
     }
 
     fn find_new_order_crossing_existing_orders(
         &self,
+        trade_place_state: &TradePlaceState,
         new_order_price: Price,
         side: OrderSide,
     ) -> Option<OrderRef> {
@@ -801,7 +1266,7 @@ impl DispositionExecutor {
             OrderSide::Sell => sell_comparator,
         };
 
-        for slot in &self.orders_state.by_side[side.change_side()].slots {
+        for slot in &trade_place_state.orders_state.by_side[side.change_side()].slots {
             for (_, order_record) in &slot.order.borrow().orders {
                 let order = &order_record.order;
                 if order.is_finished() && is_crossing(order) {
@@ -815,13 +1280,14 @@ impl DispositionExecutor {
 
     fn calculate_new_order_amount(
         &self,
-        _trade_place_account: TradePlaceAccount,
+        trade_place_state: &TradePlaceState,
         side: OrderSide,
         desired_amount: Decimal,
         max_amount: Decimal,
         explanation: &mut Explanation,
     ) -> Decimal {
-        let total_remaining_amount = self.orders_state.by_side[side].calc_total_remaining_amount();
+        let total_remaining_amount =
+            trade_place_state.orders_state.by_side[side].calc_total_remaining_amount();
         // TODO is needed high priority amount?
         let high_priority_amount = dec!(0);
         let balance_quota = max_amount - total_remaining_amount;
@@ -834,17 +1300,47 @@ impl DispositionExecutor {
         new_amount
     }
 
+    fn add_fill_to_pnl_ledger(&self, order: &OrderSnapshot) {
+        let trade_place =
+            TradePlaceAccount::new(order.header.exchange_account_id, order.header.currency_pair);
+        let trade_place_state = match self
+            .trade_places
+            .iter()
+            .find(|x| x.trade_place == trade_place)
+        {
+            Some(trade_place_state) => trade_place_state,
+            None => return,
+        };
+
+        if let Some(order_fill) = order.fills.fills.last() {
+            self.engine_ctx.pnl_ledger.add_fill(
+                self.strategy.configuration_descriptor(),
+                trade_place,
+                &trade_place_state.symbol,
+                order_fill.side().unwrap_or(order.header.side),
+                order_fill,
+            );
+        }
+    }
+
     fn get_price_slot(&self, order: &OrderRef) -> Option<&PriceSlot> {
-        let side = order.side();
-        let price_slot = self.orders_state.by_side[side].find_price_slot(order);
+        let trade_place =
+            TradePlaceAccount::new(order.exchange_account_id(), order.currency_pair());
+        let price_slot = self
+            .trade_places
+            .iter()
+            .find(|x| x.trade_place == trade_place)
+            .and_then(|x| x.orders_state.by_side[order.side()].find_price_slot(order));
         if price_slot.is_some() {
             return price_slot;
         }
 
-        log::error!(
-            "Can't find order with client_order_id {} {} in orders state of DispositionExecutor",
+        // Order could be created by another strategy hosted in the engine
+        log::trace!(
+            "Can't find order with client_order_id {} {} in orders state of DispositionExecutor for strategy {:?}",
             order.client_order_id(),
-            self.exchange_account_id
+            order.exchange_account_id(),
+            self.strategy.configuration_descriptor()
         );
         return None;
     }
@@ -875,15 +1371,21 @@ impl DispositionExecutor {
                 x.header.amount,
             )
         });
+        let reservation_id = reservation_id.expect("InternalEventsLoop: ReservationId is None");
 
-        self.engine_ctx
-            .balance_manager
-            .lock()
-            .unreserve_by_client_order_id(
-                reservation_id.expect("InternalEventsLoop: ReservationId is None"),
-                client_order_id,
-                amount,
-            )
+        let mut balance_manager = self.engine_ctx.balance_manager.lock();
+        if balance_manager.get_reservation(reservation_id).is_none() {
+            // Reservation could be already released by order replacement
+            log::trace!(
+                "Reservation {} of order {} is already released",
+                reservation_id,
+                client_order_id
+            );
+            return;
+        }
+
+        balance_manager
+            .unreserve_by_client_order_id(reservation_id, client_order_id, amount)
             .with_expect(|| format!("InternalEventsLoop: failed to unreserve order {:?}", order));
     }
 
@@ -894,7 +1396,7 @@ impl DispositionExecutor {
         let _ = self
             .engine_ctx
             .timeout_manager
-            .remove_group(self.exchange_account_id, request_group_id)?;
+            .remove_group(order.exchange_account_id(), request_group_id)?;
         Ok(())
     }
 
@@ -908,7 +1410,7 @@ impl DispositionExecutor {
         let result = self.strategy.handle_order_fill(
             cloned_order,
             price_slot,
-            self.exchange_account_id,
+            cloned_order.header.exchange_account_id,
             self.cancellation_token.clone(),
         );
 
@@ -916,10 +1418,10 @@ impl DispositionExecutor {
         result
     }
 
-    fn exchange(&self) -> Arc<Exchange> {
+    fn exchange(&self, exchange_account_id: ExchangeAccountId) -> Arc<Exchange> {
         self.engine_ctx
             .exchanges
-            .get(&self.exchange_account_id)
+            .get(&exchange_account_id)
             .expect("Target exchange for strategy should exists")
             .value()
             .clone()
@@ -965,6 +1467,19 @@ fn estimate_trading_context(
     ))
 }
 
+/// Order can be replaced if it is the only order of price slot and it is alive on exchange
+fn get_replaceable_order(composite_order: &CompositeOrder) -> Option<OrderRef> {
+    if composite_order.orders.len() != 1 {
+        return None;
+    }
+
+    composite_order
+        .orders
+        .values()
+        .find(|x| !x.is_cancellation_requested && x.order.status() == OrderStatus::Created)
+        .map(|x| x.order.clone())
+}
+
 fn get_cancelling_orders<'a>(
     order_records: impl Iterator<Item = &'a mut OrderRecord>,
     desired_amount: Amount,
@@ -974,7 +1489,8 @@ fn get_cancelling_orders<'a>(
 
     let delta_amount = remaining_amount - desired_amount;
 
-    let mut sorted_order_records = order_records.collect_vec();
+    // Amount of replaced orders is already taken into account by orders replacing them
+    let mut sorted_order_records = order_records.filter(|x| !x.is_replaced).collect_vec();
     sorted_order_records.sort_by_key(|x| x.order.amount());
 
     let mut cancelling_orders = Vec::with_capacity(sorted_order_records.len());
@@ -1006,8 +1522,21 @@ fn get_cancelling_orders<'a>(
     cancelling_orders
 }
 
+fn log_batch_errors<T>(action: &str, results: Vec<(ClientOrderId, Result<T>)>) {
+    for (client_order_id, result) in results {
+        if let Err(error) = result {
+            log::error!(
+                "Failed to {} order {} by batch: {:?}",
+                action,
+                client_order_id,
+                error
+            );
+        }
+    }
+}
+
 fn now() -> DateTime {
-    Utc::now()
+    time_manager::now()
 }
 
 #[inline(always)]
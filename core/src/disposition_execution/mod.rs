@@ -132,19 +132,50 @@ impl TradingContextBySide {
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub struct TradingContext {
+pub struct TradingContextByTradePlace {
     pub by_side: EnumMap<OrderSide, TradingContextBySide>,
 }
 
-impl TradingContext {
+impl TradingContextByTradePlace {
     pub fn new(buy_ctx: TradingContextBySide, sell_ctx: TradingContextBySide) -> Self {
-        TradingContext {
+        TradingContextByTradePlace {
             // TODO use more typesafe way when it will be available for non-Copy types
             by_side: EnumMap::from_array([buy_ctx, sell_ctx]),
         }
     }
 }
 
+/// Desired orders of strategy for each trade place where it trades
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct TradingContext {
+    pub by_trade_place: HashMap<TradePlaceAccount, TradingContextByTradePlace>,
+}
+
+impl TradingContext {
+    /// Trading context of strategy which trades on a single trade place
+    pub fn new(
+        trade_place: TradePlaceAccount,
+        buy_ctx: TradingContextBySide,
+        sell_ctx: TradingContextBySide,
+    ) -> Self {
+        let mut trading_context = TradingContext::default();
+        trading_context.add_trade_place(trade_place, buy_ctx, sell_ctx);
+        trading_context
+    }
+
+    pub fn add_trade_place(
+        &mut self,
+        trade_place: TradePlaceAccount,
+        buy_ctx: TradingContextBySide,
+        sell_ctx: TradingContextBySide,
+    ) {
+        let _ = self.by_trade_place.insert(
+            trade_place,
+            TradingContextByTradePlace::new(buy_ctx, sell_ctx),
+        );
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct PriceSlotId {
     pub strategy_name: String,
@@ -170,6 +201,8 @@ impl Display for PriceSlotId {
 pub struct OrderRecord {
     pub order: OrderRef,
     pub is_cancellation_requested: bool,
+    /// Order is replaced by another order of the same price slot
+    pub is_replaced: bool,
     pub request_group_id: RequestGroupId,
 }
 
@@ -178,6 +211,7 @@ impl OrderRecord {
         OrderRecord {
             order,
             is_cancellation_requested: false,
+            is_replaced: false,
             request_group_id,
         }
     }
@@ -207,7 +241,7 @@ impl CompositeOrder {
             .iter()
             .filter_map(|(_, or)| {
                 let order = &or.order;
-                if !order.is_finished() {
+                if !order.is_finished() && !or.is_replaced {
                     Some(order.fn_ref(|x| x.header.amount - x.fills.filled_amount))
                 } else {
                     None
@@ -294,14 +328,14 @@ struct OrdersStateBySide {
 }
 
 impl OrdersStateBySide {
-    pub fn new(_side: OrderSide) -> Self {
+    pub fn new(_side: OrderSide, price_slots_count: usize) -> Self {
         OrdersStateBySide {
             _side,
-            // TODO create list of PriceSlots by config
-            slots: vec![PriceSlot::new(
-                PriceSlotId::new("PriceSlotId".into(), 0),
-                _side,
-            )],
+            slots: (0..price_slots_count)
+                .map(|level_index| {
+                    PriceSlot::new(PriceSlotId::new("PriceSlotId".into(), level_index), _side)
+                })
+                .collect(),
         }
     }
 
@@ -327,10 +361,10 @@ struct OrdersState {
 }
 
 impl OrdersState {
-    pub fn new() -> Self {
+    pub fn new(price_slots_count: usize) -> Self {
         OrdersState {
             by_side: enum_map! {
-                side => OrdersStateBySide::new(side),
+                side => OrdersStateBySide::new(side, price_slots_count),
             },
         }
     }
@@ -506,6 +506,16 @@ impl ExchangeBlocker {
             .is_some()
     }
 
+    pub fn get_block_reasons(&self, exchange_account_id: ExchangeAccountId) -> Vec<BlockReason> {
+        self.blockers
+            .read()
+            .get(&exchange_account_id)
+            .expect(EXPECTED_EAI_SHOULD_BE_CREATED)
+            .keys()
+            .cloned()
+            .collect()
+    }
+
     pub fn is_blocked_except_reason(
         &self,
         exchange_account_id: ExchangeAccountId,
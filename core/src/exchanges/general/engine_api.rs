@@ -16,6 +16,12 @@ pub struct EngineApi {
     exchange: Arc<Exchange>,
 }
 
+impl EngineApi {
+    pub fn new(exchange: Arc<Exchange>) -> Arc<Self> {
+        Arc::new(Self { exchange })
+    }
+}
+
 #[cfg_attr(test, automock)]
 impl EngineApi {
     pub async fn close_active_positions(
@@ -55,6 +61,23 @@ impl EngineApi {
 
         closed_positions
     }
+
+    pub async fn cancel_opened_orders(&self, cancellation_token: CancellationToken) {
+        log::info!(
+            "Cancelling opened orders for exchange {}",
+            self.exchange.exchange_account_id
+        );
+
+        self.exchange
+            .clone()
+            .cancel_opened_orders(cancellation_token, true)
+            .await;
+
+        log::info!(
+            "Cancelled opened orders for exchange {}",
+            self.exchange.exchange_account_id
+        );
+    }
 }
 
 impl_mock_initializer!(MockEngineApi);
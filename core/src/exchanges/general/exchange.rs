@@ -29,10 +29,12 @@ use crate::exchanges::general::request_type::RequestType;
 use crate::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
 use crate::misc::derivative_position::DerivativePosition;
+use crate::misc::price_by_order_side::PriceByOrderSide;
 use crate::misc::time::time_manager;
 use crate::orders::buffered_fills::buffered_canceled_orders_manager::BufferedCanceledOrdersManager;
 use crate::orders::buffered_fills::buffered_fills_manager::BufferedFillsManager;
 use crate::orders::event::OrderEventType;
+use crate::orders::journal::OrdersJournal;
 use crate::orders::order::{OrderHeader, OrderSide};
 use crate::orders::pool::OrdersPool;
 use crate::orders::{order::ExchangeOrderId, pool::OrderRef};
@@ -52,7 +54,7 @@ use crate::{
     connectivity::{
         connectivity_manager::ConnectivityManager, websocket_connection::WebSocketParams,
     },
-    orders::order::ClientOrderId,
+    orders::order::{ClientOrderId, ReservationId},
 };
 use crate::{
     exchanges::common::{Amount, CurrencyCode, Price},
@@ -91,6 +93,17 @@ pub struct PriceLevel {
 pub struct OrderBookTop {
     pub ask: Option<PriceLevel>,
     pub bid: Option<PriceLevel>,
+    /// Time of the last order book update
+    pub updated_at: DateTime,
+}
+
+impl OrderBookTop {
+    pub fn get_top_prices(&self) -> PriceByOrderSide {
+        PriceByOrderSide::new(
+            self.bid.as_ref().map(|level| level.price),
+            self.ask.as_ref().map(|level| level.price),
+        )
+    }
 }
 
 pub struct Exchange {
@@ -102,7 +115,7 @@ pub struct Exchange {
     pub leverage_by_currency_pair: DashMap<CurrencyPair, Decimal>,
     pub order_book_top: DashMap<CurrencyPair, OrderBookTop>,
     pub(super) exchange_client: Box<dyn ExchangeClient>,
-    pub(super) features: ExchangeFeatures,
+    pub(crate) features: ExchangeFeatures,
     pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
     pub(super) application_manager: Arc<ApplicationManager>,
     pub(super) commission: Commission,
@@ -116,6 +129,9 @@ pub struct Exchange {
     pub(super) last_trades: DashMap<TradePlace, Trade>,
     pub(super) timeout_manager: Arc<TimeoutManager>,
     pub(super) balance_manager: Mutex<Option<Weak<Mutex<BalanceManager>>>>,
+    pub(super) orders_journal: Mutex<Option<Arc<dyn OrdersJournal>>>,
+    /// Reservations of restored orders which should be released when the orders are finished
+    pub(super) restored_reservations: DashMap<ClientOrderId, ReservationId>,
     pub(super) buffered_fills_manager: Mutex<BufferedFillsManager>,
     pub(super) buffered_canceled_orders_manager: Mutex<BufferedCanceledOrdersManager>,
     // It allows to send and receive notification about event in websocket channel
@@ -180,6 +196,8 @@ impl Exchange {
             last_trades_update_time: DashMap::new(),
             last_trades: DashMap::new(),
             balance_manager: Mutex::new(None),
+            orders_journal: Mutex::new(None),
+            restored_reservations: DashMap::new(),
             buffered_fills_manager: Mutex::new(BufferedFillsManager::new()),
             buffered_canceled_orders_manager: Mutex::new(BufferedCanceledOrdersManager::new()),
         });
@@ -342,6 +360,103 @@ impl Exchange {
         *self.balance_manager.lock() = Some(Arc::downgrade(&balance_manager));
     }
 
+    pub fn setup_orders_journal(&self, orders_journal: Arc<dyn OrdersJournal>) {
+        *self.orders_journal.lock() = Some(orders_journal);
+    }
+
+    /// Saves current state of the order to the orders journal if it is set up
+    pub(crate) fn journal_order(&self, order_ref: &OrderRef) {
+        let orders_journal = match self.orders_journal.lock().clone() {
+            Some(orders_journal) => orders_journal,
+            None => return,
+        };
+
+        self.save_reservation_configuration_descriptor(order_ref);
+        if let Err(error) = order_ref.fn_ref(|order| orders_journal.save_order(order)) {
+            log::error!(
+                "Unable to save order {} on {} to orders journal: {:?}",
+                order_ref.client_order_id(),
+                self.exchange_account_id,
+                error
+            );
+        }
+    }
+
+    /// Keeps configuration of the order reservation in the order, so the reservation
+    /// is restored after restart with the same configuration
+    fn save_reservation_configuration_descriptor(&self, order_ref: &OrderRef) {
+        let (reservation_id, saved_configuration_descriptor) = order_ref.fn_ref(|order| {
+            (
+                order.header.reservation_id,
+                order.internal_props.reservation_configuration_descriptor,
+            )
+        });
+        let reservation_id = match (reservation_id, saved_configuration_descriptor) {
+            (Some(reservation_id), None) => reservation_id,
+            _ => return,
+        };
+
+        let configuration_descriptor = self
+            .with_balance_manager(|balance_manager| {
+                Ok(balance_manager
+                    .get_reservation(reservation_id)
+                    .map(|reservation| reservation.configuration_descriptor))
+            })
+            .ok()
+            .flatten();
+        if let Some(configuration_descriptor) = configuration_descriptor {
+            order_ref.fn_mut(|order| {
+                order.internal_props.reservation_configuration_descriptor =
+                    Some(configuration_descriptor)
+            });
+        }
+    }
+
+    /// Waits until all order changes queued to the orders journal are saved
+    pub async fn flush_orders_journal(self: Arc<Self>) {
+        let orders_journal = match self.orders_journal.lock().clone() {
+            Some(orders_journal) => orders_journal,
+            None => return,
+        };
+
+        if let Err(error) = orders_journal.flush().await {
+            log::error!(
+                "Unable to flush orders journal on {}: {:?}",
+                self.exchange_account_id,
+                error
+            );
+        }
+    }
+
+    fn journal_order_change(&self, order_ref: &OrderRef, event_type: &OrderEventType) {
+        let cloned_order = match event_type {
+            OrderEventType::OrderFilled { cloned_order } => cloned_order,
+            _ => return self.journal_order(order_ref),
+        };
+
+        let orders_journal = match self.orders_journal.lock().clone() {
+            Some(orders_journal) => orders_journal,
+            None => return,
+        };
+
+        if let Some(fill) = cloned_order.fills.fills.last() {
+            let result = orders_journal.save_fill(
+                self.exchange_account_id,
+                &cloned_order.header.client_order_id,
+                fill,
+            );
+            if let Err(error) = result {
+                log::error!(
+                    "Unable to save fill {} of order {} on {} to orders journal: {:?}",
+                    fill.id(),
+                    cloned_order.header.client_order_id,
+                    self.exchange_account_id,
+                    error
+                );
+            }
+        }
+    }
+
     pub async fn connect(self: Arc<Self>) {
         self.try_connect().await;
         // TODO Reconnect
@@ -351,11 +466,31 @@ impl Exchange {
         self.connectivity_manager.clone().disconnect().await
     }
 
+    /// Callback is invoked when websocket connection is closed
+    pub fn set_websocket_disconnected_callback(&self, callback: Box<dyn Fn(bool) + Send>) {
+        self.connectivity_manager
+            .set_callback_disconnected(callback)
+    }
+
+    pub fn websocket_reconnects_count(&self, role: WebSocketRole) -> u64 {
+        self.connectivity_manager.reconnects_count(role)
+    }
+
     async fn try_connect(self: Arc<Self>) {
         // TODO IsWebSocketConnecting()
-        log::info!("Websocket: Connecting on {}", "test_exchange_id");
+        if !self
+            .exchange_client
+            .is_websocket_enabled(WebSocketRole::Main)
+        {
+            log::info!(
+                "Websocket: main websocket is disabled on {}, skipping connection",
+                self.exchange_account_id
+            );
+            return;
+        }
+
+        log::info!("Websocket: Connecting on {}", self.exchange_account_id);
 
-        // TODO if UsingWebsocket
         // TODO handle results
 
         let exchange_weak = Arc::downgrade(&self);
@@ -518,7 +653,10 @@ impl Exchange {
         role: WebSocketRole,
     ) -> Result<WebSocketParams> {
         let ws_url = self.exchange_client.create_ws_url(role).await?;
-        Ok(WebSocketParams::new(ws_url))
+        let subscription_messages = self.exchange_client.get_ws_subscription_messages(role)?;
+        Ok(WebSocketParams::new(ws_url)
+            .with_subscription_messages(subscription_messages)
+            .with_traffic_fixtures(self.exchange_client.get_traffic_fixtures()))
     }
 
     pub(crate) fn add_event_on_order_change(
@@ -535,8 +673,11 @@ impl Exchange {
                 .orders
                 .not_finished
                 .remove(&order_ref.client_order_id());
+            self.release_restored_reservation(&order_ref.client_order_id());
         }
 
+        self.journal_order_change(order_ref, &event_type);
+
         let event = ExchangeEvent::OrderEvent(OrderEvent::new(order_ref.clone(), event_type));
         self.events_channel
             .send(event)
@@ -618,25 +759,38 @@ impl Exchange {
         log::info!("Closing position {}", position.id);
 
         loop {
-            self.timeout_manager
-                .reserve_when_available(
-                    self.exchange_account_id,
-                    RequestType::GetActivePositions,
-                    None,
-                    cancellation_token.clone(),
-                )
-                .expect("Failed to reserve timeout_manager for close_position")
-                .await;
-
-            log::info!("Closing position request reserved {}", position.id);
-
-            if let Ok(closed_position) = self.close_position(position, price).await {
+            if let Ok(closed_position) = self
+                .try_close_position(position, price, cancellation_token.clone())
+                .await
+            {
                 log::info!("Closed position {}", position.id);
                 return closed_position;
             }
         }
     }
 
+    /// Single attempt of closing position, unlike `close_position_loop` which retries until success
+    pub async fn try_close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Decimal>,
+        cancellation_token: CancellationToken,
+    ) -> Result<ClosedPosition> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::GetActivePositions,
+                None,
+                cancellation_token,
+            )
+            .expect("Failed to reserve timeout_manager for close_position")
+            .await;
+
+        log::info!("Closing position request reserved {}", position.id);
+
+        self.close_position(position, price).await
+    }
+
     pub async fn get_active_positions(
         &self,
         cancellation_token: CancellationToken,
@@ -709,7 +863,8 @@ impl Exchange {
         Ok(self.exchange_client.parse_get_balance(&response))
     }
 
-    async fn get_balance_and_positions(
+    /// Request balances and positions without notifying `BalanceManager` about them
+    pub(crate) async fn get_balance_and_positions(
         &self,
         cancellation_token: CancellationToken,
     ) -> Result<ExchangeBalancesAndPositions> {
@@ -1,4 +1,8 @@
+use anyhow::{bail, Result};
+use rust_decimal::Decimal;
+
 use crate::exchanges::events::AllowedEventSourceType;
+use crate::orders::order::{OrderExecutionType, OrderHeader, OrderType, TimeInForce};
 
 #[derive(Debug)]
 pub enum OpenOrdersType {
@@ -66,6 +70,14 @@ pub struct OrderFeatures {
     pub order_was_completed_error_for_cancellation: bool,
     pub supports_already_cancelled_order: bool,
     pub supports_stop_loss_order: bool,
+    pub supports_trailing_stop_order: bool,
+    pub supports_immediate_or_cancel: bool,
+    pub supports_fill_or_kill: bool,
+    pub supports_good_till_date: bool,
+    pub supports_replace_order: bool,
+    pub supports_batch_orders: bool,
+    /// Max count of orders in single batch create or cancel request
+    pub max_batch_orders_count: usize,
 }
 
 impl OrderFeatures {
@@ -77,6 +89,13 @@ impl OrderFeatures {
         order_was_completed_error_for_cancellation: bool,
         supports_already_cancelled_order: bool,
         supports_stop_loss_order: bool,
+        supports_trailing_stop_order: bool,
+        supports_immediate_or_cancel: bool,
+        supports_fill_or_kill: bool,
+        supports_good_till_date: bool,
+        supports_replace_order: bool,
+        supports_batch_orders: bool,
+        max_batch_orders_count: usize,
     ) -> Self {
         Self {
             maker_only,
@@ -86,7 +105,85 @@ impl OrderFeatures {
             order_was_completed_error_for_cancellation,
             supports_already_cancelled_order,
             supports_stop_loss_order,
+            supports_trailing_stop_order,
+            supports_immediate_or_cancel,
+            supports_fill_or_kill,
+            supports_good_till_date,
+            supports_replace_order,
+            supports_batch_orders,
+            max_batch_orders_count,
+        }
+    }
+
+    /// Check that order type and options of the order can be sent to exchange
+    pub fn validate_order(&self, header: &OrderHeader) -> Result<()> {
+        let options = &header.options;
+
+        match header.order_type {
+            OrderType::StopLoss => {
+                if !self.supports_stop_loss_order {
+                    bail!("StopLoss orders are not supported by exchange");
+                }
+                if !options.stop_price.map_or(false, |x| x > Decimal::ZERO) {
+                    bail!("StopLoss order requires positive stop price");
+                }
+                if options.trailing_delta.is_some() {
+                    bail!("Trailing delta is not allowed for StopLoss order");
+                }
+            }
+            OrderType::TrailingStop => {
+                if !self.supports_trailing_stop_order {
+                    bail!("TrailingStop orders are not supported by exchange");
+                }
+                if !options.trailing_delta.map_or(false, |x| x > Decimal::ZERO) {
+                    bail!("TrailingStop order requires positive trailing delta");
+                }
+            }
+            _ => {
+                if options.stop_price.is_some() || options.trailing_delta.is_some() {
+                    bail!(
+                        "Stop price and trailing delta are allowed only for stop orders but order type is {:?}",
+                        header.order_type
+                    );
+                }
+            }
+        }
+
+        let is_supported_time_in_force = match options.time_in_force {
+            TimeInForce::GoodTillCancelled => true,
+            TimeInForce::ImmediateOrCancel => self.supports_immediate_or_cancel,
+            TimeInForce::FillOrKill => self.supports_fill_or_kill,
+            TimeInForce::GoodTillDate(_) => self.supports_good_till_date,
+        };
+        if !is_supported_time_in_force {
+            bail!(
+                "Time in force {:?} is not supported by exchange",
+                options.time_in_force
+            );
+        }
+
+        let is_immediate = matches!(
+            options.time_in_force,
+            TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill
+        );
+        if header.execution_type == OrderExecutionType::MakerOnly && is_immediate {
+            bail!(
+                "MakerOnly order can't have time in force {:?}",
+                options.time_in_force
+            );
         }
+
+        // Market orders are executed immediately, so exchanges don't accept time in force for them
+        if header.order_type == OrderType::Market
+            && options.time_in_force != TimeInForce::GoodTillCancelled
+        {
+            bail!(
+                "Market order can't have time in force {:?}",
+                options.time_in_force
+            );
+        }
+
+        Ok(())
     }
 }
 
@@ -147,3 +244,154 @@ impl ExchangeFeatures {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::orders::order::{ClientOrderId, OrderOptions, OrderSide};
+
+    fn header(
+        order_type: OrderType,
+        execution_type: OrderExecutionType,
+        options: OrderOptions,
+    ) -> Arc<OrderHeader> {
+        OrderHeader::new_with_options(
+            ClientOrderId::unique_id(),
+            Utc::now(),
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            order_type,
+            OrderSide::Buy,
+            dec!(1),
+            execution_type,
+            None,
+            None,
+            "test".to_owned(),
+            options,
+        )
+    }
+
+    fn all_features() -> OrderFeatures {
+        OrderFeatures {
+            supports_stop_loss_order: true,
+            supports_trailing_stop_order: true,
+            supports_immediate_or_cancel: true,
+            supports_fill_or_kill: true,
+            supports_good_till_date: true,
+            ..OrderFeatures::default()
+        }
+    }
+
+    #[test]
+    fn default_limit_order_is_valid_for_any_exchange() {
+        let header = header(
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+            OrderOptions::default(),
+        );
+
+        assert!(OrderFeatures::default().validate_order(&header).is_ok());
+    }
+
+    #[test]
+    fn stop_loss_order_requires_exchange_support() {
+        let header = header(
+            OrderType::StopLoss,
+            OrderExecutionType::None,
+            OrderOptions::stop_loss(dec!(1)),
+        );
+
+        assert!(OrderFeatures::default().validate_order(&header).is_err());
+        assert!(all_features().validate_order(&header).is_ok());
+    }
+
+    #[test]
+    fn stop_loss_order_requires_stop_price() {
+        let header = header(
+            OrderType::StopLoss,
+            OrderExecutionType::None,
+            OrderOptions::default(),
+        );
+
+        assert!(all_features().validate_order(&header).is_err());
+    }
+
+    #[test]
+    fn trailing_stop_order_requires_trailing_delta() {
+        let without_delta = header(
+            OrderType::TrailingStop,
+            OrderExecutionType::None,
+            OrderOptions::stop_loss(dec!(1)),
+        );
+        let with_delta = header(
+            OrderType::TrailingStop,
+            OrderExecutionType::None,
+            OrderOptions::trailing_stop(dec!(0.5), Some(dec!(1))),
+        );
+
+        assert!(all_features().validate_order(&without_delta).is_err());
+        assert!(all_features().validate_order(&with_delta).is_ok());
+    }
+
+    #[test]
+    fn stop_price_is_not_allowed_for_limit_order() {
+        let header = header(
+            OrderType::Limit,
+            OrderExecutionType::None,
+            OrderOptions::stop_loss(dec!(1)),
+        );
+
+        assert!(all_features().validate_order(&header).is_err());
+    }
+
+    #[test]
+    fn time_in_force_requires_exchange_support() {
+        for time_in_force in [
+            TimeInForce::ImmediateOrCancel,
+            TimeInForce::FillOrKill,
+            TimeInForce::GoodTillDate(Utc::now()),
+        ] {
+            let header = header(
+                OrderType::Limit,
+                OrderExecutionType::None,
+                OrderOptions::with_time_in_force(time_in_force),
+            );
+
+            assert!(OrderFeatures::default().validate_order(&header).is_err());
+            assert!(all_features().validate_order(&header).is_ok());
+        }
+    }
+
+    #[test]
+    fn maker_only_order_can_not_be_immediate() {
+        let header = header(
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+            OrderOptions::with_time_in_force(TimeInForce::ImmediateOrCancel),
+        );
+
+        assert!(all_features().validate_order(&header).is_err());
+    }
+
+    #[test]
+    fn market_order_can_not_have_time_in_force() {
+        for time_in_force in [
+            TimeInForce::ImmediateOrCancel,
+            TimeInForce::FillOrKill,
+            TimeInForce::GoodTillDate(Utc::now()),
+        ] {
+            let header = header(
+                OrderType::Market,
+                OrderExecutionType::None,
+                OrderOptions::with_time_in_force(time_in_force),
+            );
+
+            assert!(all_features().validate_order(&header).is_err());
+        }
+    }
+}
@@ -3235,6 +3235,7 @@ mod test {
                     price: dec!(0.3),
                     amount: dec!(0.1),
                 }),
+                updated_at: Utc::now(),
             };
             exchange
                 .order_book_top
@@ -3280,6 +3281,7 @@ mod test {
                     amount: dec!(0.1),
                 }),
                 bid: None,
+                updated_at: Utc::now(),
             };
             exchange
                 .order_book_top
@@ -0,0 +1,341 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use futures::future::join_all;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::OPERATION_CANCELED_MSG;
+
+use crate::exchanges::general::exchange::RequestResult::{Error, Success};
+use crate::exchanges::{
+    general::request_type::RequestType, timeouts::requests_timeout_manager::RequestGroupId,
+};
+use crate::{
+    exchanges::common::ExchangeError, exchanges::common::ExchangeErrorType,
+    exchanges::common::RestRequestOutcome, exchanges::general::exchange::Exchange,
+    exchanges::general::exchange::RequestResult, orders::fill::EventSourceType,
+    orders::order::ClientOrderId, orders::order::ExchangeOrderId, orders::order::OrderCancelling,
+    orders::order::OrderCreating, orders::order::OrderStatus, orders::pool::OrderRef,
+};
+
+impl Exchange {
+    /// Create orders by batch requests if exchange supports them, otherwise by single request per order.
+    /// Results are returned in the same order as orders
+    pub async fn batch_create_orders(
+        &self,
+        orders: &[OrderCreating],
+        cancellation_token: CancellationToken,
+    ) -> Vec<(ClientOrderId, Result<OrderRef>)> {
+        let order_features = &self.features.order_features;
+        if !order_features.supports_batch_orders || orders.len() < 2 {
+            let results = join_all(
+                orders
+                    .iter()
+                    .map(|order| self.create_order(order, None, cancellation_token.clone())),
+            )
+            .await;
+
+            return orders
+                .iter()
+                .map(|order| order.header.client_order_id.clone())
+                .zip(results)
+                .collect();
+        }
+
+        let chunks_results = join_all(
+            orders
+                .chunks(order_features.max_batch_orders_count.max(1))
+                .map(|chunk| self.create_orders_chunk(chunk, cancellation_token.clone())),
+        )
+        .await;
+
+        chunks_results.into_iter().flatten().collect()
+    }
+
+    async fn create_orders_chunk(
+        &self,
+        orders: &[OrderCreating],
+        cancellation_token: CancellationToken,
+    ) -> Vec<(ClientOrderId, Result<OrderRef>)> {
+        log::info!("Submitting orders by batch request {:?}", orders);
+
+        let mut order_refs = Vec::with_capacity(orders.len());
+        let mut validation_errors = Vec::with_capacity(orders.len());
+        for order in orders {
+            let order_ref = self
+                .orders
+                .add_simple_initial(order.header.clone(), Some(order.price));
+            self.journal_order(&order_ref);
+            order_refs.push(order_ref);
+
+            let validation_error = self
+                .features
+                .order_features
+                .validate_order(&order.header)
+                .err()
+                .map(|error| {
+                    ExchangeError::new(ExchangeErrorType::InvalidOrder, error.to_string(), None)
+                });
+            validation_errors.push(validation_error);
+        }
+
+        let orders_to_create = orders
+            .iter()
+            .zip(&validation_errors)
+            .filter(|(_, validation_error)| validation_error.is_none())
+            .map(|(order, _)| order.clone())
+            .collect_vec();
+
+        let mut request_results = match orders_to_create.is_empty() {
+            true => Some(Vec::new()),
+            false => tokio::select! {
+                request_outcome = self.exchange_client.request_create_orders(&orders_to_create) => {
+                    Some(self.handle_batch_orders_response(&request_outcome, orders_to_create.len()))
+                }
+                _ = cancellation_token.when_cancelled() => None,
+            },
+        }
+        .map(|results| results.into_iter());
+
+        orders
+            .iter()
+            .zip(order_refs)
+            .zip(validation_errors)
+            .map(|((order, order_ref), validation_error)| {
+                let result = match (validation_error, request_results.as_mut()) {
+                    (Some(error), _) => {
+                        self.handle_batch_created_order(order, order_ref, Error(error))
+                    }
+                    (None, Some(results)) => match results.next() {
+                        Some(result) => self.handle_batch_created_order(order, order_ref, result),
+                        None => Err(anyhow!("Missing result of batch order creation")),
+                    },
+                    (None, None) => Err(anyhow!(OPERATION_CANCELED_MSG)),
+                };
+
+                (order.header.client_order_id.clone(), result)
+            })
+            .collect()
+    }
+
+    fn handle_batch_created_order(
+        &self,
+        order: &OrderCreating,
+        order_ref: OrderRef,
+        result: RequestResult<ExchangeOrderId>,
+    ) -> Result<OrderRef> {
+        let client_order_id = &order.header.client_order_id;
+        match result {
+            Success(exchange_order_id) => {
+                self.handle_create_order_succeeded(
+                    self.exchange_account_id,
+                    client_order_id,
+                    &exchange_order_id,
+                    &EventSourceType::Rest,
+                )?;
+
+                log::info!(
+                    "Order was submitted by batch request {} {:?} {:?} on {}",
+                    client_order_id,
+                    order_ref.exchange_order_id(),
+                    order_ref.reservation_id(),
+                    self.exchange_account_id
+                );
+
+                Ok(order_ref)
+            }
+            Error(exchange_error) => {
+                if exchange_error.error_type != ExchangeErrorType::ParsingError {
+                    self.handle_create_order_failed(
+                        self.exchange_account_id,
+                        client_order_id,
+                        &exchange_error,
+                        &EventSourceType::Rest,
+                    )?;
+                }
+
+                bail!("Exchange error: {}", exchange_error.message)
+            }
+        }
+    }
+
+    /// Cancel orders by batch requests if exchange supports them, otherwise by single request per order.
+    /// Orders not cancelled by batch request are cancelled by single requests after that.
+    /// Every order is specified with its own requests group that is used for its single request.
+    /// Results are returned in the same order as orders
+    pub async fn batch_cancel_orders(
+        &self,
+        orders: &[(OrderRef, Option<RequestGroupId>)],
+        cancellation_token: CancellationToken,
+    ) -> Vec<(ClientOrderId, Result<()>)> {
+        let order_features = &self.features.order_features;
+        if order_features.supports_batch_orders && orders.len() > 1 {
+            // Exchanges usually allow to cancel orders with single currency pair by one request
+            let orders_by_currency_pair = orders
+                .iter()
+                .filter(|(order, _)| order.status() == OrderStatus::Created)
+                .filter_map(|(order, request_group_id)| {
+                    order
+                        .to_order_cancelling()
+                        .map(|order_to_cancel| (order.clone(), order_to_cancel, *request_group_id))
+                })
+                .into_group_map_by(|(_, order_to_cancel, _)| order_to_cancel.header.currency_pair);
+
+            let chunks = orders_by_currency_pair
+                .values()
+                .flat_map(|orders| orders.chunks(order_features.max_batch_orders_count.max(1)));
+
+            let chunks_results = join_all(
+                chunks.map(|chunk| self.cancel_orders_chunk(chunk, cancellation_token.clone())),
+            )
+            .await;
+
+            for result in chunks_results {
+                if let Err(error) = result {
+                    log::warn!(
+                        "Failed to cancel orders by batch request on {}: {:?}",
+                        self.exchange_account_id,
+                        error
+                    );
+                }
+            }
+        }
+
+        let results = join_all(orders.iter().map(|(order, request_group_id)| {
+            self.wait_cancel_order(
+                order.clone(),
+                *request_group_id,
+                false,
+                cancellation_token.clone(),
+            )
+        }))
+        .await;
+
+        orders
+            .iter()
+            .map(|(order, _)| order.client_order_id())
+            .zip(results)
+            .collect()
+    }
+
+    async fn cancel_orders_chunk(
+        &self,
+        orders: &[(OrderRef, OrderCancelling, Option<RequestGroupId>)],
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        // Batch request is counted in requests group of the first order in chunk.
+        // Requests reserved in groups of other orders are released when the orders are finished
+        let pre_reservation_group_id = orders
+            .first()
+            .and_then(|(_, _, request_group_id)| *request_group_id);
+
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::CancelOrder,
+                pre_reservation_group_id,
+                cancellation_token.clone(),
+            )?
+            .await
+            .into_result()?;
+
+        for (order, _, _) in orders {
+            order.fn_mut(|order| order.set_status(OrderStatus::Canceling, Utc::now()));
+        }
+
+        let orders_to_cancel = orders
+            .iter()
+            .map(|(_, order_to_cancel, _)| order_to_cancel.clone())
+            .collect_vec();
+
+        log::info!(
+            "Submitting orders cancellation by batch request {:?} on {}",
+            orders_to_cancel
+                .iter()
+                .map(|order| &order.header.client_order_id)
+                .collect_vec(),
+            self.exchange_account_id
+        );
+
+        let request_outcome = tokio::select! {
+            request_outcome = self.exchange_client.request_cancel_orders(&orders_to_cancel) => request_outcome,
+            _ = cancellation_token.when_cancelled() => bail!(OPERATION_CANCELED_MSG),
+        };
+
+        let results = self.handle_batch_orders_response(&request_outcome, orders.len());
+        for ((order, order_to_cancel, _), result) in orders.iter().zip(results) {
+            // Cancellation can be already handled by web socket event
+            if order.is_finished() {
+                continue;
+            }
+
+            match result {
+                Success(_) => self.handle_cancel_order_succeeded(
+                    Some(&order_to_cancel.header.client_order_id),
+                    &order_to_cancel.exchange_order_id,
+                    None,
+                    EventSourceType::Rest,
+                )?,
+                Error(error) => {
+                    if error.error_type != ExchangeErrorType::ParsingError {
+                        self.handle_cancel_order_failed(
+                            &order_to_cancel.exchange_order_id,
+                            error,
+                            EventSourceType::Rest,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_batch_orders_response(
+        &self,
+        request_outcome: &Result<RestRequestOutcome>,
+        orders_count: usize,
+    ) -> Vec<RequestResult<ExchangeOrderId>> {
+        log::info!(
+            "Batch request response on {}: {:?}",
+            self.exchange_account_id,
+            request_outcome
+        );
+
+        let results = match request_outcome {
+            Ok(request_outcome) => match self.get_rest_error(request_outcome) {
+                Some(rest_error) => Err(rest_error),
+                None => match self.exchange_client.parse_batch_orders(request_outcome) {
+                    Ok(results) if results.len() == orders_count => Ok(results),
+                    Ok(results) => Err(ExchangeError::parsing_error(&format!(
+                        "batch response: expected {} results, but received {}",
+                        orders_count,
+                        results.len()
+                    ))),
+                    Err(error) => Err(ExchangeError::parsing_error(&format!(
+                        "batch response: {:?}",
+                        error
+                    ))),
+                },
+            },
+            Err(error) => Err(ExchangeError::new(
+                ExchangeErrorType::SendError,
+                error.to_string(),
+                None,
+            )),
+        };
+
+        match results {
+            Ok(results) => results
+                .into_iter()
+                .map(|result| match result {
+                    Success(exchange_order_id) => Success(exchange_order_id),
+                    Error(mut error) => {
+                        self.exchange_client.clarify_error_type(&mut error);
+                        Error(error)
+                    }
+                })
+                .collect(),
+            Err(error) => (0..orders_count).map(|_| Error(error.clone())).collect(),
+        }
+    }
+}
@@ -51,8 +51,10 @@ impl Exchange {
         cancellation_token: CancellationToken,
     ) -> Result<OrderRef> {
         log::info!("Submitting order {:?}", order_to_create);
-        self.orders
+        let order_ref = self
+            .orders
             .add_simple_initial(order_to_create.header.clone(), Some(order_to_create.price));
+        self.journal_order(&order_ref);
 
         let linked_cancellation_token = cancellation_token.create_linked_token();
 
@@ -147,9 +149,20 @@ impl Exchange {
         order_to_create: &OrderCreating,
         cancellation_token: CancellationToken,
     ) -> Result<CreateOrderResult> {
-        let create_order_result = self
-            .create_order_core(&order_to_create, cancellation_token)
-            .await;
+        let create_order_result = match self
+            .features
+            .order_features
+            .validate_order(&order_to_create.header)
+        {
+            Ok(()) => {
+                self.create_order_core(&order_to_create, cancellation_token)
+                    .await
+            }
+            Err(error) => Some(CreateOrderResult::failed(
+                ExchangeError::new(ExchangeErrorType::InvalidOrder, error.to_string(), None),
+                EventSourceType::Rest,
+            )),
+        };
 
         if let Some(created_order) = create_order_result {
             match &created_order.outcome {
@@ -179,7 +192,7 @@ impl Exchange {
         bail!(OPERATION_CANCELED_MSG)
     }
 
-    fn handle_create_order_failed(
+    pub(super) fn handle_create_order_failed(
         &self,
         exchange_account_id: ExchangeAccountId,
         client_order_id: &ClientOrderId,
@@ -60,7 +60,7 @@ impl Exchange {
         };
     }
 
-    fn handle_create_order_response(
+    pub(super) fn handle_create_order_response(
         &self,
         request_outcome: &Result<RestRequestOutcome>,
         order: &OrderCreating,
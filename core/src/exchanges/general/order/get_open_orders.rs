@@ -132,7 +132,7 @@ impl Exchange {
         Ok(open_orders)
     }
 
-    fn add_missing_open_orders(&self, open_orders: &Vec<OrderInfo>) {
+    pub(crate) fn add_missing_open_orders(&self, open_orders: &Vec<OrderInfo>) {
         for order in open_orders {
             if order.client_order_id.as_str().is_empty()
                 && self
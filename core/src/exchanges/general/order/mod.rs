@@ -1,8 +1,11 @@
+pub mod batch;
 pub mod cancel;
 pub mod create;
 pub mod create_websocket_based;
 pub mod get_info;
 pub mod get_open_orders;
 pub mod get_order_trades;
+pub mod replace;
+pub mod restore;
 pub mod wait_cancel;
 pub mod wait_finish;
@@ -0,0 +1,261 @@
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use mmb_utils::cancellation_token::CancellationToken;
+
+use crate::exchanges::{
+    general::request_type::RequestType, timeouts::requests_timeout_manager::RequestGroupId,
+};
+use crate::{
+    balance_manager::balance_manager::BalanceManager, exchanges::common::ExchangeError,
+    exchanges::common::ExchangeErrorType, exchanges::general::exchange::Exchange,
+    exchanges::general::exchange::RequestResult, orders::fill::EventSourceType,
+    orders::order::ExchangeOrderId, orders::order::OrderCancelling, orders::order::OrderCreating,
+    orders::order::OrderStatus, orders::pool::OrderRef,
+};
+
+impl Exchange {
+    /// Replace order by the new one with another price and amount.
+    /// If exchange supports replacing orders it is done by single request,
+    /// otherwise the order is cancelled and the new order is created after that.
+    /// Reservation of the replaced order unfilled amount is transferred to the new order reservation.
+    pub async fn replace_order(
+        &self,
+        order: &OrderRef,
+        new_order: &OrderCreating,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        log::info!(
+            "Replacing order {} {:?} by {:?}",
+            order.client_order_id(),
+            order.exchange_order_id(),
+            new_order
+        );
+
+        let new_order_ref = self
+            .orders
+            .add_simple_initial(new_order.header.clone(), Some(new_order.price));
+        self.journal_order(&new_order_ref);
+
+        // Invalid order shouldn't be sent by single request and replaced order shouldn't be cancelled for it
+        if let Err(error) = self
+            .features
+            .order_features
+            .validate_order(&new_order.header)
+        {
+            self.fail_replacing_order(new_order, &error.to_string())?;
+            return Err(error.context(format!(
+                "Order {} can't be replaced by invalid order {}",
+                order.client_order_id(),
+                new_order.header.client_order_id
+            )));
+        }
+
+        if self.features.order_features.supports_replace_order {
+            if let Some(order_to_cancel) = order.to_order_cancelling() {
+                let replace_result = self
+                    .replace_order_core(
+                        order,
+                        &order_to_cancel,
+                        new_order,
+                        pre_reservation_group_id,
+                        cancellation_token.clone(),
+                    )
+                    .await;
+                let request_result = match replace_result {
+                    Ok(request_result) => request_result,
+                    Err(error) => {
+                        self.fail_replacing_order(new_order, "replace request wasn't sent")?;
+                        return Err(error);
+                    }
+                };
+
+                match request_result {
+                    RequestResult::Success(exchange_order_id) => {
+                        return self
+                            .handle_replace_order_succeeded(
+                                order,
+                                &order_to_cancel,
+                                new_order,
+                                &new_order_ref,
+                                &exchange_order_id,
+                                cancellation_token,
+                            )
+                            .await;
+                    }
+                    RequestResult::Error(error) => log::warn!(
+                        "Failed to replace order {} by single request, so it will be cancelled before creating order {}: {:?}",
+                        order.client_order_id(),
+                        new_order.header.client_order_id,
+                        error
+                    ),
+                }
+            }
+        }
+
+        self.wait_cancel_order(
+            order.clone(),
+            pre_reservation_group_id,
+            false,
+            cancellation_token.clone(),
+        )
+        .await?;
+
+        let status = order.status();
+        if status != OrderStatus::Canceled {
+            self.fail_replacing_order(new_order, "replaced order wasn't cancelled")?;
+            bail!(
+                "Order {} can't be replaced by {} because it has status {:?}",
+                order.client_order_id(),
+                new_order.header.client_order_id,
+                status
+            )
+        }
+
+        if !self.transfer_order_reservation(order, new_order) {
+            self.fail_replacing_order(new_order, "not enough balance")?;
+            bail!(
+                "Not enough balance to replace order {} by {}",
+                order.client_order_id(),
+                new_order.header.client_order_id
+            )
+        }
+
+        self.create_order(new_order, None, cancellation_token).await
+    }
+
+    async fn replace_order_core(
+        &self,
+        order: &OrderRef,
+        order_to_cancel: &OrderCancelling,
+        new_order: &OrderCreating,
+        pre_reservation_group_id: Option<RequestGroupId>,
+        cancellation_token: CancellationToken,
+    ) -> Result<RequestResult<ExchangeOrderId>> {
+        self.timeout_manager
+            .reserve_when_available(
+                self.exchange_account_id,
+                RequestType::ReplaceOrder,
+                pre_reservation_group_id,
+                cancellation_token,
+            )?
+            .await
+            .into_result()?;
+
+        let previous_status = order.status();
+        order.fn_mut(|order| order.set_status(OrderStatus::Canceling, Utc::now()));
+
+        let request_outcome = self
+            .exchange_client
+            .request_replace_order(order_to_cancel, new_order)
+            .await;
+
+        let outcome = self
+            .handle_create_order_response(&request_outcome, new_order)
+            .outcome;
+
+        // Order wasn't touched on exchange, so it should be cancelled by `wait_cancel_order` from its previous status
+        if let RequestResult::Error(_) = outcome {
+            order.fn_mut(|order| {
+                if order.status() == OrderStatus::Canceling {
+                    order.set_status(previous_status, Utc::now());
+                }
+            });
+        }
+
+        Ok(outcome)
+    }
+
+    async fn handle_replace_order_succeeded(
+        &self,
+        order: &OrderRef,
+        order_to_cancel: &OrderCancelling,
+        new_order: &OrderCreating,
+        new_order_ref: &OrderRef,
+        exchange_order_id: &ExchangeOrderId,
+        cancellation_token: CancellationToken,
+    ) -> Result<OrderRef> {
+        self.handle_cancel_order_succeeded(
+            Some(&order_to_cancel.header.client_order_id),
+            &order_to_cancel.exchange_order_id,
+            None,
+            EventSourceType::Rest,
+        )?;
+
+        let is_reservation_transferred = self.transfer_order_reservation(order, new_order);
+        if !is_reservation_transferred {
+            if let Some(reservation_id) = new_order.header.reservation_id {
+                self.with_balance_manager(|balance_manager| {
+                    balance_manager.unreserve_rest(reservation_id)
+                })?;
+            }
+        }
+
+        self.handle_create_order_succeeded(
+            self.exchange_account_id,
+            &new_order.header.client_order_id,
+            exchange_order_id,
+            &EventSourceType::Rest,
+        )?;
+
+        if !is_reservation_transferred {
+            // Order was already created on exchange, but we can't keep it without reservation
+            self.wait_cancel_order(new_order_ref.clone(), None, false, cancellation_token)
+                .await?;
+            bail!(
+                "Not enough balance to replace order {} by {}",
+                order.client_order_id(),
+                new_order.header.client_order_id
+            )
+        }
+
+        log::info!(
+            "Order {} was replaced by {} {:?}",
+            order.client_order_id(),
+            new_order.header.client_order_id,
+            exchange_order_id
+        );
+
+        Ok(new_order_ref.clone())
+    }
+
+    fn transfer_order_reservation(&self, order: &OrderRef, new_order: &OrderCreating) -> bool {
+        let order_snapshot = order.deep_clone();
+        self.with_balance_manager(|balance_manager| {
+            Ok(balance_manager.try_transfer_order_reservation(&order_snapshot, &new_order.header))
+        })
+        .unwrap_or_else(|error| {
+            log::warn!("Unable to transfer order reservation: {:?}", error);
+            false
+        })
+    }
+
+    fn fail_replacing_order(&self, new_order: &OrderCreating, reason: &str) -> Result<()> {
+        if let Some(reservation_id) = new_order.header.reservation_id {
+            self.with_balance_manager(|balance_manager| {
+                balance_manager.unreserve_rest(reservation_id)
+            })?;
+        }
+
+        self.handle_create_order_failed(
+            self.exchange_account_id,
+            &new_order.header.client_order_id,
+            &ExchangeError::new(ExchangeErrorType::Unknown, reason.to_owned(), None),
+            &EventSourceType::Rest,
+        )
+    }
+
+    pub(crate) fn with_balance_manager<T>(
+        &self,
+        action: impl FnOnce(&mut BalanceManager) -> Result<T>,
+    ) -> Result<T> {
+        let balance_manager = self
+            .balance_manager
+            .lock()
+            .as_ref()
+            .and_then(|balance_manager| balance_manager.upgrade())
+            .context("BalanceManager ref can't be upgraded")?;
+        let mut balance_manager = balance_manager.lock();
+        action(&mut balance_manager)
+    }
+}
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use mmb_utils::cancellation_token::CancellationToken;
+use parking_lot::RwLock;
+
+use crate::exchanges::common::Amount;
+use crate::exchanges::general::exchange::{Exchange, RequestResult};
+use crate::exchanges::general::features::RestFillsType;
+use crate::exchanges::general::request_type::RequestType;
+use crate::misc::time::time_manager;
+use crate::orders::order::{ClientOrderId, OrderInfo, OrderSnapshot, OrderStatus};
+use crate::orders::pool::OrderRef;
+
+impl Exchange {
+    /// Puts journaled orders of the exchange account back to the orders pool and reconciles them
+    /// with orders opened on the exchange. Opened orders missing in the journal are added to the pool too.
+    /// Should be called before trading is started
+    pub async fn restore_orders(&self, journaled_orders: Vec<OrderSnapshot>) -> Result<()> {
+        let open_orders = self.get_open_orders(false).await.with_context(|| {
+            format!(
+                "Unable to get open orders for restoring orders on {}",
+                self.exchange_account_id
+            )
+        })?;
+
+        for order in journaled_orders
+            .into_iter()
+            .filter(|order| order.header.exchange_account_id == self.exchange_account_id)
+        {
+            self.restore_order(order, &open_orders).await;
+        }
+
+        self.add_missing_open_orders(&open_orders);
+
+        for order in self.orders.not_finished.iter() {
+            self.journal_order(order.value());
+        }
+
+        log::info!(
+            "Restored {} orders on {}, {} of them are not finished",
+            self.orders.cache_by_client_id.len(),
+            self.exchange_account_id,
+            self.orders.not_finished.len()
+        );
+
+        Ok(())
+    }
+
+    async fn restore_order(&self, order: OrderSnapshot, open_orders: &[OrderInfo]) {
+        let is_finished = order.props.is_finished();
+        let open_order = open_orders.iter().find(|open_order| {
+            open_order.client_order_id == order.header.client_order_id
+                || Some(&open_order.exchange_order_id) == order.props.exchange_order_id.as_ref()
+        });
+
+        let order_ref = self
+            .orders
+            .add_snapshot_initial(Arc::new(RwLock::new(order)));
+
+        if let Some(open_order) = open_order.filter(|_| !is_finished) {
+            order_ref.fn_mut(|order| {
+                order.props.exchange_order_id = Some(open_order.exchange_order_id.clone());
+                if order.props.status == OrderStatus::Creating {
+                    order.set_status(OrderStatus::Created, time_manager::now());
+                }
+            });
+        }
+
+        // fills are matched with orders by exchange order id, so order should be cached before reconciliation
+        if let Some(exchange_order_id) = order_ref.exchange_order_id() {
+            let _ = self
+                .orders
+                .cache_by_exchange_id
+                .insert(exchange_order_id, order_ref.clone());
+        }
+
+        if !is_finished && open_order.is_none() {
+            self.reconcile_closed_order(&order_ref).await;
+        }
+
+        if !order_ref.is_finished() {
+            self.restore_reservation(&order_ref);
+        }
+
+        if order_ref.is_finished() {
+            let _ = self
+                .orders
+                .not_finished
+                .remove(&order_ref.client_order_id());
+        }
+
+        if !is_finished && order_ref.is_finished() {
+            self.journal_order(&order_ref);
+        }
+    }
+
+    /// Reservation of the order is lost on restart, so it is registered again
+    /// to keep unfilled amount of the order out of balance available for new orders
+    fn restore_reservation(&self, order_ref: &OrderRef) {
+        let order = order_ref.deep_clone();
+        let reservation_id = match order.header.reservation_id {
+            Some(reservation_id) => reservation_id,
+            None => return,
+        };
+
+        let configuration_descriptor = match order
+            .internal_props
+            .reservation_configuration_descriptor
+        {
+            Some(configuration_descriptor) => configuration_descriptor,
+            None => {
+                log::error!(
+                    "Unable to restore reservation {} of order {} on {} because its configuration isn't journaled",
+                    reservation_id,
+                    order.header.client_order_id,
+                    self.exchange_account_id
+                );
+                return;
+            }
+        };
+        let result = self.with_balance_manager(|balance_manager| {
+            balance_manager.restore_order_reservation(configuration_descriptor, &order)
+        });
+        match result {
+            Ok(()) => {
+                let _ = self
+                    .restored_reservations
+                    .insert(order.header.client_order_id.clone(), reservation_id);
+            }
+            Err(error) => log::error!(
+                "Unable to restore reservation {} of order {} on {}: {:?}",
+                reservation_id,
+                order.header.client_order_id,
+                self.exchange_account_id,
+                error
+            ),
+        }
+    }
+
+    pub(crate) fn release_restored_reservation(&self, client_order_id: &ClientOrderId) {
+        let (client_order_id, reservation_id) =
+            match self.restored_reservations.remove(client_order_id) {
+                Some(restored_reservation) => restored_reservation,
+                None => return,
+            };
+
+        let result = self.with_balance_manager(|balance_manager| {
+            balance_manager.release_restored_order_reservation(reservation_id, &client_order_id)
+        });
+        if let Err(error) = result {
+            log::error!(
+                "Unable to release restored reservation {} of order {} on {}: {:?}",
+                reservation_id,
+                client_order_id,
+                self.exchange_account_id,
+                error
+            );
+        }
+    }
+
+    /// Order isn't opened on the exchange anymore, so it was finished while the engine was stopped
+    async fn reconcile_closed_order(&self, order_ref: &OrderRef) {
+        if order_ref.exchange_order_id().is_none() {
+            log::warn!(
+                "Order {} on {} wasn't created on the exchange before the engine was stopped",
+                order_ref.client_order_id(),
+                self.exchange_account_id
+            );
+            order_ref
+                .fn_mut(|order| order.set_status(OrderStatus::FailedToCreate, time_manager::now()));
+            return;
+        }
+
+        let order_info = match self.get_order_info(order_ref).await {
+            Ok(order_info) => order_info,
+            Err(error) => {
+                log::error!(
+                    "Unable to get info of order {} on {} which is missing in open orders: {:?}",
+                    order_ref.client_order_id(),
+                    self.exchange_account_id,
+                    error
+                );
+                return;
+            }
+        };
+
+        if order_info.filled_amount > order_ref.filled_amount() {
+            self.fetch_missed_fills(order_ref, order_info.filled_amount)
+                .await;
+        }
+
+        if order_info.order_status.is_finished() && !order_ref.is_finished() {
+            order_ref
+                .fn_mut(|order| order.set_status(order_info.order_status, time_manager::now()));
+        }
+    }
+
+    async fn fetch_missed_fills(&self, order_ref: &OrderRef, exchange_filled_amount: Amount) {
+        let request_type = match self.features.rest_fills_features.fills_type {
+            RestFillsType::MyTrades => RequestType::GetOrderTrades,
+            RestFillsType::GetOrderInfo => RequestType::GetOrderInfo,
+            RestFillsType::None => {
+                log::warn!(
+                    "Order {} on {} was filled by {} while the engine was stopped, but only {} is journaled and fills can't be requested",
+                    order_ref.client_order_id(),
+                    self.exchange_account_id,
+                    exchange_filled_amount,
+                    order_ref.filled_amount()
+                );
+                return;
+            }
+        };
+
+        let symbol = self
+            .symbols
+            .get(&order_ref.currency_pair())
+            .map(|symbol| symbol.clone());
+        let result = match symbol {
+            Some(symbol) => {
+                self.check_order_fills_using_request_type(
+                    order_ref,
+                    &symbol,
+                    request_type,
+                    None,
+                    CancellationToken::default(),
+                )
+                .await
+            }
+            None => Err(anyhow!(
+                "No symbol for currency pair {}",
+                order_ref.currency_pair()
+            )),
+        };
+
+        let error = match result {
+            Ok(RequestResult::Success(())) => return,
+            Ok(RequestResult::Error(error)) => anyhow!("{:?}", error),
+            Err(error) => error,
+        };
+        log::error!(
+            "Unable to get fills of order {} on {} missed while the engine was stopped: {:?}",
+            order_ref.client_order_id(),
+            self.exchange_account_id,
+            error
+        );
+    }
+}
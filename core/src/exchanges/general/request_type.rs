@@ -2,6 +2,7 @@
 pub enum RequestType {
     CreateOrder,
     CancelOrder,
+    ReplaceOrder,
     GetOrderInfo,
     GetBalance,
     GetOpenOrders,
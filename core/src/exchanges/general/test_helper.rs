@@ -11,7 +11,7 @@ use crate::{
         events::{AllowedEventSourceType, ExchangeBalancesAndPositions, ExchangeEvent, TradeId},
         general::{
             commission::{Commission, CommissionForType},
-            exchange::Exchange,
+            exchange::{Exchange, RequestResult},
             features::{
                 ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption,
                 RestFillsFeatures, WebSocketOptions,
@@ -66,6 +66,25 @@ impl ExchangeClient for TestClient {
         unimplemented!("doesn't need in UT")
     }
 
+    async fn request_replace_order(
+        &self,
+        _order: &OrderCancelling,
+        _new_order: &OrderCreating,
+    ) -> Result<RestRequestOutcome> {
+        unimplemented!("doesn't need in UT")
+    }
+
+    async fn request_create_orders(&self, _orders: &[OrderCreating]) -> Result<RestRequestOutcome> {
+        unimplemented!("doesn't need in UT")
+    }
+
+    async fn request_cancel_orders(
+        &self,
+        _orders: &[OrderCancelling],
+    ) -> Result<RestRequestOutcome> {
+        unimplemented!("doesn't need in UT")
+    }
+
     async fn cancel_all_orders(&self, _currency_pair: CurrencyPair) -> Result<()> {
         unimplemented!("doesn't need in UT")
     }
@@ -122,6 +141,12 @@ impl Support for TestClient {
     fn get_order_id(&self, _response: &RestRequestOutcome) -> Result<ExchangeOrderId> {
         unimplemented!("doesn't need in UT")
     }
+    fn parse_batch_orders(
+        &self,
+        _response: &RestRequestOutcome,
+    ) -> Result<Vec<RequestResult<ExchangeOrderId>>> {
+        unimplemented!("doesn't need in UT")
+    }
     fn clarify_error_type(&self, __error: &mut ExchangeError) {
         unimplemented!("doesn't need in UT")
     }
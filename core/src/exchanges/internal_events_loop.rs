@@ -106,6 +106,7 @@ fn update_order_book_top_for_exchange(
             bid: snapshot
                 .get_top_bid()
                 .map(|(price, amount)| PriceLevel { price, amount }),
+            updated_at: snapshot.last_update_time,
         };
 
         exchanges_map
@@ -1,14 +1,18 @@
 use super::common::*;
+use crate::connectivity::traffic_fixtures::{TrafficFixtures, TrafficMode};
 use anyhow::{Context, Result};
+use futures::Future;
 use hyper::client::HttpConnector;
 use hyper::{Body, Client, Error, Request, Response, Uri};
 use hyper_tls::HttpsConnector;
 use std::convert::TryInto;
+use std::sync::Arc;
 
 pub type HttpParams = Vec<(String, String)>;
 
 pub struct RestClient {
     client: Client<HttpsConnector<HttpConnector>>,
+    traffic_fixtures: Option<Arc<TrafficFixtures>>,
 }
 
 const KEEP_ALIVE: &'static str = "keep-alive";
@@ -17,19 +21,31 @@ impl RestClient {
     pub fn new() -> Self {
         Self {
             client: create_client(),
+            traffic_fixtures: None,
         }
     }
 
-    pub async fn get(&self, url: Uri, api_key: &str) -> Result<RestRequestOutcome> {
-        let req = Request::get(url)
-            .header(hyper::header::CONNECTION, KEEP_ALIVE)
-            .header("X-MBX-APIKEY", api_key)
-            .body(Body::empty())
-            .context("Error during creation of http GET request")?;
-
-        let response = self.client.request(req).await;
+    /// Client which records traffic to fixtures or replays it from them depending on fixtures mode
+    pub fn with_traffic_fixtures(traffic_fixtures: Arc<TrafficFixtures>) -> Self {
+        Self {
+            client: create_client(),
+            traffic_fixtures: Some(traffic_fixtures),
+        }
+    }
 
-        handle_response(response, "GET").await
+    pub async fn get(&self, url: Uri, api_key: &str) -> Result<RestRequestOutcome> {
+        self.send_with_fixtures("GET", url, &HttpParams::new(), |url| async move {
+            let req = Request::get(url)
+                .header(hyper::header::CONNECTION, KEEP_ALIVE)
+                .header("X-MBX-APIKEY", api_key)
+                .body(Body::empty())
+                .context("Error during creation of http GET request")?;
+
+            let response = self.client.request(req).await;
+
+            handle_response(response, "GET").await
+        })
+        .await
     }
 
     pub async fn post(
@@ -38,31 +54,66 @@ impl RestClient {
         api_key: &str,
         http_params: &HttpParams,
     ) -> Result<RestRequestOutcome> {
-        let form_encoded = form_urlencoded::Serializer::new(String::new())
-            .extend_pairs(http_params)
-            .finish();
-
-        let req = Request::post(url)
-            .header(hyper::header::CONNECTION, KEEP_ALIVE)
-            .header("X-MBX-APIKEY", api_key)
-            .body(Body::from(form_encoded))
-            .context("Error during creation of http delete request")?;
-
-        let response = self.client.request(req).await;
-
-        handle_response(response, "POST").await
+        self.send_with_fixtures("POST", url, http_params, |url| async move {
+            let form_encoded = form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(http_params)
+                .finish();
+
+            let req = Request::post(url)
+                .header(hyper::header::CONNECTION, KEEP_ALIVE)
+                .header("X-MBX-APIKEY", api_key)
+                .body(Body::from(form_encoded))
+                .context("Error during creation of http delete request")?;
+
+            let response = self.client.request(req).await;
+
+            handle_response(response, "POST").await
+        })
+        .await
     }
 
     pub async fn delete(&self, url: Uri, api_key: &str) -> Result<RestRequestOutcome> {
-        let req = Request::delete(url)
-            .header(hyper::header::CONNECTION, KEEP_ALIVE)
-            .header("X-MBX-APIKEY", api_key)
-            .body(Body::empty())
-            .context("Error during creation of http delete request")?;
-
-        let response = self.client.request(req).await;
+        self.send_with_fixtures("DELETE", url, &HttpParams::new(), |url| async move {
+            let req = Request::delete(url)
+                .header(hyper::header::CONNECTION, KEEP_ALIVE)
+                .header("X-MBX-APIKEY", api_key)
+                .body(Body::empty())
+                .context("Error during creation of http delete request")?;
+
+            let response = self.client.request(req).await;
+
+            handle_response(response, "DELETE").await
+        })
+        .await
+    }
 
-        handle_response(response, "DELETE").await
+    async fn send_with_fixtures<F, Fut>(
+        &self,
+        method: &str,
+        url: Uri,
+        body_params: &HttpParams,
+        send: F,
+    ) -> Result<RestRequestOutcome>
+    where
+        F: FnOnce(Uri) -> Fut,
+        Fut: Future<Output = Result<RestRequestOutcome>>,
+    {
+        let traffic_fixtures = match &self.traffic_fixtures {
+            Some(traffic_fixtures) => traffic_fixtures,
+            None => return send(url).await,
+        };
+
+        let request = traffic_fixtures.make_http_request(method, &url, body_params);
+        match traffic_fixtures.mode() {
+            TrafficMode::Replay => traffic_fixtures.replay_http(&request),
+            TrafficMode::Record => {
+                // Websocket messages received after sending are bound to this request
+                traffic_fixtures.notify_http_request_sent();
+                let outcome = send(url).await?;
+                traffic_fixtures.record_http(request, &outcome);
+                Ok(outcome)
+            }
+        }
     }
 }
 
@@ -40,6 +40,13 @@ impl Display for RequestGroupId {
     }
 }
 
+/// Requests limit of the exchange account and how much of it is used in the current period
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RequestsUtilization {
+    pub requests_per_period: usize,
+    pub available_requests_count: usize,
+}
+
 pub struct RequestsTimeoutManager {
     inner: Mutex<InnerRequestsTimeoutManager>,
 }
@@ -242,6 +249,18 @@ impl RequestsTimeoutManager {
             .try_reserve_request_instant(request_type, current_time)
     }
 
+    pub fn get_utilization(&self, current_time: DateTime) -> Result<RequestsUtilization> {
+        let mut inner = self.inner.lock();
+
+        let current_time = inner.get_non_decreasing_time(current_time);
+        inner.remove_outdated_requests(current_time)?;
+
+        Ok(RequestsUtilization {
+            requests_per_period: inner.requests_per_period,
+            available_requests_count: inner.get_available_requests_count_at_present(current_time),
+        })
+    }
+
     pub fn reserve_when_available(
         self: Arc<Self>,
         request_type: RequestType,
@@ -2558,4 +2577,34 @@ mod test {
             Ok(())
         }
     }
+
+    mod get_utilization {
+        use super::*;
+
+        #[rstest]
+        fn requests_and_groups_are_taken_into_account(
+            timeout_manager: Arc<RequestsTimeoutManager>,
+        ) -> Result<()> {
+            // Arrange
+            let current_time = Utc::now();
+            let _ = timeout_manager.try_reserve_group("GroupType".to_owned(), current_time, 2)?;
+            let _ = timeout_manager.try_reserve_instant(
+                RequestType::CreateOrder,
+                current_time,
+                None,
+            )?;
+
+            // Act
+            let utilization = timeout_manager.get_utilization(current_time)?;
+            let next_period_utilization =
+                timeout_manager.get_utilization(current_time + Duration::minutes(2))?;
+
+            // Assert
+            assert_eq!(utilization.requests_per_period, 5);
+            assert_eq!(utilization.available_requests_count, 2);
+            assert_eq!(next_period_utilization.available_requests_count, 3);
+
+            Ok(())
+        }
+    }
 }
@@ -16,7 +16,7 @@ use chrono::Utc;
 use crate::exchanges::common::ExchangeAccountId;
 use crate::exchanges::general::request_type::RequestType;
 use crate::exchanges::timeouts::requests_timeout_manager::{
-    RequestGroupId, RequestsTimeoutManager,
+    RequestGroupId, RequestsTimeoutManager, RequestsUtilization,
 };
 
 pub type BoxFuture = Box<dyn Future<Output = Result<()>> + Sync + Send>;
@@ -72,6 +72,13 @@ impl TimeoutManager {
         )
     }
 
+    pub fn get_utilization(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<RequestsUtilization> {
+        self.inner[&exchange_account_id].get_utilization(now())
+    }
+
     pub fn reserve_when_available(
         &self,
         exchange_account_id: ExchangeAccountId,
@@ -19,7 +19,9 @@ use super::{
     general::{order::get_order_trades::OrderTrade, symbol::Symbol},
     timeouts::requests_timeout_manager_factory::RequestTimeoutArguments,
 };
+use crate::connectivity::traffic_fixtures::TrafficFixtures;
 use crate::exchanges::events::ExchangeEvent;
+use crate::exchanges::general::exchange::RequestResult;
 use crate::exchanges::general::features::ExchangeFeatures;
 use crate::lifecycle::application_manager::ApplicationManager;
 use crate::orders::fill::EventSourceType;
@@ -40,6 +42,23 @@ pub trait ExchangeClient: Support {
 
     async fn request_cancel_order(&self, order: &OrderCancelling) -> Result<RestRequestOutcome>;
 
+    /// Cancel the order and create the new one by single request.
+    /// Called only if `OrderFeatures::supports_replace_order` is set
+    async fn request_replace_order(
+        &self,
+        order: &OrderCancelling,
+        new_order: &OrderCreating,
+    ) -> Result<RestRequestOutcome>;
+
+    /// Create several orders by single request.
+    /// Called only if `OrderFeatures::supports_batch_orders` is set
+    async fn request_create_orders(&self, orders: &[OrderCreating]) -> Result<RestRequestOutcome>;
+
+    /// Cancel several orders by single request.
+    /// Called only if `OrderFeatures::supports_batch_orders` is set
+    async fn request_cancel_orders(&self, orders: &[OrderCancelling])
+        -> Result<RestRequestOutcome>;
+
     async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()>;
 
     async fn request_open_orders(&self) -> Result<RestRequestOutcome>;
@@ -74,6 +93,11 @@ pub trait ExchangeClient: Support {
 pub trait Support: Send + Sync {
     fn is_rest_error_code(&self, response: &RestRequestOutcome) -> Result<(), ExchangeError>;
     fn get_order_id(&self, response: &RestRequestOutcome) -> Result<ExchangeOrderId>;
+    /// Parse results of batch create or cancel request in the same order as orders in the request
+    fn parse_batch_orders(
+        &self,
+        response: &RestRequestOutcome,
+    ) -> Result<Vec<RequestResult<ExchangeOrderId>>>;
     fn clarify_error_type(&self, error: &mut ExchangeError);
 
     fn on_websocket_message(&self, msg: &str) -> Result<()>;
@@ -107,6 +131,17 @@ pub trait Support: Send + Sync {
 
     async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url>;
 
+    /// Messages to send after websocket connection is opened.
+    /// Needed for exchanges which subscribe to channels by requests instead of url
+    fn get_ws_subscription_messages(&self, _role: WebSocketRole) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Fixtures for recording or replaying websocket traffic, used for connector tests
+    fn get_traffic_fixtures(&self) -> Option<Arc<TrafficFixtures>> {
+        None
+    }
+
     fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair;
 
     fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode>;
@@ -14,14 +14,20 @@
     unused_must_use
 )]
 
+pub mod alerts;
+pub mod backtesting;
 pub(crate) mod balance_changes;
 pub mod balance_manager;
 mod balances;
 pub mod connectivity;
+pub mod data_recorder;
+pub mod database;
 pub mod exchanges;
 pub mod infrastructure;
 pub mod misc;
 pub mod orders;
+pub mod pnl;
+pub mod risk;
 pub mod rpc;
 pub mod service_configuration;
 pub mod statistic_service;
@@ -32,8 +38,9 @@ pub mod disposition_execution;
 pub mod explanation;
 pub mod lifecycle;
 pub mod math;
+pub mod metrics;
 pub mod order_book;
-pub(crate) mod services;
+pub mod services;
 pub mod settings;
 pub mod text;
 
@@ -6,6 +6,7 @@ use tokio::task::JoinHandle;
 use std::panic;
 use std::sync::{Arc, Weak};
 
+use crate::alerts::alert_bus::AlertSeverity;
 use crate::lifecycle::trading_engine::EngineContext;
 
 use mmb_utils::cancellation_token::CancellationToken;
@@ -91,6 +92,13 @@ pub fn start_graceful_shutdown_inner(
             log::warn!("Can't execute graceful shutdown with reason '{}', because 'engine_context' was dropped already", reason);
             None
         }
-        Some(ctx) => Some(ctx.graceful_shutdown()),
+        Some(ctx) => {
+            let _ = ctx.alert_bus.publish(
+                AlertSeverity::Critical,
+                "ApplicationManager",
+                format!("Graceful shutdown is requested: {}", reason),
+            );
+            Some(ctx.graceful_shutdown())
+        }
     }
 }
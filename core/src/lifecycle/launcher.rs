@@ -1,7 +1,16 @@
+use crate::alerts::alert_bus::AlertSeverity;
+use crate::backtesting::market_data::{MarketDataReader, RecordedMarketEvent};
+use crate::backtesting::report::{BacktestReport, BacktestReportBuilder};
+use crate::backtesting::BacktestMarket;
 use crate::balance_manager::balance_manager::BalanceManager;
+use crate::balance_manager::balance_reconciliation::BalanceReconciliationService;
 use crate::config::{load_pretty_settings, try_load_settings};
+use crate::data_recorder::data_recorder::DataRecorder;
+use crate::database::database_manager::DatabaseManager;
+use crate::exchanges::block_reasons;
 use crate::exchanges::common::{ExchangeAccountId, ExchangeId};
 use crate::exchanges::events::{ExchangeEvent, ExchangeEvents, CHANNEL_MAX_EVENTS_COUNT};
+use crate::exchanges::exchange_blocker::ExchangeBlockerMoment;
 use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::general::exchange_creation::create_exchange;
@@ -11,34 +20,56 @@ use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
 use crate::exchanges::traits::ExchangeClientBuilder;
 use crate::lifecycle::application_manager::ApplicationManager;
 use crate::lifecycle::trading_engine::{EngineContext, TradingEngine};
+use crate::metrics::metrics_server::MetricsServer;
+use crate::metrics::prometheus_metrics::EngineMetrics;
+use crate::misc::time::{get_current_time, reset_virtual_time, set_virtual_time};
+use crate::misc::traits::market_service::GetMarketCurrencyCodePrice;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
+use crate::orders::journal::{FileOrdersJournal, OrdersJournal, OrdersJournalSettings};
 use crate::rpc::config_waiter::ConfigWaiter;
 use crate::rpc::control_panel::ControlPanel;
-use crate::settings::{AppSettings, BaseStrategySettings, CoreSettings};
+use crate::services::market_prices::order_book_market_prices::OrderBookMarketPrices;
+use crate::services::market_prices::ticker_market_prices::TickerMarketPrices;
+use crate::services::usd_converter::price_source_service::PriceSourceService;
+use crate::services::usd_converter::price_sources_loader::PriceSourcesLoader;
+use crate::services::usd_converter::price_sources_storage::create_price_sources_storage;
+use crate::services::usd_converter::prices_sources_saver::PriceSourcesSaver;
+use crate::services::usd_converter::usd_converter::MarketPricesSettings;
+#[double]
+use crate::services::usd_converter::usd_converter::UsdConverter;
+use crate::services::usd_converter::usd_denominator::UsdDenominator;
+use crate::settings::{AppSettings, BaseStrategySettings, CoreSettings, ProfitLossStopperSettings};
 use crate::statistic_service::StatisticEventHandler;
 use crate::statistic_service::StatisticService;
 use crate::strategies::disposition_strategy::DispositionStrategy;
+use crate::strategies::hosted_strategies::HostedStrategy;
 use crate::{
     disposition_execution::executor::DispositionExecutorService,
     infrastructure::{keep_application_manager, spawn_future},
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use core::fmt::Debug;
 use dashmap::DashMap;
 use futures::{future::join_all, FutureExt};
+use itertools::Itertools;
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::logger::init_logger;
 use mmb_utils::{hashmap, nothing_to_do};
+use mockall_double::double;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::any::Any;
 use std::collections::HashMap;
 use std::convert::identity;
+use std::iter;
 use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal;
-use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::{broadcast, mpsc, oneshot, Notify};
 
 pub struct EngineBuildConfig {
     pub supported_exchange_clients: HashMap<ExchangeId, Box<dyn ExchangeClientBuilder + 'static>>,
@@ -142,14 +173,68 @@ where
         }
     };
 
+    settings.core.validate().context("Invalid core settings")?;
+
+    let (
+        events_sender,
+        events_receiver,
+        exchanges_map,
+        engine_context,
+        finish_graceful_shutdown_rx,
+    ) = create_engine_context(&settings.core, build_settings).await;
+
+    if let Some(orders_journal_settings) = &settings.core.orders_journal {
+        restore_orders(orders_journal_settings, &exchanges_map).await?;
+    }
+
+    Ok(Some((
+        events_sender,
+        events_receiver,
+        settings,
+        exchanges_map,
+        engine_context,
+        finish_graceful_shutdown_rx,
+    )))
+}
+
+/// Restores orders from the journal before trading is started and journals all further order changes
+async fn restore_orders(
+    orders_journal_settings: &OrdersJournalSettings,
+    exchanges_map: &DashMap<ExchangeAccountId, Arc<Exchange>>,
+) -> Result<()> {
+    let orders_journal = FileOrdersJournal::open(orders_journal_settings)?;
+    let journaled_orders = orders_journal.load_orders()?;
+
+    let exchanges = exchanges_map
+        .iter()
+        .map(|x| x.value().clone())
+        .collect_vec();
+    for exchange in exchanges {
+        exchange.setup_orders_journal(orders_journal.clone());
+        exchange.restore_orders(journaled_orders.clone()).await?;
+    }
+
+    Ok(())
+}
+
+async fn create_engine_context(
+    core_settings: &CoreSettings,
+    build_settings: &EngineBuildConfig,
+) -> (
+    broadcast::Sender<ExchangeEvent>,
+    broadcast::Receiver<ExchangeEvent>,
+    DashMap<ExchangeAccountId, Arc<Exchange>>,
+    Arc<EngineContext>,
+    oneshot::Receiver<()>,
+) {
     let application_manager = ApplicationManager::new(CancellationToken::new());
     keep_application_manager(application_manager.clone());
     let (events_sender, events_receiver) = broadcast::channel(CHANNEL_MAX_EVENTS_COUNT);
 
-    let timeout_manager = create_timeout_manager(&settings.core, &build_settings);
+    let timeout_manager = create_timeout_manager(core_settings, build_settings);
 
     let exchanges = create_exchanges(
-        &settings.core,
+        core_settings,
         build_settings,
         events_sender.clone(),
         application_manager.clone(),
@@ -183,25 +268,82 @@ where
             .setup_balance_manager(balance_manager.clone())
     }
 
+    let database_manager = match &core_settings.database {
+        Some(database_settings) => Some(
+            DatabaseManager::connect(database_settings)
+                .await
+                .expect("Unable to connect to database"),
+        ),
+        None => None,
+    };
+
     let (finish_graceful_shutdown_tx, finish_graceful_shutdown_rx) = oneshot::channel();
     let engine_context = EngineContext::new(
-        settings.core.clone(),
+        core_settings.clone(),
         exchanges_map.clone(),
         exchange_events,
         finish_graceful_shutdown_tx,
         timeout_manager,
         application_manager.clone(),
         balance_manager,
+        database_manager,
     );
 
-    Ok(Some((
+    setup_alerts(&engine_context);
+
+    (
         events_sender,
         events_receiver,
-        settings,
         exchanges_map,
         engine_context,
         finish_graceful_shutdown_rx,
-    )))
+    )
+}
+
+/// Publish alerts about exchange blocks and websocket disconnects
+fn setup_alerts(engine_context: &Arc<EngineContext>) {
+    let alert_bus = engine_context.alert_bus.clone();
+    engine_context
+        .exchange_blocker
+        .register_handler(Box::new(move |event, _| {
+            // graceful shutdown is alerted by itself
+            if event.reason != block_reasons::GRACEFUL_SHUTDOWN {
+                let alert = match event.moment {
+                    ExchangeBlockerMoment::Blocked => Some((AlertSeverity::Warning, "blocked")),
+                    ExchangeBlockerMoment::Unblocked => Some((AlertSeverity::Info, "unblocked")),
+                    ExchangeBlockerMoment::BeforeUnblocked => None,
+                };
+
+                if let Some((severity, action)) = alert {
+                    let _ = alert_bus.publish(
+                        severity,
+                        "ExchangeBlocker",
+                        format!(
+                            "{} is {} by reason {}",
+                            event.exchange_account_id, action, event.reason
+                        ),
+                    );
+                }
+            }
+
+            async {}.boxed()
+        }));
+
+    for exchange in engine_context.exchanges.iter() {
+        let alert_bus = engine_context.alert_bus.clone();
+        let stop_token = engine_context.application_manager.stop_token();
+        let exchange_account_id = exchange.exchange_account_id;
+        exchange.set_websocket_disconnected_callback(Box::new(move |_| {
+            // websockets are disconnected during graceful shutdown as expected
+            if !stop_token.is_cancellation_requested() {
+                let _ = alert_bus.publish(
+                    AlertSeverity::Warning,
+                    "ConnectivityManager",
+                    format!("Websocket is disconnected on {}", exchange_account_id),
+                );
+            }
+        }));
+    }
 }
 
 fn run_services<'a, StrategySettings>(
@@ -211,10 +353,7 @@ fn run_services<'a, StrategySettings>(
     settings: AppSettings<StrategySettings>,
     exchanges_map: DashMap<ExchangeAccountId, Arc<Exchange>>,
     init_user_settings: InitSettings<StrategySettings>,
-    build_strategy: impl Fn(
-        &AppSettings<StrategySettings>,
-        Arc<EngineContext>,
-    ) -> Box<dyn DispositionStrategy + 'static>,
+    build_strategies: impl Fn(&AppSettings<StrategySettings>, Arc<EngineContext>) -> Vec<HostedStrategy>,
     finish_graceful_shutdown_rx: oneshot::Receiver<()>,
 ) -> TradingEngine
 where
@@ -226,19 +365,63 @@ where
         .register_service(internal_events_loop.clone());
 
     let exchange_events = ExchangeEvents::new(events_sender.clone());
-    let statistic_service = StatisticService::new();
+    let statistic_service = engine_context.statistic_service.clone();
     let statistic_event_handler =
         create_statistic_event_handler(exchange_events, statistic_service.clone());
     let control_panel = ControlPanel::create_and_start(
         engine_context.application_manager.clone(),
         load_pretty_settings(init_user_settings),
         statistic_service.clone(),
+        engine_context.hosted_strategies.clone(),
+        engine_context.clone(),
     )
     .expect("Unable to start control panel");
     engine_context
         .shutdown_service
         .register_service(control_panel.clone());
 
+    if let Some(metrics_settings) = &settings.core.metrics {
+        let metrics_server = MetricsServer::start(
+            metrics_settings,
+            EngineMetrics::new(engine_context.clone(), statistic_service.clone()),
+        )
+        .expect("Unable to start metrics server");
+        engine_context
+            .shutdown_service
+            .register_service(metrics_server);
+    }
+
+    let data_recorder = settings
+        .core
+        .data_recorder
+        .as_ref()
+        .map(|data_recorder_settings| {
+            let data_recorder = DataRecorder::start(
+                data_recorder_settings,
+                engine_context.get_events_channel(),
+                engine_context.application_manager.stop_token(),
+            )
+            .expect("Unable to start data recorder");
+            engine_context
+                .shutdown_service
+                .register_service(data_recorder.clone());
+            data_recorder
+        });
+
+    let price_source_service =
+        start_price_source_service(&settings.core, &engine_context, data_recorder);
+    let usd_converter = start_usd_converter(&settings.core, &engine_context, price_source_service);
+    if let Err(error) = start_profit_loss_stopper(&settings.core, &engine_context, usd_converter) {
+        log::error!("Profit loss stopper isn't started: {:?}", error);
+    }
+
+    if let Some(balance_reconciliation_settings) = &settings.core.balance_reconciliation {
+        let _ = BalanceReconciliationService::start(
+            balance_reconciliation_settings.clone(),
+            engine_context.clone(),
+        );
+    }
+
     {
         let local_exchanges_map = exchanges_map.into_iter().map(identity).collect();
         let action = internal_events_loop.clone().start(
@@ -249,21 +432,192 @@ where
         let _ = spawn_future("internal_events_loop start", true, action.boxed());
     }
 
-    let disposition_strategy = build_strategy(&settings, engine_context.clone());
-    let disposition_executor_service = create_disposition_executor_service(
-        &settings.strategy,
-        &engine_context,
-        disposition_strategy,
-        &statistic_event_handler.stats,
-    );
-    engine_context
-        .shutdown_service
-        .register_service(disposition_executor_service);
+    for hosted_strategy in build_strategies(&settings, engine_context.clone()) {
+        let disposition_executor_service = create_disposition_executor_service(
+            hosted_strategy,
+            &engine_context,
+            &statistic_event_handler.stats,
+        )
+        .expect("Unable to start disposition executor");
+        engine_context
+            .shutdown_service
+            .register_service(disposition_executor_service);
+    }
 
     log::info!("TradingEngine started");
     TradingEngine::new(engine_context.clone(), finish_graceful_shutdown_rx)
 }
 
+/// Price sources are saved to the database if it is configured, otherwise to files of `DataRecorder`
+fn start_price_source_service(
+    core_settings: &CoreSettings,
+    engine_context: &Arc<EngineContext>,
+    data_recorder: Option<Arc<DataRecorder>>,
+) -> Option<Arc<PriceSourceService>> {
+    #[double]
+    use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+
+    if core_settings.price_sources.is_empty() {
+        return None;
+    }
+
+    let storage =
+        create_price_sources_storage(engine_context.database_manager.clone(), data_recorder);
+    let currency_pair_to_symbol_converter = CurrencyPairToSymbolConverter::new(
+        engine_context
+            .exchanges
+            .iter()
+            .map(|x| (*x.key(), x.value().clone()))
+            .collect(),
+    );
+    let price_source_service = PriceSourceService::new(
+        currency_pair_to_symbol_converter,
+        &core_settings.price_sources,
+        PriceSourcesLoader::new(storage.clone()),
+    );
+
+    let action = price_source_service.clone().start(
+        PriceSourcesSaver::new(storage),
+        engine_context.get_events_channel(),
+        engine_context.application_manager.stop_token(),
+    );
+    let _ = spawn_future("Start PriceSourceService", true, action.map(Ok).boxed());
+
+    Some(price_source_service)
+}
+
+/// Market prices for `UsdDenominator` are refreshed in background from the service specified in settings
+fn start_usd_converter(
+    core_settings: &CoreSettings,
+    engine_context: &Arc<EngineContext>,
+    price_source_service: Option<Arc<PriceSourceService>>,
+) -> Option<Arc<UsdConverter>> {
+    let usd_converter_settings = core_settings.usd_converter.as_ref()?;
+
+    let exchanges = engine_context
+        .exchanges
+        .iter()
+        .map(|x| x.value().clone())
+        .collect_vec();
+    let market_service: Arc<dyn GetMarketCurrencyCodePrice + Send + Sync> =
+        match &usd_converter_settings.market_prices {
+            MarketPricesSettings::OrderBooks { usd_currency_codes } => {
+                let usd_currency_codes = match usd_currency_codes.is_empty() {
+                    true => OrderBookMarketPrices::default_usd_currency_codes(),
+                    false => usd_currency_codes.clone(),
+                };
+                OrderBookMarketPrices::new(exchanges.clone(), usd_currency_codes)
+            }
+            MarketPricesSettings::Tickers { base_url } => {
+                TickerMarketPrices::with_base_url(base_url.as_str())
+            }
+        };
+    let usd_denominator = UsdDenominator::start(
+        market_service,
+        usd_converter_settings.usd_denominator,
+        engine_context.application_manager.clone(),
+    );
+
+    let currencies = exchanges
+        .iter()
+        .flat_map(|exchange| exchange.currencies.lock().clone())
+        .unique()
+        .collect_vec();
+
+    Some(Arc::new(UsdConverter::new(
+        &currencies,
+        price_source_service,
+        usd_denominator,
+    )))
+}
+
+/// Stopper conditions are checked by `BalanceChangesService` on fills and by timer.
+/// Settings are validated before engine start, so errors here mean inconsistent engine state
+fn start_profit_loss_stopper(
+    core_settings: &CoreSettings,
+    engine_context: &Arc<EngineContext>,
+    usd_converter: Option<Arc<UsdConverter>>,
+) -> Result<()> {
+    let stopper_settings = match &core_settings.profit_loss_stopper {
+        Some(stopper_settings) => stopper_settings,
+        None => return Ok(()),
+    };
+    let usd_converter = usd_converter
+        .context("Profit loss stopper requires 'usd_converter' section in settings")?;
+
+    let exchange = engine_context
+        .exchanges
+        .get(&stopper_settings.exchange_account_id)
+        .with_context(|| {
+            format!(
+                "Exchange {} of profit loss stopper isn't created",
+                stopper_settings.exchange_account_id
+            )
+        })?
+        .clone();
+
+    run_balance_changes_service(stopper_settings, engine_context, exchange, usd_converter);
+
+    Ok(())
+}
+
+/// `ProfitLossStopperService` is built on mocked `ExchangeBlocker`, `BalanceManager` and `EngineApi`
+/// in unit tests of the crate, so it can be created from `EngineContext` in non-test builds only
+#[cfg(not(test))]
+fn run_balance_changes_service(
+    stopper_settings: &ProfitLossStopperSettings,
+    engine_context: &Arc<EngineContext>,
+    exchange: Arc<Exchange>,
+    usd_converter: Arc<UsdConverter>,
+) {
+    use crate::balance_changes::balance_changes_service::BalanceChangesService;
+    use crate::balance_changes::profit_loss_stopper_service::ProfitLossStopperService;
+    use crate::exchanges::common::TradePlaceAccount;
+    use crate::exchanges::general::engine_api::EngineApi;
+
+    let profit_loss_stopper_service = Arc::new(ProfitLossStopperService::new(
+        TradePlaceAccount::new(
+            stopper_settings.exchange_account_id,
+            stopper_settings.currency_pair,
+        ),
+        stopper_settings,
+        engine_context.exchange_blocker.clone(),
+        Some(engine_context.balance_manager.clone()),
+        EngineApi::new(exchange),
+        engine_context.pre_trade_risk.clone(),
+    ));
+
+    let balance_changes_service = BalanceChangesService::new(
+        CurrencyPairToSymbolConverter::new(
+            engine_context
+                .exchanges
+                .iter()
+                .map(|x| (*x.key(), x.value().clone()))
+                .collect(),
+        ),
+        profit_loss_stopper_service,
+        usd_converter,
+        engine_context.application_manager.clone(),
+    );
+    engine_context
+        .balance_manager
+        .lock()
+        .set_balance_changes_service(balance_changes_service.clone());
+
+    let action = balance_changes_service.run(engine_context.application_manager.stop_token());
+    let _ = spawn_future("Run BalanceChangesService", true, action.map(Ok).boxed());
+}
+
+#[cfg(test)]
+fn run_balance_changes_service(
+    _stopper_settings: &ProfitLossStopperSettings,
+    _engine_context: &Arc<EngineContext>,
+    _exchange: Arc<Exchange>,
+    _usd_converter: Arc<UsdConverter>,
+) {
+    nothing_to_do()
+}
+
 pub(crate) fn handle_panic(
     application_manager: Option<Arc<ApplicationManager>>,
     panic: Box<dyn Any + Send>,
@@ -300,6 +654,25 @@ pub async fn launch_trading_engine<StrategySettings>(
         Arc<EngineContext>,
     ) -> Box<dyn DispositionStrategy + 'static>,
 ) -> Result<Option<TradingEngine>>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    launch_trading_engine_with_strategies(build_settings, init_user_settings, |settings, ctx| {
+        vec![HostedStrategy::new(
+            &settings.strategy,
+            build_strategy(settings, ctx),
+        )]
+    })
+    .await
+}
+
+/// Launches trading engine hosting several strategies. Strategies share exchange connections,
+/// but each of them has own disposition executor and can be stopped through control panel independently
+pub async fn launch_trading_engine_with_strategies<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    init_user_settings: InitSettings<StrategySettings>,
+    build_strategies: impl Fn(&AppSettings<StrategySettings>, Arc<EngineContext>) -> Vec<HostedStrategy>,
+) -> Result<Option<TradingEngine>>
 where
     StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
 {
@@ -343,7 +716,7 @@ where
             settings,
             exchanges_map,
             init_user_settings,
-            build_strategy,
+            build_strategies,
             finish_graceful_shutdown_rx,
         )
     }));
@@ -357,23 +730,320 @@ where
     .map(|trading_engine| Some(trading_engine))
 }
 
+/// Settings of the historical market data replay
+pub struct BacktestSettings {
+    /// Files with recorded market events in chronological order
+    pub market_data_paths: Vec<PathBuf>,
+    /// Simulated exchange which executes orders against replayed market data.
+    /// Its client builder should be registered in `EngineBuildConfig`
+    pub market: Arc<dyn BacktestMarket>,
+    /// Interval between inventory and PnL samples in report
+    pub sample_interval: chrono::Duration,
+}
+
+static IS_BACKTEST_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Marks the process as running backtest until dropped, so virtual time is reset even if backtest panics
+struct BacktestRunningGuard;
+
+impl BacktestRunningGuard {
+    fn try_acquire() -> Result<Self> {
+        if IS_BACKTEST_RUNNING.swap(true, Ordering::SeqCst) {
+            bail!("Another backtest is running in the process, but virtual time can't be shared between backtests");
+        }
+
+        Ok(Self)
+    }
+}
+
+impl Drop for BacktestRunningGuard {
+    fn drop(&mut self) {
+        reset_virtual_time();
+        IS_BACKTEST_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Replays recorded market data through the disposition executor of the strategy.
+/// Engine time follows replayed events, so the whole replay takes as much wall time as event processing.
+/// Backtest runs on its own runtime, so it shouldn't be called within async context
+pub fn launch_backtest<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    settings: AppSettings<StrategySettings>,
+    backtest_settings: BacktestSettings,
+    build_strategy: impl Fn(
+        &AppSettings<StrategySettings>,
+        Arc<EngineContext>,
+    ) -> Box<dyn DispositionStrategy + 'static>,
+) -> Result<BacktestReport>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    launch_backtest_with_strategies(
+        build_settings,
+        settings,
+        backtest_settings,
+        |settings, ctx| {
+            vec![HostedStrategy::new(
+                &settings.strategy,
+                build_strategy(settings, ctx),
+            )]
+        },
+    )
+}
+
+/// Replays recorded market data through disposition executors of several strategies hosted in one engine.
+/// Report covers every trade place of every strategy and trade places where engine services like `Hedger` trade.
+/// Backtest runs on its own runtime, so it shouldn't be called within async context.
+/// Virtual time is global for the process, so only one backtest can run in the process at a time
+pub fn launch_backtest_with_strategies<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    settings: AppSettings<StrategySettings>,
+    backtest_settings: BacktestSettings,
+    build_strategies: impl Fn(&AppSettings<StrategySettings>, Arc<EngineContext>) -> Vec<HostedStrategy>,
+) -> Result<BacktestReport>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    init_logger();
+
+    // Engine tasks run on the single thread, so they handle replayed events in the same order on every run.
+    // The thread is parked only when there are no tasks ready to run, i.e. all queued events are handled
+    let runtime_idle = Arc::new(Notify::new());
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .on_thread_park({
+            let runtime_idle = runtime_idle.clone();
+            move || runtime_idle.notify_one()
+        })
+        .build()
+        .context("Unable to create runtime for backtest")?;
+
+    let mut market_data = MarketDataReader::new(backtest_settings.market_data_paths.clone());
+    let first_event = match market_data.next() {
+        Some(event) => event?,
+        None => bail!("There are no market events to replay"),
+    };
+
+    let _backtest_running_guard = BacktestRunningGuard::try_acquire()?;
+
+    log::info!("Backtest starting from {}", first_event.time());
+    set_virtual_time(first_event.time());
+
+    runtime.block_on(run_backtest(
+        build_settings,
+        settings,
+        backtest_settings,
+        first_event,
+        market_data,
+        &runtime_idle,
+        build_strategies,
+    ))
+}
+
+async fn run_backtest<StrategySettings>(
+    build_settings: &EngineBuildConfig,
+    settings: AppSettings<StrategySettings>,
+    backtest_settings: BacktestSettings,
+    first_event: RecordedMarketEvent,
+    market_data: MarketDataReader,
+    runtime_idle: &Notify,
+    build_strategies: impl Fn(&AppSettings<StrategySettings>, Arc<EngineContext>) -> Vec<HostedStrategy>,
+) -> Result<BacktestReport>
+where
+    StrategySettings: BaseStrategySettings + Clone + Debug + DeserializeOwned + Serialize,
+{
+    let (
+        events_sender,
+        events_receiver,
+        exchanges_map,
+        engine_context,
+        _finish_graceful_shutdown_rx,
+    ) = create_engine_context(&settings.core, build_settings).await;
+
+    // Subscribe before any service starts so no event caused by replay is missed
+    let mut events_observer = engine_context.get_events_channel();
+
+    let internal_events_loop = InternalEventsLoop::new();
+    engine_context
+        .shutdown_service
+        .register_service(internal_events_loop.clone());
+
+    {
+        let local_exchanges_map = exchanges_map.into_iter().collect();
+        let action = internal_events_loop.start(
+            events_receiver,
+            local_exchanges_map,
+            engine_context.application_manager.stop_token(),
+        );
+        let _ = spawn_future("internal_events_loop start", true, action.boxed());
+    }
+
+    let currency_pair_to_symbol_converter = {
+        #[double]
+        use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+
+        CurrencyPairToSymbolConverter::new(
+            engine_context
+                .exchanges
+                .iter()
+                .map(|x| (*x.key(), x.value().clone()))
+                .collect(),
+        )
+    };
+    let mut report = BacktestReportBuilder::new(
+        currency_pair_to_symbol_converter,
+        backtest_settings.sample_interval,
+        first_event.time(),
+    );
+
+    let statistics = engine_context.statistic_service.clone();
+    for hosted_strategy in build_strategies(&settings, engine_context.clone()) {
+        let strategy_name = hosted_strategy
+            .strategy
+            .configuration_descriptor()
+            .service_name;
+        for trade_place in &hosted_strategy.trade_places {
+            let _ = engine_context
+                .exchanges
+                .get(&trade_place.exchange_account_id)
+                .with_context(|| {
+                    format!(
+                        "Exchange {} is not created",
+                        trade_place.exchange_account_id
+                    )
+                })?
+                .get_symbol(trade_place.currency_pair)?;
+            report.add_trade_place(strategy_name.as_str(), *trade_place);
+        }
+
+        let disposition_executor_service =
+            create_disposition_executor_service(hosted_strategy, &engine_context, &statistics)?;
+        engine_context
+            .shutdown_service
+            .register_service(disposition_executor_service);
+    }
+
+    let stop_token = engine_context.application_manager.stop_token();
+    for recorded_event in iter::once(Ok(first_event)).chain(market_data) {
+        if stop_token.is_cancellation_requested() {
+            bail!("Backtest was stopped before all market events were replayed");
+        }
+
+        let recorded_event = recorded_event?;
+        let now = recorded_event.time();
+        set_virtual_time(now);
+
+        let event = recorded_event.to_exchange_event();
+        match &event {
+            ExchangeEvent::OrderBookEvent(event) => {
+                backtest_settings.market.handle_order_book_event(event)
+            }
+            ExchangeEvent::Trades(event) => backtest_settings.market.handle_trades_event(event),
+            _ => nothing_to_do(),
+        }
+
+        events_sender
+            .send(event)
+            .map_err(|_| anyhow!("Unable to send replayed market event"))?;
+
+        wait_until_events_handled(
+            &mut events_observer,
+            runtime_idle,
+            backtest_settings.market.as_ref(),
+            &mut report,
+        )
+        .await?;
+        report.market_event_handled(now);
+    }
+
+    let finished_at = get_current_time();
+    log::info!("Backtest finished at {}", finished_at);
+
+    engine_context.clone().graceful_shutdown().await;
+
+    Ok(report.build(finished_at))
+}
+
+/// Lets spawned engine tasks handle all events caused by the last replayed event.
+/// Backtest runtime notifies `runtime_idle` when it has no ready tasks, so every event queued before that
+/// is handled. Runtime is also idle while requests wait for simulated latency, so events are considered
+/// handled when the runtime becomes idle without new observed events and requests in flight
+async fn wait_until_events_handled(
+    events_observer: &mut broadcast::Receiver<ExchangeEvent>,
+    runtime_idle: &Notify,
+    market: &dyn BacktestMarket,
+    report: &mut BacktestReportBuilder,
+) -> Result<()> {
+    loop {
+        // Runtime could become idle before the replayed event was sent, so the stored notification is stale
+        let _ = runtime_idle.notified().now_or_never();
+        runtime_idle.notified().await;
+
+        let mut has_new_events = false;
+        loop {
+            match events_observer.try_recv() {
+                Ok(event) => {
+                    report.handle_event(&event, get_current_time());
+                    has_new_events = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(error) => bail!("Unable to observe engine events during backtest: {}", error),
+            }
+        }
+
+        if !has_new_events && !market.has_requests_in_flight() {
+            return Ok(());
+        }
+    }
+}
+
 fn create_disposition_executor_service(
-    base_settings: &dyn BaseStrategySettings,
+    hosted_strategy: HostedStrategy,
     engine_context: &Arc<EngineContext>,
-    disposition_strategy: Box<dyn DispositionStrategy>,
     statistics: &Arc<StatisticService>,
-) -> Arc<DispositionExecutorService> {
-    DispositionExecutorService::new(
+) -> Result<Arc<DispositionExecutorService>> {
+    let configuration_descriptor = hosted_strategy.strategy.configuration_descriptor();
+    let is_enabled = engine_context
+        .hosted_strategies
+        .register(configuration_descriptor)?;
+
+    if let Some(amount_limit) = hosted_strategy.amount_limit {
+        for trade_place in &hosted_strategy.trade_places {
+            let symbol = engine_context
+                .exchanges
+                .get(&trade_place.exchange_account_id)
+                .with_context(|| {
+                    format!(
+                        "Exchange {} is not created",
+                        trade_place.exchange_account_id
+                    )
+                })?
+                .get_symbol(trade_place.currency_pair)?;
+
+            engine_context
+                .balance_manager
+                .lock()
+                .set_target_amount_limit(
+                    configuration_descriptor,
+                    trade_place.exchange_account_id,
+                    symbol,
+                    amount_limit,
+                );
+        }
+    }
+
+    Ok(DispositionExecutorService::new(
         engine_context.clone(),
         engine_context.get_events_channel(),
         LocalSnapshotsService::default(),
-        base_settings.exchange_account_id(),
-        base_settings.currency_pair(),
-        base_settings.max_amount(),
-        disposition_strategy,
+        hosted_strategy.trade_places,
+        hosted_strategy.max_amount,
+        hosted_strategy.price_slots_count,
+        hosted_strategy.strategy,
+        is_enabled,
         engine_context.application_manager.stop_token(),
         statistics.clone(),
-    )
+    ))
 }
 
 fn create_statistic_event_handler(
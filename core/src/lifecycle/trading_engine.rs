@@ -14,7 +14,9 @@ use mmb_utils::cancellation_token::CancellationToken;
 use tokio::sync::{broadcast, oneshot};
 use tokio::time::Duration;
 
+use crate::alerts::alert_bus::AlertBus;
 use crate::balance_manager::balance_manager::BalanceManager;
+use crate::database::database_manager::DatabaseManager;
 use crate::exchanges::block_reasons;
 use crate::exchanges::common::ExchangeAccountId;
 use crate::exchanges::events::{ExchangeEvent, ExchangeEvents};
@@ -23,7 +25,12 @@ use crate::exchanges::exchange_blocker::ExchangeBlocker;
 use crate::exchanges::general::exchange::Exchange;
 use crate::exchanges::timeouts::timeout_manager::TimeoutManager;
 use crate::lifecycle::shutdown::ShutdownService;
+use crate::pnl::pnl_ledger::PnlLedger;
+use crate::risk::kill_switch::KillSwitch;
+use crate::risk::pre_trade_risk::PreTradeRiskEngine;
 use crate::settings::CoreSettings;
+use crate::statistic_service::StatisticService;
+use crate::strategies::hosted_strategies::HostedStrategies;
 use crate::{
     infrastructure::unset_application_manager, lifecycle::application_manager::ApplicationManager,
 };
@@ -45,6 +52,17 @@ pub struct EngineContext {
     pub application_manager: Arc<ApplicationManager>,
     pub timeout_manager: Arc<TimeoutManager>,
     pub balance_manager: Arc<Mutex<BalanceManager>>,
+    /// Connected only if `CoreSettings::database` is specified
+    pub database_manager: Option<Arc<DatabaseManager>>,
+    pub hosted_strategies: Arc<HostedStrategies>,
+    pub statistic_service: Arc<StatisticService>,
+    /// Checks orders of strategies by `CoreSettings::risk_limits`
+    pub pre_trade_risk: Arc<PreTradeRiskEngine>,
+    pub kill_switch: Arc<KillSwitch>,
+    /// PnL of strategies calculated by their fills
+    pub pnl_ledger: Arc<PnlLedger>,
+    /// Alerts are sent to sinks from `CoreSettings::alerts`
+    pub alert_bus: Arc<AlertBus>,
     is_graceful_shutdown_started: AtomicBool,
     exchange_events: ExchangeEvents,
     finish_graceful_shutdown_sender: Mutex<Option<oneshot::Sender<()>>>,
@@ -59,6 +77,7 @@ impl EngineContext {
         timeout_manager: Arc<TimeoutManager>,
         application_manager: Arc<ApplicationManager>,
         balance_manager: Arc<Mutex<BalanceManager>>,
+        database_manager: Option<Arc<DatabaseManager>>,
     ) -> Arc<Self> {
         let exchange_account_ids = app_settings
             .exchanges
@@ -66,6 +85,13 @@ impl EngineContext {
             .map(|x| x.exchange_account_id)
             .collect_vec();
 
+        let pre_trade_risk =
+            PreTradeRiskEngine::new(app_settings.risk_limits.clone().unwrap_or_default());
+
+        let kill_switch = KillSwitch::new(app_settings.kill_switch.clone());
+
+        let alert_bus = AlertBus::new(app_settings.alerts.as_ref());
+
         let engine_context = Arc::new(EngineContext {
             app_settings,
             exchanges,
@@ -74,6 +100,13 @@ impl EngineContext {
             application_manager: application_manager.clone(),
             timeout_manager,
             balance_manager,
+            database_manager,
+            hosted_strategies: Default::default(),
+            statistic_service: StatisticService::new(),
+            pre_trade_risk,
+            kill_switch,
+            pnl_ledger: PnlLedger::new(),
+            alert_bus,
             is_graceful_shutdown_started: Default::default(),
             exchange_events,
             finish_graceful_shutdown_sender: Mutex::new(Some(finish_graceful_shutdown_sender)),
@@ -128,6 +161,15 @@ impl EngineContext {
             .map(|exchange| exchange.clone().disconnect());
         join_all(disconnect_websockets).await;
 
+        // orders canceled on shutdown should be restored as finished after restart
+        let flush_orders_journals = self
+            .exchanges
+            .iter()
+            .map(|exchange| exchange.clone().flush_orders_journal());
+        join_all(flush_orders_journals).await;
+
+        self.alert_bus.flush(TIMEOUT).await;
+
         self.finish_graceful_shutdown_sender
             .lock()
             .take()
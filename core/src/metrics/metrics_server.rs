@@ -0,0 +1,107 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures::FutureExt;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::Service;
+use crate::metrics::prometheus_metrics::EngineMetrics;
+
+static METRICS_SERVER: &str = "MetricsServer";
+const METRICS_PATH: &str = "/metrics";
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MetricsSettings {
+    /// Address for Prometheus scraping, e.g. `127.0.0.1:9100`
+    pub address: SocketAddr,
+}
+
+/// HTTP server exposing `EngineMetrics` on `/metrics` in Prometheus format
+pub struct MetricsServer {
+    stop_sender: Mutex<Option<oneshot::Sender<()>>>,
+    work_finished_receiver: Mutex<Option<oneshot::Receiver<Result<()>>>>,
+}
+
+impl MetricsServer {
+    pub fn start(settings: &MetricsSettings, metrics: EngineMetrics) -> Result<Arc<Self>> {
+        let metrics = Arc::new(metrics);
+        let make_service = make_service_fn(move |_| {
+            let metrics = metrics.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let response = handle_request(&request, &metrics);
+                    async move { Ok::<_, Infallible>(response) }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&settings.address)
+            .with_context(|| format!("Unable to bind metrics server to {}", settings.address))?
+            .serve(make_service);
+        log::info!("Metrics server is listening on {}", server.local_addr());
+
+        let (stop_sender, stop_receiver) = oneshot::channel();
+        let (work_finished_sender, work_finished_receiver) = oneshot::channel();
+
+        let action = async move {
+            let result = server
+                .with_graceful_shutdown(async {
+                    let _ = stop_receiver.await;
+                })
+                .await
+                .context("Metrics server failed");
+            let _ = work_finished_sender.send(result);
+
+            Ok(())
+        };
+        let _ = spawn_future("Start metrics server", true, action.boxed());
+
+        Ok(Arc::new(Self {
+            stop_sender: Mutex::new(Some(stop_sender)),
+            work_finished_receiver: Mutex::new(Some(work_finished_receiver)),
+        }))
+    }
+}
+
+fn handle_request(request: &Request<Body>, metrics: &EngineMetrics) -> Response<Body> {
+    if request.method() != Method::GET || request.uri().path() != METRICS_PATH {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+
+    let mut response = Response::new(Body::from(metrics.render()));
+    let _ = response.headers_mut().insert(
+        CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static(METRICS_CONTENT_TYPE),
+    );
+    response
+}
+
+impl Service for MetricsServer {
+    fn name(&self) -> &str {
+        METRICS_SERVER
+    }
+
+    fn graceful_shutdown(self: Arc<Self>) -> Option<oneshot::Receiver<Result<()>>> {
+        if let Some(stop_sender) = self.stop_sender.lock().take() {
+            let _ = stop_sender.send(());
+        }
+
+        let work_finished_receiver = self.work_finished_receiver.lock().take();
+        if work_finished_receiver.is_none() {
+            log::warn!("'work_finished_receiver' wasn't created when started graceful shutdown in MetricsServer");
+        }
+
+        work_finished_receiver
+    }
+}
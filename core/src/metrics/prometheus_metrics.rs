@@ -0,0 +1,353 @@
+use std::fmt::{Display, Write};
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::connectivity::connectivity_manager::WebSocketRole;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::statistic_service::{StatisticService, TradePlaceAccountStatistic};
+
+enum MetricType {
+    Counter,
+    Gauge,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+    }
+}
+
+/// Writes metrics in Prometheus text exposition format
+#[derive(Default)]
+struct MetricsWriter {
+    buffer: String,
+}
+
+impl MetricsWriter {
+    fn describe(&mut self, name: &str, metric_type: MetricType, help: &str) {
+        // writing to String can't fail
+        let _ = writeln!(self.buffer, "# HELP {} {}", name, help);
+        let _ = writeln!(self.buffer, "# TYPE {} {}", name, metric_type.as_str());
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &dyn Display)], value: impl Display) {
+        self.buffer.push_str(name);
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(*value)))
+                .join(",");
+            let _ = write!(self.buffer, "{{{}}}", labels);
+        }
+        let _ = writeln!(self.buffer, " {}", value);
+    }
+
+    fn finish(self) -> String {
+        self.buffer
+    }
+}
+
+fn escape_label_value(value: &dyn Display) -> String {
+    value
+        .to_string()
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+fn role_label(role: WebSocketRole) -> &'static str {
+    match role {
+        WebSocketRole::Main => "main",
+        WebSocketRole::Secondary => "secondary",
+    }
+}
+
+/// Current state of the engine for `/metrics` endpoint
+pub struct EngineMetrics {
+    engine_context: Arc<EngineContext>,
+    statistics: Arc<StatisticService>,
+}
+
+impl EngineMetrics {
+    pub fn new(engine_context: Arc<EngineContext>, statistics: Arc<StatisticService>) -> Self {
+        Self {
+            engine_context,
+            statistics,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut writer = MetricsWriter::default();
+        write_statistics(&mut writer, &self.statistics);
+        write_exchanges(&mut writer, &self.engine_context);
+        writer.finish()
+    }
+}
+
+type TradePlaceMetricValue = fn(&TradePlaceAccountStatistic) -> String;
+
+fn write_statistics(writer: &mut MetricsWriter, statistics: &StatisticService) {
+    let state = &statistics.statistic_service_state;
+    let trade_place_stats = state.trade_place_stats.read();
+    let trade_place_stats = trade_place_stats
+        .iter()
+        .sorted_by_key(|(trade_place, _)| {
+            (
+                trade_place.exchange_account_id.to_string(),
+                trade_place.currency_pair.as_str(),
+            )
+        })
+        .collect_vec();
+
+    let trade_place_metrics: [(&str, MetricType, &str, TradePlaceMetricValue); 7] = [
+        (
+            "mmb_orders_created_total",
+            MetricType::Counter,
+            "Count of successfully created orders",
+            |stats| stats.opened_orders_count.to_string(),
+        ),
+        (
+            "mmb_orders_canceled_total",
+            MetricType::Counter,
+            "Count of successfully canceled orders",
+            |stats| stats.canceled_orders_count.to_string(),
+        ),
+        (
+            "mmb_orders_partially_filled",
+            MetricType::Gauge,
+            "Count of partially filled orders which aren't completed yet",
+            |stats| stats.partially_filled_orders_count.to_string(),
+        ),
+        (
+            "mmb_orders_filled_total",
+            MetricType::Counter,
+            "Count of completely filled orders",
+            |stats| stats.fully_filled_orders_count.to_string(),
+        ),
+        (
+            "mmb_filled_amount_total",
+            MetricType::Counter,
+            "Summary filled amount of completely filled orders",
+            |stats| stats.summary_filled_amount.to_string(),
+        ),
+        (
+            "mmb_commission_total",
+            MetricType::Counter,
+            "Summary commission of completely filled orders",
+            |stats| stats.summary_commission.to_string(),
+        ),
+        (
+            "mmb_orders_rejected_by_risk_total",
+            MetricType::Counter,
+            "Count of strategy orders rejected by pre-trade risk limits",
+            |stats| stats.risk_rejected_orders_count.to_string(),
+        ),
+    ];
+
+    for (name, metric_type, help, value) in trade_place_metrics {
+        writer.describe(name, metric_type, help);
+        for (trade_place, stats) in &trade_place_stats {
+            writer.sample(
+                name,
+                &[
+                    ("exchange_account_id", &trade_place.exchange_account_id),
+                    ("currency_pair", &trade_place.currency_pair),
+                ],
+                value(stats),
+            );
+        }
+    }
+
+    let name = "mmb_disposition_executor_skipped_events_total";
+    writer.describe(
+        name,
+        MetricType::Counter,
+        "Count of events skipped by DispositionExecutor",
+    );
+    writer.sample(
+        name,
+        &[],
+        state
+            .disposition_executor_stats
+            .lock()
+            .skipped_events_amount,
+    );
+}
+
+fn write_exchanges(writer: &mut MetricsWriter, engine_context: &EngineContext) {
+    let exchanges = engine_context
+        .exchanges
+        .iter()
+        .map(|x| x.value().clone())
+        .sorted_by_key(|x| x.exchange_account_id.to_string())
+        .collect_vec();
+
+    let name = "mmb_websocket_reconnects_total";
+    writer.describe(
+        name,
+        MetricType::Counter,
+        "Count of attempts to open websocket connection after the first one",
+    );
+    for exchange in &exchanges {
+        for role in [WebSocketRole::Main, WebSocketRole::Secondary] {
+            writer.sample(
+                name,
+                &[
+                    ("exchange_account_id", &exchange.exchange_account_id),
+                    ("role", &role_label(role)),
+                ],
+                exchange.websocket_reconnects_count(role),
+            );
+        }
+    }
+
+    let utilizations = exchanges
+        .iter()
+        .filter_map(|exchange| {
+            let exchange_account_id = exchange.exchange_account_id;
+            match engine_context
+                .timeout_manager
+                .get_utilization(exchange_account_id)
+            {
+                Ok(utilization) => Some((exchange_account_id, utilization)),
+                Err(error) => {
+                    log::error!(
+                        "Unable to get requests utilization for {}: {:?}",
+                        exchange_account_id,
+                        error
+                    );
+                    None
+                }
+            }
+        })
+        .collect_vec();
+
+    let name = "mmb_requests_per_period";
+    writer.describe(
+        name,
+        MetricType::Gauge,
+        "Limit of requests to the exchange per period",
+    );
+    for (exchange_account_id, utilization) in &utilizations {
+        writer.sample(
+            name,
+            &[("exchange_account_id", exchange_account_id)],
+            utilization.requests_per_period,
+        );
+    }
+
+    let name = "mmb_requests_available";
+    writer.describe(
+        name,
+        MetricType::Gauge,
+        "Count of requests to the exchange available in the current period",
+    );
+    for (exchange_account_id, utilization) in &utilizations {
+        writer.sample(
+            name,
+            &[("exchange_account_id", exchange_account_id)],
+            utilization.available_requests_count,
+        );
+    }
+
+    let exchange_blocker = &engine_context.exchange_blocker;
+    let name = "mmb_exchange_blocked";
+    writer.describe(
+        name,
+        MetricType::Gauge,
+        "1 if the exchange account is blocked by any reason, 0 otherwise",
+    );
+    for exchange in &exchanges {
+        let is_blocked = exchange_blocker.is_blocked(exchange.exchange_account_id);
+        writer.sample(
+            name,
+            &[("exchange_account_id", &exchange.exchange_account_id)],
+            is_blocked as u8,
+        );
+    }
+
+    let name = "mmb_exchange_block_reason";
+    writer.describe(
+        name,
+        MetricType::Gauge,
+        "Active block reasons of the exchange account",
+    );
+    for exchange in &exchanges {
+        let reasons = exchange_blocker
+            .get_block_reasons(exchange.exchange_account_id)
+            .into_iter()
+            .sorted_by_key(|reason| reason.to_string());
+        for reason in reasons {
+            writer.sample(
+                name,
+                &[
+                    ("exchange_account_id", &exchange.exchange_account_id),
+                    ("reason", &reason),
+                ],
+                1,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::{CurrencyPair, ExchangeAccountId, TradePlaceAccount};
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn escape_label_values() {
+        let mut writer = MetricsWriter::default();
+        writer.sample("metric", &[("label", &r#"a"b\c"#)], 1);
+
+        assert_eq!(writer.finish(), "metric{label=\"a\\\"b\\\\c\"} 1\n");
+    }
+
+    #[test]
+    fn write_trade_place_statistics() {
+        let statistics = StatisticService::new();
+        let trade_place = TradePlaceAccount::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        );
+        statistics.register_created_order(trade_place);
+        statistics.register_created_order(trade_place);
+        statistics.register_completely_filled_order(
+            trade_place,
+            &"order".into(),
+            dec!(1.5),
+            dec!(0.01),
+        );
+        statistics.register_risk_rejected_order(trade_place);
+        statistics.register_skipped_event();
+
+        let mut writer = MetricsWriter::default();
+        write_statistics(&mut writer, &statistics);
+        let metrics = writer.finish();
+
+        let labels = r#"{exchange_account_id="Binance_0",currency_pair="btc/usdt"}"#;
+        for expected in [
+            "# TYPE mmb_orders_created_total counter".to_owned(),
+            format!("mmb_orders_created_total{} 2", labels),
+            format!("mmb_orders_canceled_total{} 0", labels),
+            "# TYPE mmb_orders_partially_filled gauge".to_owned(),
+            format!("mmb_orders_filled_total{} 1", labels),
+            format!("mmb_filled_amount_total{} 1.5", labels),
+            format!("mmb_commission_total{} 0.01", labels),
+            format!("mmb_orders_rejected_by_risk_total{} 1", labels),
+            "mmb_disposition_executor_skipped_events_total 1".to_owned(),
+        ] {
+            assert!(
+                metrics.lines().any(|line| line == expected),
+                "Line '{}' not found in metrics:\n{}",
+                expected,
+                metrics
+            );
+        }
+    }
+}
@@ -2,8 +2,9 @@ use crate::exchanges::common::{CurrencyPair, Price};
 use crate::orders::order::OrderSide;
 
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DerivativePosition {
     pub currency_pair: CurrencyPair,
     pub position: Decimal,
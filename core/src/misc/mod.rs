@@ -1,8 +1,8 @@
 pub mod derivative_position;
 pub(crate) mod position_helper;
 pub mod price_by_order_side;
-pub(crate) mod price_source_model;
+pub mod price_source_model;
 pub mod reserve_parameters;
 pub(crate) mod service_value_tree;
-pub(crate) mod time;
+pub mod time;
 pub mod traits;
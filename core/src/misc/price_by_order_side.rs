@@ -1,6 +1,7 @@
 use crate::exchanges::common::Price;
+use rust_decimal_macros::dec;
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PriceByOrderSide {
     pub top_bid: Option<Price>,
     pub top_ask: Option<Price>,
@@ -10,4 +11,9 @@ impl PriceByOrderSide {
     pub fn new(top_bid: Option<Price>, top_ask: Option<Price>) -> Self {
         Self { top_bid, top_ask }
     }
+
+    /// Return None if any side of the order book is empty
+    pub fn calculate_middle_price(&self) -> Option<Price> {
+        Some((self.top_ask? + self.top_bid?) * dec!(0.5))
+    }
 }
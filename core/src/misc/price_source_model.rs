@@ -1,29 +1,31 @@
 use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
 
 use crate::exchanges::common::{CurrencyPair, ExchangeId, Price};
 
-pub(crate) struct PriceSourceModel {
-    pub _init_time: DateTime,
-    pub _exchange_id: ExchangeId,
-    pub _currency_pair: CurrencyPair,
-    pub _bid: Option<Price>,
-    pub _ask: Option<Price>,
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceSourceModel {
+    pub init_time: DateTime,
+    pub exchange_id: ExchangeId,
+    pub currency_pair: CurrencyPair,
+    pub bid: Option<Price>,
+    pub ask: Option<Price>,
 }
 
 impl PriceSourceModel {
     pub fn new(
-        _init_time: DateTime,
-        _exchange_id: ExchangeId,
-        _currency_pair: CurrencyPair,
-        _bid: Option<Price>,
-        _ask: Option<Price>,
+        init_time: DateTime,
+        exchange_id: ExchangeId,
+        currency_pair: CurrencyPair,
+        bid: Option<Price>,
+        ask: Option<Price>,
     ) -> Self {
         Self {
-            _init_time,
-            _exchange_id,
-            _currency_pair,
-            _bid,
-            _ask,
+            init_time,
+            exchange_id,
+            currency_pair,
+            bid,
+            ask,
         }
     }
 }
@@ -1,5 +1,26 @@
+use mmb_utils::DateTime;
 #[cfg(test)]
 use mockall::automock;
+use parking_lot::{const_mutex, Mutex};
+
+static VIRTUAL_TIME: Mutex<Option<DateTime>> = const_mutex(None);
+
+/// Replace wall clock time with the specified time for all engine time sources.
+/// Used for replaying historical data, where time should follow replayed events.
+/// Virtual time is shared by all engines of the process, so several replays can't run in parallel
+pub fn set_virtual_time(time: DateTime) {
+    *VIRTUAL_TIME.lock() = Some(time);
+}
+
+/// Return engine time sources back to wall clock time
+pub fn reset_virtual_time() {
+    *VIRTUAL_TIME.lock() = None;
+}
+
+/// Current engine time in UTC: virtual time if it was set, otherwise wall clock time
+pub fn get_current_time() -> DateTime {
+    VIRTUAL_TIME.lock().unwrap_or_else(chrono::Utc::now)
+}
 
 /// If you'll use this mod in some tests, mocks object should be created.
 /// Automock doesn't support default implementation.
@@ -12,7 +33,7 @@ pub(crate) mod time_manager {
 
     /// Return current date in UTC
     pub(crate) fn now() -> DateTime {
-        chrono::Utc::now()
+        super::get_current_time()
     }
 }
 
@@ -37,8 +58,10 @@ pub(crate) mod tests {
         let time_manager_mock_object = time_manager::now_context();
         time_manager_mock_object.expect().returning(move || {
             chrono::Utc
-                .ymd(2021, 9, 20)
-                .and_hms(0, 0, seconds_offset.lock().clone())
+                .with_ymd_and_hms(2021, 9, 20, 0, 0, 0)
+                .single()
+                .expect("in test")
+                + chrono::Duration::seconds(seconds_offset.lock().clone().into())
         });
 
         (time_manager_mock_object, mock_locker)
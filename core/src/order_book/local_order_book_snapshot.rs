@@ -1,7 +1,11 @@
+use itertools::Either;
 use mmb_utils::DateTime;
+use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
 use crate::exchanges::common::*;
+use crate::exchanges::general::commission::Percent;
+use crate::math::ConvertPercentToRate;
 use crate::misc::price_by_order_side::PriceByOrderSide;
 use crate::order_book::order_book_data::OrderBookData;
 use crate::orders::order::*;
@@ -90,6 +94,17 @@ impl LocalOrderBookSnapshot {
         self.bids.iter().rev()
     }
 
+    /// Return asks or bids values starting from the top of the book
+    pub fn get_price_levels(
+        &self,
+        book_side: OrderSide,
+    ) -> impl Iterator<Item = (&Price, &Amount)> {
+        match book_side {
+            OrderSide::Buy => Either::Left(self.get_bids_price_levels()),
+            OrderSide::Sell => Either::Right(self.get_asks_price_levels()),
+        }
+    }
+
     fn try_remove_order(&mut self, order: DataToExcludeOrder) {
         let book_side = self.get_order_book_side(order.side);
 
@@ -120,31 +135,118 @@ impl LocalOrderBookSnapshot {
 
     pub fn calculate_middle_price(&self, trade_place: TradePlace) -> Option<Price> {
         let prices = self.get_top_prices();
-        let top_ask = match prices.top_ask {
-            Some(top_ask) => top_ask,
-            None => {
-                log::warn!(
+        if prices.top_ask.is_none() {
+            log::warn!(
                 "Can't get top ask price in {:?} in LocalOrderBookSnapshot::calculate_middle_price() {:?}",
                 trade_place,
                 self
             );
-                return None;
-            }
-        };
+            return None;
+        }
 
-        let top_bid = match prices.top_bid {
-            Some(top_bid) => top_bid,
-            None => {
-                log::warn!(
+        if prices.top_bid.is_none() {
+            log::warn!(
                 "Can't get top bid price in {:?} in LocalOrderBookSnapshot::calculate_middle_price() {:?}",
                 trade_place,
                 self
             );
-                return None;
+            return None;
+        }
+
+        prices.calculate_middle_price()
+    }
+
+    // Methods below work with current levels of the snapshot,
+    // so our own orders should be removed with `exclude_orders` beforehand
+
+    /// Cost in quote currency of consuming `amount` from the top of `book_side`
+    /// (bids for sell orders, asks for buy orders).
+    /// Return None if there is not enough depth on the side
+    pub fn calculate_cost_to_sweep(&self, book_side: OrderSide, amount: Amount) -> Option<Decimal> {
+        let mut remaining_amount = amount;
+        let mut cost = dec!(0);
+
+        for (price, level_amount) in self.get_price_levels(book_side) {
+            if remaining_amount <= dec!(0) {
+                break;
             }
+
+            let consumed_amount = remaining_amount.min(*level_amount);
+            cost += price * consumed_amount;
+            remaining_amount -= consumed_amount;
+        }
+
+        if remaining_amount > dec!(0) {
+            return None;
+        }
+
+        Some(cost)
+    }
+
+    /// Volume weighted average price of consuming `amount` from the top of `book_side`.
+    /// Return None if amount isn't positive or there is not enough depth on the side
+    pub fn calculate_vwap_for_amount(&self, book_side: OrderSide, amount: Amount) -> Option<Price> {
+        if amount <= dec!(0) {
+            return None;
+        }
+
+        self.calculate_cost_to_sweep(book_side, amount)
+            .map(|cost| cost / amount)
+    }
+
+    /// Cumulative amount of `book_side` levels with price deviating from middle price not more than `max_deviation`
+    pub fn calculate_depth_within(
+        &self,
+        book_side: OrderSide,
+        max_deviation: Percent,
+    ) -> Option<Amount> {
+        let middle_price = self.get_top_prices().calculate_middle_price()?;
+        let price_delta = middle_price * max_deviation.percent_to_rate();
+
+        let depth = self
+            .get_price_levels(book_side)
+            .take_while(|(price, _)| match book_side {
+                OrderSide::Buy => **price >= middle_price - price_delta,
+                OrderSide::Sell => **price <= middle_price + price_delta,
+            })
+            .map(|(_, amount)| *amount)
+            .sum();
+
+        Some(depth)
+    }
+
+    /// Imbalance of `levels_count` top levels in range [-1, 1]:
+    /// positive values mean bids prevail over asks, negative ones mean asks prevail over bids.
+    /// Return None if both sides are empty
+    pub fn calculate_imbalance(&self, levels_count: usize) -> Option<Decimal> {
+        let sum_amount = |book_side| -> Amount {
+            self.get_price_levels(book_side)
+                .take(levels_count)
+                .map(|(_, amount)| *amount)
+                .sum()
         };
 
-        Some((top_ask + top_bid) * dec!(0.5))
+        let bids_amount = sum_amount(OrderSide::Buy);
+        let asks_amount = sum_amount(OrderSide::Sell);
+        let total_amount = bids_amount + asks_amount;
+        if total_amount.is_zero() {
+            return None;
+        }
+
+        Some((bids_amount - asks_amount) / total_amount)
+    }
+
+    /// Middle price weighted by top levels amounts, so it is shifted to the side with less liquidity
+    pub fn calculate_microprice(&self) -> Option<Price> {
+        let (top_ask_price, top_ask_amount) = self.get_top_ask()?;
+        let (top_bid_price, top_bid_amount) = self.get_top_bid()?;
+
+        let total_amount = top_ask_amount + top_bid_amount;
+        if total_amount.is_zero() {
+            return None;
+        }
+
+        Some((top_ask_price * top_bid_amount + top_bid_price * top_ask_amount) / total_amount)
     }
 }
 
@@ -314,4 +416,151 @@ mod tests {
         // Still exists
         assert_eq!(asks.next().expect("in test"), (&dec!(3.0), &dec!(4.2)));
     }
+
+    fn make_snapshot(asks: &[(Price, Amount)], bids: &[(Price, Amount)]) -> LocalOrderBookSnapshot {
+        LocalOrderBookSnapshot::new(
+            asks.iter().cloned().collect(),
+            bids.iter().cloned().collect(),
+            Utc::now(),
+        )
+    }
+
+    fn make_default_snapshot() -> LocalOrderBookSnapshot {
+        make_snapshot(
+            &[
+                (dec!(101), dec!(1)),
+                (dec!(102), dec!(2)),
+                (dec!(110), dec!(5)),
+            ],
+            &[
+                (dec!(99), dec!(3)),
+                (dec!(98), dec!(1)),
+                (dec!(90), dec!(5)),
+            ],
+        )
+    }
+
+    #[test]
+    fn cost_to_sweep() {
+        let snapshot = make_default_snapshot();
+
+        // 1 * 101 + 1.5 * 102
+        assert_eq!(
+            snapshot.calculate_cost_to_sweep(OrderSide::Sell, dec!(2.5)),
+            Some(dec!(254))
+        );
+        // 3 * 99 + 1 * 98 + 1 * 90
+        assert_eq!(
+            snapshot.calculate_cost_to_sweep(OrderSide::Buy, dec!(5)),
+            Some(dec!(485))
+        );
+        assert_eq!(
+            snapshot.calculate_cost_to_sweep(OrderSide::Sell, dec!(0)),
+            Some(dec!(0))
+        );
+    }
+
+    #[test]
+    fn cost_to_sweep_without_enough_depth() {
+        let snapshot = make_default_snapshot();
+
+        assert_eq!(
+            snapshot.calculate_cost_to_sweep(OrderSide::Sell, dec!(8.1)),
+            None
+        );
+    }
+
+    #[test]
+    fn vwap_for_amount() {
+        let snapshot = make_default_snapshot();
+
+        assert_eq!(
+            snapshot.calculate_vwap_for_amount(OrderSide::Sell, dec!(2.5)),
+            Some(dec!(101.6))
+        );
+        assert_eq!(
+            snapshot.calculate_vwap_for_amount(OrderSide::Buy, dec!(2)),
+            Some(dec!(99))
+        );
+        assert_eq!(
+            snapshot.calculate_vwap_for_amount(OrderSide::Buy, dec!(0)),
+            None
+        );
+        assert_eq!(
+            snapshot.calculate_vwap_for_amount(OrderSide::Buy, dec!(100)),
+            None
+        );
+    }
+
+    #[test]
+    fn vwap_respects_excluded_orders() {
+        let mut snapshot = make_default_snapshot();
+        snapshot.exclude_orders(vec![DataToExcludeOrder::new(
+            dec!(101),
+            dec!(1),
+            OrderSide::Sell,
+        )]);
+
+        assert_eq!(
+            snapshot.calculate_vwap_for_amount(OrderSide::Sell, dec!(2)),
+            Some(dec!(102))
+        );
+    }
+
+    #[test]
+    fn depth_within() {
+        // middle price is 100
+        let snapshot = make_default_snapshot();
+
+        assert_eq!(
+            snapshot.calculate_depth_within(OrderSide::Sell, dec!(2)),
+            Some(dec!(3))
+        );
+        assert_eq!(
+            snapshot.calculate_depth_within(OrderSide::Buy, dec!(2)),
+            Some(dec!(4))
+        );
+        assert_eq!(
+            snapshot.calculate_depth_within(OrderSide::Buy, dec!(0.5)),
+            Some(dec!(0))
+        );
+        assert_eq!(
+            snapshot.calculate_depth_within(OrderSide::Sell, dec!(10)),
+            Some(dec!(8))
+        );
+    }
+
+    #[test]
+    fn depth_within_for_one_sided_book() {
+        let snapshot = make_snapshot(&[(dec!(101), dec!(1))], &[]);
+
+        assert_eq!(
+            snapshot.calculate_depth_within(OrderSide::Sell, dec!(2)),
+            None
+        );
+    }
+
+    #[test]
+    fn imbalance() {
+        let snapshot = make_default_snapshot();
+
+        // (3 - 1) / (3 + 1)
+        assert_eq!(snapshot.calculate_imbalance(1), Some(dec!(0.5)));
+        // (4 - 3) / (4 + 3)
+        assert_eq!(snapshot.calculate_imbalance(2), Some(dec!(1) / dec!(7)));
+
+        let empty_snapshot = make_snapshot(&[], &[]);
+        assert_eq!(empty_snapshot.calculate_imbalance(5), None);
+    }
+
+    #[test]
+    fn microprice() {
+        let snapshot = make_default_snapshot();
+
+        // (101 * 3 + 99 * 1) / 4
+        assert_eq!(snapshot.calculate_microprice(), Some(dec!(100.5)));
+
+        let one_sided_snapshot = make_snapshot(&[], &[(dec!(99), dec!(3))]);
+        assert_eq!(one_sided_snapshot.calculate_microprice(), None);
+    }
 }
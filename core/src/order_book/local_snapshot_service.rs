@@ -33,8 +33,12 @@ impl LocalSnapshotsService {
 
         match event.event_type {
             event::EventType::Snapshot => {
-                self.local_snapshots
-                    .insert(trade_place, event.data.to_local_order_book_snapshot());
+                let snapshot = LocalOrderBookSnapshot::new(
+                    event.data.asks.clone(),
+                    event.data.bids.clone(),
+                    event.creation_time,
+                );
+                self.local_snapshots.insert(trade_place, snapshot);
                 Some(trade_place_account)
             }
             event::EventType::Update => {
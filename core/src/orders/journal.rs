@@ -0,0 +1,631 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use futures::FutureExt;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::exchanges::common::ExchangeAccountId;
+use crate::infrastructure::spawn_future;
+use crate::orders::fill::OrderFill;
+use crate::orders::order::{ClientOrderId, OrderSnapshot};
+
+fn default_compaction_records_count() -> usize {
+    10_000
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OrdersJournalSettings {
+    /// Journal file. Will be created if doesn't exist
+    pub path: PathBuf,
+    /// Journal is rewritten with not finished orders only after this count of records is appended
+    #[serde(default = "default_compaction_records_count")]
+    pub compaction_records_count: usize,
+}
+
+/// Persistent storage of orders state, so orders can be restored after engine restart
+#[async_trait]
+pub trait OrdersJournal: Send + Sync {
+    /// Queues current state of the order for saving
+    fn save_order(&self, order: &OrderSnapshot) -> Result<()>;
+
+    /// Queues fill received for the order for saving. It's cheaper than saving whole order on every fill
+    fn save_fill(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        client_order_id: &ClientOrderId,
+        fill: &OrderFill,
+    ) -> Result<()>;
+
+    /// Waits until all queued orders and fills are saved
+    async fn flush(&self) -> Result<()>;
+
+    /// Last saved state of every journaled order with all saved fills, ordered by creation time
+    fn load_orders(&self) -> Result<Vec<OrderSnapshot>>;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum JournalRecord {
+    Order(OrderSnapshot),
+    Fill {
+        exchange_account_id: ExchangeAccountId,
+        client_order_id: ClientOrderId,
+        fill: OrderFill,
+    },
+}
+
+enum JournalMessage {
+    Record(JournalRecord),
+    Flush(oneshot::Sender<Result<()>>),
+}
+
+type OrderKey = (ExchangeAccountId, ClientOrderId);
+
+/// Embedded `OrdersJournal` which appends records to a single JSON lines file.
+/// Records are written by a background task, so saving doesn't block order processing.
+/// Finished orders are dropped from the file on opening and after every `compaction_records_count` records
+pub struct FileOrdersJournal {
+    path: PathBuf,
+    messages_sender: mpsc::UnboundedSender<JournalMessage>,
+}
+
+impl FileOrdersJournal {
+    /// Should be called within tokio runtime because it spawns the writing task.
+    /// The task is finished when the journal is dropped
+    pub fn open(settings: &OrdersJournalSettings) -> Result<Arc<Self>> {
+        let writer = JournalWriter::open(&settings.path, settings.compaction_records_count)?;
+
+        let (messages_sender, messages_receiver) = mpsc::unbounded_channel();
+        let action = writer.write_messages(messages_receiver);
+        let _ = spawn_future("Orders journal writer", true, action.boxed());
+
+        Ok(Arc::new(Self {
+            path: settings.path.clone(),
+            messages_sender,
+        }))
+    }
+
+    fn send(&self, message: JournalMessage) -> Result<()> {
+        if self.messages_sender.send(message).is_err() {
+            bail!(
+                "Writing to orders journal {} is already stopped",
+                self.path.display()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OrdersJournal for FileOrdersJournal {
+    fn save_order(&self, order: &OrderSnapshot) -> Result<()> {
+        self.send(JournalMessage::Record(JournalRecord::Order(order.clone())))
+    }
+
+    fn save_fill(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        client_order_id: &ClientOrderId,
+        fill: &OrderFill,
+    ) -> Result<()> {
+        self.send(JournalMessage::Record(JournalRecord::Fill {
+            exchange_account_id,
+            client_order_id: client_order_id.clone(),
+            fill: fill.clone(),
+        }))
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let (flushed_sender, flushed_receiver) = oneshot::channel();
+        self.send(JournalMessage::Flush(flushed_sender))?;
+        flushed_receiver
+            .await
+            .context("Orders journal writer is stopped before flushing")?
+    }
+
+    fn load_orders(&self) -> Result<Vec<OrderSnapshot>> {
+        let mut orders = HashMap::new();
+        for record in read_records(&self.path)? {
+            apply_record(&mut orders, record, &self.path);
+        }
+
+        Ok(orders
+            .into_values()
+            .sorted_by_key(|order| order.header.init_time)
+            .collect_vec())
+    }
+}
+
+/// The only writer of the journal file. Keeps state of not finished orders for compaction
+struct JournalWriter {
+    path: PathBuf,
+    compaction_records_count: usize,
+    file: BufWriter<File>,
+    appended_records_count: usize,
+    not_finished_orders: HashMap<OrderKey, OrderSnapshot>,
+}
+
+impl JournalWriter {
+    fn open(path: &Path, compaction_records_count: usize) -> Result<Self> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)
+                .with_context(|| format!("Unable to create directory {}", directory.display()))?;
+        }
+
+        let mut not_finished_orders = HashMap::new();
+        if path.exists() {
+            for record in read_records(path)? {
+                apply_record(&mut not_finished_orders, record, path);
+            }
+        }
+        not_finished_orders.retain(|_, order: &mut OrderSnapshot| !order.props.is_finished());
+
+        let mut writer = Self {
+            path: path.to_owned(),
+            compaction_records_count,
+            file: open_for_append(path)?,
+            appended_records_count: 0,
+            not_finished_orders,
+        };
+        writer.compact()?;
+
+        Ok(writer)
+    }
+
+    async fn write_messages(
+        mut self,
+        mut messages_receiver: mpsc::UnboundedReceiver<JournalMessage>,
+    ) -> Result<()> {
+        while let Some(mut message) = messages_receiver.recv().await {
+            // records are flushed to the file as soon as there are no more queued messages
+            loop {
+                match message {
+                    JournalMessage::Record(record) => {
+                        if let Err(error) = self.append(record) {
+                            log::error!("Unable to save record to orders journal: {:?}", error);
+                        }
+                    }
+                    JournalMessage::Flush(flushed_sender) => {
+                        let _ = flushed_sender.send(self.flush());
+                    }
+                }
+
+                message = match messages_receiver.try_recv() {
+                    Ok(message) => message,
+                    Err(_) => break,
+                };
+            }
+
+            if let Err(error) = self.flush() {
+                log::error!("{:?}", error);
+            }
+        }
+
+        self.flush()
+    }
+
+    fn append(&mut self, record: JournalRecord) -> Result<()> {
+        let mut line = serde_json::to_vec(&record).context("Unable to serialize journal record")?;
+        line.push(b'\n');
+        self.file
+            .write_all(&line)
+            .with_context(|| format!("Unable to write orders journal {}", self.path.display()))?;
+        self.appended_records_count += 1;
+
+        if let JournalRecord::Order(order) = &record {
+            if order.props.is_finished() {
+                let key = (
+                    order.header.exchange_account_id,
+                    order.header.client_order_id.clone(),
+                );
+                let _ = self.not_finished_orders.remove(&key);
+                return self.compact_if_needed();
+            }
+        }
+
+        apply_record(&mut self.not_finished_orders, record, &self.path);
+        self.compact_if_needed()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.file
+            .flush()
+            .with_context(|| format!("Unable to flush orders journal {}", self.path.display()))
+    }
+
+    fn compact_if_needed(&mut self) -> Result<()> {
+        match self.appended_records_count >= self.compaction_records_count {
+            true => self.compact(),
+            false => Ok(()),
+        }
+    }
+
+    /// Replaces the journal file with a new one containing only not finished orders
+    fn compact(&mut self) -> Result<()> {
+        self.flush()?;
+
+        let mut compacted_path = self.path.clone().into_os_string();
+        compacted_path.push(".compacted");
+        let compacted_path = PathBuf::from(compacted_path);
+
+        let mut compacted_file =
+            BufWriter::new(File::create(&compacted_path).with_context(|| {
+                format!(
+                    "Unable to create compacted orders journal {}",
+                    compacted_path.display()
+                )
+            })?);
+        for order in self
+            .not_finished_orders
+            .values()
+            .sorted_by_key(|order| order.header.init_time)
+        {
+            let mut line = serde_json::to_vec(&JournalRecord::Order(order.clone()))
+                .context("Unable to serialize journal record")?;
+            line.push(b'\n');
+            compacted_file.write_all(&line).with_context(|| {
+                format!(
+                    "Unable to write compacted orders journal {}",
+                    compacted_path.display()
+                )
+            })?;
+        }
+        compacted_file
+            .into_inner()
+            .context("Unable to flush compacted orders journal")?
+            .sync_all()
+            .context("Unable to sync compacted orders journal")?;
+
+        fs::rename(&compacted_path, &self.path).with_context(|| {
+            format!(
+                "Unable to replace orders journal {} with compacted one",
+                self.path.display()
+            )
+        })?;
+        self.file = open_for_append(&self.path)?;
+        self.appended_records_count = 0;
+
+        log::info!(
+            "Orders journal {} is compacted to {} not finished orders",
+            self.path.display(),
+            self.not_finished_orders.len()
+        );
+
+        Ok(())
+    }
+}
+
+fn open_for_append(path: &Path) -> Result<BufWriter<File>> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Unable to open orders journal {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+fn read_records(path: &Path) -> Result<Vec<JournalRecord>> {
+    let file = File::open(path)
+        .with_context(|| format!("Unable to open orders journal {}", path.display()))?;
+    let lines: Vec<String> = BufReader::new(file)
+        .lines()
+        .try_collect()
+        .with_context(|| format!("Unable to read orders journal {}", path.display()))?;
+
+    let mut records = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            // last record can be partially written if the engine was stopped abnormally
+            Err(error) if index == lines.len() - 1 => log::warn!(
+                "Skipped incomplete last record of orders journal {}: {:?}",
+                path.display(),
+                error
+            ),
+            Err(error) => bail!(
+                "Unable to parse record {} of orders journal {}: {:?}",
+                index + 1,
+                path.display(),
+                error
+            ),
+        }
+    }
+
+    Ok(records)
+}
+
+fn apply_record(orders: &mut HashMap<OrderKey, OrderSnapshot>, record: JournalRecord, path: &Path) {
+    match record {
+        JournalRecord::Order(order) => {
+            let key = (
+                order.header.exchange_account_id,
+                order.header.client_order_id.clone(),
+            );
+            let _ = orders.insert(key, order);
+        }
+        JournalRecord::Fill {
+            exchange_account_id,
+            client_order_id,
+            fill,
+        } => match orders.get_mut(&(exchange_account_id, client_order_id.clone())) {
+            Some(order) => {
+                if order.fills.fills.iter().all(|x| x.id() != fill.id()) {
+                    order.add_fill(fill);
+                }
+            }
+            None => log::warn!(
+                "Fill {} in orders journal {} belongs to unknown order {} on {}",
+                fill.id(),
+                path.display(),
+                client_order_id,
+                exchange_account_id
+            ),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::common::CurrencyPair;
+    use crate::orders::fill::OrderFillType;
+    use crate::orders::order::{OrderFillRole, OrderSide, OrderStatus, OrderType};
+    use chrono::Utc;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    fn journal_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "mmb_orders_journal_{}_{}.jsonl",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn journal_settings(path: &Path) -> OrdersJournalSettings {
+        OrdersJournalSettings {
+            path: path.to_owned(),
+            compaction_records_count: default_compaction_records_count(),
+        }
+    }
+
+    fn order(client_order_id: &str) -> OrderSnapshot {
+        OrderSnapshot::with_params(
+            client_order_id.into(),
+            OrderType::Limit,
+            None,
+            ExchangeAccountId::new("Binance".into(), 0),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            dec!(10),
+            dec!(2),
+            OrderSide::Buy,
+            None,
+            "FromOrdersJournalTest",
+        )
+    }
+
+    fn fill(amount: rust_decimal::Decimal) -> OrderFill {
+        OrderFill::new(
+            Uuid::new_v4(),
+            None,
+            Utc::now(),
+            OrderFillType::UserTrade,
+            None,
+            dec!(10),
+            amount,
+            dec!(10) * amount,
+            OrderFillRole::Maker,
+            "usdt".into(),
+            dec!(0),
+            dec!(0),
+            "usdt".into(),
+            dec!(0),
+            dec!(0),
+            true,
+            None,
+            Some(OrderSide::Buy),
+        )
+    }
+
+    #[tokio::test]
+    async fn load_last_order_state_with_fills() {
+        let path = journal_path("load");
+        let _ = fs::remove_file(&path);
+        let journal = FileOrdersJournal::open(&journal_settings(&path)).expect("in test");
+
+        let mut first_order = order("first");
+        journal.save_order(&first_order).expect("in test");
+        first_order.set_status(OrderStatus::Created, Utc::now());
+        journal.save_order(&first_order).expect("in test");
+
+        let first_fill = fill(dec!(0.5));
+        let second_fill = fill(dec!(1));
+        let order_account = first_order.header.exchange_account_id;
+        for fill in [&first_fill, &second_fill] {
+            journal
+                .save_fill(order_account, &first_order.header.client_order_id, fill)
+                .expect("in test");
+        }
+
+        let second_order = order("second");
+        journal.save_order(&second_order).expect("in test");
+
+        // completed order snapshot already contains fills, they shouldn't be duplicated
+        first_order.add_fill(first_fill);
+        first_order.add_fill(second_fill);
+        first_order.set_status(OrderStatus::Completed, Utc::now());
+        journal.save_order(&first_order).expect("in test");
+
+        journal.flush().await.expect("in test");
+        let orders = journal.load_orders().expect("in test");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].header.client_order_id, "first".into());
+        assert_eq!(orders[0].props.status, OrderStatus::Completed);
+        assert_eq!(orders[0].fills.fills.len(), 2);
+        assert_eq!(orders[0].fills.filled_amount, dec!(1.5));
+        assert_eq!(orders[1].header.client_order_id, "second".into());
+        assert_eq!(orders[1].props.status, OrderStatus::Creating);
+    }
+
+    #[tokio::test]
+    async fn apply_fills_saved_after_order() {
+        let path = journal_path("fills");
+        let _ = fs::remove_file(&path);
+        let journal = FileOrdersJournal::open(&journal_settings(&path)).expect("in test");
+
+        let order = order("filled");
+        journal.save_order(&order).expect("in test");
+        journal
+            .save_fill(
+                order.header.exchange_account_id,
+                &order.header.client_order_id,
+                &fill(dec!(2)),
+            )
+            .expect("in test");
+
+        journal.flush().await.expect("in test");
+        let orders = journal.load_orders().expect("in test");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].fills.filled_amount, dec!(2));
+    }
+
+    #[tokio::test]
+    async fn skip_incomplete_last_record() {
+        let path = journal_path("incomplete");
+        let _ = fs::remove_file(&path);
+        let journal = FileOrdersJournal::open(&journal_settings(&path)).expect("in test");
+
+        journal.save_order(&order("saved")).expect("in test");
+        journal.flush().await.expect("in test");
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("in test")
+            .write_all(br#"{"type":"Order","hea"#)
+            .expect("in test");
+
+        let orders = journal.load_orders().expect("in test");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].header.client_order_id, "saved".into());
+    }
+
+    #[tokio::test]
+    async fn drop_finished_orders_on_opening() {
+        let path = journal_path("reopen");
+        let _ = fs::remove_file(&path);
+        let journal = FileOrdersJournal::open(&journal_settings(&path)).expect("in test");
+
+        let mut finished_order = order("finished");
+        finished_order.set_status(OrderStatus::Canceled, Utc::now());
+        journal.save_order(&finished_order).expect("in test");
+        let opened_order = order("opened");
+        journal.save_order(&opened_order).expect("in test");
+        journal
+            .save_fill(
+                opened_order.header.exchange_account_id,
+                &opened_order.header.client_order_id,
+                &fill(dec!(1)),
+            )
+            .expect("in test");
+        journal.flush().await.expect("in test");
+
+        let reopened_journal = FileOrdersJournal::open(&journal_settings(&path)).expect("in test");
+        let orders = reopened_journal.load_orders().expect("in test");
+        let lines_count = fs::read_to_string(&path).expect("in test").lines().count();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(orders.len(), 1);
+        assert_eq!(orders[0].header.client_order_id, "opened".into());
+        assert_eq!(orders[0].fills.filled_amount, dec!(1));
+        // fill is merged into the order record
+        assert_eq!(lines_count, 1);
+    }
+
+    #[tokio::test]
+    async fn compact_after_compaction_records_count() {
+        let path = journal_path("compaction");
+        let _ = fs::remove_file(&path);
+        let settings = OrdersJournalSettings {
+            path: path.clone(),
+            compaction_records_count: 4,
+        };
+        let journal = FileOrdersJournal::open(&settings).expect("in test");
+
+        let opened_order = order("opened");
+        journal.save_order(&opened_order).expect("in test");
+        for index in 0..3 {
+            let mut order = order(&format!("finished_{}", index));
+            journal.save_order(&order).expect("in test");
+            order.set_status(OrderStatus::Completed, Utc::now());
+            journal.save_order(&order).expect("in test");
+        }
+        journal.flush().await.expect("in test");
+
+        let orders = journal.load_orders().expect("in test");
+        let lines_count = fs::read_to_string(&path).expect("in test").lines().count();
+        let _ = fs::remove_file(&path);
+
+        // journal is compacted to opened and second created orders on 4th record,
+        // so only the next 3 records are appended after compaction
+        assert_eq!(lines_count, 5);
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].header.client_order_id, "opened".into());
+        assert_eq!(orders[1].header.client_order_id, "finished_1".into());
+        assert_eq!(orders[1].props.status, OrderStatus::Completed);
+        assert_eq!(orders[2].header.client_order_id, "finished_2".into());
+        assert_eq!(orders[2].props.status, OrderStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn dont_compact_on_every_record_when_many_orders_are_opened() {
+        let path = journal_path("many_opened");
+        let _ = fs::remove_file(&path);
+        let settings = OrdersJournalSettings {
+            path: path.clone(),
+            compaction_records_count: 3,
+        };
+        let journal = FileOrdersJournal::open(&settings).expect("in test");
+
+        let opened_orders = (0..3)
+            .map(|index| order(&format!("opened_{}", index)))
+            .collect_vec();
+        for order in &opened_orders {
+            journal.save_order(order).expect("in test");
+        }
+        journal
+            .save_fill(
+                opened_orders[0].header.exchange_account_id,
+                &opened_orders[0].header.client_order_id,
+                &fill(dec!(1)),
+            )
+            .expect("in test");
+        journal.flush().await.expect("in test");
+
+        let orders = journal.load_orders().expect("in test");
+        let lines_count = fs::read_to_string(&path).expect("in test").lines().count();
+        let _ = fs::remove_file(&path);
+
+        // fill is appended instead of compacting journal with opened orders count above the limit
+        assert_eq!(lines_count, 4);
+        assert_eq!(orders.len(), 3);
+        assert_eq!(orders[0].fills.filled_amount, dec!(1));
+    }
+}
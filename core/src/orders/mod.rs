@@ -1,5 +1,6 @@
 pub mod buffered_fills;
 pub mod event;
 pub mod fill;
+pub mod journal;
 pub mod order;
 pub mod pool;
@@ -17,7 +17,9 @@ use smallstr::SmallString;
 use uuid::Uuid;
 
 use crate::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, ExchangeErrorType, Price};
+use crate::exchanges::general::commission::Percent;
 use crate::orders::fill::{EventSourceType, OrderFill};
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash, Enum)]
 pub enum OrderSide {
@@ -99,6 +101,58 @@ pub enum OrderExecutionType {
     MakerOnly = 1,
 }
 
+/// How long order stays active on exchange until it is executed or expired
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
+pub enum TimeInForce {
+    GoodTillCancelled,
+    /// Part of order that can't be filled immediately is cancelled
+    ImmediateOrCancel,
+    /// Order is cancelled if it can't be filled immediately and completely
+    FillOrKill,
+    /// Order is expired by exchange at the specified time
+    GoodTillDate(DateTime),
+}
+
+impl Default for TimeInForce {
+    fn default() -> Self {
+        TimeInForce::GoodTillCancelled
+    }
+}
+
+/// Execution options of order specific for some order types
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Hash)]
+pub struct OrderOptions {
+    pub time_in_force: TimeInForce,
+    /// Price triggering StopLoss order. For TrailingStop order it is an activation price
+    pub stop_price: Option<Price>,
+    /// Distance between the best price since activation and trigger price of TrailingStop order
+    pub trailing_delta: Option<Percent>,
+}
+
+impl OrderOptions {
+    pub fn with_time_in_force(time_in_force: TimeInForce) -> Self {
+        OrderOptions {
+            time_in_force,
+            ..OrderOptions::default()
+        }
+    }
+
+    pub fn stop_loss(stop_price: Price) -> Self {
+        OrderOptions {
+            stop_price: Some(stop_price),
+            ..OrderOptions::default()
+        }
+    }
+
+    pub fn trailing_stop(trailing_delta: Percent, activation_price: Option<Price>) -> Self {
+        OrderOptions {
+            stop_price: activation_price,
+            trailing_delta: Some(trailing_delta),
+            ..OrderOptions::default()
+        }
+    }
+}
+
 impl_str_id!(ClientOrderId);
 impl_str_id!(ClientOrderFillId);
 impl_str_id!(ExchangeOrderId);
@@ -166,6 +220,9 @@ pub struct OrderHeader {
 
     pub signal_id: Option<String>,
     pub strategy_name: String,
+
+    #[serde(default)]
+    pub options: OrderOptions,
 }
 
 impl OrderHeader {
@@ -181,6 +238,36 @@ impl OrderHeader {
         reservation_id: Option<ReservationId>,
         signal_id: Option<String>,
         strategy_name: String,
+    ) -> Arc<Self> {
+        Self::new_with_options(
+            client_order_id,
+            init_time,
+            exchange_account_id,
+            currency_pair,
+            order_type,
+            side,
+            amount,
+            execution_type,
+            reservation_id,
+            signal_id,
+            strategy_name,
+            OrderOptions::default(),
+        )
+    }
+
+    pub fn new_with_options(
+        client_order_id: ClientOrderId,
+        init_time: DateTime,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+        order_type: OrderType,
+        side: OrderSide,
+        amount: Amount,
+        execution_type: OrderExecutionType,
+        reservation_id: Option<ReservationId>,
+        signal_id: Option<String>,
+        strategy_name: String,
+        options: OrderOptions,
     ) -> Arc<Self> {
         Arc::new(Self {
             version: CURRENT_ORDER_VERSION,
@@ -195,6 +282,7 @@ impl OrderHeader {
             reservation_id,
             signal_id,
             strategy_name,
+            options,
         })
     }
 
@@ -305,19 +393,23 @@ pub struct SystemInternalOrderProps {
     pub last_order_cancellation_status_request_time: Option<DateTime>,
     pub last_cancellation_error: Option<ExchangeErrorType>,
 
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub is_canceling_from_wait_cancel_order: bool,
 
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub canceled_not_from_wait_cancel_order: bool,
 
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub was_cancellation_event_raised: bool,
 
     pub last_order_trades_request_time: Option<DateTime>,
 
     pub handled_by_balance_recovery: bool,
     pub filled_amount_after_cancellation: Option<Amount>,
+
+    /// Configuration of the order reservation, so the reservation can be restored after restart
+    #[serde(default)]
+    pub reservation_configuration_descriptor: Option<ConfigurationDescriptor>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1 @@
+pub mod pnl_ledger;
@@ -0,0 +1,441 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use crate::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price, TradePlace, TradePlaceAccount,
+};
+use crate::exchanges::general::symbol::Symbol;
+use crate::order_book::local_order_book_snapshot::LocalOrderBookSnapshot;
+use crate::orders::fill::OrderFill;
+use crate::orders::order::OrderSide;
+use crate::service_configuration::configuration_descriptor::{
+    ConfigurationDescriptor, ServiceConfigurationKey, ServiceName,
+};
+
+/// Part of position opened by a single fill
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PnlLot {
+    price: Price,
+    /// Positive for long lot, negative for short lot
+    amount: Amount,
+}
+
+/// PnL of strategy configuration on a trade place in quote currency of the currency pair
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TradePlacePnl {
+    pnl_currency_code: CurrencyCode,
+    /// Open lots in order of opening. All lots have the same direction
+    lots: VecDeque<PnlLot>,
+    realized_pnl: Decimal,
+    commission: Decimal,
+    traded_amount: Amount,
+    fills_count: u64,
+}
+
+impl TradePlacePnl {
+    fn new(pnl_currency_code: CurrencyCode) -> Self {
+        Self {
+            pnl_currency_code,
+            lots: VecDeque::new(),
+            realized_pnl: dec!(0),
+            commission: dec!(0),
+            traded_amount: dec!(0),
+            fills_count: 0,
+        }
+    }
+
+    /// Fill closes opposite lots by FIFO and the rest of fill amount opens a new lot
+    fn add_fill(&mut self, side: OrderSide, price: Price, amount: Amount, commission: Decimal) {
+        let mut remaining_amount = match side {
+            OrderSide::Buy => amount,
+            OrderSide::Sell => -amount,
+        };
+
+        while !remaining_amount.is_zero() {
+            let lot = match self.lots.front_mut() {
+                Some(lot)
+                    if lot.amount.is_sign_positive() != remaining_amount.is_sign_positive() =>
+                {
+                    lot
+                }
+                _ => break,
+            };
+
+            let closed_amount = match lot.amount.is_sign_positive() {
+                true => remaining_amount.abs().min(lot.amount),
+                false => -remaining_amount.abs().min(-lot.amount),
+            };
+            self.realized_pnl += (price - lot.price) * closed_amount;
+            lot.amount -= closed_amount;
+            remaining_amount += closed_amount;
+
+            if lot.amount.is_zero() {
+                let _ = self.lots.pop_front();
+            }
+        }
+
+        if !remaining_amount.is_zero() {
+            self.lots.push_back(PnlLot {
+                price,
+                amount: remaining_amount,
+            });
+        }
+
+        self.commission += commission;
+        self.traded_amount += amount;
+        self.fills_count += 1;
+    }
+
+    fn position(&self) -> Amount {
+        self.lots.iter().map(|lot| lot.amount).sum()
+    }
+
+    fn average_entry_price(&self) -> Option<Price> {
+        let position = self.position();
+        if position.is_zero() {
+            return None;
+        }
+
+        let cost: Decimal = self.lots.iter().map(|lot| lot.price * lot.amount).sum();
+        Some(cost / position)
+    }
+
+    fn unrealized_pnl(&self, mark_price: Price) -> Decimal {
+        self.lots
+            .iter()
+            .map(|lot| (mark_price - lot.price) * lot.amount)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PnlKey {
+    configuration_descriptor: ConfigurationDescriptor,
+    trade_place: TradePlaceAccount,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradePlacePnlInfo {
+    pub service_name: ServiceName,
+    pub service_configuration_key: ServiceConfigurationKey,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    /// Currency of all PnL values
+    pub pnl_currency_code: CurrencyCode,
+    /// Positive for long position
+    pub position: Amount,
+    pub average_entry_price: Option<Price>,
+    /// Middle price of order book which open position is marked to
+    pub mark_price: Option<Price>,
+    pub realized_pnl: Decimal,
+    /// `None` if there is open position, but order book for the trade place isn't received yet
+    pub unrealized_pnl: Option<Decimal>,
+    pub commission: Decimal,
+    /// Realized and unrealized PnL with commission subtracted
+    pub total_pnl: Option<Decimal>,
+    pub traded_amount: Amount,
+    pub fills_count: u64,
+}
+
+#[derive(Default)]
+struct PnlLedgerState {
+    trade_places: HashMap<PnlKey, TradePlacePnl>,
+    mark_prices: HashMap<TradePlace, Price>,
+}
+
+/// Realized and unrealized PnL of strategies by their configurations and trade places.
+/// PnL is calculated by FIFO lots in quote currency of currency pair, so it's applicable
+/// for spot and linear derivative currency pairs
+#[derive(Default)]
+pub struct PnlLedger {
+    state: Mutex<PnlLedgerState>,
+}
+
+impl PnlLedger {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn add_fill(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+        trade_place: TradePlaceAccount,
+        symbol: &Symbol,
+        side: OrderSide,
+        order_fill: &OrderFill,
+    ) {
+        let commission = get_commission_in_quote(symbol, order_fill);
+        let key = PnlKey {
+            configuration_descriptor,
+            trade_place,
+        };
+
+        self.state
+            .lock()
+            .trade_places
+            .entry(key)
+            .or_insert_with(|| TradePlacePnl::new(symbol.quote_currency_code()))
+            .add_fill(side, order_fill.price(), order_fill.amount(), commission);
+    }
+
+    /// Open positions are marked to the middle price of the order book snapshot
+    pub fn update_mark_price(&self, trade_place: TradePlace, snapshot: &LocalOrderBookSnapshot) {
+        if let Some(middle_price) = snapshot.get_top_prices().calculate_middle_price() {
+            let _ = self
+                .state
+                .lock()
+                .mark_prices
+                .insert(trade_place, middle_price);
+        }
+    }
+
+    pub fn get_pnl(&self) -> Vec<TradePlacePnlInfo> {
+        let state = self.state.lock();
+
+        state
+            .trade_places
+            .iter()
+            .map(|(key, pnl)| {
+                let position = pnl.position();
+                let mark_price = state
+                    .mark_prices
+                    .get(&key.trade_place.trade_place())
+                    .cloned();
+                let unrealized_pnl = match position.is_zero() {
+                    true => Some(dec!(0)),
+                    false => mark_price.map(|mark_price| pnl.unrealized_pnl(mark_price)),
+                };
+
+                TradePlacePnlInfo {
+                    service_name: key.configuration_descriptor.service_name,
+                    service_configuration_key: key
+                        .configuration_descriptor
+                        .service_configuration_key,
+                    exchange_account_id: key.trade_place.exchange_account_id,
+                    currency_pair: key.trade_place.currency_pair,
+                    pnl_currency_code: pnl.pnl_currency_code,
+                    position,
+                    average_entry_price: pnl.average_entry_price(),
+                    mark_price,
+                    realized_pnl: pnl.realized_pnl,
+                    unrealized_pnl,
+                    commission: pnl.commission,
+                    total_pnl: unrealized_pnl
+                        .map(|unrealized_pnl| pnl.realized_pnl + unrealized_pnl - pnl.commission),
+                    traded_amount: pnl.traded_amount,
+                    fills_count: pnl.fills_count,
+                }
+            })
+            .sorted_by_key(|info| {
+                (
+                    info.service_name.as_str().to_owned(),
+                    info.service_configuration_key.as_str().to_owned(),
+                    info.exchange_account_id.to_string(),
+                    info.currency_pair.as_str().to_owned(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Commission is converted to quote currency if it's paid in base currency.
+/// Commission in other currencies should be already converted in `OrderFill::converted_commission_amount`
+fn get_commission_in_quote(symbol: &Symbol, order_fill: &OrderFill) -> Decimal {
+    let commission_currency_code = order_fill.converted_commission_currency_code();
+    let commission_amount = order_fill.converted_commission_amount();
+
+    if commission_currency_code == symbol.quote_currency_code() {
+        commission_amount
+    } else if commission_currency_code == symbol.base_currency_code() {
+        commission_amount * order_fill.price()
+    } else {
+        log::warn!(
+            "Commission in {} can't be taken into account in PnL of {}",
+            commission_currency_code,
+            symbol.currency_pair()
+        );
+        dec!(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::exchanges::general::symbol::Precision;
+    use crate::orders::fill::OrderFillType;
+    use crate::orders::order::OrderFillRole;
+
+    fn trade_place_pnl() -> TradePlacePnl {
+        TradePlacePnl::new("usdt".into())
+    }
+
+    #[test]
+    fn realized_pnl_by_fifo_lots() {
+        let mut pnl = trade_place_pnl();
+
+        pnl.add_fill(OrderSide::Buy, dec!(100), dec!(1), dec!(0.1));
+        pnl.add_fill(OrderSide::Buy, dec!(110), dec!(1), dec!(0.1));
+        pnl.add_fill(OrderSide::Sell, dec!(120), dec!(1.5), dec!(0.2));
+
+        // 1 closed by 100 and 0.5 closed by 110
+        assert_eq!(pnl.realized_pnl, dec!(25));
+        assert_eq!(pnl.commission, dec!(0.4));
+        assert_eq!(pnl.position(), dec!(0.5));
+        assert_eq!(pnl.average_entry_price(), Some(dec!(110)));
+        assert_eq!(pnl.unrealized_pnl(dec!(130)), dec!(10));
+        assert_eq!(pnl.traded_amount, dec!(3.5));
+        assert_eq!(pnl.fills_count, 3);
+    }
+
+    #[test]
+    fn short_position_pnl() {
+        let mut pnl = trade_place_pnl();
+
+        pnl.add_fill(OrderSide::Sell, dec!(100), dec!(2), dec!(0));
+        pnl.add_fill(OrderSide::Buy, dec!(90), dec!(1), dec!(0));
+
+        assert_eq!(pnl.realized_pnl, dec!(10));
+        assert_eq!(pnl.position(), dec!(-1));
+        assert_eq!(pnl.average_entry_price(), Some(dec!(100)));
+        assert_eq!(pnl.unrealized_pnl(dec!(95)), dec!(5));
+        assert_eq!(pnl.unrealized_pnl(dec!(105)), dec!(-5));
+    }
+
+    #[test]
+    fn position_reversal_opens_lot_by_fill_price() {
+        let mut pnl = trade_place_pnl();
+
+        pnl.add_fill(OrderSide::Buy, dec!(100), dec!(1), dec!(0));
+        pnl.add_fill(OrderSide::Sell, dec!(90), dec!(3), dec!(0));
+
+        assert_eq!(pnl.realized_pnl, dec!(-10));
+        assert_eq!(pnl.position(), dec!(-2));
+        assert_eq!(pnl.average_entry_price(), Some(dec!(90)));
+        assert_eq!(
+            pnl.lots,
+            VecDeque::from(vec![PnlLot {
+                price: dec!(90),
+                amount: dec!(-2)
+            }])
+        );
+    }
+
+    #[test]
+    fn closed_position_has_no_entry_price() {
+        let mut pnl = trade_place_pnl();
+
+        pnl.add_fill(OrderSide::Buy, dec!(100), dec!(1), dec!(0));
+        pnl.add_fill(OrderSide::Sell, dec!(101), dec!(1), dec!(0));
+
+        assert_eq!(pnl.position(), dec!(0));
+        assert_eq!(pnl.average_entry_price(), None);
+        assert!(pnl.lots.is_empty());
+        assert_eq!(pnl.realized_pnl, dec!(1));
+    }
+
+    fn symbol() -> Symbol {
+        Symbol::new(
+            false,
+            false,
+            "btc".into(),
+            "btc".into(),
+            "usdt".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.001) },
+        )
+    }
+
+    fn order_fill(price: Price, amount: Amount, commission_currency_code: &str) -> OrderFill {
+        OrderFill::new(
+            Uuid::new_v4(),
+            None,
+            Utc::now(),
+            OrderFillType::UserTrade,
+            None,
+            price,
+            amount,
+            price * amount,
+            OrderFillRole::Taker,
+            commission_currency_code.into(),
+            dec!(0.001),
+            dec!(0),
+            commission_currency_code.into(),
+            dec!(0.001),
+            dec!(0.001),
+            false,
+            None,
+            None,
+        )
+    }
+
+    fn snapshot(top_ask: Price, top_bid: Price) -> LocalOrderBookSnapshot {
+        LocalOrderBookSnapshot::new(
+            BTreeMap::from([(top_ask, dec!(1))]),
+            BTreeMap::from([(top_bid, dec!(1))]),
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn ledger_marks_positions_to_order_book() {
+        let ledger = PnlLedger::new();
+        let symbol = symbol();
+        let configuration_descriptor =
+            ConfigurationDescriptor::new("ExampleStrategy".into(), "Binance_0;btc/usdt".into());
+        let trade_place = TradePlaceAccount::new(
+            ExchangeAccountId::new("Binance".into(), 0),
+            symbol.currency_pair(),
+        );
+
+        ledger.add_fill(
+            configuration_descriptor,
+            trade_place,
+            &symbol,
+            OrderSide::Buy,
+            &order_fill(dec!(100), dec!(2), "btc"),
+        );
+        ledger.add_fill(
+            configuration_descriptor,
+            trade_place,
+            &symbol,
+            OrderSide::Sell,
+            &order_fill(dec!(110), dec!(1), "usdt"),
+        );
+
+        let pnl = ledger.get_pnl();
+        assert_eq!(pnl.len(), 1);
+        assert_eq!(pnl[0].realized_pnl, dec!(10));
+        // 0.001 btc by price 100 and 0.001 usdt
+        assert_eq!(pnl[0].commission, dec!(0.101));
+        assert_eq!(pnl[0].unrealized_pnl, None);
+        assert_eq!(pnl[0].total_pnl, None);
+
+        ledger.update_mark_price(trade_place.trade_place(), &snapshot(dec!(121), dec!(119)));
+
+        let pnl = ledger.get_pnl();
+        assert_eq!(pnl[0].mark_price, Some(dec!(120)));
+        assert_eq!(pnl[0].position, dec!(1));
+        assert_eq!(pnl[0].unrealized_pnl, Some(dec!(20)));
+        assert_eq!(pnl[0].total_pnl, Some(dec!(29.899)));
+        assert_eq!(pnl[0].pnl_currency_code, "usdt".into());
+    }
+}
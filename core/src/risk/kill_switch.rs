@@ -0,0 +1,461 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::future::join_all;
+use futures::FutureExt;
+use itertools::Itertools;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::block_reasons;
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId};
+use crate::exchanges::events::ExchangeBalance;
+use crate::exchanges::exchange_blocker::BlockType;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::features::BalancePositionOption;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::misc::time::get_current_time;
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderSide, OrderStatus,
+    OrderType,
+};
+
+const FLATTEN_TIMEOUT: Duration = Duration::from_secs(30);
+static KILL_SWITCH: &str = "KillSwitch";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KillSwitchSettings {
+    /// Spot balances are sold for this currency when kill switch is activated with flattening
+    pub flatten_currency_code: CurrencyCode,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlattenOrderReport {
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub amount: Amount,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExchangeKillSwitchReport {
+    pub exchange_account_id: ExchangeAccountId,
+    /// Orders which are still not finished after cancellation
+    pub not_cancelled_orders: Vec<ClientOrderId>,
+    pub closed_positions_count: usize,
+    pub flatten_orders: Vec<FlattenOrderReport>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KillSwitchReport {
+    pub reason: String,
+    pub is_flatten_requested: bool,
+    pub activated_at: DateTime,
+    /// Not specified while orders are cancelling and positions are flattening
+    pub finished_at: Option<DateTime>,
+    pub exchanges: Vec<ExchangeKillSwitchReport>,
+}
+
+/// Engine-wide emergency stop. Activated kill switch blocks all exchanges, so strategies stop creating orders,
+/// cancels all open orders and optionally flattens positions. Trading engine should be restarted to trade again
+pub struct KillSwitch {
+    settings: Option<KillSwitchSettings>,
+    is_activated: AtomicBool,
+    report: Mutex<Option<KillSwitchReport>>,
+}
+
+impl KillSwitch {
+    pub fn new(settings: Option<KillSwitchSettings>) -> Arc<Self> {
+        Arc::new(Self {
+            settings,
+            is_activated: Default::default(),
+            report: Default::default(),
+        })
+    }
+
+    pub fn is_activated(&self) -> bool {
+        self.is_activated.load(Ordering::SeqCst)
+    }
+
+    /// Report of the kill switch activation. It's updated when stopping of trading is finished
+    pub fn report(&self) -> Option<KillSwitchReport> {
+        self.report.lock().clone()
+    }
+
+    /// Blocks all exchanges immediately and spawns cancellation of open orders and flattening.
+    /// Derivative positions are closed and spot balances are sold for `KillSwitchSettings::flatten_currency_code`
+    /// only if `flatten` is requested
+    pub fn activate(
+        self: &Arc<Self>,
+        engine_context: Arc<EngineContext>,
+        flatten: bool,
+        reason: String,
+    ) -> Result<()> {
+        if self
+            .is_activated
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            bail!("Kill switch is already activated");
+        }
+
+        log::error!(
+            "Kill switch is activated by reason '{}' with flattening {}",
+            reason,
+            flatten
+        );
+
+        for exchange in engine_context.exchanges.iter() {
+            engine_context.exchange_blocker.block(
+                exchange.exchange_account_id,
+                block_reasons::KILL_SWITCH,
+                BlockType::Manual,
+            );
+        }
+
+        *self.report.lock() = Some(KillSwitchReport {
+            reason,
+            is_flatten_requested: flatten,
+            activated_at: get_current_time(),
+            finished_at: None,
+            exchanges: Vec::new(),
+        });
+
+        let action = self.clone().stop_trading(engine_context, flatten);
+        let _ = spawn_future("KillSwitch::stop_trading", true, action.boxed());
+
+        Ok(())
+    }
+
+    async fn stop_trading(
+        self: Arc<Self>,
+        engine_context: Arc<EngineContext>,
+        flatten: bool,
+    ) -> Result<()> {
+        let cancellation_token = engine_context.application_manager.stop_token();
+        let exchanges = engine_context
+            .exchanges
+            .iter()
+            .map(|exchange| exchange.value().clone())
+            .collect_vec();
+
+        let exchange_reports = join_all(exchanges.into_iter().map(|exchange| {
+            self.stop_trading_on_exchange(exchange, flatten, cancellation_token.clone())
+        }))
+        .await;
+
+        let mut report_guard = self.report.lock();
+        if let Some(report) = report_guard.as_mut() {
+            report.finished_at = Some(get_current_time());
+            report.exchanges = exchange_reports;
+
+            match serde_json::to_string(report) {
+                Ok(report) => {
+                    log::warn!("{} finished stopping of trading: {}", KILL_SWITCH, report)
+                }
+                Err(error) => log::error!("Failed to serialize kill switch report: {}", error),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn stop_trading_on_exchange(
+        &self,
+        exchange: Arc<Exchange>,
+        flatten: bool,
+        cancellation_token: CancellationToken,
+    ) -> ExchangeKillSwitchReport {
+        let exchange_account_id = exchange.exchange_account_id;
+        let mut report = ExchangeKillSwitchReport {
+            exchange_account_id,
+            not_cancelled_orders: Vec::new(),
+            closed_positions_count: 0,
+            flatten_orders: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        exchange
+            .clone()
+            .cancel_opened_orders(cancellation_token.clone(), true)
+            .await;
+
+        report.not_cancelled_orders = exchange
+            .orders
+            .not_finished
+            .iter()
+            .map(|order| order.key().clone())
+            .collect_vec();
+
+        if !flatten {
+            return report;
+        }
+
+        let flattening = async {
+            match exchange.features.balance_position_option {
+                BalancePositionOption::NonDerivative => match &self.settings {
+                    Some(settings) => {
+                        self.flatten_spot_balances(
+                            &exchange,
+                            settings.flatten_currency_code,
+                            &mut report,
+                            cancellation_token.clone(),
+                        )
+                        .await
+                    }
+                    None => report.errors.push(
+                        "Spot balances aren't flattened because kill switch settings aren't specified".to_owned(),
+                    ),
+                },
+                _ => close_positions(&exchange, &mut report, cancellation_token.clone()).await,
+            }
+        };
+
+        if tokio::time::timeout(FLATTEN_TIMEOUT, flattening)
+            .await
+            .is_err()
+        {
+            report.errors.push(format!(
+                "Flattening wasn't finished in {} secs",
+                FLATTEN_TIMEOUT.as_secs()
+            ));
+        }
+
+        report
+    }
+
+    async fn flatten_spot_balances(
+        &self,
+        exchange: &Exchange,
+        flatten_currency_code: CurrencyCode,
+        report: &mut ExchangeKillSwitchReport,
+        cancellation_token: CancellationToken,
+    ) {
+        let balances = match exchange.get_balance(cancellation_token.clone()).await {
+            Some(balances_and_positions) => balances_and_positions.balances,
+            None => {
+                report.errors.push("Failed to get balances".to_owned());
+                return;
+            }
+        };
+
+        let symbols = exchange
+            .symbols
+            .iter()
+            .map(|symbol| symbol.value().clone())
+            .collect_vec();
+        let (flatten_orders, errors) =
+            get_flatten_orders(&symbols, &balances, flatten_currency_code);
+        report.errors.extend(errors);
+
+        for (symbol, amount) in flatten_orders {
+            let header = OrderHeader::new(
+                ClientOrderId::unique_id(),
+                get_current_time(),
+                exchange.exchange_account_id,
+                symbol.currency_pair(),
+                OrderType::Market,
+                OrderSide::Sell,
+                amount,
+                OrderExecutionType::None,
+                None,
+                None,
+                KILL_SWITCH.to_owned(),
+            );
+            let price = exchange
+                .order_book_top
+                .get(&symbol.currency_pair())
+                .and_then(|top| top.bid.as_ref().map(|bid| bid.price))
+                .unwrap_or(dec!(0));
+            // Selling of spot balance only reduces exposure, so pre-trade risk limits aren't applied to it
+            let order_creating = OrderCreating { header, price };
+
+            let error = match exchange
+                .create_order(&order_creating, None, cancellation_token.clone())
+                .await
+            {
+                Ok(order) if order.status() == OrderStatus::FailedToCreate => {
+                    Some("Order was rejected by exchange".to_owned())
+                }
+                Ok(_) => None,
+                Err(error) => Some(error.to_string()),
+            };
+            if let Some(error) = &error {
+                report.errors.push(format!(
+                    "Failed to sell {} {} on {}: {}",
+                    amount,
+                    symbol.base_currency_code(),
+                    symbol.currency_pair(),
+                    error
+                ));
+            }
+
+            report.flatten_orders.push(FlattenOrderReport {
+                currency_pair: symbol.currency_pair(),
+                side: OrderSide::Sell,
+                amount,
+                error,
+            });
+        }
+    }
+}
+
+/// Every position is closed by a single attempt, so failed closings are reported instead of retrying them
+/// until flattening timeout
+async fn close_positions(
+    exchange: &Exchange,
+    report: &mut ExchangeKillSwitchReport,
+    cancellation_token: CancellationToken,
+) {
+    let active_positions = exchange
+        .get_active_positions(cancellation_token.clone())
+        .await;
+
+    let results = join_all(
+        active_positions
+            .iter()
+            .filter(|active_position| !active_position.derivative.position.is_zero())
+            .map(|active_position| async {
+                exchange
+                    .try_close_position(active_position, None, cancellation_token.clone())
+                    .await
+                    .map_err(|error| {
+                        format!(
+                            "Failed to close position {}: {:?}",
+                            active_position.id, error
+                        )
+                    })
+            }),
+    )
+    .await;
+
+    for result in results {
+        match result {
+            Ok(_) => report.closed_positions_count += 1,
+            Err(error) => report.errors.push(error),
+        }
+    }
+}
+
+/// Market sell orders for converting spot balances into `flatten_currency_code`.
+/// Balances which can't be sold directly for `flatten_currency_code` are returned as errors
+fn get_flatten_orders(
+    symbols: &[Arc<Symbol>],
+    balances: &[ExchangeBalance],
+    flatten_currency_code: CurrencyCode,
+) -> (Vec<(Arc<Symbol>, Amount)>, Vec<String>) {
+    let mut orders = Vec::new();
+    let mut errors = Vec::new();
+
+    for balance in balances {
+        if balance.currency_code == flatten_currency_code || balance.balance <= dec!(0) {
+            continue;
+        }
+
+        let symbol = symbols.iter().find(|symbol| {
+            !symbol.is_derivative()
+                && symbol.base_currency_code() == balance.currency_code
+                && symbol.quote_currency_code() == flatten_currency_code
+        });
+
+        let symbol = match symbol {
+            Some(symbol) => symbol,
+            None => {
+                errors.push(format!(
+                    "There is no currency pair to sell {} {} for {}",
+                    balance.balance, balance.currency_code, flatten_currency_code
+                ));
+                continue;
+            }
+        };
+
+        let amount = symbol.amount_round(balance.balance, Round::Floor);
+        if amount <= dec!(0) || symbol.min_amount.map_or(false, |min| amount < min) {
+            continue;
+        }
+
+        orders.push((symbol.clone(), amount));
+    }
+
+    (orders, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exchanges::general::symbol::Precision;
+
+    fn symbol(base: &str, quote: &str) -> Arc<Symbol> {
+        Arc::new(Symbol::new(
+            false,
+            false,
+            base.into(),
+            base.into(),
+            quote.into(),
+            quote.into(),
+            None,
+            None,
+            Some(dec!(0.01)),
+            None,
+            None,
+            base.into(),
+            None,
+            Precision::ByTick { tick: dec!(0.01) },
+            Precision::ByTick { tick: dec!(0.01) },
+        ))
+    }
+
+    fn balance(currency_code: &str, balance: Amount) -> ExchangeBalance {
+        ExchangeBalance {
+            currency_code: currency_code.into(),
+            balance,
+        }
+    }
+
+    #[test]
+    fn flatten_orders_sell_balances_for_flatten_currency() {
+        let symbols = [symbol("btc", "usdt"), symbol("eth", "btc")];
+        let balances = [
+            balance("usdt", dec!(100)),
+            balance("btc", dec!(1.234)),
+            balance("eth", dec!(2)),
+            balance("cnd", dec!(10)),
+            balance("eos", dec!(0)),
+        ];
+
+        let (orders, errors) = get_flatten_orders(&symbols, &balances, "usdt".into());
+
+        assert_eq!(
+            orders
+                .iter()
+                .map(|(symbol, amount)| (symbol.currency_pair(), *amount))
+                .collect_vec(),
+            vec![(
+                CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                dec!(1.23)
+            )]
+        );
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("eth"));
+        assert!(errors[1].contains("cnd"));
+    }
+
+    #[test]
+    fn flatten_orders_skip_amount_less_than_min_amount() {
+        let symbols = [symbol("btc", "usdt")];
+        let balances = [balance("btc", dec!(0.009))];
+
+        let (orders, errors) = get_flatten_orders(&symbols, &balances, "usdt".into());
+
+        assert!(orders.is_empty());
+        assert!(errors.is_empty());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod kill_switch;
+pub mod pre_trade_risk;
@@ -0,0 +1,816 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use chrono::Duration;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::balance_manager::balance_manager::BalanceManager;
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::Symbol;
+use crate::orders::order::{OrderCreating, OrderSide};
+use crate::orders::pool::OrderRef;
+
+/// Limits checked before creating every order of strategies. Limit isn't checked if it isn't specified
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RiskLimitsSettings {
+    /// Max order amount multiplied by order price
+    #[serde(default)]
+    pub max_order_notional: Option<Amount>,
+    /// Max absolute position on trade place after execution of the order and all open orders on the same side
+    /// by amount currency code of trade place. Orders decreasing position are always allowed
+    #[serde(default)]
+    pub max_positions: HashMap<CurrencyCode, Amount>,
+    /// Max count of not finished orders on trade place
+    #[serde(default)]
+    pub max_open_orders: Option<usize>,
+    /// Max deviation of order price from order book middle price as a fraction of middle price, e.g. 0.05 for 5%
+    #[serde(default)]
+    pub max_price_deviation: Option<Decimal>,
+    /// Max count of orders created on exchange account during the last minute
+    #[serde(default)]
+    pub max_orders_per_minute: Option<usize>,
+}
+
+/// State of trade place required for checking order by risk limits
+#[derive(Debug, Clone)]
+pub struct OrderRiskContext {
+    /// Position on trade place in amount currency, positive for long position
+    pub position: Amount,
+    /// Unfilled amount of not finished buy orders on trade place without the checked order
+    pub open_buy_amount: Amount,
+    /// Unfilled amount of not finished sell orders on trade place without the checked order
+    pub open_sell_amount: Amount,
+    /// Count of not finished orders on trade place without the checked order
+    pub open_orders_count: usize,
+    pub middle_price: Option<Price>,
+}
+
+impl OrderRiskContext {
+    /// Collects position and not finished orders of trade place. `pending_orders` are orders which requests
+    /// aren't sent yet, they are counted even if they aren't added to orders pool.
+    /// `replaced_order` isn't counted because it will be cancelled
+    pub fn new<'a>(
+        exchange: &Exchange,
+        balance_manager: &BalanceManager,
+        currency_pair: CurrencyPair,
+        pending_orders: impl IntoIterator<Item = &'a OrderCreating>,
+        replaced_order: Option<&OrderRef>,
+        middle_price: Option<Price>,
+    ) -> Self {
+        let mut context = OrderRiskContext {
+            position: balance_manager.get_position(
+                exchange.exchange_account_id,
+                currency_pair,
+                OrderSide::Buy,
+            ),
+            open_buy_amount: dec!(0),
+            open_sell_amount: dec!(0),
+            open_orders_count: 0,
+            middle_price,
+        };
+
+        let replaced_client_order_id = replaced_order.map(|order| order.client_order_id());
+        for order in exchange.orders.not_finished.iter() {
+            if order.currency_pair() != currency_pair
+                || Some(order.key()) == replaced_client_order_id.as_ref()
+            {
+                continue;
+            }
+
+            context.add_open_order(order.side(), order.amount() - order.filled_amount());
+        }
+
+        for order in pending_orders {
+            let header = &order.header;
+            if header.currency_pair != currency_pair
+                || exchange
+                    .orders
+                    .not_finished
+                    .contains_key(&header.client_order_id)
+            {
+                continue;
+            }
+
+            context.add_open_order(header.side, header.amount);
+        }
+
+        context
+    }
+
+    fn add_open_order(&mut self, side: OrderSide, unfilled_amount: Amount) {
+        self.open_orders_count += 1;
+        match side {
+            OrderSide::Buy => self.open_buy_amount += unfilled_amount,
+            OrderSide::Sell => self.open_sell_amount += unfilled_amount,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskLimitViolation {
+    OrderNotional {
+        notional: Amount,
+        limit: Amount,
+    },
+    Position {
+        currency_code: CurrencyCode,
+        position: Amount,
+        limit: Amount,
+    },
+    OpenOrders {
+        count: usize,
+        limit: usize,
+    },
+    PriceBand {
+        price: Price,
+        middle_price: Option<Price>,
+        max_deviation: Decimal,
+    },
+    OrderRate {
+        count: usize,
+        limit: usize,
+    },
+    ReduceOnly {
+        exchange_account_id: ExchangeAccountId,
+        position: Amount,
+    },
+}
+
+impl Display for RiskLimitViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiskLimitViolation::OrderNotional { notional, limit } => {
+                write!(f, "order notional {} exceeds limit {}", notional, limit)
+            }
+            RiskLimitViolation::Position {
+                currency_code,
+                position,
+                limit,
+            } => write!(
+                f,
+                "position {} {} after order execution exceeds limit {}",
+                position, currency_code, limit
+            ),
+            RiskLimitViolation::OpenOrders { count, limit } => write!(
+                f,
+                "there are already {} open orders with limit {}",
+                count, limit
+            ),
+            RiskLimitViolation::PriceBand {
+                price,
+                middle_price: Some(middle_price),
+                max_deviation,
+            } => write!(
+                f,
+                "price {} deviates from middle price {} more than {}",
+                price, middle_price, max_deviation
+            ),
+            RiskLimitViolation::PriceBand {
+                price,
+                middle_price: None,
+                ..
+            } => write!(
+                f,
+                "price {} can't be checked because there is no middle price",
+                price
+            ),
+            RiskLimitViolation::OrderRate { count, limit } => write!(
+                f,
+                "{} orders were created during the last minute with limit {}",
+                count, limit
+            ),
+            RiskLimitViolation::ReduceOnly {
+                exchange_account_id,
+                position,
+            } => write!(
+                f,
+                "{} is in reduce only mode, but order doesn't decrease position {}",
+                exchange_account_id, position
+            ),
+        }
+    }
+}
+
+/// Checks orders by `RiskLimitsSettings` so a bug in strategy can't create orders
+/// with unexpected amount or price
+pub struct PreTradeRiskEngine {
+    limits: RiskLimitsSettings,
+    created_orders_times: Mutex<HashMap<ExchangeAccountId, VecDeque<DateTime>>>,
+    /// Count of sources which enabled reduce only mode on exchange account, so the mode is
+    /// disabled only when every source disabled it
+    reduce_only_exchange_accounts: Mutex<HashMap<ExchangeAccountId, usize>>,
+}
+
+impl PreTradeRiskEngine {
+    pub fn new(limits: RiskLimitsSettings) -> Arc<Self> {
+        Arc::new(Self {
+            limits,
+            created_orders_times: Default::default(),
+            reduce_only_exchange_accounts: Default::default(),
+        })
+    }
+
+    /// In reduce only mode orders are allowed only if they decrease position without reversing it.
+    /// Position is tracked by fills on spot trade places too, so there only orders returning
+    /// bought or sold amount back to zero are allowed.
+    /// Every call should be paired with `disable_reduce_only` by the same source
+    pub fn enable_reduce_only(&self, exchange_account_id: ExchangeAccountId) {
+        *self
+            .reduce_only_exchange_accounts
+            .lock()
+            .entry(exchange_account_id)
+            .or_default() += 1;
+    }
+
+    pub fn disable_reduce_only(&self, exchange_account_id: ExchangeAccountId) {
+        let mut reduce_only_exchange_accounts = self.reduce_only_exchange_accounts.lock();
+        if let Some(sources_count) = reduce_only_exchange_accounts.get_mut(&exchange_account_id) {
+            *sources_count -= 1;
+            if *sources_count == 0 {
+                let _ = reduce_only_exchange_accounts.remove(&exchange_account_id);
+            }
+        }
+    }
+
+    pub fn is_reduce_only(&self, exchange_account_id: ExchangeAccountId) -> bool {
+        self.reduce_only_exchange_accounts
+            .lock()
+            .contains_key(&exchange_account_id)
+    }
+
+    /// Checks order created directly through `Exchange` by engine services like `Hedger`.
+    /// Middle price is taken from order book top of the exchange
+    pub fn check_order_creating(
+        &self,
+        exchange: &Exchange,
+        balance_manager: &Mutex<BalanceManager>,
+        symbol: &Symbol,
+        order: &OrderCreating,
+        now: DateTime,
+    ) -> Result<(), RiskLimitViolation> {
+        let header = &order.header;
+        let middle_price = exchange
+            .order_book_top
+            .get(&header.currency_pair)
+            .and_then(|top| top.get_top_prices().calculate_middle_price());
+        let context = OrderRiskContext::new(
+            exchange,
+            &balance_manager.lock(),
+            header.currency_pair,
+            std::iter::empty(),
+            None,
+            middle_price,
+        );
+
+        self.check_order(
+            exchange.exchange_account_id,
+            symbol,
+            header.side,
+            order.price,
+            header.amount,
+            &context,
+            now,
+        )
+    }
+
+    /// Checks order by all specified limits. Allowed order is counted for the order rate limit,
+    /// so the method should be called only right before order creation
+    pub fn check_order(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        symbol: &Symbol,
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        context: &OrderRiskContext,
+        now: DateTime,
+    ) -> Result<(), RiskLimitViolation> {
+        self.check_reduce_only(exchange_account_id, side, amount, context.position)?;
+        self.check_order_notional(amount, price)?;
+        self.check_position(symbol, side, amount, context)?;
+        self.check_open_orders(context.open_orders_count)?;
+        self.check_price_band(price, context.middle_price)?;
+        self.check_order_rate(exchange_account_id, now)
+    }
+
+    fn check_reduce_only(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        side: OrderSide,
+        amount: Amount,
+        position: Amount,
+    ) -> Result<(), RiskLimitViolation> {
+        if !self.is_reduce_only(exchange_account_id) {
+            return Ok(());
+        }
+
+        let new_position = match side {
+            OrderSide::Buy => position + amount,
+            OrderSide::Sell => position - amount,
+        };
+
+        let is_decreasing = new_position.is_zero()
+            || (new_position.abs() < position.abs()
+                && new_position.is_sign_positive() == position.is_sign_positive());
+        if !is_decreasing {
+            return Err(RiskLimitViolation::ReduceOnly {
+                exchange_account_id,
+                position,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_order_notional(&self, amount: Amount, price: Price) -> Result<(), RiskLimitViolation> {
+        match self.limits.max_order_notional {
+            Some(limit) if amount * price > limit => Err(RiskLimitViolation::OrderNotional {
+                notional: amount * price,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_position(
+        &self,
+        symbol: &Symbol,
+        side: OrderSide,
+        amount: Amount,
+        context: &OrderRiskContext,
+    ) -> Result<(), RiskLimitViolation> {
+        let currency_code = symbol.amount_currency_code;
+        let limit = match self.limits.max_positions.get(&currency_code) {
+            None => return Ok(()),
+            Some(limit) => *limit,
+        };
+
+        // open orders on the same side can be filled before the checked order
+        let (position, new_position) = match side {
+            OrderSide::Buy => {
+                let position = context.position + context.open_buy_amount;
+                (position, position + amount)
+            }
+            OrderSide::Sell => {
+                let position = context.position - context.open_sell_amount;
+                (position, position - amount)
+            }
+        };
+
+        if new_position.abs() > limit && new_position.abs() > position.abs() {
+            return Err(RiskLimitViolation::Position {
+                currency_code,
+                position: new_position,
+                limit,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn check_open_orders(&self, open_orders_count: usize) -> Result<(), RiskLimitViolation> {
+        match self.limits.max_open_orders {
+            Some(limit) if open_orders_count >= limit => Err(RiskLimitViolation::OpenOrders {
+                count: open_orders_count,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_price_band(
+        &self,
+        price: Price,
+        middle_price: Option<Price>,
+    ) -> Result<(), RiskLimitViolation> {
+        let max_deviation = match self.limits.max_price_deviation {
+            None => return Ok(()),
+            Some(max_deviation) => max_deviation,
+        };
+
+        match middle_price {
+            Some(middle_price) if (price - middle_price).abs() <= middle_price * max_deviation => {
+                Ok(())
+            }
+            _ => Err(RiskLimitViolation::PriceBand {
+                price,
+                middle_price,
+                max_deviation,
+            }),
+        }
+    }
+
+    fn check_order_rate(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        now: DateTime,
+    ) -> Result<(), RiskLimitViolation> {
+        let limit = match self.limits.max_orders_per_minute {
+            None => return Ok(()),
+            Some(limit) => limit,
+        };
+
+        let mut created_orders_times = self.created_orders_times.lock();
+        let times = created_orders_times.entry(exchange_account_id).or_default();
+
+        let period_start = now - Duration::minutes(1);
+        while times.front().map_or(false, |time| *time <= period_start) {
+            let _ = times.pop_front();
+        }
+
+        if times.len() >= limit {
+            return Err(RiskLimitViolation::OrderRate {
+                count: times.len(),
+                limit,
+            });
+        }
+
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mmb_utils::hashmap;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+    use crate::exchanges::general::symbol::Precision;
+    use crate::exchanges::general::test_helper::get_test_exchange;
+    use crate::orders::order::{ClientOrderId, OrderExecutionType, OrderHeader, OrderType};
+
+    fn symbol() -> Symbol {
+        Symbol::new(
+            false,
+            false,
+            "BTC".into(),
+            "btc".into(),
+            "USDT".into(),
+            "usdt".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "btc".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.01) },
+            Precision::ByTick { tick: dec!(0.001) },
+        )
+    }
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance".into(), 0)
+    }
+
+    fn context() -> OrderRiskContext {
+        OrderRiskContext {
+            position: dec!(0),
+            open_buy_amount: dec!(0),
+            open_sell_amount: dec!(0),
+            open_orders_count: 0,
+            middle_price: Some(dec!(100)),
+        }
+    }
+
+    fn check(
+        limits: RiskLimitsSettings,
+        side: OrderSide,
+        amount: Amount,
+        price: Price,
+        context: &OrderRiskContext,
+    ) -> Result<(), RiskLimitViolation> {
+        PreTradeRiskEngine::new(limits).check_order(
+            exchange_account_id(),
+            &symbol(),
+            side,
+            price,
+            amount,
+            context,
+            Utc::now(),
+        )
+    }
+
+    #[test]
+    fn no_limits_allow_any_order() {
+        let context = OrderRiskContext {
+            position: dec!(1000),
+            open_buy_amount: dec!(1000),
+            open_sell_amount: dec!(0),
+            open_orders_count: 1000,
+            middle_price: None,
+        };
+
+        assert_eq!(
+            check(
+                RiskLimitsSettings::default(),
+                OrderSide::Buy,
+                dec!(1000),
+                dec!(1),
+                &context
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn order_notional_limit() {
+        let limits = RiskLimitsSettings {
+            max_order_notional: Some(dec!(100)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check(
+                limits.clone(),
+                OrderSide::Buy,
+                dec!(1),
+                dec!(100),
+                &context()
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check(limits, OrderSide::Buy, dec!(1.5), dec!(100), &context()),
+            Err(RiskLimitViolation::OrderNotional {
+                notional: dec!(150),
+                limit: dec!(100)
+            })
+        );
+    }
+
+    #[test]
+    fn position_limit_allows_decreasing_position() {
+        let limits = RiskLimitsSettings {
+            max_positions: hashmap!["btc".into() => dec!(2)],
+            ..Default::default()
+        };
+        let context = OrderRiskContext {
+            position: dec!(3),
+            ..context()
+        };
+
+        assert_eq!(
+            check(
+                limits.clone(),
+                OrderSide::Sell,
+                dec!(0.5),
+                dec!(100),
+                &context
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check(limits, OrderSide::Buy, dec!(0.5), dec!(100), &context),
+            Err(RiskLimitViolation::Position {
+                currency_code: "btc".into(),
+                position: dec!(3.5),
+                limit: dec!(2)
+            })
+        );
+    }
+
+    #[test]
+    fn position_limit_for_short_position() {
+        let limits = RiskLimitsSettings {
+            max_positions: hashmap!["btc".into() => dec!(2)],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check(
+                limits.clone(),
+                OrderSide::Sell,
+                dec!(2),
+                dec!(100),
+                &context()
+            ),
+            Ok(())
+        );
+        assert!(matches!(
+            check(limits, OrderSide::Sell, dec!(2.5), dec!(100), &context()),
+            Err(RiskLimitViolation::Position { .. })
+        ));
+    }
+
+    #[test]
+    fn position_limit_includes_open_orders_on_the_same_side() {
+        let limits = RiskLimitsSettings {
+            max_positions: hashmap!["btc".into() => dec!(2)],
+            ..Default::default()
+        };
+        let context = OrderRiskContext {
+            position: dec!(1),
+            open_buy_amount: dec!(0.8),
+            open_sell_amount: dec!(5),
+            ..context()
+        };
+
+        assert_eq!(
+            check(
+                limits.clone(),
+                OrderSide::Buy,
+                dec!(0.5),
+                dec!(100),
+                &context
+            ),
+            Err(RiskLimitViolation::Position {
+                currency_code: "btc".into(),
+                position: dec!(2.3),
+                limit: dec!(2)
+            })
+        );
+        assert_eq!(
+            check(limits, OrderSide::Buy, dec!(0.2), dec!(100), &context),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn open_orders_limit() {
+        let limits = RiskLimitsSettings {
+            max_open_orders: Some(2),
+            ..Default::default()
+        };
+        let context = OrderRiskContext {
+            open_orders_count: 2,
+            ..context()
+        };
+
+        assert_eq!(
+            check(limits, OrderSide::Buy, dec!(1), dec!(100), &context),
+            Err(RiskLimitViolation::OpenOrders { count: 2, limit: 2 })
+        );
+    }
+
+    #[test]
+    fn price_band() {
+        let limits = RiskLimitsSettings {
+            max_price_deviation: Some(dec!(0.05)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check(
+                limits.clone(),
+                OrderSide::Buy,
+                dec!(1),
+                dec!(95),
+                &context()
+            ),
+            Ok(())
+        );
+        assert!(matches!(
+            check(
+                limits.clone(),
+                OrderSide::Sell,
+                dec!(1),
+                dec!(106),
+                &context()
+            ),
+            Err(RiskLimitViolation::PriceBand { .. })
+        ));
+
+        let context = OrderRiskContext {
+            middle_price: None,
+            ..context()
+        };
+        assert!(matches!(
+            check(limits, OrderSide::Buy, dec!(1), dec!(100), &context),
+            Err(RiskLimitViolation::PriceBand {
+                middle_price: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn order_rate_limit() {
+        let risk_engine = PreTradeRiskEngine::new(RiskLimitsSettings {
+            max_orders_per_minute: Some(2),
+            ..Default::default()
+        });
+        let symbol = symbol();
+        let start = Utc::now();
+        let check_at = |time| {
+            risk_engine.check_order(
+                exchange_account_id(),
+                &symbol,
+                OrderSide::Buy,
+                dec!(100),
+                dec!(1),
+                &context(),
+                time,
+            )
+        };
+
+        assert_eq!(check_at(start), Ok(()));
+        assert_eq!(check_at(start + Duration::seconds(10)), Ok(()));
+        assert_eq!(
+            check_at(start + Duration::seconds(20)),
+            Err(RiskLimitViolation::OrderRate { count: 2, limit: 2 })
+        );
+        // the first order is out of the period
+        assert_eq!(check_at(start + Duration::seconds(61)), Ok(()));
+    }
+
+    #[test]
+    fn reduce_only_allows_only_decreasing_orders() {
+        let risk_engine = PreTradeRiskEngine::new(RiskLimitsSettings::default());
+        risk_engine.enable_reduce_only(exchange_account_id());
+        let context = OrderRiskContext {
+            position: dec!(1),
+            ..context()
+        };
+        let check = |side, amount| {
+            risk_engine.check_order(
+                exchange_account_id(),
+                &symbol(),
+                side,
+                dec!(100),
+                amount,
+                &context,
+                Utc::now(),
+            )
+        };
+
+        assert_eq!(check(OrderSide::Sell, dec!(0.5)), Ok(()));
+        assert_eq!(check(OrderSide::Sell, dec!(1)), Ok(()));
+        assert_eq!(
+            check(OrderSide::Sell, dec!(1.5)),
+            Err(RiskLimitViolation::ReduceOnly {
+                exchange_account_id: exchange_account_id(),
+                position: dec!(1)
+            })
+        );
+        assert!(check(OrderSide::Buy, dec!(0.1)).is_err());
+
+        risk_engine.disable_reduce_only(exchange_account_id());
+        assert_eq!(check(OrderSide::Buy, dec!(0.1)), Ok(()));
+    }
+
+    #[test]
+    fn reduce_only_is_disabled_when_every_source_disabled_it() {
+        let risk_engine = PreTradeRiskEngine::new(RiskLimitsSettings::default());
+        risk_engine.enable_reduce_only(exchange_account_id());
+        risk_engine.enable_reduce_only(exchange_account_id());
+
+        risk_engine.disable_reduce_only(exchange_account_id());
+        assert!(risk_engine.is_reduce_only(exchange_account_id()));
+
+        risk_engine.disable_reduce_only(exchange_account_id());
+        assert!(!risk_engine.is_reduce_only(exchange_account_id()));
+    }
+
+    #[test]
+    fn order_creating_at_limits_is_allowed() {
+        let (exchange, _rx) = get_test_exchange(false);
+        let symbol = exchange.symbols.iter().next().expect("in test").clone();
+        let balance_manager = BalanceManager::new(CurrencyPairToSymbolConverter::new(
+            hashmap![exchange.exchange_account_id => exchange.clone()],
+        ));
+
+        let limits = RiskLimitsSettings {
+            max_positions: hashmap![symbol.amount_currency_code => dec!(1)],
+            max_open_orders: Some(1),
+            ..Default::default()
+        };
+        let header = OrderHeader::new(
+            ClientOrderId::unique_id(),
+            Utc::now(),
+            exchange.exchange_account_id,
+            symbol.currency_pair(),
+            OrderType::Limit,
+            OrderSide::Buy,
+            dec!(1),
+            OrderExecutionType::None,
+            None,
+            None,
+            "test".to_owned(),
+        );
+        let order = OrderCreating {
+            header,
+            price: dec!(1),
+        };
+
+        assert_eq!(
+            PreTradeRiskEngine::new(limits).check_order_creating(
+                &exchange,
+                &balance_manager,
+                &symbol,
+                &order,
+                Utc::now(),
+            ),
+            Ok(())
+        );
+    }
+}
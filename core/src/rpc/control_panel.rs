@@ -1,12 +1,17 @@
 use anyhow::Result;
 use parking_lot::Mutex;
+use tokio::runtime::Handle;
 use tokio::sync::{mpsc, oneshot};
 
 use std::sync::Arc;
 
 use crate::{
-    lifecycle::{application_manager::ApplicationManager, trading_engine::Service},
+    lifecycle::{
+        application_manager::ApplicationManager,
+        trading_engine::{EngineContext, Service},
+    },
     statistic_service::StatisticService,
+    strategies::hosted_strategies::HostedStrategies,
 };
 
 use super::{
@@ -29,6 +34,8 @@ impl ControlPanel {
         application_manager: Arc<ApplicationManager>,
         engine_settings: String,
         statistics: Arc<StatisticService>,
+        hosted_strategies: Arc<HostedStrategies>,
+        engine_context: Arc<EngineContext>,
     ) -> Result<Arc<Self>> {
         let (server_stopper_tx, server_stopper_rx) = mpsc::channel::<()>(10);
         let server_stopper_tx = Arc::new(Mutex::new(Some(server_stopper_tx.clone())));
@@ -40,6 +47,9 @@ impl ControlPanel {
             server_stopper_tx.clone(),
             statistics,
             engine_settings,
+            hosted_strategies,
+            engine_context,
+            Handle::current(),
         ));
 
         spawn_server_stopping_action(
@@ -3,3 +3,4 @@ pub mod config_waiter;
 pub mod control_panel;
 pub mod rpc_impl;
 pub mod rpc_impl_no_config;
+mod trading_state;
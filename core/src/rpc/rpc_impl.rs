@@ -1,21 +1,33 @@
 use jsonrpc_core::Result;
 use mmb_rpc::rest_api::server_side_error;
 use mmb_rpc::rest_api::MmbRpc;
+use mmb_rpc::rest_api::PnlFilter;
+use mmb_rpc::rest_api::TradePlaceFilter;
 use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::runtime::Handle;
 use tokio::sync::mpsc;
 
 use std::sync::Arc;
 
+use crate::lifecycle::trading_engine::EngineContext;
 use crate::statistic_service::StatisticService;
+use crate::strategies::hosted_strategies::HostedStrategies;
 use mmb_rpc::rest_api::ErrorCode;
 
 use super::common::send_stop;
 use super::common::set_config;
+use super::trading_state;
 
 pub struct RpcImpl {
     server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<()>>>>,
     statistics: Arc<StatisticService>,
     engine_settings: String,
+    hosted_strategies: Arc<HostedStrategies>,
+    engine_context: Arc<EngineContext>,
+    /// Runtime of trading engine. RPC requests are handled on threads of IPC server,
+    /// so futures started by requests should be spawned on this runtime
+    runtime_handle: Handle,
 }
 
 impl RpcImpl {
@@ -23,13 +35,35 @@ impl RpcImpl {
         server_stopper_tx: Arc<Mutex<Option<mpsc::Sender<()>>>>,
         statistics: Arc<StatisticService>,
         engine_settings: String,
+        hosted_strategies: Arc<HostedStrategies>,
+        engine_context: Arc<EngineContext>,
+        runtime_handle: Handle,
     ) -> Self {
         Self {
             server_stopper_tx,
             statistics,
             engine_settings,
+            hosted_strategies,
+            engine_context,
+            runtime_handle,
         }
     }
+
+    fn set_strategy_enabled(&self, strategy_name: &str, is_enabled: bool) -> Result<()> {
+        self.hosted_strategies
+            .set_enabled(strategy_name, is_enabled)
+            .map_err(|err| {
+                log::warn!("Failed to change state of strategy: {:?}", err);
+                server_side_error(ErrorCode::StrategyNotFound)
+            })
+    }
+
+    fn to_json(value: &impl Serialize, description: &str) -> Result<String> {
+        serde_json::to_string(value).map_err(|err| {
+            log::warn!("Failed to convert {} to string: {}", description, err);
+            server_side_error(ErrorCode::FailedToSerializeResponse)
+        })
+    }
 }
 
 impl MmbRpc for RpcImpl {
@@ -64,4 +98,71 @@ impl MmbRpc for RpcImpl {
 
         Ok(json_statistic)
     }
+
+    fn strategies(&self) -> Result<String> {
+        Self::to_json(&self.hosted_strategies.get_states(), "hosted strategies")
+    }
+
+    fn start_strategy(&self, strategy_name: String) -> Result<String> {
+        self.set_strategy_enabled(&strategy_name, true)?;
+        Ok(format!("Strategy {} is started", strategy_name))
+    }
+
+    fn stop_strategy(&self, strategy_name: String) -> Result<String> {
+        self.set_strategy_enabled(&strategy_name, false)?;
+        Ok(format!(
+            "Strategy {} is stopped. Its orders will be cancelled",
+            strategy_name
+        ))
+    }
+
+    fn kill_switch(&self, flatten: bool) -> Result<String> {
+        let _runtime_guard = self.runtime_handle.enter();
+        self.engine_context
+            .kill_switch
+            .activate(
+                self.engine_context.clone(),
+                flatten,
+                "Requested by control panel".to_owned(),
+            )
+            .map_err(|err| {
+                log::warn!("Failed to activate kill switch: {:?}", err);
+                server_side_error(ErrorCode::KillSwitchIsAlreadyActivated)
+            })?;
+
+        Ok(
+            "Kill switch is activated. All exchanges are blocked, open orders will be cancelled"
+                .into(),
+        )
+    }
+
+    fn kill_switch_report(&self) -> Result<String> {
+        let report = self.engine_context.kill_switch.report();
+        Self::to_json(&report, "kill switch report")
+    }
+
+    fn orders(&self, filter: TradePlaceFilter) -> Result<String> {
+        let orders = trading_state::get_orders(&self.engine_context, &filter);
+        Self::to_json(&orders, "orders")
+    }
+
+    fn balances(&self, filter: TradePlaceFilter) -> Result<String> {
+        let balances = trading_state::get_balances(&self.engine_context, &filter);
+        Self::to_json(&balances, "balances")
+    }
+
+    fn reservations(&self, filter: TradePlaceFilter) -> Result<String> {
+        let reservations = trading_state::get_reservations(&self.engine_context, &filter);
+        Self::to_json(&reservations, "reservations")
+    }
+
+    fn positions(&self, filter: TradePlaceFilter) -> Result<String> {
+        let positions = trading_state::get_positions(&self.engine_context, &filter);
+        Self::to_json(&positions, "positions")
+    }
+
+    fn pnl(&self, filter: PnlFilter) -> Result<String> {
+        let pnl = trading_state::get_pnl(&self.engine_context, &filter);
+        Self::to_json(&pnl, "pnl")
+    }
 }
@@ -1,5 +1,5 @@
 use jsonrpc_core::Result;
-use mmb_rpc::rest_api::MmbRpc;
+use mmb_rpc::rest_api::{MmbRpc, PnlFilter, TradePlaceFilter};
 use mmb_utils::send_expected::SendExpectedByRef;
 use parking_lot::Mutex;
 use tokio::sync::mpsc;
@@ -50,4 +50,44 @@ impl MmbRpc for RpcImplNoConfig {
     fn stats(&self) -> Result<String> {
         Ok(CONFIG_IS_NOT_SET.into())
     }
+
+    fn strategies(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn start_strategy(&self, _strategy_name: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn stop_strategy(&self, _strategy_name: String) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn kill_switch(&self, _flatten: bool) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn kill_switch_report(&self) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn orders(&self, _filter: TradePlaceFilter) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn balances(&self, _filter: TradePlaceFilter) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn reservations(&self, _filter: TradePlaceFilter) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn positions(&self, _filter: TradePlaceFilter) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
+
+    fn pnl(&self, _filter: PnlFilter) -> Result<String> {
+        Ok(CONFIG_IS_NOT_SET.into())
+    }
 }
@@ -0,0 +1,344 @@
+use itertools::Itertools;
+use mmb_rpc::rest_api::{PnlFilter, TradePlaceFilter};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price};
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderSide, OrderStatus, OrderType, ReservationId,
+};
+use crate::pnl::pnl_ledger::TradePlacePnlInfo;
+use crate::service_configuration::configuration_descriptor::{
+    ServiceConfigurationKey, ServiceName,
+};
+use crate::strategies::hosted_strategies::get_strategy_name;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct OrderInfo {
+    pub client_order_id: ClientOrderId,
+    pub exchange_order_id: Option<ExchangeOrderId>,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub status: OrderStatus,
+    pub price: Price,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+    pub reservation_id: Option<ReservationId>,
+    pub strategy_name: String,
+    pub init_time: DateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct BalanceInfo {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_code: CurrencyCode,
+    /// Balance received from exchange
+    pub balance: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ReservationInfo {
+    pub reservation_id: ReservationId,
+    pub strategy_name: String,
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub unreserved_amount: Amount,
+    /// Amount which isn't approved by created orders yet
+    pub not_approved_amount: Amount,
+    pub reservation_currency_code: CurrencyCode,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct PositionInfo {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
+    /// Position in amount currency, positive for long position
+    pub position: Amount,
+}
+
+fn is_exchange_account_matched(
+    filter: &TradePlaceFilter,
+    exchange_account_id: ExchangeAccountId,
+) -> bool {
+    filter
+        .exchange_account_id
+        .as_ref()
+        .map_or(true, |filter| *filter == exchange_account_id.to_string())
+}
+
+fn is_currency_pair_matched(filter: &TradePlaceFilter, currency_pair: CurrencyPair) -> bool {
+    filter
+        .currency_pair
+        .as_ref()
+        .map_or(true, |filter| filter == currency_pair.as_str())
+}
+
+/// Balance is matched by currency pair if its currency is the base or the quote currency of the pair
+fn is_currency_code_matched(filter: &TradePlaceFilter, currency_code: CurrencyCode) -> bool {
+    filter.currency_pair.as_ref().map_or(true, |filter| {
+        filter
+            .split('/')
+            .any(|filter_currency_code| filter_currency_code == currency_code.as_str())
+    })
+}
+
+fn is_service_matched(
+    filter: &PnlFilter,
+    service_name: ServiceName,
+    service_configuration_key: ServiceConfigurationKey,
+) -> bool {
+    filter
+        .service_name
+        .as_ref()
+        .map_or(true, |filter| filter == service_name.as_str())
+        && filter
+            .service_configuration_key
+            .as_ref()
+            .map_or(true, |filter| filter == service_configuration_key.as_str())
+}
+
+/// Orders which aren't finished yet sorted by creation time
+pub(crate) fn get_orders(
+    engine_context: &EngineContext,
+    filter: &TradePlaceFilter,
+) -> Vec<OrderInfo> {
+    engine_context
+        .exchanges
+        .iter()
+        .filter(|exchange| is_exchange_account_matched(filter, exchange.exchange_account_id))
+        .flat_map(|exchange| {
+            exchange
+                .orders
+                .not_finished
+                .iter()
+                .map(|order| {
+                    order.fn_ref(|order| OrderInfo {
+                        client_order_id: order.header.client_order_id.clone(),
+                        exchange_order_id: order.props.exchange_order_id.clone(),
+                        exchange_account_id: order.header.exchange_account_id,
+                        currency_pair: order.header.currency_pair,
+                        side: order.header.side,
+                        order_type: order.header.order_type,
+                        status: order.status(),
+                        price: order.price(),
+                        amount: order.amount(),
+                        filled_amount: order.fills.filled_amount,
+                        reservation_id: order.header.reservation_id,
+                        strategy_name: order.header.strategy_name.clone(),
+                        init_time: order.header.init_time,
+                    })
+                })
+                .collect_vec()
+        })
+        .filter(|order| is_currency_pair_matched(filter, order.currency_pair))
+        .sorted_by(|a, b| {
+            (a.init_time, a.client_order_id.as_str())
+                .cmp(&(b.init_time, b.client_order_id.as_str()))
+        })
+        .collect()
+}
+
+pub(crate) fn get_balances(
+    engine_context: &EngineContext,
+    filter: &TradePlaceFilter,
+) -> Vec<BalanceInfo> {
+    let balances = engine_context.balance_manager.lock().get_balances();
+
+    balances
+        .balances_by_exchange_id
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(exchange_account_id, _)| {
+            is_exchange_account_matched(filter, *exchange_account_id)
+        })
+        .flat_map(|(exchange_account_id, balances)| {
+            balances
+                .into_iter()
+                .map(move |(currency_code, balance)| BalanceInfo {
+                    exchange_account_id,
+                    currency_code,
+                    balance,
+                })
+        })
+        .filter(|balance| is_currency_code_matched(filter, balance.currency_code))
+        .sorted_by_key(|balance| {
+            (
+                balance.exchange_account_id.to_string(),
+                balance.currency_code.as_str().to_owned(),
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn get_reservations(
+    engine_context: &EngineContext,
+    filter: &TradePlaceFilter,
+) -> Vec<ReservationInfo> {
+    let balances = engine_context.balance_manager.lock().get_balances();
+
+    balances
+        .balance_reservations_by_reservation_id
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(reservation_id, reservation)| ReservationInfo {
+            reservation_id,
+            strategy_name: get_strategy_name(reservation.configuration_descriptor),
+            exchange_account_id: reservation.exchange_account_id,
+            currency_pair: reservation.symbol.currency_pair(),
+            side: reservation.order_side,
+            price: reservation.price,
+            amount: reservation.amount,
+            unreserved_amount: reservation.unreserved_amount,
+            not_approved_amount: reservation.not_approved_amount,
+            reservation_currency_code: reservation.reservation_currency_code,
+        })
+        .filter(|reservation| {
+            is_exchange_account_matched(filter, reservation.exchange_account_id)
+                && is_currency_pair_matched(filter, reservation.currency_pair)
+        })
+        .sorted_by_key(|reservation| reservation.reservation_id)
+        .collect()
+}
+
+/// Not zero positions on derivative currency pairs
+pub(crate) fn get_positions(
+    engine_context: &EngineContext,
+    filter: &TradePlaceFilter,
+) -> Vec<PositionInfo> {
+    let trade_places = engine_context
+        .exchanges
+        .iter()
+        .filter(|exchange| is_exchange_account_matched(filter, exchange.exchange_account_id))
+        .flat_map(|exchange| {
+            exchange
+                .symbols
+                .iter()
+                .filter(|symbol| symbol.is_derivative())
+                .map(|symbol| (exchange.exchange_account_id, symbol.currency_pair()))
+                .collect_vec()
+        })
+        .filter(|(_, currency_pair)| is_currency_pair_matched(filter, *currency_pair))
+        .collect_vec();
+
+    let balance_manager = engine_context.balance_manager.lock();
+    trade_places
+        .into_iter()
+        .map(|(exchange_account_id, currency_pair)| PositionInfo {
+            exchange_account_id,
+            currency_pair,
+            position: balance_manager.get_position(
+                exchange_account_id,
+                currency_pair,
+                OrderSide::Buy,
+            ),
+        })
+        .filter(|position| !position.position.is_zero())
+        .sorted_by_key(|position| {
+            (
+                position.exchange_account_id.to_string(),
+                position.currency_pair.as_str().to_owned(),
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn get_pnl(
+    engine_context: &EngineContext,
+    filter: &PnlFilter,
+) -> Vec<TradePlacePnlInfo> {
+    engine_context
+        .pnl_ledger
+        .get_pnl()
+        .into_iter()
+        .filter(|pnl| is_service_matched(filter, pnl.service_name, pnl.service_configuration_key))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(exchange_account_id: Option<&str>, currency_pair: Option<&str>) -> TradePlaceFilter {
+        TradePlaceFilter {
+            exchange_account_id: exchange_account_id.map(|x| x.to_owned()),
+            currency_pair: currency_pair.map(|x| x.to_owned()),
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = TradePlaceFilter::default();
+
+        assert!(is_exchange_account_matched(
+            &filter,
+            ExchangeAccountId::new("Binance".into(), 0)
+        ));
+        assert!(is_currency_pair_matched(
+            &filter,
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        ));
+        assert!(is_currency_code_matched(&filter, "btc".into()));
+    }
+
+    #[test]
+    fn filter_by_exchange_account_id() {
+        let filter = filter(Some("Binance_0"), None);
+
+        assert!(is_exchange_account_matched(
+            &filter,
+            ExchangeAccountId::new("Binance".into(), 0)
+        ));
+        assert!(!is_exchange_account_matched(
+            &filter,
+            ExchangeAccountId::new("Binance".into(), 1)
+        ));
+    }
+
+    #[test]
+    fn filter_by_currency_pair() {
+        let filter = filter(None, Some("btc/usdt"));
+
+        assert!(is_currency_pair_matched(
+            &filter,
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        ));
+        assert!(!is_currency_pair_matched(
+            &filter,
+            CurrencyPair::from_codes("eth".into(), "usdt".into())
+        ));
+        assert!(is_currency_code_matched(&filter, "btc".into()));
+        assert!(is_currency_code_matched(&filter, "usdt".into()));
+        assert!(!is_currency_code_matched(&filter, "eth".into()));
+    }
+
+    #[test]
+    fn filter_by_service() {
+        let filter = PnlFilter {
+            service_name: Some("ExampleStrategy".to_owned()),
+            service_configuration_key: None,
+        };
+
+        assert!(is_service_matched(
+            &filter,
+            "ExampleStrategy".into(),
+            "Binance_0;btc/usdt".into()
+        ));
+        assert!(!is_service_matched(
+            &filter,
+            "OtherStrategy".into(),
+            "Binance_0;btc/usdt".into()
+        ));
+        assert!(is_service_matched(
+            &PnlFilter::default(),
+            "OtherStrategy".into(),
+            "Binance_0;btc/usdt".into()
+        ));
+    }
+}
@@ -1,16 +1,25 @@
+use mmb_utils::DateTime;
+
 use crate::exchanges::common::{CurrencyCode, Price};
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct MarketCurrencyCodePrice {
     pub currency_code: CurrencyCode,
     pub price_usd: Option<Price>,
+    /// Time when the price was received from market data source
+    pub updated_at: DateTime,
 }
 
 impl MarketCurrencyCodePrice {
-    pub fn new(currency_code: CurrencyCode, price_usd: Option<Price>) -> Self {
+    pub fn new(
+        currency_code: CurrencyCode,
+        price_usd: Option<Price>,
+        updated_at: DateTime,
+    ) -> Self {
         Self {
             currency_code,
             price_usd,
+            updated_at,
         }
     }
 }
@@ -1 +1,3 @@
 pub mod market_currency_code_price;
+pub mod order_book_market_prices;
+pub mod ticker_market_prices;
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+
+use crate::exchanges::common::{CurrencyCode, Price};
+use crate::exchanges::general::exchange::Exchange;
+use crate::misc::time::get_current_time;
+use crate::misc::traits::market_service::GetMarketCurrencyCodePrice;
+use crate::services::market_prices::market_currency_code_price::MarketCurrencyCodePrice;
+
+/// Max count of conversion steps from USD-like currency to the priced currency
+const MAX_CONVERSION_DEPTH: usize = 3;
+
+/// Middle price of currency pair from order book top
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MiddlePrice {
+    pub base_currency_code: CurrencyCode,
+    pub quote_currency_code: CurrencyCode,
+    pub price: Price,
+    pub updated_at: DateTime,
+}
+
+/// USD price of currency calculated from middle prices
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UsdPrice {
+    pub price: Price,
+    /// Update time of the oldest order book used for calculation. `None` for USD-like currencies
+    pub updated_at: Option<DateTime>,
+}
+
+impl UsdPrice {
+    fn derived(price: Price, source: &UsdPrice, middle_price: &MiddlePrice) -> Self {
+        let updated_at = match source.updated_at {
+            Some(source_updated_at) => source_updated_at.min(middle_price.updated_at),
+            None => middle_price.updated_at,
+        };
+
+        Self {
+            price,
+            updated_at: Some(updated_at),
+        }
+    }
+}
+
+/// Market service which calculates USD prices from order books of the engine exchanges.
+/// Currencies from `usd_currency_codes` (stablecoins) are priced as 1 USD,
+/// other currencies are priced through pairs with already priced currencies.
+pub struct OrderBookMarketPrices {
+    exchanges: Vec<Arc<Exchange>>,
+    usd_currency_codes: Vec<CurrencyCode>,
+}
+
+impl OrderBookMarketPrices {
+    pub fn new(exchanges: Vec<Arc<Exchange>>, usd_currency_codes: Vec<CurrencyCode>) -> Arc<Self> {
+        Arc::new(Self {
+            exchanges,
+            usd_currency_codes,
+        })
+    }
+
+    pub fn default_usd_currency_codes() -> Vec<CurrencyCode> {
+        ["usd", "usdt", "usdc", "busd", "tusd", "dai"]
+            .into_iter()
+            .map(CurrencyCode::from)
+            .collect()
+    }
+
+    fn get_middle_prices(&self) -> Vec<MiddlePrice> {
+        self.exchanges
+            .iter()
+            .flat_map(|exchange| {
+                exchange
+                    .symbols
+                    .iter()
+                    .filter_map(|symbol| {
+                        let order_book_top =
+                            exchange.order_book_top.get(&symbol.currency_pair())?;
+                        let price = order_book_top.get_top_prices().calculate_middle_price()?;
+
+                        Some(MiddlePrice {
+                            base_currency_code: symbol.base_currency_code(),
+                            quote_currency_code: symbol.quote_currency_code(),
+                            price,
+                            updated_at: order_book_top.updated_at,
+                        })
+                    })
+                    .collect_vec()
+            })
+            .collect()
+    }
+}
+
+/// Calculate USD prices of currencies from middle prices of currency pairs.
+/// Each step prices currencies which have a pair with currency priced on the previous step.
+/// If there are several pairs for a currency on the same step, the average price is taken.
+/// Calculated price is as old as the oldest order book used for it.
+pub(crate) fn calculate_usd_prices(
+    middle_prices: &[MiddlePrice],
+    usd_currency_codes: &[CurrencyCode],
+) -> HashMap<CurrencyCode, UsdPrice> {
+    let mut usd_prices: HashMap<CurrencyCode, UsdPrice> = usd_currency_codes
+        .iter()
+        .map(|currency_code| {
+            let usd_price = UsdPrice {
+                price: Decimal::ONE,
+                updated_at: None,
+            };
+            (*currency_code, usd_price)
+        })
+        .collect();
+
+    for _ in 0..MAX_CONVERSION_DEPTH {
+        let mut candidates: HashMap<CurrencyCode, Vec<UsdPrice>> = HashMap::new();
+
+        for middle_price in middle_prices.iter().filter(|x| !x.price.is_zero()) {
+            let base_price = usd_prices.get(&middle_price.base_currency_code);
+            let quote_price = usd_prices.get(&middle_price.quote_currency_code);
+
+            match (base_price, quote_price) {
+                (None, Some(quote_price)) => candidates
+                    .entry(middle_price.base_currency_code)
+                    .or_default()
+                    .push(UsdPrice::derived(
+                        middle_price.price * quote_price.price,
+                        quote_price,
+                        middle_price,
+                    )),
+                (Some(base_price), None) => candidates
+                    .entry(middle_price.quote_currency_code)
+                    .or_default()
+                    .push(UsdPrice::derived(
+                        base_price.price / middle_price.price,
+                        base_price,
+                        middle_price,
+                    )),
+                _ => {}
+            }
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+
+        usd_prices.extend(candidates.into_iter().map(|(currency_code, prices)| {
+            let count = Decimal::from(prices.len());
+            let usd_price = UsdPrice {
+                price: prices.iter().map(|x| x.price).sum::<Decimal>() / count,
+                updated_at: prices.iter().filter_map(|x| x.updated_at).min(),
+            };
+            (currency_code, usd_price)
+        }));
+    }
+
+    usd_prices
+}
+
+#[async_trait]
+impl GetMarketCurrencyCodePrice for OrderBookMarketPrices {
+    async fn get_market_currency_code_price(&self) -> Vec<MarketCurrencyCodePrice> {
+        let now = get_current_time();
+
+        calculate_usd_prices(&self.get_middle_prices(), &self.usd_currency_codes)
+            .into_iter()
+            .map(|(currency_code, usd_price)| {
+                // USD-like currencies are priced as 1 USD constantly, so their price is always actual
+                let updated_at = usd_price.updated_at.unwrap_or(now);
+                MarketCurrencyCodePrice::new(currency_code, Some(usd_price.price), updated_at)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn middle_price(base: &str, quote: &str, price: Price) -> MiddlePrice {
+        middle_price_updated_at(base, quote, price, get_current_time())
+    }
+
+    fn middle_price_updated_at(
+        base: &str,
+        quote: &str,
+        price: Price,
+        updated_at: DateTime,
+    ) -> MiddlePrice {
+        MiddlePrice {
+            base_currency_code: base.into(),
+            quote_currency_code: quote.into(),
+            price,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn stablecoins_are_priced_as_usd() {
+        let usd_prices = calculate_usd_prices(
+            &[middle_price("btc", "usdt", dec!(40000))],
+            &OrderBookMarketPrices::default_usd_currency_codes(),
+        );
+
+        assert_eq!(usd_prices[&"usdt".into()].price, dec!(1));
+        assert_eq!(usd_prices[&"usdc".into()].price, dec!(1));
+        assert_eq!(usd_prices[&"btc".into()].price, dec!(40000));
+    }
+
+    #[test]
+    fn average_price_of_several_pairs() {
+        let usd_prices = calculate_usd_prices(
+            &[
+                middle_price("btc", "usdt", dec!(40000)),
+                middle_price("btc", "busd", dec!(40100)),
+            ],
+            &["usdt".into(), "busd".into()],
+        );
+
+        assert_eq!(usd_prices[&"btc".into()].price, dec!(40050));
+    }
+
+    #[test]
+    fn price_through_intermediate_currency() {
+        let usd_prices = calculate_usd_prices(
+            &[
+                middle_price("eth", "btc", dec!(0.075)),
+                middle_price("btc", "usdt", dec!(40000)),
+                middle_price("usdt", "rub", dec!(80)),
+            ],
+            &["usdt".into()],
+        );
+
+        assert_eq!(usd_prices[&"btc".into()].price, dec!(40000));
+        assert_eq!(usd_prices[&"eth".into()].price, dec!(3000));
+        assert_eq!(usd_prices[&"rub".into()].price, dec!(0.0125));
+    }
+
+    #[test]
+    fn price_is_as_old_as_oldest_order_book() {
+        let now = get_current_time();
+        let hour_ago = now - chrono::Duration::hours(1);
+        let usd_prices = calculate_usd_prices(
+            &[
+                middle_price_updated_at("eth", "btc", dec!(0.075), now),
+                middle_price_updated_at("btc", "usdt", dec!(40000), hour_ago),
+            ],
+            &["usdt".into()],
+        );
+
+        assert_eq!(usd_prices[&"usdt".into()].updated_at, None);
+        assert_eq!(usd_prices[&"btc".into()].updated_at, Some(hour_ago));
+        assert_eq!(usd_prices[&"eth".into()].updated_at, Some(hour_ago));
+    }
+
+    #[test]
+    fn currency_without_path_to_usd_is_not_priced() {
+        let usd_prices =
+            calculate_usd_prices(&[middle_price("eth", "btc", dec!(0.075))], &["usdt".into()]);
+
+        assert_eq!(usd_prices.get(&"eth".into()), None);
+        assert_eq!(usd_prices.get(&"btc".into()), None);
+    }
+}
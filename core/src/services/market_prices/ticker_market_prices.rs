@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use mmb_utils::DateTime;
+use serde::Deserialize;
+
+use crate::exchanges::common::{CurrencyCode, Price};
+use crate::exchanges::rest_client::{build_uri, HttpParams, RestClient};
+use crate::misc::time::get_current_time;
+use crate::misc::traits::market_service::{CreateMarketService, GetMarketCurrencyCodePrice};
+use crate::services::market_prices::market_currency_code_price::MarketCurrencyCodePrice;
+
+pub const DEFAULT_TICKERS_BASE_URL: &str = "https://api.coinpaprika.com";
+const TICKERS_PATH: &str = "/v1/tickers";
+
+#[derive(Deserialize)]
+struct TickerQuote {
+    price: Option<Price>,
+}
+
+#[derive(Deserialize)]
+struct Ticker {
+    symbol: String,
+    last_updated: Option<DateTime>,
+    quotes: HashMap<String, TickerQuote>,
+}
+
+/// Market service which receives USD prices from tickers REST API (coinpaprika format).
+/// Base URL is configurable, so tests can use local stub server instead of real API.
+pub struct TickerMarketPrices {
+    base_url: String,
+    rest_client: RestClient,
+}
+
+impl TickerMarketPrices {
+    pub fn with_base_url(base_url: impl Into<String>) -> Arc<Self> {
+        Arc::new(Self {
+            base_url: base_url.into(),
+            rest_client: RestClient::new(),
+        })
+    }
+
+    async fn request_prices(&self) -> Result<Vec<MarketCurrencyCodePrice>> {
+        let uri = build_uri(&self.base_url, TICKERS_PATH, &HttpParams::new())?;
+        let response = self.rest_client.get(uri, "").await?;
+        if !response.status.is_success() {
+            bail!(
+                "Tickers request failed with status {}: {}",
+                response.status,
+                response.content
+            );
+        }
+
+        parse_tickers(&response.content, get_current_time())
+    }
+}
+
+/// Tickers are ordered by market capitalization, so for duplicated symbols the first ticker is taken
+fn parse_tickers(content: &str, now: DateTime) -> Result<Vec<MarketCurrencyCodePrice>> {
+    let tickers: Vec<Ticker> =
+        serde_json::from_str(content).context("Unable to parse tickers response")?;
+
+    let mut handled_symbols = HashSet::new();
+    Ok(tickers
+        .into_iter()
+        .filter(|ticker| handled_symbols.insert(ticker.symbol.to_lowercase()))
+        .map(|ticker| {
+            let price_usd = ticker.quotes.get("USD").and_then(|quote| quote.price);
+            MarketCurrencyCodePrice::new(
+                CurrencyCode::new(&ticker.symbol),
+                price_usd,
+                ticker.last_updated.unwrap_or(now),
+            )
+        })
+        .collect())
+}
+
+#[async_trait]
+impl GetMarketCurrencyCodePrice for TickerMarketPrices {
+    async fn get_market_currency_code_price(&self) -> Vec<MarketCurrencyCodePrice> {
+        match self.request_prices().await {
+            Ok(market_prices) => market_prices,
+            Err(error) => {
+                log::warn!(
+                    "Unable to get market prices from {}: {:?}",
+                    self.base_url,
+                    error
+                );
+                Vec::new()
+            }
+        }
+    }
+}
+
+impl CreateMarketService for TickerMarketPrices {
+    fn new() -> Arc<dyn GetMarketCurrencyCodePrice + Send + Sync> {
+        TickerMarketPrices::with_base_url(DEFAULT_TICKERS_BASE_URL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use chrono::{TimeZone, Utc};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Response, Server, StatusCode};
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    const TICKERS_RESPONSE: &str = r#"[
+        {"id":"btc-bitcoin","symbol":"BTC","last_updated":"2021-11-01T10:00:00Z","quotes":{"USD":{"price":61000.5}}},
+        {"id":"eth-ethereum","symbol":"ETH","last_updated":"2021-11-01T10:00:05Z","quotes":{"USD":{"price":4300}}},
+        {"id":"btc-bitcoin-fake","symbol":"BTC","last_updated":"2021-11-01T10:00:00Z","quotes":{"USD":{"price":1}}},
+        {"id":"xyz-no-quote","symbol":"XYZ","quotes":{}}
+    ]"#;
+
+    fn start_stub_server(status: StatusCode, content: &'static str) -> String {
+        let make_service = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |request| async move {
+                let response = match request.uri().path() {
+                    TICKERS_PATH => Response::builder().status(status).body(Body::from(content)),
+                    _ => Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(Body::empty()),
+                };
+                response
+            }))
+        });
+
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+        let base_url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        base_url
+    }
+
+    fn time(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> DateTime {
+        Utc.with_ymd_and_hms(year, month, day, hour, min, sec)
+            .single()
+            .expect("in test")
+    }
+
+    #[test]
+    fn parse_tickers_takes_first_ticker_for_symbol() {
+        let now = time(2021, 11, 2, 0, 0, 0);
+
+        let market_prices = parse_tickers(TICKERS_RESPONSE, now).expect("in test");
+
+        assert_eq!(
+            market_prices,
+            vec![
+                MarketCurrencyCodePrice::new(
+                    "btc".into(),
+                    Some(dec!(61000.5)),
+                    time(2021, 11, 1, 10, 0, 0)
+                ),
+                MarketCurrencyCodePrice::new(
+                    "eth".into(),
+                    Some(dec!(4300)),
+                    time(2021, 11, 1, 10, 0, 5)
+                ),
+                MarketCurrencyCodePrice::new("xyz".into(), None, now),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_prices_from_stub_server() {
+        let base_url = start_stub_server(StatusCode::OK, TICKERS_RESPONSE);
+        let market_service = TickerMarketPrices::with_base_url(base_url);
+
+        let market_prices = market_service.get_market_currency_code_price().await;
+
+        assert_eq!(market_prices.len(), 3);
+        assert_eq!(market_prices[0].currency_code, "btc".into());
+        assert_eq!(market_prices[0].price_usd, Some(dec!(61000.5)));
+    }
+
+    #[tokio::test]
+    async fn no_prices_on_failed_request() {
+        let base_url = start_stub_server(StatusCode::INTERNAL_SERVER_ERROR, "error");
+        let market_service = TickerMarketPrices::with_base_url(base_url);
+
+        let market_prices = market_service.get_market_currency_code_price().await;
+
+        assert!(market_prices.is_empty());
+    }
+}
@@ -1,2 +1,2 @@
-pub(crate) mod market_prices;
+pub mod market_prices;
 pub mod usd_converter;
@@ -4,6 +4,9 @@ pub mod denominator_usd_converter;
 pub mod price_source_chain;
 pub mod price_source_service;
 pub mod price_sources_loader;
+// TODO: remove when PriceSourceService will be started by the engine
+#[allow(dead_code)]
+pub mod price_sources_storage;
 pub(crate) mod prices_calculator;
 pub(crate) mod prices_sources_saver;
 pub mod rebase_price_step;
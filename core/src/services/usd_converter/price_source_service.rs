@@ -20,7 +20,7 @@ use crate::{
     settings::CurrencyPriceSourceSettings,
 };
 
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use futures::FutureExt;
 use itertools::Itertools;
 use mmb_utils::infrastructure::WithExpect;
@@ -98,7 +98,7 @@ impl PriceSourceEventLoop {
                         _ => continue,
                     }
                 }
-                _ = cancellation_token.when_cancelled() => bail!("main_loop has been stopped by CancellationToken"),
+                _ = cancellation_token.when_cancelled() => return Ok(()),
             };
         }
     }
@@ -185,16 +185,18 @@ impl PriceSourceService {
         rx_core: broadcast::Receiver<ExchangeEvent>,
         cancellation_token: CancellationToken,
     ) {
+        let convert_currency_notification_receiver = self
+            .convert_currency_notification_receiver
+            .lock()
+            .take()
+            .expect(
+                "Failed to run PriceSourceEventLoop convert_currency_notification_receiver is none",
+            );
         PriceSourceEventLoop::run(
             self.price_source_chains.values().cloned().collect_vec(),
             price_sources_saver,
             rx_core,
-            self.convert_currency_notification_receiver
-                .lock()
-                .take()
-                .expect(
-                "Failed to run PriceSourceEventLoop convert_currency_notification_receiver is none",
-            ),
+            convert_currency_notification_receiver,
             cancellation_token,
         )
         .await;
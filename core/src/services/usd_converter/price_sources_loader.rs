@@ -1,44 +1,44 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use mmb_utils::{cancellation_token::CancellationToken, DateTime};
 
-use crate::{exchanges::common::TradePlace, misc::price_by_order_side::PriceByOrderSide};
+use crate::{
+    exchanges::common::TradePlace, misc::price_by_order_side::PriceByOrderSide,
+    services::usd_converter::price_sources_storage::PriceSourcesStorage,
+};
 
 pub struct PriceSourcesLoader {
-    // TODO: fix when DatabaseManager will be added
-//database_manager: DatabaseManager
+    storage: Option<Arc<dyn PriceSourcesStorage>>,
 }
 
 impl PriceSourcesLoader {
-    pub fn new(//database_manager: DatabaseManager
-    ) -> Self {
-        Self{
-            //database_manager: DatabaseManager
-        }
+    /// Without `storage` there are no historical prices
+    pub fn new(storage: Option<Arc<dyn PriceSourcesStorage>>) -> Self {
+        Self { storage }
     }
 
+    /// Latest prices saved by `PriceSourcesSaver` not later than `save_time` for every trade place
     pub async fn load(
         &self,
-        _save_time: DateTime,
-        _cancellation_token: CancellationToken,
+        save_time: DateTime,
+        cancellation_token: CancellationToken,
     ) -> Option<HashMap<TradePlace, PriceByOrderSide>> {
-        //     const string sqlQuery =
-        //         "SELECT a.* FROM public.\"PriceSources\" a " +
-        //         "JOIN ( " +
-        //         "SELECT \"ExchangeName\", \"CurrencyCodePair\", max(\"DateTime\") \"DateTime\" " +
-        //         "FROM public.\"PriceSources\" " +
-        //         "WHERE \"DateTime\" <= {0} " +
-        //         "GROUP BY \"ExchangeName\", \"CurrencyCodePair\" " +
-        //         ") b ON a.\"ExchangeName\" = b.\"ExchangeName\" AND a.\"CurrencyCodePair\" = b.\"CurrencyCodePair\" AND a.\"DateTime\" = b.\"DateTime\"";
-
-        //     await using var session = _databaseManager.Sql;
-        //     return await session.Set<PriceSourceModel>()
-        //         .FromSqlRaw(sqlQuery, dateTime)
-        //         .ToDictionaryAsync(
-        //             x => new ExchangeNameSymbol(x.ExchangeName, x.CurrencyCodePair),
-        //             x => new PricesBySide(x.Ask, x.Bid),
-        //             cancellationToken);
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return Some(HashMap::new()),
+        };
 
-        Some(HashMap::new())
+        match storage.load(save_time, cancellation_token).await {
+            Ok(prices) => prices,
+            Err(error) => {
+                log::error!(
+                    "Unable to load price sources for {}: {:?}",
+                    save_time,
+                    error
+                );
+                None
+            }
+        }
     }
 }
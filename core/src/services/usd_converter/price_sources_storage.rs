@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use futures::FutureExt;
+use mmb_utils::{cancellation_token::CancellationToken, DateTime};
+use rust_decimal::Decimal;
+use tokio::sync::mpsc;
+use tokio_postgres::types::ToSql;
+
+use crate::{
+    data_recorder::{
+        data_recorder::DataRecorder,
+        recorded_event::RecordedEvent,
+        storage::{list_recorded_files, recorded_file_start_time, RecordedFileReader},
+    },
+    database::database_manager::DatabaseManager,
+    exchanges::common::{CurrencyPair, TradePlace},
+    infrastructure::spawn_future,
+    misc::{price_by_order_side::PriceByOrderSide, price_source_model::PriceSourceModel},
+};
+
+const INSERT_PRICE_SOURCE: &str = r#"
+    INSERT INTO "PriceSources" ("ExchangeName", "CurrencyCodePair", "DateTime", "Bid", "Ask")
+    VALUES ($1, $2, $3, $4, $5)"#;
+
+const SELECT_LATEST_PRICE_SOURCES: &str = r#"
+    SELECT a."ExchangeName", a."CurrencyCodePair", a."Bid", a."Ask" FROM "PriceSources" a
+    JOIN (
+        SELECT "ExchangeName", "CurrencyCodePair", max("DateTime") "DateTime"
+        FROM "PriceSources"
+        WHERE "DateTime" <= $1
+        GROUP BY "ExchangeName", "CurrencyCodePair"
+    ) b ON a."ExchangeName" = b."ExchangeName"
+        AND a."CurrencyCodePair" = b."CurrencyCodePair"
+        AND a."DateTime" = b."DateTime""#;
+
+/// Persistent storage of prices used by `PriceSourceService` for converting amounts in the past
+#[async_trait]
+pub trait PriceSourcesStorage: Send + Sync {
+    /// Queues prices for saving without waiting until they are written
+    fn save(&self, price_source: PriceSourceModel);
+
+    /// Latest prices saved not later than `save_time` for every trade place.
+    /// Returns `None` if loading was cancelled
+    async fn load(
+        &self,
+        save_time: DateTime,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<HashMap<TradePlace, PriceByOrderSide>>>;
+}
+
+/// Prefers the database if it is configured, otherwise falls back to files of `DataRecorder`.
+/// Without both of them prices aren't persisted
+pub fn create_price_sources_storage(
+    database_manager: Option<Arc<DatabaseManager>>,
+    data_recorder: Option<Arc<DataRecorder>>,
+) -> Option<Arc<dyn PriceSourcesStorage>> {
+    match (database_manager, data_recorder) {
+        (Some(database_manager), _) => Some(DatabasePriceSourcesStorage::new(database_manager)),
+        (None, Some(data_recorder)) => Some(Arc::new(RecordedPriceSourcesStorage::new(
+            data_recorder.directory().clone(),
+            Some(data_recorder),
+        ))),
+        (None, None) => None,
+    }
+}
+
+/// Embedded storage which keeps prices in recorded files of `DataRecorder`
+pub struct RecordedPriceSourcesStorage {
+    directory: PathBuf,
+    data_recorder: Option<Arc<DataRecorder>>,
+}
+
+impl RecordedPriceSourcesStorage {
+    /// `directory` should be the directory of `DataRecorder`.
+    /// Saved prices are ignored if `data_recorder` isn't specified
+    pub fn new(directory: PathBuf, data_recorder: Option<Arc<DataRecorder>>) -> Self {
+        Self {
+            directory,
+            data_recorder,
+        }
+    }
+}
+
+#[async_trait]
+impl PriceSourcesStorage for RecordedPriceSourcesStorage {
+    fn save(&self, price_source: PriceSourceModel) {
+        if let Some(data_recorder) = &self.data_recorder {
+            data_recorder.save(RecordedEvent::PriceSource(price_source));
+        }
+    }
+
+    async fn load(
+        &self,
+        save_time: DateTime,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<HashMap<TradePlace, PriceByOrderSide>>> {
+        // reading of recorded files is blocking, so it shouldn't stall the async runtime
+        let directory = self.directory.clone();
+        tokio::task::spawn_blocking(move || {
+            load_recorded_price_sources(&directory, save_time, cancellation_token).with_context(
+                || format!("Unable to load price sources from {}", directory.display()),
+            )
+        })
+        .await
+        .context("Loading of recorded price sources panicked")?
+    }
+}
+
+fn load_recorded_price_sources(
+    directory: &Path,
+    save_time: DateTime,
+    cancellation_token: CancellationToken,
+) -> Result<Option<HashMap<TradePlace, PriceByOrderSide>>> {
+    let mut prices = HashMap::new();
+    if !directory.exists() {
+        return Ok(Some(prices));
+    }
+
+    for path in list_recorded_files(directory)? {
+        if cancellation_token.is_cancellation_requested() {
+            return Ok(None);
+        }
+
+        if recorded_file_start_time(&path).map_or(false, |start_time| start_time > save_time) {
+            break;
+        }
+
+        for event in RecordedFileReader::open(&path)? {
+            let price_source = match event? {
+                RecordedEvent::PriceSource(price_source) => price_source,
+                _ => continue,
+            };
+
+            if price_source.init_time > save_time {
+                continue;
+            }
+
+            // events are written in chronological order, so later prices replace earlier ones
+            let _ = prices.insert(
+                TradePlace::new(price_source.exchange_id, price_source.currency_pair),
+                PriceByOrderSide::new(price_source.bid, price_source.ask),
+            );
+        }
+    }
+
+    Ok(Some(prices))
+}
+
+/// Storage which keeps prices in `PriceSources` table of PostgreSQL database
+pub struct DatabasePriceSourcesStorage {
+    database_manager: Arc<DatabaseManager>,
+    price_sources_sender: mpsc::UnboundedSender<PriceSourceModel>,
+}
+
+impl DatabasePriceSourcesStorage {
+    pub fn new(database_manager: Arc<DatabaseManager>) -> Arc<Self> {
+        let (price_sources_sender, price_sources_receiver) = mpsc::unbounded_channel();
+
+        let action = insert_price_sources(database_manager.clone(), price_sources_receiver);
+        let _ = spawn_future("Save price sources to database", false, action.boxed());
+
+        Arc::new(Self {
+            database_manager,
+            price_sources_sender,
+        })
+    }
+}
+
+#[async_trait]
+impl PriceSourcesStorage for DatabasePriceSourcesStorage {
+    fn save(&self, price_source: PriceSourceModel) {
+        if self.price_sources_sender.send(price_source).is_err() {
+            log::warn!("Unable to save price source because saving to database is stopped");
+        }
+    }
+
+    async fn load(
+        &self,
+        save_time: DateTime,
+        cancellation_token: CancellationToken,
+    ) -> Result<Option<HashMap<TradePlace, PriceByOrderSide>>> {
+        let params: [&(dyn ToSql + Sync); 1] = [&save_time];
+        let query = self
+            .database_manager
+            .client()
+            .query(SELECT_LATEST_PRICE_SOURCES, &params);
+        let rows = tokio::select! {
+            rows = query => {
+                rows.context("Unable to load price sources from database")?
+            }
+            _ = cancellation_token.when_cancelled() => return Ok(None),
+        };
+
+        let mut prices = HashMap::new();
+        for row in rows {
+            let exchange_name: &str = row.try_get("ExchangeName")?;
+            let currency_code_pair: &str = row.try_get("CurrencyCodePair")?;
+            let bid: Option<Decimal> = row.try_get("Bid")?;
+            let ask: Option<Decimal> = row.try_get("Ask")?;
+
+            let _ = prices.insert(
+                TradePlace::new(
+                    exchange_name.into(),
+                    parse_currency_pair(currency_code_pair)?,
+                ),
+                PriceByOrderSide::new(bid, ask),
+            );
+        }
+
+        Ok(Some(prices))
+    }
+}
+
+async fn insert_price_sources(
+    database_manager: Arc<DatabaseManager>,
+    mut price_sources_receiver: mpsc::UnboundedReceiver<PriceSourceModel>,
+) -> Result<()> {
+    while let Some(price_source) = price_sources_receiver.recv().await {
+        let result = database_manager
+            .client()
+            .execute(
+                INSERT_PRICE_SOURCE,
+                &[
+                    &price_source.exchange_id.as_str(),
+                    &price_source.currency_pair.as_str(),
+                    &price_source.init_time,
+                    &price_source.bid,
+                    &price_source.ask,
+                ],
+            )
+            .await;
+
+        if let Err(error) = result {
+            log::error!(
+                "Unable to save price source {:?} to database: {:?}",
+                price_source,
+                error
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Currency pair is saved in unified format `base/quote`
+fn parse_currency_pair(currency_code_pair: &str) -> Result<CurrencyPair> {
+    let (base, quote) = currency_code_pair
+        .split_once('/')
+        .with_context(|| format!("Invalid currency pair {} in database", currency_code_pair))?;
+
+    Ok(CurrencyPair::from_codes(base.into(), quote.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_recorder::data_recorder::DataRecorderSettings;
+    use crate::data_recorder::storage::tests::test_directory;
+    use crate::database::database_manager::tests::test_database_settings;
+    use crate::lifecycle::trading_engine::Service;
+    use chrono::{Duration, TimeZone, Utc};
+    use rust_decimal_macros::dec;
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    fn price_source(exchange_name: &str, init_time: DateTime, bid: Decimal) -> PriceSourceModel {
+        PriceSourceModel::new(
+            init_time,
+            exchange_name.into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            Some(bid),
+            Some(bid + dec!(1)),
+        )
+    }
+
+    fn trade_place(exchange_name: &str) -> TradePlace {
+        TradePlace::new(
+            exchange_name.into(),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+        )
+    }
+
+    #[tokio::test]
+    async fn load_latest_recorded_prices_before_save_time() {
+        let directory = test_directory("price_sources_storage");
+        let (_events_sender, events_receiver) = broadcast::channel(10);
+        let cancellation_token = CancellationToken::new();
+        let data_recorder = DataRecorder::start(
+            &DataRecorderSettings {
+                directory: directory.clone(),
+                rotation_period_minutes: 60,
+            },
+            events_receiver,
+            cancellation_token.clone(),
+        )
+        .expect("in test");
+
+        let storage =
+            create_price_sources_storage(None, Some(data_recorder.clone())).expect("in test");
+        let start = Utc
+            .with_ymd_and_hms(2021, 9, 20, 10, 0, 0)
+            .single()
+            .expect("in test");
+        for (minutes, bid) in [(0, dec!(1)), (30, dec!(2)), (90, dec!(3))] {
+            storage.save(price_source(
+                "Binance",
+                start + Duration::minutes(minutes),
+                bid,
+            ));
+        }
+
+        cancellation_token.cancel();
+        data_recorder
+            .graceful_shutdown()
+            .expect("in test")
+            .await
+            .expect("in test")
+            .expect("in test");
+
+        let prices = storage
+            .load(start + Duration::minutes(60), CancellationToken::new())
+            .await
+            .expect("in test")
+            .expect("in test");
+        let _ = std::fs::remove_dir_all(&directory);
+
+        assert_eq!(prices.len(), 1);
+        assert_eq!(
+            prices[&trade_place("Binance")],
+            PriceByOrderSide::new(Some(dec!(2)), Some(dec!(3)))
+        );
+    }
+
+    #[ignore] // Requires database specified by MMB_TEST_DATABASE_URL
+    #[tokio::test]
+    async fn load_latest_database_prices_before_save_time() {
+        let settings = test_database_settings();
+        let database_manager = DatabaseManager::connect(&settings).await.expect("in test");
+        let storage =
+            create_price_sources_storage(Some(database_manager.clone()), None).expect("in test");
+
+        // unique exchange name isolates the test from data of previous runs
+        let exchange_name = format!("Test{}", &Uuid::new_v4().to_simple().to_string()[..8]);
+        let start = Utc
+            .with_ymd_and_hms(2021, 9, 20, 10, 0, 0)
+            .single()
+            .expect("in test");
+        for (minutes, bid) in [(0, dec!(1)), (30, dec!(2)), (90, dec!(3))] {
+            storage.save(price_source(
+                &exchange_name,
+                start + Duration::minutes(minutes),
+                bid,
+            ));
+        }
+
+        // saving is asynchronous, so wait until all prices are inserted
+        for _ in 0..50 {
+            let count: i64 = database_manager
+                .client()
+                .query_one(
+                    r#"SELECT count(*) FROM "PriceSources" WHERE "ExchangeName" = $1"#,
+                    &[&exchange_name],
+                )
+                .await
+                .expect("in test")
+                .get(0);
+            if count == 3 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        let prices = storage
+            .load(start + Duration::minutes(60), CancellationToken::new())
+            .await
+            .expect("in test")
+            .expect("in test");
+
+        assert_eq!(
+            prices[&trade_place(&exchange_name)],
+            PriceByOrderSide::new(Some(dec!(2)), Some(dec!(3)))
+        );
+    }
+}
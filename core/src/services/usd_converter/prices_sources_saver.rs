@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use mockall_double::double;
 
 #[double]
@@ -6,29 +8,32 @@ use crate::misc::time::time_manager;
 use crate::{
     exchanges::common::TradePlace,
     misc::{price_by_order_side::PriceByOrderSide, price_source_model::PriceSourceModel},
+    services::usd_converter::price_sources_storage::PriceSourcesStorage,
 };
 
 pub struct PriceSourcesSaver {
-    // TODO: implement when DataRecorder will be added
-// data_recorder: DataRecorder;
+    storage: Option<Arc<dyn PriceSourcesStorage>>,
 }
 
 impl PriceSourcesSaver {
-    pub fn new(// data_recorder: DataRecorder
-    ) -> Self {
-        Self{
-            // data_recorder
-        }
+    /// Prices aren't persisted if `storage` isn't specified
+    pub fn new(storage: Option<Arc<dyn PriceSourcesStorage>>) -> Self {
+        Self { storage }
     }
 
     pub fn save(&mut self, trade_place: TradePlace, prices: PriceByOrderSide) {
-        let _prices_source = PriceSourceModel::new(
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return,
+        };
+
+        let price_source = PriceSourceModel::new(
             time_manager::now(),
             trade_place.exchange_id,
             trade_place.currency_pair,
             prices.top_bid,
             prices.top_ask,
         );
-        //     _dataRecorder.Save(priceSource);
+        storage.save(price_source);
     }
 }
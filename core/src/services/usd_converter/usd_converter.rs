@@ -6,15 +6,48 @@ use mmb_utils::{cancellation_token::CancellationToken, impl_mock_initializer};
 #[cfg(test)]
 use mockall::automock;
 
+use serde::{Deserialize, Serialize};
+
 use crate::exchanges::common::{Amount, CurrencyCode};
+use crate::services::market_prices::ticker_market_prices::DEFAULT_TICKERS_BASE_URL;
 
 use super::{
-    denominator_usd_converter::DenominatorUsdConverter, price_source_service::PriceSourceService,
-    usd_denominator::UsdDenominator,
+    denominator_usd_converter::DenominatorUsdConverter,
+    price_source_service::PriceSourceService,
+    usd_denominator::{UsdDenominator, UsdDenominatorSettings},
 };
 
+fn default_tickers_base_url() -> String {
+    DEFAULT_TICKERS_BASE_URL.to_owned()
+}
+
+/// Market service which provides USD prices for `UsdDenominator`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum MarketPricesSettings {
+    /// Prices are calculated from order books of the engine exchanges.
+    /// Default USD-like currencies are used if `usd_currency_codes` is empty
+    OrderBooks {
+        #[serde(default)]
+        usd_currency_codes: Vec<CurrencyCode>,
+    },
+    /// Prices are requested from tickers REST API
+    Tickers {
+        #[serde(default = "default_tickers_base_url")]
+        base_url: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UsdConverterSettings {
+    pub market_prices: MarketPricesSettings,
+    #[serde(default)]
+    pub usd_denominator: UsdDenominatorSettings,
+}
+
+/// Converts amounts to USD by price sources if they are configured for the currency, otherwise by market prices
 pub struct UsdConverter {
-    price_source_service: PriceSourceService,
+    price_source_service: Option<Arc<PriceSourceService>>,
     usd_currency_code: CurrencyCode,
     denominator_usd_converter: DenominatorUsdConverter,
 }
@@ -23,7 +56,7 @@ pub struct UsdConverter {
 impl UsdConverter {
     pub fn new(
         currencies: &Vec<CurrencyCode>,
-        price_source_service: PriceSourceService,
+        price_source_service: Option<Arc<PriceSourceService>>,
         usd_denominator: Arc<UsdDenominator>,
     ) -> Self {
         let usd = "USD".into();
@@ -49,30 +82,31 @@ impl UsdConverter {
             return Some(src_amount);
         }
 
-        match self
-            .price_source_service
-            .convert_amount(
-                from_currency_code,
-                self.usd_currency_code,
-                src_amount,
-                cancellation_token,
-            )
-            .await
-        {
-            Ok(usd_amount) => {
-                if usd_amount.is_some() {
-                    return usd_amount;
+        if let Some(price_source_service) = &self.price_source_service {
+            match price_source_service
+                .convert_amount(
+                    from_currency_code,
+                    self.usd_currency_code,
+                    src_amount,
+                    cancellation_token,
+                )
+                .await
+            {
+                Ok(usd_amount) => {
+                    if usd_amount.is_some() {
+                        return usd_amount;
+                    }
                 }
+                Err(error) => log::warn!(
+                    "Failed to calculate price {} -> {}: {:?}",
+                    from_currency_code,
+                    self.usd_currency_code,
+                    error
+                ),
             }
-            Err(error) => log::warn!(
-                "Failed to calculate price {} -> {}: {:?}",
-                from_currency_code,
-                self.usd_currency_code,
-                error
-            ),
-        }
 
-        log::warn!("Can't calculate USD price using PriceSourceService => trying to use UsdDenominator ({})", from_currency_code);
+            log::warn!("Can't calculate USD price using PriceSourceService => trying to use UsdDenominator ({})", from_currency_code);
+        }
 
         self.denominator_usd_converter
             .calculate_using_denominator(from_currency_code, src_amount)
@@ -2,19 +2,42 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use futures::FutureExt;
 use itertools::Itertools;
-use mmb_utils::hashmap;
+use mmb_utils::{hashmap, DateTime};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     exchanges::common::{Amount, CurrencyCode, CurrencyId, Price},
     infrastructure::spawn_by_timer,
     lifecycle::application_manager::ApplicationManager,
-    misc::traits::market_service::{CreateMarketService, GetMarketCurrencyCodePrice},
+    misc::{
+        time::get_current_time,
+        traits::market_service::{CreateMarketService, GetMarketCurrencyCodePrice},
+    },
     services::market_prices::market_currency_code_price::MarketCurrencyCodePrice,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UsdDenominatorSettings {
+    /// Period of requesting market prices from market service
+    pub refresh_period_secs: u64,
+    /// Prices which weren't updated longer than this period are considered stale and aren't used for conversions
+    pub max_price_age_secs: u64,
+}
+
+impl Default for UsdDenominatorSettings {
+    fn default() -> Self {
+        Self {
+            refresh_period_secs: 2 * 60 * 60,
+            max_price_age_secs: 6 * 60 * 60,
+        }
+    }
+}
+
 pub struct UsdDenominator {
     market_service: Arc<dyn GetMarketCurrencyCodePrice + Send + Sync>,
+    settings: UsdDenominatorSettings,
     application_manager: Arc<ApplicationManager>,
     market_prices_by_currency_code: Mutex<HashMap<CurrencyCode, MarketCurrencyCodePrice>>,
     pub price_update_callback: Box<dyn Fn() + Sync + Send>,
@@ -43,12 +66,14 @@ impl UsdDenominator {
 
     fn new(
         market_service: Arc<dyn GetMarketCurrencyCodePrice + Send + Sync>,
+        settings: UsdDenominatorSettings,
         market_prices: Vec<MarketCurrencyCodePrice>,
         auto_refresh_data: bool,
         application_manager: Arc<ApplicationManager>,
     ) -> Arc<Self> {
         let this = Arc::new(Self {
             market_service,
+            settings,
             application_manager: application_manager.clone(),
             market_prices_by_currency_code: Mutex::new(UsdDenominator::create_prices_dictionary(
                 market_prices,
@@ -62,7 +87,7 @@ impl UsdDenominator {
                 move || Self::refresh_data(cloned_this.clone()).boxed(),
                 "UsdDenominator::refresh_data()",
                 Duration::ZERO,
-                Duration::from_secs(7200), // 2 hours
+                Duration::from_secs(settings.refresh_period_secs),
                 true,
             );
         }
@@ -70,13 +95,34 @@ impl UsdDenominator {
         this
     }
 
+    /// Prices missed in the market service response are kept until they become stale
     pub async fn refresh_data(this: Arc<Self>) {
         let market_prices = this.market_service.get_market_currency_code_price().await;
-        *this.market_prices_by_currency_code.lock() =
-            UsdDenominator::create_prices_dictionary(market_prices);
+        if market_prices.is_empty() {
+            log::warn!("Market service returned no prices for UsdDenominator");
+        }
+
+        this.market_prices_by_currency_code
+            .lock()
+            .extend(UsdDenominator::create_prices_dictionary(market_prices));
         (this.price_update_callback)()
     }
 
+    /// Prices are requested from market service in background right after starting
+    pub fn start(
+        market_service: Arc<dyn GetMarketCurrencyCodePrice + Send + Sync>,
+        settings: UsdDenominatorSettings,
+        application_manager: Arc<ApplicationManager>,
+    ) -> Arc<Self> {
+        UsdDenominator::new(
+            market_service,
+            settings,
+            Vec::new(),
+            true,
+            application_manager,
+        )
+    }
+
     pub async fn create_async<T>(
         auto_refresh_data: bool,
         application_manager: Arc<ApplicationManager>,
@@ -84,10 +130,25 @@ impl UsdDenominator {
     where
         T: GetMarketCurrencyCodePrice + CreateMarketService,
     {
-        let service = T::new();
-        let market_prices = service.get_market_currency_code_price().await;
+        UsdDenominator::create_with_market_service(
+            T::new(),
+            UsdDenominatorSettings::default(),
+            auto_refresh_data,
+            application_manager,
+        )
+        .await
+    }
+
+    pub async fn create_with_market_service(
+        market_service: Arc<dyn GetMarketCurrencyCodePrice + Send + Sync>,
+        settings: UsdDenominatorSettings,
+        auto_refresh_data: bool,
+        application_manager: Arc<ApplicationManager>,
+    ) -> Arc<Self> {
+        let market_prices = market_service.get_market_currency_code_price().await;
         UsdDenominator::new(
-            service,
+            market_service,
+            settings,
             market_prices,
             auto_refresh_data,
             application_manager,
@@ -97,6 +158,7 @@ impl UsdDenominator {
     pub fn get_non_refreshing_usd_denominator(&self) -> Arc<Self> {
         UsdDenominator::new(
             self.market_service.clone(),
+            self.settings,
             self.market_prices_by_currency_code
                 .lock()
                 .values()
@@ -111,10 +173,22 @@ impl UsdDenominator {
         hashmap!["IOTA".into() => "MIOTA".into()]
     }
 
+    fn is_actual(&self, market_price: &MarketCurrencyCodePrice, now: DateTime) -> bool {
+        // negative age means that price is received from source with clock ahead of ours
+        (now - market_price.updated_at)
+            .to_std()
+            .map_or(true, |price_age| {
+                price_age <= Duration::from_secs(self.settings.max_price_age_secs)
+            })
+    }
+
+    /// Prices which are stale aren't returned
     pub fn get_all_prices_in_usd(&self) -> HashMap<CurrencyCode, Price> {
+        let now = get_current_time();
         self.market_prices_by_currency_code
             .lock()
             .iter()
+            .filter(|(_, market_price)| self.is_actual(market_price, now))
             .filter_map(|(currency_code, market_currency_code_price)| {
                 market_currency_code_price
                     .price_usd
@@ -123,11 +197,21 @@ impl UsdDenominator {
             .collect()
     }
 
+    /// Returns `None` if there is no price for currency or the price is stale
     pub fn get_price_in_usd(&self, currency_code: CurrencyCode) -> Option<Price> {
-        self.market_prices_by_currency_code
-            .lock()
-            .get(&currency_code)?
-            .price_usd
+        let market_prices = self.market_prices_by_currency_code.lock();
+        let market_price = market_prices.get(&currency_code)?;
+
+        if !self.is_actual(market_price, get_current_time()) {
+            log::warn!(
+                "Price in USD for {} is stale: last update was at {}",
+                currency_code,
+                market_price.updated_at
+            );
+            return None;
+        }
+
+        market_price.price_usd
     }
 
     pub fn usd_to_currency(
@@ -146,3 +230,93 @@ impl UsdDenominator {
         Some(amount_in_base * self.get_price_in_usd(currency_code)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use mmb_utils::cancellation_token::CancellationToken;
+
+    struct MarketServiceStub {
+        market_prices: Mutex<Vec<MarketCurrencyCodePrice>>,
+    }
+
+    #[async_trait]
+    impl GetMarketCurrencyCodePrice for MarketServiceStub {
+        async fn get_market_currency_code_price(&self) -> Vec<MarketCurrencyCodePrice> {
+            self.market_prices.lock().clone()
+        }
+    }
+
+    fn price(currency_code: &str, price_usd: Price, age_hours: i64) -> MarketCurrencyCodePrice {
+        MarketCurrencyCodePrice::new(
+            currency_code.into(),
+            Some(price_usd),
+            get_current_time() - chrono::Duration::hours(age_hours),
+        )
+    }
+
+    async fn create_usd_denominator(
+        market_prices: Vec<MarketCurrencyCodePrice>,
+    ) -> (Arc<MarketServiceStub>, Arc<UsdDenominator>) {
+        let market_service = Arc::new(MarketServiceStub {
+            market_prices: Mutex::new(market_prices),
+        });
+        let usd_denominator = UsdDenominator::create_with_market_service(
+            market_service.clone(),
+            UsdDenominatorSettings::default(),
+            false,
+            ApplicationManager::new(CancellationToken::new()),
+        )
+        .await;
+
+        (market_service, usd_denominator)
+    }
+
+    #[tokio::test]
+    async fn stale_prices_are_not_used() {
+        let (_, usd_denominator) = create_usd_denominator(vec![
+            price("btc", dec!(40000), 1),
+            price("eth", dec!(3000), 7),
+        ])
+        .await;
+
+        assert_eq!(
+            usd_denominator.get_price_in_usd("btc".into()),
+            Some(dec!(40000))
+        );
+        assert_eq!(usd_denominator.get_price_in_usd("eth".into()), None);
+        assert_eq!(
+            usd_denominator.currency_to_usd("btc".into(), dec!(2)),
+            Some(dec!(80000))
+        );
+        assert_eq!(usd_denominator.currency_to_usd("eth".into(), dec!(2)), None);
+        assert_eq!(
+            usd_denominator.get_all_prices_in_usd(),
+            hashmap!["btc".into() => dec!(40000)]
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_prices_missed_in_response() {
+        let (market_service, usd_denominator) = create_usd_denominator(vec![
+            price("btc", dec!(40000), 1),
+            price("eth", dec!(3000), 1),
+        ])
+        .await;
+
+        *market_service.market_prices.lock() = vec![price("btc", dec!(41000), 0)];
+        UsdDenominator::refresh_data(usd_denominator.clone()).await;
+
+        assert_eq!(
+            usd_denominator.get_price_in_usd("btc".into()),
+            Some(dec!(41000))
+        );
+        assert_eq!(
+            usd_denominator.get_price_in_usd("eth".into()),
+            Some(dec!(3000))
+        );
+    }
+}
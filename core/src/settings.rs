@@ -1,10 +1,45 @@
-use crate::exchanges::common::{Amount, CurrencyCode, CurrencyPair, ExchangeAccountId};
+use crate::alerts::alert_bus::AlertsSettings;
+use crate::balance_manager::balance_reconciliation::BalanceReconciliationSettings;
+use crate::data_recorder::data_recorder::DataRecorderSettings;
+use crate::database::database_manager::DatabaseSettings;
+use crate::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, TradePlaceAccount,
+};
+use crate::metrics::metrics_server::MetricsSettings;
+use crate::orders::journal::OrdersJournalSettings;
+use crate::risk::kill_switch::KillSwitchSettings;
+use crate::risk::pre_trade_risk::RiskLimitsSettings;
+use crate::services::usd_converter::usd_converter::UsdConverterSettings;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
 pub trait BaseStrategySettings {
+    /// Exchange account of main trade place of strategy
     fn exchange_account_id(&self) -> ExchangeAccountId;
+    /// Currency pair of main trade place of strategy
     fn currency_pair(&self) -> CurrencyPair;
     fn max_amount(&self) -> Amount;
+
+    /// All trade places where strategy creates orders. Strategy trading on several exchanges
+    /// or currency pairs should override it, by default there is only the main trade place
+    fn trade_places(&self) -> Vec<TradePlaceAccount> {
+        vec![TradePlaceAccount::new(
+            self.exchange_account_id(),
+            self.currency_pair(),
+        )]
+    }
+
+    /// Balance budget of strategy on each of its trade places in amount currency.
+    /// Should be specified if several strategies are trading with the same exchange accounts
+    fn amount_limit(&self) -> Option<Amount> {
+        None
+    }
+
+    /// Count of price levels quoted by strategy on each side of each of its trade places.
+    /// Strategy should return the same count of estimating trade cycles for every side
+    fn price_slots_count(&self) -> usize {
+        1
+    }
 }
 
 /// Application settings
@@ -21,9 +56,73 @@ where
 
 #[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CoreSettings {
+    /// Events are recorded only if the section is specified
+    #[serde(default)]
+    pub data_recorder: Option<DataRecorderSettings>,
+    /// Orders are restored after restart only if the section is specified
+    #[serde(default)]
+    pub orders_journal: Option<OrdersJournalSettings>,
+    /// Prices for converting amounts in the past are saved to the database if the section is specified,
+    /// otherwise they are saved by `DataRecorder`
+    #[serde(default)]
+    pub database: Option<DatabaseSettings>,
+    /// Metrics are exposed for Prometheus only if the section is specified
+    #[serde(default)]
+    pub metrics: Option<MetricsSettings>,
+    /// Orders of strategies are checked by pre-trade risk limits only if the section is specified
+    #[serde(default)]
+    pub risk_limits: Option<RiskLimitsSettings>,
+    /// Spot balances are flattened by kill switch only if the section is specified
+    #[serde(default)]
+    pub kill_switch: Option<KillSwitchSettings>,
+    /// Alerts are sent to sinks only if the section is specified, otherwise they are only logged
+    #[serde(default)]
+    pub alerts: Option<AlertsSettings>,
+    /// Balances of `BalanceManager` are periodically compared with exchange balances only if the section is specified
+    #[serde(default)]
+    pub balance_reconciliation: Option<BalanceReconciliationSettings>,
+    /// Prices for converting amounts between currencies are tracked and saved only if price sources are specified
+    #[serde(default)]
+    pub price_sources: Vec<CurrencyPriceSourceSettings>,
+    /// Amounts are converted to USD by price sources and market prices only if the section is specified
+    #[serde(default)]
+    pub usd_converter: Option<UsdConverterSettings>,
+    /// Profit and loss of fills is checked by stopper conditions only if the section is specified.
+    /// Requires `usd_converter` section
+    #[serde(default)]
+    pub profit_loss_stopper: Option<ProfitLossStopperSettings>,
     pub exchanges: Vec<ExchangeSettings>,
 }
 
+impl CoreSettings {
+    /// Checks sections depending on each other, so misconfiguration is reported before engine is started
+    pub fn validate(&self) -> Result<()> {
+        if let Some(stopper_settings) = &self.profit_loss_stopper {
+            if self.usd_converter.is_none() {
+                bail!("Profit loss stopper requires 'usd_converter' section in settings");
+            }
+
+            if stopper_settings.conditions.is_empty() {
+                bail!("Profit loss stopper requires at least one condition in settings");
+            }
+
+            let exchange_account_id = stopper_settings.exchange_account_id;
+            if !self
+                .exchanges
+                .iter()
+                .any(|x| x.exchange_account_id == exchange_account_id)
+            {
+                bail!(
+                    "Exchange {} of profit loss stopper isn't specified in settings",
+                    exchange_account_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CurrencyPairSetting {
     pub base: CurrencyCode,
@@ -42,6 +141,9 @@ pub struct ExchangeSettings {
     pub api_key: String,
     pub secret_key: String,
     pub is_margin_trading: bool,
+    /// Derivatives API of exchange is used instead of spot one, e.g. USD-M futures on Binance.
+    /// If it isn't set, value of `is_margin_trading` is used like before this setting was added
+    pub is_futures: Option<bool>,
     pub request_trades: bool,
     pub is_reducing_market_data: Option<bool>,
     pub subscribe_to_market_data: bool,
@@ -50,6 +152,10 @@ pub struct ExchangeSettings {
 }
 
 impl ExchangeSettings {
+    pub fn is_futures(&self) -> bool {
+        self.is_futures.unwrap_or(self.is_margin_trading)
+    }
+
     // only for tests
     pub fn new_short(
         exchange_account_id: ExchangeAccountId,
@@ -62,6 +168,7 @@ impl ExchangeSettings {
             api_key,
             secret_key,
             is_margin_trading,
+            is_futures: None,
             request_trades: false,
             websocket_channels: vec![],
             currency_pairs: None,
@@ -78,6 +185,7 @@ impl Default for ExchangeSettings {
             api_key: "".to_string(),
             secret_key: "".to_string(),
             is_margin_trading: false,
+            is_futures: None,
             request_trades: false,
             websocket_channels: vec![],
             currency_pairs: None,
@@ -87,6 +195,7 @@ impl Default for ExchangeSettings {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct CurrencyPriceSourceSettings {
     pub start_currency_code: CurrencyCode,
     pub end_currency_code: CurrencyCode,
@@ -108,22 +217,167 @@ impl CurrencyPriceSourceSettings {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ExchangeIdCurrencyPairSettings {
     pub exchange_account_id: ExchangeAccountId,
     pub currency_pair: CurrencyPair,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum TimePeriodKind {
+    Minute,
     Hour,
     Day,
 }
 
+/// How USD change over the period is compared with `StopperCondition::limit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StopperLimitKind {
+    /// Loss over the period exceeds the limit
+    Loss,
+    /// USD change over the period fell from its maximum (high-water mark) more than the limit
+    Drawdown,
+}
+
+impl Default for StopperLimitKind {
+    fn default() -> Self {
+        StopperLimitKind::Loss
+    }
+}
+
+/// Action taken by stopper when its condition is exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StopperAction {
+    /// Block the exchange account, so strategies can't create orders
+    Block,
+    /// Cancel all opened orders on the exchange account
+    CancelOrders,
+    /// Allow only orders decreasing position on the exchange account
+    ReduceOnly,
+    /// Close active positions on the exchange account
+    ClosePositions,
+    /// Only log that condition is exceeded
+    NotifyOnly,
+}
+
+/// When actions of exceeded stopper condition are reverted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum UnblockPolicy {
+    /// As soon as condition isn't exceeded anymore
+    WhenRecovered,
+    /// When condition isn't exceeded anymore, but not earlier than the specified minutes after exceeding
+    AfterCooldown { minutes: i64 },
+    /// Actions are never reverted, trading engine should be restarted to trade again
+    Never,
+}
+
+impl Default for UnblockPolicy {
+    fn default() -> Self {
+        UnblockPolicy::WhenRecovered
+    }
+}
+
+pub(crate) fn default_stopper_actions() -> Vec<StopperAction> {
+    vec![StopperAction::Block, StopperAction::ClosePositions]
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct StopperCondition {
     pub period_kind: TimePeriodKind,
     pub period_value: i64,
+    /// Limit in USD
     pub limit: Amount,
+    #[serde(default)]
+    pub limit_kind: StopperLimitKind,
+    /// Only balance changes of the currency are taken into account if specified,
+    /// so the condition limits loss on inventory of the currency
+    #[serde(default)]
+    pub currency_code: Option<CurrencyCode>,
+    #[serde(default = "default_stopper_actions")]
+    pub actions: Vec<StopperAction>,
+    #[serde(default)]
+    pub unblock_policy: UnblockPolicy,
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct ProfitLossStopperSettings {
+    /// Actions of exceeded conditions are taken on the exchange account,
+    /// positions are closed by the currency pair
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPair,
     pub conditions: Vec<StopperCondition>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::usd_converter::usd_converter::MarketPricesSettings;
+    use rust_decimal_macros::dec;
+
+    fn exchange_account_id() -> ExchangeAccountId {
+        ExchangeAccountId::new("Binance".into(), 0)
+    }
+
+    fn core_settings_with_stopper() -> CoreSettings {
+        CoreSettings {
+            usd_converter: Some(UsdConverterSettings {
+                market_prices: MarketPricesSettings::OrderBooks {
+                    usd_currency_codes: Vec::new(),
+                },
+                usd_denominator: Default::default(),
+            }),
+            profit_loss_stopper: Some(ProfitLossStopperSettings {
+                exchange_account_id: exchange_account_id(),
+                currency_pair: CurrencyPair::from_codes("btc".into(), "usdt".into()),
+                conditions: vec![StopperCondition {
+                    period_kind: TimePeriodKind::Day,
+                    period_value: 1,
+                    limit: dec!(50),
+                    limit_kind: StopperLimitKind::Loss,
+                    currency_code: None,
+                    actions: default_stopper_actions(),
+                    unblock_policy: UnblockPolicy::WhenRecovered,
+                }],
+            }),
+            exchanges: vec![ExchangeSettings::new_short(
+                exchange_account_id(),
+                "key".to_owned(),
+                "secret".to_owned(),
+                false,
+            )],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid_profit_loss_stopper_settings() {
+        core_settings_with_stopper().validate().expect("in test");
+    }
+
+    #[test]
+    fn profit_loss_stopper_without_usd_converter() {
+        let mut settings = core_settings_with_stopper();
+        settings.usd_converter = None;
+
+        let error = settings.validate().expect_err("in test");
+        assert!(error.to_string().contains("usd_converter"));
+    }
+
+    #[test]
+    fn profit_loss_stopper_without_conditions() {
+        let mut settings = core_settings_with_stopper();
+        if let Some(stopper_settings) = &mut settings.profit_loss_stopper {
+            stopper_settings.conditions.clear();
+        }
+
+        let _ = settings.validate().expect_err("in test");
+    }
+
+    #[test]
+    fn profit_loss_stopper_on_unknown_exchange() {
+        let mut settings = core_settings_with_stopper();
+        settings.exchanges.clear();
+
+        let _ = settings.validate().expect_err("in test");
+    }
+}
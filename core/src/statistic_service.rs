@@ -11,7 +11,7 @@ use tokio::sync::broadcast;
 
 use super::{
     exchanges::{
-        common::{Amount, Price, TradePlaceAccount},
+        common::{Amount, CurrencyCode, ExchangeAccountId, Price, TradePlaceAccount},
         events::ExchangeEvent,
     },
     infrastructure::spawn_future,
@@ -19,14 +19,17 @@ use super::{
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TradePlaceAccountStatistic {
-    opened_orders_count: u64,
-    canceled_orders_count: u64,
-    partially_filled_orders_count: u64,
-    fully_filled_orders_count: u64,
+    pub(crate) opened_orders_count: u64,
+    pub(crate) canceled_orders_count: u64,
+    pub(crate) partially_filled_orders_count: u64,
+    pub(crate) fully_filled_orders_count: u64,
     // Calculated only for completely filled orders
-    summary_filled_amount: Amount,
+    pub(crate) summary_filled_amount: Amount,
     // Calculated only for completely filled orders
-    summary_commission: Amount,
+    pub(crate) summary_commission: Amount,
+    // Orders of strategies which weren't created because of pre-trade risk limits
+    #[serde(default)]
+    pub(crate) risk_rejected_orders_count: u64,
 }
 
 impl TradePlaceAccountStatistic {
@@ -63,15 +66,54 @@ impl TradePlaceAccountStatistic {
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HedgeStatistic {
+    pub hedges_count: u64,
+    /// Hedges which weren't completed after all attempts
+    pub failed_hedges_count: u64,
+    pub hedged_amount: Amount,
+    /// Time from maker fill till the end of hedging
+    pub summary_latency_ms: u64,
+    pub max_latency_ms: u64,
+    /// Loss in quote currency because hedge orders were executed worse than maker fills
+    pub summary_slippage: Amount,
+}
+
+impl HedgeStatistic {
+    fn register_hedge(&mut self, hedged_amount: Amount, latency_ms: u64, slippage: Amount) {
+        self.hedges_count += 1;
+        self.hedged_amount += hedged_amount;
+        self.summary_latency_ms += latency_ms;
+        self.max_latency_ms = self.max_latency_ms.max(latency_ms);
+        self.summary_slippage += slippage;
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceReconciliationStatistic {
+    pub checks_count: u64,
+    /// Checks where currencies of orders in creating or cancelling state were skipped
+    pub skipped_checks_count: u64,
+    /// Checks where drift of some currency exceeded threshold
+    pub drifts_count: u64,
+    /// Exchange balance minus expected balance by currency on the last check
+    pub last_drifts: HashMap<CurrencyCode, Amount>,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct DispositionExecutorStatistic {
-    skipped_events_amount: u64,
+    pub(crate) skipped_events_amount: u64,
 }
 
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub(crate) struct StatisticServiceState {
-    trade_place_stats: RwLock<HashMap<TradePlaceAccount, TradePlaceAccountStatistic>>,
-    disposition_executor_stats: Mutex<DispositionExecutorStatistic>,
+    pub(crate) trade_place_stats: RwLock<HashMap<TradePlaceAccount, TradePlaceAccountStatistic>>,
+    pub(crate) disposition_executor_stats: Mutex<DispositionExecutorStatistic>,
+    // Statistic by trade place where hedge orders are created
+    pub(crate) hedge_stats: RwLock<HashMap<TradePlaceAccount, HedgeStatistic>>,
+    #[serde(default)]
+    pub(crate) balance_reconciliation_stats:
+        RwLock<HashMap<ExchangeAccountId, BalanceReconciliationStatistic>>,
 }
 
 impl StatisticServiceState {
@@ -139,9 +181,65 @@ impl StatisticServiceState {
             .add_summary_commission(commission);
     }
 
+    pub(crate) fn register_risk_rejected_order(&self, trade_place_account: TradePlaceAccount) {
+        self.trade_place_stats
+            .write()
+            .entry(trade_place_account)
+            .or_default()
+            .risk_rejected_orders_count += 1;
+    }
+
     pub(crate) fn register_skipped_event(&self) {
         (*self.disposition_executor_stats.lock()).skipped_events_amount += 1;
     }
+
+    pub(crate) fn register_hedge(
+        &self,
+        trade_place_account: TradePlaceAccount,
+        hedged_amount: Amount,
+        latency_ms: u64,
+        slippage: Amount,
+    ) {
+        self.hedge_stats
+            .write()
+            .entry(trade_place_account)
+            .or_default()
+            .register_hedge(hedged_amount, latency_ms, slippage);
+    }
+
+    pub(crate) fn register_failed_hedge(&self, trade_place_account: TradePlaceAccount) {
+        self.hedge_stats
+            .write()
+            .entry(trade_place_account)
+            .or_default()
+            .failed_hedges_count += 1;
+    }
+
+    pub(crate) fn register_balance_reconciliation(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        drifts: HashMap<CurrencyCode, Amount>,
+        is_drift_exceeded: bool,
+    ) {
+        let mut stats = self.balance_reconciliation_stats.write();
+        let statistic = stats.entry(exchange_account_id).or_default();
+        statistic.checks_count += 1;
+        if is_drift_exceeded {
+            statistic.drifts_count += 1;
+        }
+        statistic.last_drifts = drifts;
+    }
+
+    pub(crate) fn register_skipped_balance_reconciliation(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) {
+        self.balance_reconciliation_stats
+            .write()
+            .entry(exchange_account_id)
+            .or_default()
+            .skipped_checks_count += 1;
+    }
 }
 
 #[derive(Default, Debug)]
@@ -221,9 +319,76 @@ impl StatisticService {
         }
     }
 
+    pub(crate) fn register_risk_rejected_order(&self, trade_place_account: TradePlaceAccount) {
+        self.statistic_service_state
+            .register_risk_rejected_order(trade_place_account);
+    }
+
     pub(crate) fn register_skipped_event(&self) {
         self.statistic_service_state.register_skipped_event();
     }
+
+    pub(crate) fn register_hedge(
+        &self,
+        trade_place_account: TradePlaceAccount,
+        hedged_amount: Amount,
+        latency_ms: u64,
+        slippage: Amount,
+    ) {
+        self.statistic_service_state.register_hedge(
+            trade_place_account,
+            hedged_amount,
+            latency_ms,
+            slippage,
+        );
+    }
+
+    pub(crate) fn register_failed_hedge(&self, trade_place_account: TradePlaceAccount) {
+        self.statistic_service_state
+            .register_failed_hedge(trade_place_account);
+    }
+
+    pub(crate) fn register_balance_reconciliation(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        drifts: HashMap<CurrencyCode, Amount>,
+        is_drift_exceeded: bool,
+    ) {
+        self.statistic_service_state
+            .register_balance_reconciliation(exchange_account_id, drifts, is_drift_exceeded);
+    }
+
+    pub(crate) fn register_skipped_balance_reconciliation(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) {
+        self.statistic_service_state
+            .register_skipped_balance_reconciliation(exchange_account_id);
+    }
+
+    /// Statistic of balance reconciliation for the exchange
+    pub fn get_balance_reconciliation_statistic(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+    ) -> Option<BalanceReconciliationStatistic> {
+        self.statistic_service_state
+            .balance_reconciliation_stats
+            .read()
+            .get(&exchange_account_id)
+            .cloned()
+    }
+
+    /// Statistic of hedge orders created on the trade place
+    pub fn get_hedge_statistic(
+        &self,
+        trade_place_account: TradePlaceAccount,
+    ) -> Option<HedgeStatistic> {
+        self.statistic_service_state
+            .hedge_stats
+            .read()
+            .get(&trade_place_account)
+            .cloned()
+    }
 }
 
 pub struct StatisticEventHandler {
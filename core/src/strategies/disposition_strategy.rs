@@ -6,6 +6,7 @@ use rust_decimal::Decimal;
 
 use crate::disposition_execution::{PriceSlot, TradingContext};
 use crate::exchanges::common::ExchangeAccountId;
+use crate::exchanges::events::TradesEvent;
 use crate::explanation::Explanation;
 use crate::order_book::local_snapshot_service::LocalSnapshotsService;
 use crate::orders::order::OrderSnapshot;
@@ -29,5 +30,9 @@ pub trait DispositionStrategy: Send + Sync + 'static {
         cancellation_token: CancellationToken,
     ) -> Result<()>;
 
+    /// Called for every trades event received by trading engine before trading context recalculation.
+    /// Strategies which don't need trades stream can ignore it
+    fn handle_trades_event(&mut self, _trades_event: &TradesEvent) {}
+
     fn configuration_descriptor(&self) -> ConfigurationDescriptor;
 }
@@ -0,0 +1,504 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::FutureExt;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::nothing_to_do;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeAccountId, Price, TradePlaceAccount,
+};
+use crate::exchanges::general::commission::Percent;
+use crate::exchanges::general::exchange::Exchange;
+use crate::exchanges::general::symbol::{Round, Symbol};
+use crate::infrastructure::spawn_future;
+use crate::lifecycle::trading_engine::EngineContext;
+use crate::math::ConvertPercentToRate;
+use crate::misc::time::get_current_time;
+use crate::orders::order::{
+    ClientOrderId, OrderCreating, OrderExecutionType, OrderHeader, OrderOptions, OrderSide,
+    OrderSnapshot, OrderType, TimeInForce,
+};
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+
+static HEDGER: &str = "Hedger";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct HedgerSettings {
+    /// Exchange account where maker fills are offset by taker orders on the same currency pair
+    pub exchange_account_id: ExchangeAccountId,
+    /// Max price deviation of hedge order from price of maker fill
+    pub max_slippage: Percent,
+    /// Allowed slippage is increased by this step on every attempt until `max_slippage` is reached
+    pub slippage_step: Percent,
+    pub max_attempts: u32,
+    /// Delay before the next attempt after failed hedge order, it grows linearly with count of failed attempts
+    pub retry_delay_ms: u64,
+    /// Hedge order is cancelled explicitly and counted as failed attempt if it isn't finished
+    /// by exchange during this timeout, e.g. because its finish event was lost
+    pub order_finish_timeout_ms: u64,
+}
+
+/// Hedge of one maker fill
+#[derive(Debug, Clone, Copy)]
+struct Hedge {
+    currency_pair: CurrencyPair,
+    /// Side of hedge order, it's opposite to side of maker order
+    side: OrderSide,
+    amount: Amount,
+    maker_price: Price,
+    maker_filled_at: DateTime,
+}
+
+#[derive(Default)]
+struct HedgerState {
+    /// Signed amount of maker fills in amount currency which isn't offset by hedge orders yet
+    unhedged_exposure: HashMap<CurrencyCode, Amount>,
+    /// Amount of maker orders which was passed to hedging already
+    handled_filled_amount: HashMap<ClientOrderId, Amount>,
+}
+
+/// Offsets maker fills of strategy with taker orders on hedge exchange.
+/// Hedge orders are IOC limit orders priced at the worst allowed price, so they are executed immediately
+/// against the hedge exchange order book and their unfilled rest is cancelled by exchange
+pub struct Hedger {
+    settings: HedgerSettings,
+    configuration_descriptor: ConfigurationDescriptor,
+    engine_context: Arc<EngineContext>,
+    state: Mutex<HedgerState>,
+}
+
+impl Hedger {
+    pub fn new(
+        settings: HedgerSettings,
+        configuration_descriptor: ConfigurationDescriptor,
+        engine_context: Arc<EngineContext>,
+    ) -> Arc<Self> {
+        Arc::new(Hedger {
+            settings,
+            configuration_descriptor,
+            engine_context,
+            state: Default::default(),
+        })
+    }
+
+    /// Starts hedging of the part of maker order which was filled since previous call.
+    /// Should be called from `DispositionStrategy::handle_order_fill`
+    pub fn handle_order_fill(
+        self: &Arc<Self>,
+        filled_order: &OrderSnapshot,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let hedge = match self.register_maker_fill(filled_order)? {
+            None => return Ok(()),
+            Some(hedge) => hedge,
+        };
+
+        let action = self.clone().hedge(hedge, cancellation_token);
+        let _ = spawn_future("Hedger::hedge", false, action.boxed());
+
+        Ok(())
+    }
+
+    /// Signed amount in `currency_code` which was filled by maker orders, but isn't offset by hedge orders yet.
+    /// Positive exposure means that maker orders bought more than hedge orders sold
+    pub fn unhedged_exposure(&self, currency_code: CurrencyCode) -> Amount {
+        self.state
+            .lock()
+            .unhedged_exposure
+            .get(&currency_code)
+            .copied()
+            .unwrap_or(dec!(0))
+    }
+
+    pub fn get_unhedged_exposures(&self) -> HashMap<CurrencyCode, Amount> {
+        self.state.lock().unhedged_exposure.clone()
+    }
+
+    fn register_maker_fill(&self, filled_order: &OrderSnapshot) -> Result<Option<Hedge>> {
+        let header = &filled_order.header;
+        let last_fill = filled_order.fills.fills.last().with_context(|| {
+            format!(
+                "There are no fills in order {} passed to Hedger",
+                header.client_order_id
+            )
+        })?;
+        let symbol = self.get_symbol(header.exchange_account_id, header.currency_pair)?;
+
+        let mut state = self.state.lock();
+        let filled_amount = filled_order.fills.filled_amount;
+        let handled_filled_amount = state
+            .handled_filled_amount
+            .entry(header.client_order_id.clone())
+            .or_default();
+        let new_filled_amount = filled_amount - *handled_filled_amount;
+        *handled_filled_amount = filled_amount;
+
+        if filled_order.status().is_finished() {
+            let _ = state.handled_filled_amount.remove(&header.client_order_id);
+        }
+
+        if new_filled_amount <= dec!(0) {
+            return Ok(None);
+        }
+
+        *state
+            .unhedged_exposure
+            .entry(symbol.base_currency_code())
+            .or_default() += signed_amount(header.side, new_filled_amount);
+
+        Ok(Some(Hedge {
+            currency_pair: header.currency_pair,
+            side: header.side.change_side(),
+            amount: new_filled_amount,
+            maker_price: last_fill.price(),
+            maker_filled_at: last_fill.receive_time(),
+        }))
+    }
+
+    async fn hedge(
+        self: Arc<Self>,
+        hedge: Hedge,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        let trade_place =
+            TradePlaceAccount::new(self.settings.exchange_account_id, hedge.currency_pair);
+        let exchange = self.get_exchange(self.settings.exchange_account_id)?;
+        let symbol = exchange.get_symbol(hedge.currency_pair)?;
+
+        let mut remaining_amount = hedge.amount;
+        let mut hedged_cost = dec!(0);
+        let mut failed_attempts = 0;
+        for attempt in 0..self.settings.max_attempts {
+            let amount = symbol.amount_round(remaining_amount, Round::Floor);
+            if amount <= dec!(0) {
+                break;
+            }
+
+            cancellation_token.error_if_cancellation_requested()?;
+
+            if self.engine_context.kill_switch.is_activated() {
+                log::warn!(
+                    "Hedging of {} on {:?} is stopped because kill switch is activated",
+                    remaining_amount,
+                    trade_place
+                );
+                break;
+            }
+
+            if failed_attempts > 0 {
+                let retry_delay = self.settings.retry_delay_ms * failed_attempts;
+                tokio::time::sleep(Duration::from_millis(retry_delay)).await;
+            }
+
+            let price = self.calculate_hedge_price(&hedge, attempt, &symbol);
+            let order_creating = OrderCreating {
+                header: create_hedge_order_header(
+                    self.settings.exchange_account_id,
+                    &hedge,
+                    amount,
+                ),
+                price,
+            };
+
+            if let Err(violation) = self.engine_context.pre_trade_risk.check_order_creating(
+                &exchange,
+                &self.engine_context.balance_manager,
+                &symbol,
+                &order_creating,
+                get_current_time(),
+            ) {
+                // risk limits don't change between attempts, so there is no sense to retry
+                log::error!(
+                    "Hedge order on {:?} for {} at price limit {} violates risk limit: {}",
+                    trade_place,
+                    amount,
+                    price,
+                    violation
+                );
+                self.engine_context
+                    .statistic_service
+                    .register_risk_rejected_order(trade_place);
+                break;
+            }
+
+            let hedge_order = match self
+                .execute_hedge_order(&exchange, &order_creating, cancellation_token.clone())
+                .await
+            {
+                Ok((hedge_order, is_finished_in_time)) => {
+                    if !is_finished_in_time {
+                        failed_attempts += 1;
+                    }
+                    hedge_order
+                }
+                Err(error) => {
+                    // failed order is counted as an attempt, so hedging goes on with next one after delay
+                    log::warn!(
+                        "Hedge order attempt {} on {:?} for {} at price limit {} failed: {:?}",
+                        attempt,
+                        trade_place,
+                        amount,
+                        price,
+                        error
+                    );
+                    failed_attempts += 1;
+                    continue;
+                }
+            };
+
+            for fill in &hedge_order.fills.fills {
+                hedged_cost += fill.price() * fill.amount();
+            }
+
+            let filled_amount = hedge_order.fills.filled_amount;
+            remaining_amount -= filled_amount;
+            self.register_hedge_fills(&hedge_order, &symbol);
+
+            log::info!(
+                "Hedge order {} attempt {} on {:?}: filled {} of {} at price limit {}",
+                hedge_order.header.client_order_id,
+                attempt,
+                trade_place,
+                filled_amount,
+                amount,
+                price
+            );
+        }
+
+        let statistics = &self.engine_context.statistic_service;
+        if symbol.amount_round(remaining_amount, Round::Floor) > dec!(0) {
+            log::error!(
+                "Hedger didn't offset maker fill of {} {:?} on {:?} after {} attempts. Unhedged amount {}",
+                hedge.amount,
+                hedge.side.change_side(),
+                trade_place,
+                self.settings.max_attempts,
+                remaining_amount
+            );
+            statistics.register_failed_hedge(trade_place);
+            return Ok(());
+        }
+
+        let hedged_amount = hedge.amount - remaining_amount;
+        let latency_ms = (get_current_time() - hedge.maker_filled_at)
+            .num_milliseconds()
+            .max(0) as u64;
+        statistics.register_hedge(
+            trade_place,
+            hedged_amount,
+            latency_ms,
+            calculate_slippage(&hedge, hedged_amount, hedged_cost),
+        );
+
+        Ok(())
+    }
+
+    /// Limit price of hedge order for the attempt with allowed slippage from maker fill price
+    fn calculate_hedge_price(&self, hedge: &Hedge, attempt: u32, symbol: &Symbol) -> Price {
+        let slippage = (self.settings.slippage_step * Decimal::from(attempt + 1))
+            .min(self.settings.max_slippage)
+            .percent_to_rate();
+
+        match hedge.side {
+            OrderSide::Buy => {
+                symbol.price_round(hedge.maker_price * (dec!(1) + slippage), Round::Floor)
+            }
+            OrderSide::Sell => {
+                symbol.price_round(hedge.maker_price * (dec!(1) - slippage), Round::Ceiling)
+            }
+        }
+    }
+
+    /// Returns hedge order and whether it was finished by exchange before timeout
+    async fn execute_hedge_order(
+        &self,
+        exchange: &Arc<Exchange>,
+        order_creating: &OrderCreating,
+        cancellation_token: CancellationToken,
+    ) -> Result<(OrderSnapshot, bool)> {
+        let order = exchange
+            .create_order(order_creating, None, cancellation_token.clone())
+            .await?;
+
+        // unfilled rest of IOC order is cancelled by exchange, so order is finished soon after creation
+        let finish_timeout = Duration::from_millis(self.settings.order_finish_timeout_ms);
+        let wait_finish =
+            exchange
+                .clone()
+                .wait_order_finish(&order, None, cancellation_token.clone());
+        if let Ok(finished_order) = tokio::time::timeout(finish_timeout, wait_finish).await {
+            let _ = finished_order?;
+            return Ok((order.deep_clone(), true));
+        }
+
+        // finish event can be lost, so order state is requested from exchange by cancellation
+        log::warn!(
+            "Hedge order {} isn't finished after {} ms, so it is cancelled",
+            order_creating.header.client_order_id,
+            self.settings.order_finish_timeout_ms
+        );
+        let wait_cancel = exchange.wait_cancel_order(order.clone(), None, true, cancellation_token);
+        match tokio::time::timeout(finish_timeout, wait_cancel).await {
+            Ok(Ok(())) => nothing_to_do(),
+            Ok(Err(error)) => log::error!(
+                "Unable to cancel hedge order {}: {:?}",
+                order_creating.header.client_order_id,
+                error
+            ),
+            Err(_) => log::error!(
+                "Hedge order {} isn't cancelled after {} ms",
+                order_creating.header.client_order_id,
+                self.settings.order_finish_timeout_ms
+            ),
+        }
+
+        Ok((order.deep_clone(), false))
+    }
+
+    fn register_hedge_fills(&self, hedge_order: &OrderSnapshot, symbol: &Symbol) {
+        if hedge_order.fills.fills.is_empty() {
+            return;
+        }
+
+        {
+            let mut balance_manager = self.engine_context.balance_manager.lock();
+            for fill in &hedge_order.fills.fills {
+                balance_manager.order_was_filled_with_fill(
+                    self.configuration_descriptor,
+                    hedge_order,
+                    fill,
+                );
+            }
+        }
+
+        *self
+            .state
+            .lock()
+            .unhedged_exposure
+            .entry(symbol.base_currency_code())
+            .or_default() +=
+            signed_amount(hedge_order.header.side, hedge_order.fills.filled_amount);
+    }
+
+    fn get_exchange(&self, exchange_account_id: ExchangeAccountId) -> Result<Arc<Exchange>> {
+        Ok(self
+            .engine_context
+            .exchanges
+            .get(&exchange_account_id)
+            .with_context(|| format!("Exchange {} is not created", exchange_account_id))?
+            .clone())
+    }
+
+    fn get_symbol(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        currency_pair: CurrencyPair,
+    ) -> Result<Arc<Symbol>> {
+        self.get_exchange(exchange_account_id)?
+            .get_symbol(currency_pair)
+    }
+}
+
+fn create_hedge_order_header(
+    exchange_account_id: ExchangeAccountId,
+    hedge: &Hedge,
+    amount: Amount,
+) -> Arc<OrderHeader> {
+    OrderHeader::new_with_options(
+        ClientOrderId::unique_id(),
+        get_current_time(),
+        exchange_account_id,
+        hedge.currency_pair,
+        OrderType::Limit,
+        hedge.side,
+        amount,
+        OrderExecutionType::None,
+        None,
+        None,
+        HEDGER.to_owned(),
+        OrderOptions::with_time_in_force(TimeInForce::ImmediateOrCancel),
+    )
+}
+
+fn signed_amount(side: OrderSide, amount: Amount) -> Amount {
+    match side {
+        OrderSide::Buy => amount,
+        OrderSide::Sell => -amount,
+    }
+}
+
+/// Loss in quote currency because hedge was executed worse than maker fill. Negative slippage is a gain
+fn calculate_slippage(hedge: &Hedge, hedged_amount: Amount, hedged_cost: Amount) -> Amount {
+    let maker_cost = hedge.maker_price * hedged_amount;
+    match hedge.side {
+        OrderSide::Buy => hedged_cost - maker_cost,
+        OrderSide::Sell => maker_cost - hedged_cost,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hedge(side: OrderSide) -> Hedge {
+        Hedge {
+            currency_pair: CurrencyPair::from_codes("eth".into(), "btc".into()),
+            side,
+            amount: dec!(2),
+            maker_price: dec!(10),
+            maker_filled_at: get_current_time(),
+        }
+    }
+
+    #[test]
+    fn slippage_of_sell_hedge() {
+        // sold cheaper than bought by maker order
+        assert_eq!(
+            calculate_slippage(&hedge(OrderSide::Sell), dec!(2), dec!(19)),
+            dec!(1)
+        );
+        // sold more expensive than bought by maker order
+        assert_eq!(
+            calculate_slippage(&hedge(OrderSide::Sell), dec!(2), dec!(21)),
+            dec!(-1)
+        );
+    }
+
+    #[test]
+    fn slippage_of_buy_hedge() {
+        assert_eq!(
+            calculate_slippage(&hedge(OrderSide::Buy), dec!(2), dec!(21)),
+            dec!(1)
+        );
+        assert_eq!(
+            calculate_slippage(&hedge(OrderSide::Buy), dec!(2), dec!(19)),
+            dec!(-1)
+        );
+    }
+
+    #[test]
+    fn hedge_order_is_immediate_or_cancel() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let header =
+            create_hedge_order_header(exchange_account_id, &hedge(OrderSide::Sell), dec!(1));
+
+        assert_eq!(header.order_type, OrderType::Limit);
+        assert_eq!(header.side, OrderSide::Sell);
+        assert_eq!(header.amount, dec!(1));
+        assert_eq!(header.options.time_in_force, TimeInForce::ImmediateOrCancel);
+    }
+
+    #[test]
+    fn signed_amount_by_side() {
+        assert_eq!(signed_amount(OrderSide::Buy, dec!(3)), dec!(3));
+        assert_eq!(signed_amount(OrderSide::Sell, dec!(3)), dec!(-3));
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use itertools::Itertools;
+use parking_lot::Mutex;
+use serde::Serialize;
+use tokio::sync::watch;
+
+use crate::exchanges::common::{Amount, TradePlaceAccount};
+use crate::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use crate::settings::BaseStrategySettings;
+use crate::strategies::disposition_strategy::DispositionStrategy;
+
+/// Strategy hosted by trading engine with settings of its disposition executor.
+/// Each hosted strategy has its own disposition executor, but exchange connections are shared
+pub struct HostedStrategy {
+    pub strategy: Box<dyn DispositionStrategy>,
+    pub trade_places: Vec<TradePlaceAccount>,
+    pub max_amount: Amount,
+    /// Balance budget of strategy on each of its trade places in amount currency.
+    /// Strategy without budget can use whole balance of exchange account
+    pub amount_limit: Option<Amount>,
+    /// Count of price levels on each side of each trade place
+    pub price_slots_count: usize,
+}
+
+impl HostedStrategy {
+    pub fn new(
+        settings: &dyn BaseStrategySettings,
+        strategy: Box<dyn DispositionStrategy>,
+    ) -> Self {
+        HostedStrategy {
+            strategy,
+            trade_places: settings.trade_places(),
+            max_amount: settings.max_amount(),
+            amount_limit: settings.amount_limit(),
+            price_slots_count: settings.price_slots_count(),
+        }
+    }
+}
+
+/// Name of hosted strategy built from its configuration descriptor, e.g. `ExampleStrategy|Binance_0;cnd/btc`
+pub fn get_strategy_name(configuration_descriptor: ConfigurationDescriptor) -> String {
+    format!(
+        "{}|{}",
+        configuration_descriptor.service_name, configuration_descriptor.service_configuration_key
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HostedStrategyState {
+    pub name: String,
+    pub is_enabled: bool,
+}
+
+/// Strategies hosted by trading engine. Allows to stop trading of one strategy
+/// and start it again while other strategies keep trading
+#[derive(Default)]
+pub struct HostedStrategies {
+    is_enabled_senders: Mutex<HashMap<String, watch::Sender<bool>>>,
+}
+
+impl HostedStrategies {
+    /// Returns receiver of changes of strategy state. Strategy is enabled after registration
+    pub(crate) fn register(
+        &self,
+        configuration_descriptor: ConfigurationDescriptor,
+    ) -> Result<watch::Receiver<bool>> {
+        let name = get_strategy_name(configuration_descriptor);
+
+        let mut is_enabled_senders = self.is_enabled_senders.lock();
+        if is_enabled_senders.contains_key(&name) {
+            bail!(
+                "Strategy {} is already hosted. Configuration descriptors of hosted strategies should be unique",
+                name
+            );
+        }
+
+        let (is_enabled_sender, is_enabled_receiver) = watch::channel(true);
+        let _ = is_enabled_senders.insert(name, is_enabled_sender);
+
+        Ok(is_enabled_receiver)
+    }
+
+    /// Stopped strategy cancels its orders and doesn't create new ones until it is started again
+    pub fn set_enabled(&self, strategy_name: &str, is_enabled: bool) -> Result<()> {
+        let is_enabled_senders = self.is_enabled_senders.lock();
+        let is_enabled_sender = is_enabled_senders
+            .get(strategy_name)
+            .with_context(|| format!("Strategy {} is not hosted", strategy_name))?;
+
+        let _ = is_enabled_sender.send_replace(is_enabled);
+        log::info!(
+            "Strategy {} is {}",
+            strategy_name,
+            if is_enabled { "started" } else { "stopped" }
+        );
+
+        Ok(())
+    }
+
+    pub fn get_states(&self) -> Vec<HostedStrategyState> {
+        self.is_enabled_senders
+            .lock()
+            .iter()
+            .map(|(name, is_enabled_sender)| HostedStrategyState {
+                name: name.clone(),
+                is_enabled: *is_enabled_sender.borrow(),
+            })
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configuration_descriptor(service_name: &str) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(service_name.into(), "Binance_0;cnd/btc".into())
+    }
+
+    #[test]
+    fn strategy_is_enabled_after_registration() {
+        let hosted_strategies = HostedStrategies::default();
+
+        let is_enabled = hosted_strategies
+            .register(configuration_descriptor("MarketMaker"))
+            .expect("in test");
+
+        assert!(*is_enabled.borrow());
+        assert_eq!(
+            hosted_strategies.get_states(),
+            vec![HostedStrategyState {
+                name: "MarketMaker|Binance_0;cnd/btc".to_owned(),
+                is_enabled: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn register_same_strategy_twice() {
+        let hosted_strategies = HostedStrategies::default();
+
+        let _ = hosted_strategies
+            .register(configuration_descriptor("MarketMaker"))
+            .expect("in test");
+
+        assert!(hosted_strategies
+            .register(configuration_descriptor("MarketMaker"))
+            .is_err());
+    }
+
+    #[test]
+    fn stop_one_of_strategies() {
+        let hosted_strategies = HostedStrategies::default();
+        let market_maker = hosted_strategies
+            .register(configuration_descriptor("MarketMaker"))
+            .expect("in test");
+        let hedger = hosted_strategies
+            .register(configuration_descriptor("Hedger"))
+            .expect("in test");
+
+        hosted_strategies
+            .set_enabled("MarketMaker|Binance_0;cnd/btc", false)
+            .expect("in test");
+
+        assert!(!*market_maker.borrow());
+        assert!(*hedger.borrow());
+        assert_eq!(
+            hosted_strategies.get_states(),
+            vec![
+                HostedStrategyState {
+                    name: "Hedger|Binance_0;cnd/btc".to_owned(),
+                    is_enabled: true,
+                },
+                HostedStrategyState {
+                    name: "MarketMaker|Binance_0;cnd/btc".to_owned(),
+                    is_enabled: false,
+                },
+            ]
+        );
+
+        hosted_strategies
+            .set_enabled("MarketMaker|Binance_0;cnd/btc", true)
+            .expect("in test");
+
+        assert!(*market_maker.borrow());
+    }
+
+    #[test]
+    fn stop_not_hosted_strategy() {
+        let hosted_strategies = HostedStrategies::default();
+
+        assert!(hosted_strategies.set_enabled("Unknown|key", false).is_err());
+    }
+}
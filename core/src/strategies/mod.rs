@@ -1 +1,3 @@
 pub mod disposition_strategy;
+pub mod hedger;
+pub mod hosted_strategies;
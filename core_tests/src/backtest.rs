@@ -0,0 +1,285 @@
+//! Market data and strategies shared by backtest tests. All currency pairs are quoted in btc
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use mmb_core::backtesting::market_data::{
+    write_market_data, RecordedMarketEvent, RecordedOrderBookEvent, RecordedTrade,
+    RecordedTradesEvent,
+};
+use mmb_core::disposition_execution::{
+    PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
+};
+use mmb_core::exchanges::common::{
+    Amount, CurrencyPair, ExchangeAccountId, Price, TradePlaceAccount,
+};
+use mmb_core::exchanges::events::TradeId;
+use mmb_core::exchanges::general::symbol::{Precision, Symbol};
+use mmb_core::explanation::{Explanation, WithExplanation};
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::{OrderRole, OrderSide, OrderSnapshot};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{BaseStrategySettings, CurrencyPairSetting, ExchangeSettings};
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_core::strategies::hosted_strategies::HostedStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+pub fn exchange_account_id() -> ExchangeAccountId {
+    "Simulated_0".parse().expect("in test")
+}
+
+pub fn cnd_btc() -> CurrencyPair {
+    CurrencyPair::from_codes("cnd".into(), "btc".into())
+}
+
+pub fn eth_btc() -> CurrencyPair {
+    CurrencyPair::from_codes("eth".into(), "btc".into())
+}
+
+/// Time of the first replayed market event
+pub fn start_time() -> DateTime {
+    Utc.with_ymd_and_hms(2021, 9, 20, 0, 0, 0)
+        .single()
+        .expect("in test")
+}
+
+pub fn symbol(base: &str, base_specific: &str) -> Arc<Symbol> {
+    Arc::new(Symbol::new(
+        false,
+        false,
+        base_specific.into(),
+        base.into(),
+        "BTC".into(),
+        "btc".into(),
+        Some(dec!(0.01)),
+        Some(dec!(1000)),
+        Some(dec!(0.1)),
+        Some(dec!(10000)),
+        None,
+        base.into(),
+        None,
+        Precision::ByTick { tick: dec!(0.01) },
+        Precision::ByTick { tick: dec!(0.1) },
+    ))
+}
+
+pub fn currency_pair_setting(base: &str) -> CurrencyPairSetting {
+    CurrencyPairSetting {
+        base: base.into(),
+        quote: "btc".into(),
+        currency_pair: None,
+    }
+}
+
+pub fn exchange_settings(
+    exchange_account_id: ExchangeAccountId,
+    bases: &[&str],
+) -> ExchangeSettings {
+    let mut exchange_settings =
+        ExchangeSettings::new_short(exchange_account_id, "".to_owned(), "".to_owned(), false);
+    exchange_settings.currency_pairs = Some(
+        bases
+            .iter()
+            .map(|base| currency_pair_setting(base))
+            .collect(),
+    );
+    exchange_settings
+}
+
+/// Order book snapshot with best ask 1.1 and best bid 0.9
+pub fn order_book(time: DateTime, trade_place: TradePlaceAccount) -> RecordedMarketEvent {
+    order_book_with_spread_offset(time, trade_place, dec!(0))
+}
+
+/// Order book snapshot with spread narrowed by `spread_offset` from each side
+pub fn order_book_with_spread_offset(
+    time: DateTime,
+    trade_place: TradePlaceAccount,
+    spread_offset: Price,
+) -> RecordedMarketEvent {
+    RecordedMarketEvent::OrderBook(RecordedOrderBookEvent {
+        time,
+        exchange_account_id: trade_place.exchange_account_id,
+        currency_pair: trade_place.currency_pair,
+        is_snapshot: true,
+        asks: vec![
+            (dec!(1.1) - spread_offset, dec!(5)),
+            (dec!(1.2) - spread_offset, dec!(5)),
+        ],
+        bids: vec![
+            (dec!(0.9) + spread_offset, dec!(5)),
+            (dec!(0.8) + spread_offset, dec!(5)),
+        ],
+    })
+}
+
+/// Trade by the top of the book from `order_book`
+pub fn trade(
+    time: DateTime,
+    trade_place: TradePlaceAccount,
+    trade_id: u64,
+    taker_side: OrderSide,
+) -> RecordedMarketEvent {
+    let price = match taker_side {
+        OrderSide::Buy => dec!(1.1),
+        OrderSide::Sell => dec!(0.9),
+    };
+
+    trade_by_price(time, trade_place, trade_id, price, taker_side)
+}
+
+pub fn trade_by_price(
+    time: DateTime,
+    trade_place: TradePlaceAccount,
+    trade_id: u64,
+    price: Price,
+    taker_side: OrderSide,
+) -> RecordedMarketEvent {
+    RecordedMarketEvent::Trades(RecordedTradesEvent {
+        time,
+        exchange_account_id: trade_place.exchange_account_id,
+        currency_pair: trade_place.currency_pair,
+        trades: vec![RecordedTrade {
+            trade_id: TradeId::Number(trade_id),
+            price,
+            amount: dec!(10),
+            side: taker_side,
+            transaction_time: time,
+        }],
+    })
+}
+
+/// Every test binary should use its own `name`, because test binaries can run in parallel
+pub fn write_market_data_file(name: &str, events: &[RecordedMarketEvent]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}_{}.jsonl", name, std::process::id()));
+    write_market_data(&path, events).expect("Unable to write market data");
+    path
+}
+
+/// Settings of strategy trading on cnd/btc of `exchange_account_id()`
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct BacktestStrategySettings {
+    pub max_amount: Amount,
+}
+
+impl BaseStrategySettings for BacktestStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        exchange_account_id()
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        cnd_btc()
+    }
+
+    fn max_amount(&self) -> Amount {
+        self.max_amount
+    }
+}
+
+/// Quotes top of the book with max amount on every trade place.
+/// `name` is used as strategy name of orders, so fills of strategies are reported separately
+pub struct TopOfBookStrategy {
+    name: String,
+    trade_places: Vec<TradePlaceAccount>,
+}
+
+impl TopOfBookStrategy {
+    pub fn new(name: &str, trade_places: Vec<TradePlaceAccount>) -> Self {
+        Self {
+            name: name.to_owned(),
+            trade_places,
+        }
+    }
+
+    pub fn hosted(
+        name: &str,
+        trade_place: TradePlaceAccount,
+        max_amount: Amount,
+        amount_limit: Amount,
+    ) -> HostedStrategy {
+        HostedStrategy {
+            strategy: Box::new(Self::new(name, vec![trade_place])),
+            trade_places: vec![trade_place],
+            max_amount,
+            amount_limit: Some(amount_limit),
+            price_slots_count: 1,
+        }
+    }
+
+    fn calc_trading_context_by_side(
+        &self,
+        trade_place: TradePlaceAccount,
+        side: OrderSide,
+        max_amount: Amount,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &Explanation,
+    ) -> Option<TradingContextBySide> {
+        let price = local_snapshots_service
+            .get_snapshot(trade_place.trade_place())?
+            .get_top(side)?
+            .0;
+
+        Some(TradingContextBySide {
+            max_amount,
+            estimating: vec![WithExplanation {
+                value: Some(TradeCycle {
+                    order_role: OrderRole::Maker,
+                    strategy_name: self.name.clone(),
+                    disposition: TradeDisposition::new(trade_place, side, price, max_amount),
+                }),
+                explanation: explanation.clone(),
+            }],
+        })
+    }
+}
+
+impl DispositionStrategy for TopOfBookStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        max_amount: Decimal,
+        _now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        let mut trading_context = TradingContext::default();
+        for &trade_place in &self.trade_places {
+            let calc_by_side = |side| {
+                self.calc_trading_context_by_side(
+                    trade_place,
+                    side,
+                    max_amount,
+                    local_snapshots_service,
+                    explanation,
+                )
+            };
+
+            trading_context.add_trade_place(
+                trade_place,
+                calc_by_side(OrderSide::Buy)?,
+                calc_by_side(OrderSide::Sell)?,
+            );
+        }
+
+        Some(trading_context)
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new(self.name.as_str().into(), "backtest".into())
+    }
+}
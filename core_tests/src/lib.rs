@@ -14,4 +14,5 @@
     unused_must_use
 )]
 
+pub mod backtest;
 pub mod order;
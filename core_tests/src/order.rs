@@ -52,6 +52,7 @@ pub struct OrderProxy {
     pub reservation_id: Option<ReservationId>,
     pub signal_id: Option<String>,
     pub strategy_name: String,
+    pub options: OrderOptions,
 
     pub price: Price,
     pub cancellation_token: CancellationToken,
@@ -78,6 +79,7 @@ impl OrderProxy {
             reservation_id: None,
             signal_id: None,
             strategy_name: strategy_name.unwrap_or("OrderTest".to_owned()),
+            options: OrderOptions::default(),
             price,
             cancellation_token,
             timeout: Duration::from_secs(5),
@@ -89,7 +91,7 @@ impl OrderProxy {
     }
 
     pub fn make_header(&self) -> Arc<OrderHeader> {
-        OrderHeader::new(
+        OrderHeader::new_with_options(
             self.client_order_id.clone(),
             self.init_time,
             self.exchange_account_id,
@@ -101,6 +103,7 @@ impl OrderProxy {
             self.reservation_id.clone(),
             self.signal_id.clone(),
             self.strategy_name.clone(),
+            self.options,
         )
     }
 
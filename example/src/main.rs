@@ -1,5 +1,6 @@
 use anyhow::Result;
 use binance::binance::BinanceBuilder;
+use bybit::bybit::BybitBuilder;
 use mmb_core::exchanges::traits::ExchangeClientBuilder;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -37,8 +38,11 @@ impl BaseStrategySettings for ExampleStrategySettings {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let engine_config =
+    let mut engine_config =
         EngineBuildConfig::standard(Box::new(BinanceBuilder) as Box<dyn ExchangeClientBuilder>);
+    let _ = engine_config
+        .supported_exchange_clients
+        .insert("Bybit".into(), Box::new(BybitBuilder));
 
     let init_settings = InitSettings::<ExampleStrategySettings>::Load {
         config_path: CONFIG_PATH.to_owned(),
@@ -198,7 +198,11 @@ impl DispositionStrategy for ExampleStrategy {
             explanation.clone(),
         )?;
 
-        Some(TradingContext::new(buy_trading_ctx, sell_trading_ctx))
+        Some(TradingContext::new(
+            self.trade_place_account(),
+            buy_trading_ctx,
+            sell_trading_ctx,
+        ))
     }
 
     fn handle_order_fill(
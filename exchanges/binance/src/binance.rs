@@ -2,29 +2,38 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
 use dashmap::DashMap;
+use futures::FutureExt;
 use hex;
 use hmac::{Hmac, Mac, NewMac};
 use itertools::Itertools;
 use mmb_utils::infrastructure::WithExpect;
+use mmb_utils::nothing_to_do;
 use mmb_utils::time::{get_current_milliseconds, u64_to_date_time};
 use mmb_utils::DateTime;
 use parking_lot::{Mutex, RwLock};
+use rust_decimal_macros::dec;
 use serde_json::Value;
 use sha2::Sha256;
 use tokio::sync::broadcast;
 
-use super::support::{BinanceBalances, BinanceOrderInfo};
+use super::support::{
+    BinanceAccountUpdate, BinanceBalances, BinanceFuturesAccountInfo, BinanceOrderInfo,
+    BinancePosition,
+};
+use mmb_core::connectivity::traffic_fixtures::TrafficFixtures;
 use mmb_core::exchanges::common::{Amount, Price};
 use mmb_core::exchanges::events::{
-    ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TradeId,
+    ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, LiquidationPriceEvent, TradeId,
 };
 use mmb_core::exchanges::general::features::{
-    OrderFeatures, OrderTradeOption, RestFillsFeatures, RestFillsType, WebSocketOptions,
+    BalancePositionOption, OrderFeatures, OrderTradeOption, RestFillsFeatures, RestFillsType,
+    WebSocketOptions,
 };
 use mmb_core::exchanges::hosts::Hosts;
-use mmb_core::exchanges::rest_client::RestClient;
-use mmb_core::exchanges::traits::ExchangeClientBuilderResult;
+use mmb_core::exchanges::rest_client::{HttpParams, RestClient};
+use mmb_core::exchanges::traits::{ExchangeClientBuilderResult, Support};
 use mmb_core::exchanges::{
     common::CurrencyCode,
     general::features::{ExchangeFeatures, OpenOrdersType},
@@ -36,7 +45,9 @@ use mmb_core::exchanges::{
     events::AllowedEventSourceType,
 };
 use mmb_core::exchanges::{general::handlers::handle_order_filled::FillEventData, rest_client};
+use mmb_core::infrastructure::spawn_future;
 use mmb_core::lifecycle::application_manager::ApplicationManager;
+use mmb_core::misc::derivative_position::DerivativePosition;
 use mmb_core::orders::fill::EventSourceType;
 use mmb_core::orders::order::*;
 use mmb_core::settings::ExchangeSettings;
@@ -69,7 +80,8 @@ pub struct Binance {
     pub(super) subscribe_to_market_data: bool,
     pub(super) is_reducing_market_data: bool,
 
-    pub(super) rest_client: RestClient,
+    pub(super) rest_client: Arc<RestClient>,
+    pub(super) traffic_fixtures: Option<Arc<TrafficFixtures>>,
 }
 
 impl Binance {
@@ -84,7 +96,14 @@ impl Binance {
             .is_reducing_market_data
             .unwrap_or(is_reducing_market_data);
 
-        let hosts = Self::make_hosts(settings.is_margin_trading);
+        if settings.is_margin_trading && settings.is_futures.is_none() {
+            log::warn!(
+                "Setting is_futures isn't set for {} with is_margin_trading = true, so USD-M futures are used as before. Set is_futures = false explicitly to trade on spot margin",
+                id
+            );
+        }
+
+        let hosts = Self::make_hosts(settings.is_futures());
 
         Self {
             id,
@@ -103,12 +122,21 @@ impl Binance {
             hosts,
             events_channel,
             application_manager,
-            rest_client: RestClient::new(),
+            rest_client: Arc::new(RestClient::new()),
+            traffic_fixtures: None,
         }
     }
 
-    pub fn make_hosts(is_margin_trading: bool) -> Hosts {
-        if is_margin_trading {
+    /// Records traffic to fixtures or replays it from them instead of real exchange
+    pub fn with_traffic_fixtures(mut self, traffic_fixtures: Arc<TrafficFixtures>) -> Self {
+        self.rest_client = Arc::new(RestClient::with_traffic_fixtures(traffic_fixtures.clone()));
+        self.traffic_fixtures = Some(traffic_fixtures);
+        self
+    }
+
+    /// USD-M futures have their own hosts, spot and margin trading use the spot ones
+    pub fn make_hosts(is_futures: bool) -> Hosts {
+        if is_futures {
             Hosts {
                 web_socket_host: "wss://fstream.binance.com",
                 web_socket2_host: "wss://fstream3.binance.com",
@@ -124,13 +152,16 @@ impl Binance {
     }
 
     pub async fn get_listen_key(&self) -> Result<RestRequestOutcome> {
-        let url_path = match self.settings.is_margin_trading {
-            true => "/sapi/v1/userDataStream",
-            false => "/api/v3/userDataStream",
+        let url_path = if self.settings.is_futures() {
+            "/fapi/v1/listenKey"
+        } else if self.settings.is_margin_trading {
+            "/sapi/v1/userDataStream"
+        } else {
+            "/api/v3/userDataStream"
         };
 
         let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &vec![])?;
-        let http_params = rest_client::HttpParams::new();
+        let http_params = HttpParams::new();
         self.rest_client
             .post(full_url, &self.settings.api_key, &http_params)
             .await
@@ -178,12 +209,176 @@ impl Binance {
         }
     }
 
-    pub(super) fn to_server_order_type(order_type: OrderType) -> String {
-        match order_type {
-            OrderType::Limit => "LIMIT".to_owned(),
-            OrderType::Market => "MARKET".to_owned(),
-            unexpected_variant => panic!("{:?} are not expected", unexpected_variant),
+    pub(super) fn to_server_order_type(&self, header: &OrderHeader) -> Result<String> {
+        let is_futures = self.settings.is_futures();
+        let order_type = match header.order_type {
+            OrderType::Limit
+                if header.execution_type == OrderExecutionType::MakerOnly && !is_futures =>
+            {
+                "LIMIT_MAKER"
+            }
+            OrderType::Limit => "LIMIT",
+            OrderType::Market => "MARKET",
+            OrderType::StopLoss if is_futures => "STOP",
+            OrderType::TrailingStop if is_futures => "TRAILING_STOP_MARKET",
+            // Spot trailing stop is a stop-limit order with trailingDelta parameter
+            OrderType::StopLoss | OrderType::TrailingStop => "STOP_LOSS_LIMIT",
+            unexpected_variant => bail!("{:?} are not expected", unexpected_variant),
+        };
+
+        Ok(order_type.to_owned())
+    }
+
+    fn to_server_time_in_force(&self, header: &OrderHeader) -> Result<String> {
+        let is_futures = self.settings.is_futures();
+        let time_in_force = match header.options.time_in_force {
+            // Futures don't have LIMIT_MAKER order type, post-only orders are marked by GTX instead
+            TimeInForce::GoodTillCancelled
+                if header.execution_type == OrderExecutionType::MakerOnly && is_futures =>
+            {
+                "GTX"
+            }
+            TimeInForce::GoodTillCancelled => "GTC",
+            TimeInForce::ImmediateOrCancel => "IOC",
+            TimeInForce::FillOrKill => "FOK",
+            TimeInForce::GoodTillDate(_) if is_futures => "GTD",
+            TimeInForce::GoodTillDate(_) => {
+                bail!("GoodTillDate orders are supported only on futures")
+            }
+        };
+
+        Ok(time_in_force.to_owned())
+    }
+
+    pub(super) fn create_order_params(&self, order: &OrderCreating) -> Result<HttpParams> {
+        let header = &order.header;
+        let options = &header.options;
+        let specific_currency_pair = self.get_specific_currency_pair(header.currency_pair);
+        let server_order_type = self.to_server_order_type(header)?;
+
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("side".to_owned(), Self::to_server_order_side(header.side)),
+            ("type".to_owned(), server_order_type.clone()),
+            ("quantity".to_owned(), header.amount.to_string()),
+            (
+                "newClientOrderId".to_owned(),
+                header.client_order_id.as_str().to_owned(),
+            ),
+        ];
+
+        match server_order_type.as_str() {
+            "MARKET" => nothing_to_do(),
+            "LIMIT_MAKER" => http_params.push(("price".to_owned(), order.price.to_string())),
+            "TRAILING_STOP_MARKET" => {
+                let trailing_delta = options
+                    .trailing_delta
+                    .context("Trailing delta isn't specified for TrailingStop order")?;
+                http_params.push(("callbackRate".to_owned(), trailing_delta.to_string()));
+                if let Some(activation_price) = options.stop_price {
+                    http_params.push(("activationPrice".to_owned(), activation_price.to_string()));
+                }
+            }
+            _ => {
+                http_params.push((
+                    "timeInForce".to_owned(),
+                    self.to_server_time_in_force(header)?,
+                ));
+                if let TimeInForce::GoodTillDate(expiration_time) = options.time_in_force {
+                    http_params.push((
+                        "goodTillDate".to_owned(),
+                        expiration_time.timestamp_millis().to_string(),
+                    ));
+                }
+                http_params.push(("price".to_owned(), order.price.to_string()));
+                if let Some(stop_price) = options.stop_price {
+                    http_params.push(("stopPrice".to_owned(), stop_price.to_string()));
+                }
+                if let Some(trailing_delta) = options.trailing_delta {
+                    // Spot trailing delta is measured in basis points
+                    let trailing_delta_bips = (trailing_delta * dec!(100)).normalize();
+                    http_params.push(("trailingDelta".to_owned(), trailing_delta_bips.to_string()));
+                }
+            }
+        }
+
+        Ok(http_params)
+    }
+
+    pub(super) fn replace_order_params(
+        &self,
+        order: &OrderCancelling,
+        new_order: &OrderCreating,
+    ) -> Result<HttpParams> {
+        let mut http_params = self.create_order_params(new_order)?;
+        http_params.push(("cancelReplaceMode".to_owned(), "STOP_ON_FAILURE".to_owned()));
+        http_params.push((
+            "cancelOrderId".to_owned(),
+            order.exchange_order_id.as_str().to_owned(),
+        ));
+
+        Ok(http_params)
+    }
+
+    /// Batch params are sent in url query, so JSON value is url encoded here
+    /// to sign the same string as sent to exchange
+    pub(super) fn batch_create_orders_params(
+        &self,
+        orders: &[OrderCreating],
+    ) -> Result<HttpParams> {
+        let batch_orders = orders
+            .iter()
+            .map(|order| {
+                let order_params = self
+                    .create_order_params(order)?
+                    .into_iter()
+                    .map(|(key, value)| (key, Value::String(value)))
+                    .collect();
+                Ok(Value::Object(order_params))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(vec![(
+            "batchOrders".to_owned(),
+            url_encode(&Value::Array(batch_orders).to_string()),
+        )])
+    }
+
+    pub(super) fn batch_cancel_orders_params(
+        &self,
+        orders: &[OrderCancelling],
+    ) -> Result<HttpParams> {
+        let currency_pair = orders
+            .first()
+            .context("There are no orders for batch cancellation")?
+            .header
+            .currency_pair;
+        if orders
+            .iter()
+            .any(|order| order.header.currency_pair != currency_pair)
+        {
+            bail!("Binance batch cancellation supports orders with single currency pair only");
         }
+
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let order_ids = orders
+            .iter()
+            .map(|order| order.exchange_order_id.as_str())
+            .join(",");
+
+        Ok(vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            (
+                "orderIdList".to_owned(),
+                url_encode(&format!("[{}]", order_ids)),
+            ),
+        ])
     }
 
     fn generate_signature(&self, data: String) -> Result<String> {
@@ -195,10 +390,7 @@ impl Binance {
         return Ok(result);
     }
 
-    pub(super) fn add_authentification_headers(
-        &self,
-        parameters: &mut rest_client::HttpParams,
-    ) -> Result<()> {
+    pub(super) fn add_authentification_headers(&self, parameters: &mut HttpParams) -> Result<()> {
         let time_stamp = get_current_milliseconds();
         parameters.push(("timestamp".to_owned(), time_stamp.to_string()));
 
@@ -244,9 +436,8 @@ impl Binance {
     }
 
     pub(super) fn handle_order_fill(&self, msg_to_log: &str, json_response: Value) -> Result<()> {
-        let original_client_order_id = json_response["C"]
-            .as_str()
-            .ok_or(anyhow!("Unable to parse original client order id"))?;
+        // Futures order updates don't contain original client order id
+        let original_client_order_id = json_response["C"].as_str().unwrap_or_default();
 
         let client_order_id = if original_client_order_id.is_empty() {
             json_response["c"]
@@ -301,20 +492,19 @@ impl Binance {
                 // TODO: May be not handle error in Rest but move it here to make it unified?
                 // We get notification of rejected orders from the rest responses
             }
-            "EXPIRED" => match time_in_force {
-                "GTX" => {
-                    (&self.order_cancelled_callback).lock()(
-                        client_order_id.into(),
-                        exchange_order_id.into(),
-                        EventSourceType::WebSocket,
-                    );
-                }
-                _ => log::error!(
-                    "Order {} was expired, message: {}",
+            "EXPIRED" => {
+                // Unfilled rest of IOC, FOK, GTD and rejected GTX orders is expired by exchange
+                log::info!(
+                    "Order {} with time in force {} was expired",
                     client_order_id,
-                    msg_to_log
-                ),
-            },
+                    time_in_force
+                );
+                (&self.order_cancelled_callback).lock()(
+                    client_order_id.into(),
+                    exchange_order_id.into(),
+                    EventSourceType::WebSocket,
+                );
+            }
             "TRADE" | "CALCULATED" => {
                 let event_data = self.prepare_data_for_fill_handler(
                     &json_response,
@@ -380,9 +570,11 @@ impl Binance {
                 .as_str()
                 .ok_or(anyhow!("Unable to parse last filled amount"))?,
         );
+        // Futures order updates contain only transaction time inside of order data
         let fill_date: DateTime = u64_to_date_time(
             json_response["E"]
                 .as_u64()
+                .or_else(|| json_response["T"].as_u64())
                 .ok_or(anyhow!("Unable to parse transaction time"))?,
         );
 
@@ -443,13 +635,177 @@ impl Binance {
         }
     }
 
-    pub(super) fn get_margin_exchange_balances_and_positions(
-        _raw_balances: Vec<BinanceBalances>,
+    pub(super) fn get_futures_exchange_balances_and_positions(
+        &self,
+        account_info: BinanceFuturesAccountInfo,
     ) -> ExchangeBalancesAndPositions {
-        todo!("implement it later")
+        let balances = account_info
+            .assets
+            .iter()
+            .filter_map(|asset| {
+                // Futures account contains assets which may be absent among traded symbols
+                let currency_code = self.get_currency_code(&asset.asset.as_str().into())?;
+                Some(ExchangeBalance {
+                    currency_code,
+                    balance: asset.available_balance,
+                })
+            })
+            .collect_vec();
+
+        ExchangeBalancesAndPositions {
+            balances,
+            positions: Some(self.binance_positions_to_derivative(&account_info.positions)),
+        }
+    }
+
+    /// Positions on symbols which aren't traded by the engine are skipped
+    pub(super) fn binance_positions_to_derivative(
+        &self,
+        positions: &[BinancePosition],
+    ) -> Vec<DerivativePosition> {
+        positions
+            .iter()
+            .filter(|position| !position.position_amount.is_zero())
+            .filter_map(
+                |position| match self.binance_position_to_derivative(position) {
+                    Ok(derivative_position) => Some(derivative_position),
+                    Err(error) => {
+                        log::warn!(
+                            "Position on {} for {} is skipped: {:?}",
+                            position.specific_currency_pair,
+                            self.id,
+                            error
+                        );
+                        None
+                    }
+                },
+            )
+            .collect_vec()
+    }
+
+    pub(super) fn position_risk_uri(&self) -> Result<hyper::Uri> {
+        let mut http_params = Vec::new();
+        self.add_authentification_headers(&mut http_params)?;
+
+        rest_client::build_uri(&self.hosts.rest_host, "/fapi/v2/positionRisk", &http_params)
+    }
+
+    /// Currency pairs of opened positions changed by ACCOUNT_UPDATE, positions on unknown symbols are skipped
+    pub(super) fn get_changed_positions(
+        &self,
+        data: Value,
+    ) -> Result<HashMap<SpecificCurrencyPair, CurrencyPair>> {
+        let account_update: BinanceAccountUpdate =
+            serde_json::from_value(data).context("Unable to parse ACCOUNT_UPDATE")?;
+
+        let specific_to_unified = self.specific_to_unified.read();
+        Ok(account_update
+            .data
+            .positions
+            .iter()
+            .filter(|position| !position.position_amount.is_zero())
+            .filter_map(|position| {
+                let specific_currency_pair = position.specific_currency_pair;
+                let currency_pair = specific_to_unified.get(&specific_currency_pair)?;
+                Some((specific_currency_pair, *currency_pair))
+            })
+            .collect())
+    }
+
+    /// ACCOUNT_UPDATE doesn't contain liquidation prices, so changed positions are requested
+    /// from position risk in background and `LiquidationPriceEvent` is sent for each of them
+    pub(super) fn handle_account_update(&self, data: Value) -> Result<()> {
+        let changed_positions = self.get_changed_positions(data)?;
+        if changed_positions.is_empty() {
+            return Ok(());
+        }
+
+        let full_url = self.position_risk_uri()?;
+        let rest_client = self.rest_client.clone();
+        let api_key = self.settings.api_key.clone();
+        let events_channel = self.events_channel.clone();
+        let exchange_account_id = self.id;
+
+        let action = async move {
+            let response = rest_client.get(full_url, &api_key).await?;
+            let positions: Vec<BinancePosition> = serde_json::from_str(&response.content)
+                .with_context(|| {
+                    format!(
+                        "Unable to parse position risk response {}",
+                        response.content
+                    )
+                })?;
+
+            for event in
+                liquidation_price_events(exchange_account_id, &changed_positions, &positions)
+            {
+                if let Err(error) = events_channel.send(ExchangeEvent::LiquidationPrice(event)) {
+                    log::error!(
+                        "Unable to send liquidation price event in {}: {}",
+                        exchange_account_id,
+                        error
+                    );
+                }
+            }
+
+            Ok(())
+        };
+        let _ = spawn_future("Request Binance liquidation prices", false, action.boxed());
+
+        Ok(())
+    }
+
+    pub(super) fn binance_position_to_derivative(
+        &self,
+        position: &BinancePosition,
+    ) -> Result<DerivativePosition> {
+        let currency_pair = self.get_unified_currency_pair(&position.specific_currency_pair)?;
+
+        Ok(DerivativePosition::new(
+            currency_pair,
+            position.position_amount,
+            Some(position_side(position)),
+            position.entry_price,
+            position.liquidation_price,
+            position.leverage,
+        ))
     }
 }
 
+fn position_side(position: &BinancePosition) -> OrderSide {
+    // Position amount is signed in one-way mode: negative values mean short position
+    match position.position_amount.is_sign_negative() {
+        true => OrderSide::Sell,
+        false => OrderSide::Buy,
+    }
+}
+
+fn liquidation_price_events(
+    exchange_account_id: ExchangeAccountId,
+    changed_positions: &HashMap<SpecificCurrencyPair, CurrencyPair>,
+    positions: &[BinancePosition],
+) -> Vec<LiquidationPriceEvent> {
+    positions
+        .iter()
+        .filter(|position| !position.position_amount.is_zero())
+        .filter_map(|position| {
+            let currency_pair = changed_positions.get(&position.specific_currency_pair)?;
+            Some(LiquidationPriceEvent::new(
+                Utc::now(),
+                exchange_account_id,
+                *currency_pair,
+                position.liquidation_price,
+                position.entry_price,
+                position_side(position),
+            ))
+        })
+        .collect_vec()
+}
+
+fn url_encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
 pub struct BinanceBuilder;
 
 impl ExchangeClientBuilder for BinanceBuilder {
@@ -460,6 +816,32 @@ impl ExchangeClientBuilder for BinanceBuilder {
         application_manager: Arc<ApplicationManager>,
     ) -> ExchangeClientBuilderResult {
         let exchange_account_id = exchange_settings.exchange_account_id;
+        let is_futures = exchange_settings.is_futures();
+
+        let mut features = ExchangeFeatures::new(
+            OpenOrdersType::AllCurrencyPair,
+            RestFillsFeatures::new(RestFillsType::None),
+            OrderFeatures {
+                supports_stop_loss_order: true,
+                supports_trailing_stop_order: true,
+                supports_immediate_or_cancel: true,
+                supports_fill_or_kill: true,
+                supports_good_till_date: is_futures,
+                supports_replace_order: !is_futures,
+                supports_batch_orders: is_futures,
+                max_batch_orders_count: 5,
+                ..OrderFeatures::default()
+            },
+            OrderTradeOption::default(),
+            WebSocketOptions::default(),
+            false,
+            false,
+            AllowedEventSourceType::All,
+            AllowedEventSourceType::All,
+        );
+        if is_futures {
+            features.balance_position_option = BalancePositionOption::SingleRequest;
+        }
 
         ExchangeClientBuilderResult {
             client: Box::new(Binance::new(
@@ -469,17 +851,7 @@ impl ExchangeClientBuilder for BinanceBuilder {
                 application_manager,
                 false,
             )) as BoxExchangeClient,
-            features: ExchangeFeatures::new(
-                OpenOrdersType::AllCurrencyPair,
-                RestFillsFeatures::new(RestFillsType::None),
-                OrderFeatures::default(),
-                OrderTradeOption::default(),
-                WebSocketOptions::default(),
-                false,
-                false,
-                AllowedEventSourceType::All,
-                AllowedEventSourceType::All,
-            ),
+            features,
         }
     }
 
@@ -491,7 +863,11 @@ impl ExchangeClientBuilder for BinanceBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{TimeZone, Utc};
+    use hyper::StatusCode;
+    use mmb_core::exchanges::general::exchange::RequestResult;
     use mmb_utils::cancellation_token::CancellationToken;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn generate_signature() {
@@ -520,9 +896,627 @@ mod tests {
         assert_eq!(result, right_value);
     }
 
+    fn futures_binance() -> Binance {
+        binance_with_btc_usdt(true)
+    }
+
+    fn binance_with_btc_usdt(is_futures: bool) -> Binance {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let mut settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false);
+        settings.is_futures = Some(is_futures);
+
+        let (tx, _) = broadcast::channel(10);
+        let binance = Binance::new(
+            exchange_account_id,
+            settings,
+            tx,
+            ApplicationManager::new(CancellationToken::default()),
+            false,
+        );
+
+        let specific_currency_pair: SpecificCurrencyPair = "BTCUSDT".into();
+        let currency_pair = CurrencyPair::from_codes("btc".into(), "usdt".into());
+        let _ = binance
+            .specific_to_unified
+            .write()
+            .insert(specific_currency_pair, currency_pair);
+        let _ = binance
+            .unified_to_specific
+            .write()
+            .insert(currency_pair, specific_currency_pair);
+        let _ = binance
+            .supported_currencies
+            .insert("USDT".into(), "usdt".into());
+
+        binance
+    }
+
+    #[test]
+    fn futures_hosts() {
+        let hosts = Binance::make_hosts(true);
+
+        assert_eq!(hosts.rest_host, "https://fapi.binance.com");
+        assert_eq!(hosts.web_socket_host, "wss://fstream.binance.com");
+    }
+
+    #[test]
+    fn hosts_by_trading_settings() {
+        let futures_host = "https://fapi.binance.com";
+        let spot_host = "https://api.binance.com";
+        let cases = [
+            (false, None, spot_host),
+            (false, Some(false), spot_host),
+            (false, Some(true), futures_host),
+            // margin trading meant futures before is_futures setting was added
+            (true, None, futures_host),
+            (true, Some(false), spot_host),
+            (true, Some(true), futures_host),
+        ];
+
+        for (is_margin_trading, is_futures, expected_rest_host) in cases {
+            let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+            let mut settings = ExchangeSettings::new_short(
+                exchange_account_id,
+                "".into(),
+                "".into(),
+                is_margin_trading,
+            );
+            settings.is_futures = is_futures;
+            let (tx, _) = broadcast::channel(10);
+
+            let binance = Binance::new(
+                exchange_account_id,
+                settings,
+                tx,
+                ApplicationManager::new(CancellationToken::default()),
+                false,
+            );
+
+            assert_eq!(
+                binance.hosts.rest_host, expected_rest_host,
+                "is_margin_trading = {}, is_futures = {:?}",
+                is_margin_trading, is_futures
+            );
+        }
+    }
+
+    #[test]
+    fn futures_builder_reports_single_request_balance_option() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let mut settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), false);
+        settings.is_futures = Some(true);
+        let (tx, _) = broadcast::channel(10);
+
+        let result = BinanceBuilder.create_exchange_client(
+            settings,
+            tx,
+            ApplicationManager::new(CancellationToken::default()),
+        );
+
+        assert!(matches!(
+            result.features.balance_position_option,
+            BalancePositionOption::SingleRequest
+        ));
+    }
+
+    #[test]
+    fn margin_builder_keeps_spot_features() {
+        let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+        let mut settings =
+            ExchangeSettings::new_short(exchange_account_id, "".into(), "".into(), true);
+        settings.is_futures = Some(false);
+        let (tx, _) = broadcast::channel(10);
+
+        let result = BinanceBuilder.create_exchange_client(
+            settings,
+            tx,
+            ApplicationManager::new(CancellationToken::default()),
+        );
+
+        assert!(matches!(
+            result.features.balance_position_option,
+            BalancePositionOption::NonDerivative
+        ));
+        assert!(result.features.order_features.supports_replace_order);
+        assert!(!result.features.order_features.supports_batch_orders);
+    }
+
+    #[test]
+    fn parse_futures_balances_and_positions() {
+        let binance = futures_binance();
+        let content = r#"{
+            "assets": [
+                { "asset": "USDT", "walletBalance": "120.5", "availableBalance": "100.25" },
+                { "asset": "BNB", "walletBalance": "1", "availableBalance": "1" }
+            ],
+            "positions": [
+                {
+                    "symbol": "BTCUSDT",
+                    "positionAmt": "-0.010",
+                    "entryPrice": "20000.0",
+                    "markPrice": "20100.0",
+                    "liquidationPrice": "35000.5",
+                    "leverage": "10",
+                    "positionSide": "BOTH"
+                },
+                {
+                    "symbol": "BTCUSDT",
+                    "positionAmt": "0.000",
+                    "entryPrice": "0.0",
+                    "markPrice": "20100.0",
+                    "liquidationPrice": "0",
+                    "leverage": "10",
+                    "positionSide": "BOTH"
+                },
+                {
+                    "symbol": "ETHUSDT",
+                    "positionAmt": "1.5",
+                    "entryPrice": "1500.0",
+                    "markPrice": "1510.0",
+                    "liquidationPrice": "1000",
+                    "leverage": "5",
+                    "positionSide": "BOTH"
+                }
+            ]
+        }"#;
+        let response = RestRequestOutcome::new(content.to_owned(), StatusCode::OK);
+
+        let result = binance.parse_get_balance(&response);
+
+        assert_eq!(result.balances.len(), 1);
+        assert_eq!(result.balances[0].currency_code, "usdt".into());
+        assert_eq!(result.balances[0].balance, dec!(100.25));
+
+        let positions = result.positions.expect("in test");
+        assert_eq!(positions.len(), 1);
+        let position = &positions[0];
+        assert_eq!(
+            position.currency_pair,
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        );
+        assert_eq!(position.position, dec!(-0.010));
+        assert_eq!(position.side, Some(OrderSide::Sell));
+        assert_eq!(position.average_entry_price, dec!(20000));
+        assert_eq!(position.liquidation_price, dec!(35000.5));
+        assert_eq!(position.leverage, dec!(10));
+    }
+
+    #[test]
+    fn parse_futures_positions_skips_unknown_symbols() {
+        let binance = futures_binance();
+        let content = r#"[
+            {
+                "symbol": "ETHUSDT",
+                "positionAmt": "1.5",
+                "entryPrice": "1500.0",
+                "markPrice": "1510.0",
+                "liquidationPrice": "1000",
+                "leverage": "5",
+                "positionSide": "BOTH"
+            },
+            {
+                "symbol": "BTCUSDT",
+                "positionAmt": "0.5",
+                "entryPrice": "20000.0",
+                "markPrice": "20100.0",
+                "liquidationPrice": "10000",
+                "leverage": "10",
+                "positionSide": "BOTH"
+            }
+        ]"#;
+        let response = RestRequestOutcome::new(content.to_owned(), StatusCode::OK);
+
+        let positions = binance.parse_get_position(&response);
+
+        assert_eq!(positions.len(), 1);
+        assert_eq!(
+            positions[0].derivative.currency_pair,
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        );
+        assert_eq!(positions[0].derivative.side, Some(OrderSide::Buy));
+    }
+
+    #[test]
+    fn liquidation_prices_of_positions_changed_by_account_update() {
+        let binance = futures_binance();
+        let account_update = serde_json::json!({
+            "e": "ACCOUNT_UPDATE",
+            "E": 1564745798939u64,
+            "T": 1564745798938u64,
+            "a": {
+                "m": "ORDER",
+                "B": [{ "a": "USDT", "wb": "122624.12345678", "cw": "100.12345678", "bc": "50.12345678" }],
+                "P": [
+                    { "s": "BTCUSDT", "pa": "-0.010", "ep": "20000.0", "cr": "200", "up": "0", "mt": "cross", "iw": "0", "ps": "BOTH" },
+                    { "s": "ETHUSDT", "pa": "1.5", "ep": "1500.0", "cr": "0", "up": "0", "mt": "cross", "iw": "0", "ps": "BOTH" }
+                ]
+            }
+        });
+        let position_risk = r#"[
+            {
+                "symbol": "BTCUSDT",
+                "positionAmt": "-0.010",
+                "entryPrice": "20000.0",
+                "markPrice": "20100.0",
+                "liquidationPrice": "35000.5",
+                "leverage": "10",
+                "positionSide": "BOTH"
+            },
+            {
+                "symbol": "ETHUSDT",
+                "positionAmt": "1.5",
+                "entryPrice": "1500.0",
+                "markPrice": "1510.0",
+                "liquidationPrice": "1000",
+                "leverage": "5",
+                "positionSide": "BOTH"
+            }
+        ]"#;
+        let positions: Vec<BinancePosition> = serde_json::from_str(position_risk).expect("in test");
+
+        let changed_positions = binance
+            .get_changed_positions(account_update)
+            .expect("in test");
+        let events = liquidation_price_events(binance.id, &changed_positions, &positions);
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(
+            event.currency_pair,
+            CurrencyPair::from_codes("btc".into(), "usdt".into())
+        );
+        assert_eq!(event.liq_price, dec!(35000.5));
+        assert_eq!(event.entry_price, dec!(20000));
+        assert_eq!(event.side, OrderSide::Sell);
+    }
+
+    fn order_creating(
+        order_type: OrderType,
+        execution_type: OrderExecutionType,
+        options: OrderOptions,
+    ) -> OrderCreating {
+        let header = OrderHeader::new_with_options(
+            ClientOrderId::new("test".into()),
+            Utc::now(),
+            "Binance_0".parse().expect("in test"),
+            CurrencyPair::from_codes("btc".into(), "usdt".into()),
+            order_type,
+            OrderSide::Sell,
+            dec!(0.01),
+            execution_type,
+            None,
+            None,
+            "test".to_owned(),
+            options,
+        );
+
+        OrderCreating {
+            header,
+            price: dec!(20000),
+        }
+    }
+
+    fn create_order_params(binance: &Binance, order: &OrderCreating) -> Vec<String> {
+        binance
+            .create_order_params(order)
+            .expect("in test")
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect()
+    }
+
+    fn expected_params(order_type: &str, specific_params: &[&str]) -> Vec<String> {
+        let common_params = vec![
+            "symbol=BTCUSDT".to_owned(),
+            "side=SELL".to_owned(),
+            format!("type={}", order_type),
+            "quantity=0.01".to_owned(),
+            "newClientOrderId=test".to_owned(),
+        ];
+
+        common_params
+            .into_iter()
+            .chain(specific_params.iter().map(|x| x.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn spot_immediate_or_cancel_limit_order_params() {
+        let binance = binance_with_btc_usdt(false);
+        let order = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::None,
+            OrderOptions::with_time_in_force(TimeInForce::ImmediateOrCancel),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params("LIMIT", &["timeInForce=IOC", "price=20000"])
+        );
+    }
+
+    #[test]
+    fn spot_maker_only_order_params() {
+        let binance = binance_with_btc_usdt(false);
+        let order = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+            OrderOptions::default(),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params("LIMIT_MAKER", &["price=20000"])
+        );
+    }
+
+    #[test]
+    fn spot_stop_loss_order_params() {
+        let binance = binance_with_btc_usdt(false);
+        let order = order_creating(
+            OrderType::StopLoss,
+            OrderExecutionType::None,
+            OrderOptions::stop_loss(dec!(20100)),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params(
+                "STOP_LOSS_LIMIT",
+                &["timeInForce=GTC", "price=20000", "stopPrice=20100"]
+            )
+        );
+    }
+
+    #[test]
+    fn spot_trailing_stop_order_params() {
+        let binance = binance_with_btc_usdt(false);
+        let order = order_creating(
+            OrderType::TrailingStop,
+            OrderExecutionType::None,
+            OrderOptions::trailing_stop(dec!(1.5), None),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params(
+                "STOP_LOSS_LIMIT",
+                &["timeInForce=GTC", "price=20000", "trailingDelta=150"]
+            )
+        );
+    }
+
+    #[test]
+    fn spot_good_till_date_order_is_not_supported() {
+        let binance = binance_with_btc_usdt(false);
+        let order = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::None,
+            OrderOptions::with_time_in_force(TimeInForce::GoodTillDate(Utc::now())),
+        );
+
+        assert!(binance.create_order_params(&order).is_err());
+    }
+
+    #[test]
+    fn futures_maker_only_order_params() {
+        let binance = futures_binance();
+        let order = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+            OrderOptions::default(),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params("LIMIT", &["timeInForce=GTX", "price=20000"])
+        );
+    }
+
+    #[test]
+    fn futures_good_till_date_order_params() {
+        let binance = futures_binance();
+        let expiration_time = Utc
+            .timestamp_millis_opt(1_640_000_000_000)
+            .single()
+            .expect("in test");
+        let order = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::None,
+            OrderOptions::with_time_in_force(TimeInForce::GoodTillDate(expiration_time)),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params(
+                "LIMIT",
+                &[
+                    "timeInForce=GTD",
+                    "goodTillDate=1640000000000",
+                    "price=20000"
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn futures_stop_loss_order_params() {
+        let binance = futures_binance();
+        let order = order_creating(
+            OrderType::StopLoss,
+            OrderExecutionType::None,
+            OrderOptions::stop_loss(dec!(20100)),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params(
+                "STOP",
+                &["timeInForce=GTC", "price=20000", "stopPrice=20100"]
+            )
+        );
+    }
+
+    #[test]
+    fn futures_trailing_stop_order_params() {
+        let binance = futures_binance();
+        let order = order_creating(
+            OrderType::TrailingStop,
+            OrderExecutionType::None,
+            OrderOptions::trailing_stop(dec!(1.5), Some(dec!(20100))),
+        );
+
+        assert_eq!(
+            create_order_params(&binance, &order),
+            expected_params(
+                "TRAILING_STOP_MARKET",
+                &["callbackRate=1.5", "activationPrice=20100"]
+            )
+        );
+    }
+
+    #[test]
+    fn spot_replace_order_params() {
+        let binance = binance_with_btc_usdt(false);
+        let new_order = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+            OrderOptions::default(),
+        );
+        let order_to_cancel = OrderCancelling {
+            header: new_order.header.clone(),
+            exchange_order_id: ExchangeOrderId::new("12345".into()),
+        };
+
+        let params = binance
+            .replace_order_params(&order_to_cancel, &new_order)
+            .expect("in test")
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            params,
+            expected_params(
+                "LIMIT_MAKER",
+                &[
+                    "price=20000",
+                    "cancelReplaceMode=STOP_ON_FAILURE",
+                    "cancelOrderId=12345"
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn order_id_from_cancel_replace_response() {
+        let binance = binance_with_btc_usdt(false);
+        let response = RestRequestOutcome::new(
+            r#"{"cancelResult":"SUCCESS","newOrderResult":"SUCCESS","cancelResponse":{"orderId":12345},"newOrderResponse":{"orderId":12346}}"#.to_owned(),
+            StatusCode::OK,
+        );
+
+        assert_eq!(
+            binance.get_order_id(&response).expect("in test"),
+            ExchangeOrderId::new("12346".into())
+        );
+    }
+
+    fn decode_param(params: &HttpParams, key: &str) -> Value {
+        let (_, value) = params
+            .iter()
+            .find(|(param_key, _)| param_key == key)
+            .expect("in test");
+        let decoded = url::form_urlencoded::parse(format!("{}={}", key, value).as_bytes())
+            .map(|(_, value)| value.into_owned())
+            .next()
+            .expect("in test");
+
+        serde_json::from_str(&decoded).expect("in test")
+    }
+
+    #[test]
+    fn futures_batch_create_orders_params() {
+        let binance = futures_binance();
+        let order = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+            OrderOptions::default(),
+        );
+
+        let params = binance
+            .batch_create_orders_params(&[order.clone(), order])
+            .expect("in test");
+
+        let expected_order = serde_json::json!({
+            "symbol": "BTCUSDT",
+            "side": "SELL",
+            "type": "LIMIT",
+            "quantity": "0.01",
+            "newClientOrderId": "test",
+            "timeInForce": "GTX",
+            "price": "20000",
+        });
+        assert_eq!(params.len(), 1);
+        assert_eq!(
+            decode_param(&params, "batchOrders"),
+            Value::Array(vec![expected_order.clone(), expected_order])
+        );
+    }
+
+    #[test]
+    fn futures_batch_cancel_orders_params() {
+        let binance = futures_binance();
+        let header = order_creating(
+            OrderType::Limit,
+            OrderExecutionType::MakerOnly,
+            OrderOptions::default(),
+        )
+        .header;
+        let orders = ["123", "456"].map(|exchange_order_id| OrderCancelling {
+            header: header.clone(),
+            exchange_order_id: ExchangeOrderId::new(exchange_order_id.into()),
+        });
+
+        let params = binance
+            .batch_cancel_orders_params(&orders)
+            .expect("in test");
+
+        assert_eq!(params[0], ("symbol".to_owned(), "BTCUSDT".to_owned()));
+        assert_eq!(
+            decode_param(&params, "orderIdList"),
+            serde_json::json!([123, 456])
+        );
+    }
+
+    #[test]
+    fn parse_batch_orders_response() {
+        let binance = futures_binance();
+        let response = RestRequestOutcome::new(
+            r#"[{"orderId":12345,"status":"NEW"},{"code":-2019,"msg":"Margin is insufficient."}]"#
+                .to_owned(),
+            StatusCode::OK,
+        );
+
+        assert!(binance.is_rest_error_code(&response).is_ok());
+        let results = binance.parse_batch_orders(&response).expect("in test");
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(
+            &results[0],
+            RequestResult::Success(exchange_order_id) if exchange_order_id.as_str() == "12345"
+        ));
+        assert!(matches!(
+            &results[1],
+            RequestResult::Error(error) if error.code == Some(-2019)
+        ));
+    }
+
     #[test]
     fn to_http_string() {
-        let parameters: rest_client::HttpParams = vec![
+        let parameters: HttpParams = vec![
             ("symbol".to_owned(), "LTCBTC".to_owned()),
             ("side".to_owned(), "BUY".to_owned()),
             ("type".to_owned(), "LIMIT".to_owned()),
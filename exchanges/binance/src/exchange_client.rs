@@ -1,5 +1,5 @@
 use super::binance::Binance;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use mmb_core::exchanges::common::{ActivePosition, Price};
 use mmb_core::exchanges::general::symbol::Symbol;
@@ -11,49 +11,25 @@ use mmb_core::{
     orders::pool::OrderRef,
 };
 use mmb_utils::DateTime;
+use serde_json::Value;
 
 #[async_trait]
 impl ExchangeClient for Binance {
     async fn request_all_symbols(&self) -> Result<RestRequestOutcome> {
-        // In current versions works only with Spot market
-        let url_path = "/api/v3/exchangeInfo";
+        let url_path = match self.settings.is_futures() {
+            true => "/fapi/v1/exchangeInfo",
+            false => "/api/v3/exchangeInfo",
+        };
         let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &vec![])?;
 
         self.rest_client.get(full_url, &self.settings.api_key).await
     }
 
     async fn create_order(&self, order: &OrderCreating) -> Result<RestRequestOutcome> {
-        let specific_currency_pair = self.get_specific_currency_pair(order.header.currency_pair);
-
-        let mut http_params = vec![
-            (
-                "symbol".to_owned(),
-                specific_currency_pair.as_str().to_owned(),
-            ),
-            (
-                "side".to_owned(),
-                Self::to_server_order_side(order.header.side),
-            ),
-            (
-                "type".to_owned(),
-                Self::to_server_order_type(order.header.order_type),
-            ),
-            ("quantity".to_owned(), order.header.amount.to_string()),
-            (
-                "newClientOrderId".to_owned(),
-                order.header.client_order_id.as_str().to_owned(),
-            ),
-        ];
-
-        if order.header.order_type != OrderType::Market {
-            http_params.push(("timeInForce".to_owned(), "GTC".to_owned()));
-            http_params.push(("price".to_owned(), order.price.to_string()));
-        } else if order.header.execution_type == OrderExecutionType::MakerOnly {
-            http_params.push(("timeInForce".to_owned(), "GTX".to_owned()));
-        }
+        let mut http_params = self.create_order_params(order)?;
         self.add_authentification_headers(&mut http_params)?;
 
-        let url_path = match self.settings.is_margin_trading {
+        let url_path = match self.settings.is_futures() {
             true => "/fapi/v1/order",
             false => "/api/v3/order",
         };
@@ -68,7 +44,7 @@ impl ExchangeClient for Binance {
     async fn request_cancel_order(&self, order: &OrderCancelling) -> Result<RestRequestOutcome> {
         let specific_currency_pair = self.get_specific_currency_pair(order.header.currency_pair);
 
-        let url_path = match self.settings.is_margin_trading {
+        let url_path = match self.settings.is_futures() {
             true => "/fapi/v1/order",
             false => "/api/v3/order",
         };
@@ -95,11 +71,72 @@ impl ExchangeClient for Binance {
         Ok(outcome)
     }
 
+    async fn request_replace_order(
+        &self,
+        order: &OrderCancelling,
+        new_order: &OrderCreating,
+    ) -> Result<RestRequestOutcome> {
+        if self.settings.is_futures() {
+            bail!("Binance futures don't support replacing orders");
+        }
+
+        let mut http_params = self.replace_order_params(order, new_order)?;
+        self.add_authentification_headers(&mut http_params)?;
+
+        let full_url = rest_client::build_uri(
+            &self.hosts.rest_host,
+            "/api/v3/order/cancelReplace",
+            &vec![],
+        )?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &http_params)
+            .await
+    }
+
+    async fn request_create_orders(&self, orders: &[OrderCreating]) -> Result<RestRequestOutcome> {
+        if !self.settings.is_futures() {
+            bail!("Binance spot doesn't support batch orders");
+        }
+
+        let mut http_params = self.batch_create_orders_params(orders)?;
+        self.add_authentification_headers(&mut http_params)?;
+
+        let full_url =
+            rest_client::build_uri(&self.hosts.rest_host, "/fapi/v1/batchOrders", &http_params)?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &vec![])
+            .await
+    }
+
+    async fn request_cancel_orders(
+        &self,
+        orders: &[OrderCancelling],
+    ) -> Result<RestRequestOutcome> {
+        if !self.settings.is_futures() {
+            bail!("Binance spot doesn't support batch orders");
+        }
+
+        let mut http_params = self.batch_cancel_orders_params(orders)?;
+        self.add_authentification_headers(&mut http_params)?;
+
+        let full_url =
+            rest_client::build_uri(&self.hosts.rest_host, "/fapi/v1/batchOrders", &http_params)?;
+
+        self.rest_client
+            .delete(full_url, &self.settings.api_key)
+            .await
+    }
+
     async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
         let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
 
         let host = &self.hosts.rest_host;
-        let path_to_delete = "/api/v3/openOrders";
+        let path_to_delete = match self.settings.is_futures() {
+            true => "/fapi/v1/allOpenOrders",
+            false => "/api/v3/openOrders",
+        };
 
         let mut http_params = vec![(
             "symbol".to_owned(),
@@ -141,7 +178,7 @@ impl ExchangeClient for Binance {
     async fn request_order_info(&self, order: &OrderRef) -> Result<RestRequestOutcome> {
         let specific_currency_pair = self.get_specific_currency_pair(order.currency_pair());
 
-        let url_path = match self.settings.is_margin_trading {
+        let url_path = match self.settings.is_futures() {
             true => "/fapi/v1/order",
             false => "/api/v3/order",
         };
@@ -176,7 +213,7 @@ impl ExchangeClient for Binance {
 
         self.add_authentification_headers(&mut http_params)?;
 
-        let url_path = match self.settings.is_margin_trading {
+        let url_path = match self.settings.is_futures() {
             true => "/fapi/v1/userTrades",
             false => "/api/v3/myTrades",
         };
@@ -186,27 +223,47 @@ impl ExchangeClient for Binance {
     }
 
     async fn request_get_position(&self) -> Result<RestRequestOutcome> {
-        let mut http_params = Vec::new();
-        self.add_authentification_headers(&mut http_params)?;
-
-        let url_path = "/fapi/v2/positionRisk";
-        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        let full_url = self.position_risk_uri()?;
 
         self.rest_client.get(full_url, &self.settings.api_key).await
     }
 
     async fn request_get_balance_and_position(&self) -> Result<RestRequestOutcome> {
-        panic!("not supported request")
+        if !self.settings.is_futures() {
+            bail!("Balances and positions can be requested only for Binance futures")
+        }
+
+        let account_outcome = self.request_futures_account().await?;
+        if self.is_rest_error_code(&account_outcome).is_err() {
+            return Ok(account_outcome);
+        }
+
+        let positions_outcome = self.request_get_position().await?;
+        if self.is_rest_error_code(&positions_outcome).is_err() {
+            return Ok(positions_outcome);
+        }
+
+        // Futures account info doesn't contain liquidation prices so positions are taken from position risk
+        let mut account_info: Value = serde_json::from_str(&account_outcome.content)
+            .context("Unable to parse futures account info")?;
+        account_info["positions"] = serde_json::from_str(&positions_outcome.content)
+            .context("Unable to parse futures positions")?;
+
+        Ok(RestRequestOutcome::new(
+            account_info.to_string(),
+            account_outcome.status,
+        ))
     }
 
     async fn request_get_balance(&self) -> Result<RestRequestOutcome> {
+        if self.settings.is_futures() {
+            return self.request_get_balance_and_position().await;
+        }
+
         let mut http_params = Vec::new();
         self.add_authentification_headers(&mut http_params)?;
-        let url_path = match self.settings.is_margin_trading {
-            true => "/fapi/v2/account",
-            false => "/api/v3/account",
-        };
 
+        let url_path = "/api/v3/account";
         let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
         self.rest_client.get(full_url, &self.settings.api_key).await
     }
@@ -217,42 +274,52 @@ impl ExchangeClient for Binance {
         price: Option<Price>,
     ) -> Result<RestRequestOutcome> {
         let side = match position.derivative.side {
-            Some(side) => side.change_side().to_string(),
-            None => "0".to_string(), // unknown side
+            Some(side) => side.change_side(),
+            None => bail!("Unable to close position {} with unknown side", position.id),
         };
+        let specific_currency_pair =
+            self.get_specific_currency_pair(position.derivative.currency_pair);
 
         let mut http_params = vec![
             (
-                "leverage".to_string(),
-                position.derivative.leverage.to_string(),
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
             ),
-            ("positionSide".to_string(), "BOTH".to_string()),
+            ("side".to_owned(), Self::to_server_order_side(side)),
             (
-                "quantity".to_string(),
+                "quantity".to_owned(),
                 position.derivative.position.abs().to_string(),
             ),
-            ("side".to_string(), side),
-            (
-                "symbol".to_string(),
-                position.derivative.currency_pair.to_string(),
-            ),
+            ("reduceOnly".to_owned(), "true".to_owned()),
         ];
 
         match price {
             Some(price) => {
-                http_params.push(("type".to_string(), "MARKET".to_string()));
-                http_params.push(("price".to_string(), price.to_string()));
+                http_params.push(("type".to_owned(), "LIMIT".to_owned()));
+                http_params.push(("timeInForce".to_owned(), "GTC".to_owned()));
+                http_params.push(("price".to_owned(), price.to_string()));
             }
-            None => http_params.push(("type".to_string(), "LIMIT".to_string())),
+            None => http_params.push(("type".to_owned(), "MARKET".to_owned())),
         }
 
         self.add_authentification_headers(&mut http_params)?;
 
         let url_path = "/fapi/v1/order";
-        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &vec![])?;
 
         self.rest_client
             .post(full_url, &self.settings.api_key, &http_params)
             .await
     }
 }
+
+impl Binance {
+    async fn request_futures_account(&self) -> Result<RestRequestOutcome> {
+        let mut http_params = Vec::new();
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/fapi/v2/account";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+}
@@ -1,4 +1,3 @@
-use mmb_core::misc::derivative_position::DerivativePosition;
 use mmb_utils::infrastructure::WithExpect;
 
 use std::sync::Arc;
@@ -11,14 +10,15 @@ use dashmap::DashMap;
 use itertools::Itertools;
 use mmb_utils::{value_to_decimal::GetOrErr, DateTime};
 use rust_decimal::Decimal;
-use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use url::Url;
 
 use super::binance::Binance;
+use mmb_core::connectivity::traffic_fixtures::TrafficFixtures;
 use mmb_core::exchanges::common::{ActivePosition, ClosedPosition, SortedOrderData};
 use mmb_core::exchanges::events::{ExchangeBalancesAndPositions, ExchangeEvent, TradeId};
+use mmb_core::exchanges::general::exchange::RequestResult;
 use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
 use mmb_core::exchanges::rest_client;
 use mmb_core::exchanges::{
@@ -72,21 +72,63 @@ pub struct BinanceBalances {
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
-struct BinancePosition {
+#[serde(rename_all = "camelCase")]
+pub struct BinancePosition {
     #[serde(rename = "symbol")]
     pub specific_currency_pair: SpecificCurrencyPair,
-    #[serde(rename = "PositionAmt")]
+    #[serde(rename = "positionAmt")]
     pub position_amount: Amount,
-    #[serde(rename = "LiquidationPrice")]
+    pub entry_price: Price,
     pub liquidation_price: Price,
     pub leverage: Decimal,
-    #[serde(rename = "PositionSide")]
-    pub position_side: Decimal,
+    pub position_side: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BinanceAccountUpdatePosition {
+    #[serde(rename = "s")]
+    pub specific_currency_pair: SpecificCurrencyPair,
+    #[serde(rename = "pa")]
+    pub position_amount: Amount,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BinanceAccountUpdateData {
+    #[serde(rename = "P", default)]
+    pub positions: Vec<BinanceAccountUpdatePosition>,
+}
+
+/// Futures user data stream event about changes of balances and positions
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BinanceAccountUpdate {
+    #[serde(rename = "a")]
+    pub data: BinanceAccountUpdateData,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinanceFuturesAsset {
+    pub asset: String,
+    pub wallet_balance: Decimal,
+    pub available_balance: Decimal,
+}
+
+/// Futures account info where positions are taken from position risk response,
+/// because account info itself doesn't contain liquidation prices
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BinanceFuturesAccountInfo {
+    pub assets: Vec<BinanceFuturesAsset>,
+    pub positions: Vec<BinancePosition>,
 }
 
 #[async_trait]
 impl Support for Binance {
     fn is_rest_error_code(&self, response: &RestRequestOutcome) -> Result<(), ExchangeError> {
+        // Batch responses contain errors per order, they are handled in parse_batch_orders
+        if response.content.starts_with('[') {
+            return Ok(());
+        }
+
         //Binance is a little inconsistent: for failed responses sometimes they include
         //only code or only success:false but sometimes both
         if !(response.content.contains(r#""success":false"#)
@@ -116,11 +158,45 @@ impl Support for Binance {
     fn get_order_id(&self, response: &RestRequestOutcome) -> Result<ExchangeOrderId> {
         let response: Value =
             serde_json::from_str(&response.content).context("Unable to parse response content")?;
-        let id = response["orderId"].to_string();
+        // cancelReplace response contains the created order in the nested object
+        let order = match response.get("newOrderResponse") {
+            Some(new_order_response) => new_order_response,
+            None => &response,
+        };
+        let id = order["orderId"].to_string();
         let id = id.trim_matches('"');
         Ok(ExchangeOrderId::new(id.into()))
     }
 
+    fn parse_batch_orders(
+        &self,
+        response: &RestRequestOutcome,
+    ) -> Result<Vec<RequestResult<ExchangeOrderId>>> {
+        let results: Vec<Value> = serde_json::from_str(&response.content)
+            .context("Unable to parse batch orders response content")?;
+
+        results
+            .iter()
+            .map(|result| match result.get("code").and_then(Value::as_i64) {
+                Some(code) => {
+                    let message = result["msg"]
+                        .as_str()
+                        .context("Unable to get `msg` of batch order error")?;
+                    Ok(RequestResult::Error(ExchangeError::new(
+                        ExchangeErrorType::Unknown,
+                        message.to_owned(),
+                        Some(code),
+                    )))
+                }
+                None => {
+                    let id = result["orderId"].to_string();
+                    let id = id.trim_matches('"');
+                    Ok(RequestResult::Success(ExchangeOrderId::new(id.into())))
+                }
+            })
+            .collect()
+    }
+
     fn clarify_error_type(&self, error: &mut ExchangeError) {
         // -1010 ERROR_MSG_RECEIVED
         // -2010 NEW_ORDER_REJECTED
@@ -183,6 +259,8 @@ impl Support for Binance {
         } else if event_type == "ORDER_TRADE_UPDATE" {
             let json_response = data["o"].take();
             self.handle_order_fill(msg, json_response)?;
+        } else if event_type == "ACCOUNT_UPDATE" {
+            self.handle_account_update(data)?;
         } else {
             self.log_unknown_message(self.id, msg);
         }
@@ -262,6 +340,10 @@ impl Support for Binance {
             .with_context(|| format!("Unable parse websocket {:?} uri", role))
     }
 
+    fn get_traffic_fixtures(&self) -> Option<Arc<TrafficFixtures>> {
+        self.traffic_fixtures.clone()
+    }
+
     fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
         self.unified_to_specific.read()[&currency_pair]
     }
@@ -271,7 +353,7 @@ impl Support for Binance {
     }
 
     fn should_log_message(&self, message: &str) -> bool {
-        message.contains("executionReport")
+        message.contains("executionReport") || message.contains("ORDER_TRADE_UPDATE")
     }
 
     fn log_unknown_message(
@@ -314,8 +396,11 @@ impl Support for Binance {
         for symbol in symbols {
             let is_active = symbol["status"] == "TRADING";
 
-            // TODO There is no work with derivatives in current version
-            let is_derivative = false;
+            let is_derivative = self.settings.is_futures();
+            if is_derivative && symbol["contractType"] != "PERPETUAL" {
+                // Delivery contracts have the same base and quote currencies as perpetual ones
+                continue;
+            }
             let base_currency_id = &symbol
                 .get_as_str("baseAsset")
                 .context("Unable to get base currency id from Binance")?;
@@ -338,7 +423,14 @@ impl Support for Binance {
             let amount_currency_code = base;
 
             // TODO There are no balance_currency_code for spot, why does it set here this way?
-            let balance_currency_code = base;
+            let balance_currency_code = match is_derivative {
+                true => symbol
+                    .get_as_str("marginAsset")
+                    .context("Unable to get margin asset from Binance")?
+                    .as_str()
+                    .into(),
+                false => base,
+            };
 
             let mut min_amount = None;
             let mut max_amount = None;
@@ -366,7 +458,9 @@ impl Support for Binance {
                         amount_tick = filter.get_as_decimal("stepSize");
                     }
                     "MIN_NOTIONAL" => {
-                        min_cost = filter.get_as_decimal("minNotional");
+                        min_cost = filter
+                            .get_as_decimal("minNotional")
+                            .or_else(|| filter.get_as_decimal("notional"));
                     }
                     _ => {}
                 }
@@ -420,7 +514,7 @@ impl Support for Binance {
         #[derive(Serialize, Deserialize, Debug)]
         #[serde(rename_all = "camelCase")]
         struct BinanceMyTrade {
-            id: TradeId,
+            id: u64,
             order_id: u64,
             price: Price,
             #[serde(alias = "qty")]
@@ -447,7 +541,7 @@ impl Support for Binance {
                 let fee_currency_code = commission_currency_code.context("There is no suitable currency code to get specific_currency_pair for unified_order_trade converting")?;
                 Ok(OrderTrade::new(
                     ExchangeOrderId::from(self.order_id.to_string().as_ref()),
-                    self.id.clone(),
+                    TradeId::Number(self.id),
                     datetime,
                     self.price,
                     self.amount,
@@ -480,9 +574,9 @@ impl Support for Binance {
         let binance_positions: Vec<BinancePosition> = serde_json::from_str(&response.content)
             .expect("Unable to parse response content for get_active_positions_core request");
 
-        binance_positions
+        self.binance_positions_to_derivative(&binance_positions)
             .into_iter()
-            .map(|x| self.binance_position_to_active_position(x))
+            .map(ActivePosition::new)
             .collect_vec()
     }
 
@@ -499,12 +593,15 @@ impl Support for Binance {
     }
 
     fn parse_get_balance(&self, response: &RestRequestOutcome) -> ExchangeBalancesAndPositions {
-        let binance_account_info: BinanceAccountInfo = serde_json::from_str(&response.content)
-            .expect("Unable to parse response content for get_balance request");
+        if self.settings.is_futures() {
+            let account_info: BinanceFuturesAccountInfo = serde_json::from_str(&response.content)
+                .expect("Unable to parse response content for get_balance_and_position request");
 
-        if self.settings.is_margin_trading {
-            Binance::get_margin_exchange_balances_and_positions(binance_account_info.balances)
+            self.get_futures_exchange_balances_and_positions(account_info)
         } else {
+            let binance_account_info: BinanceAccountInfo = serde_json::from_str(&response.content)
+                .expect("Unable to parse response content for get_balance request");
+
             self.get_spot_exchange_balances_and_positions(binance_account_info.balances)
         }
     }
@@ -672,7 +769,7 @@ impl Binance {
         &self,
         http_params: Vec<(String, String)>,
     ) -> Result<RestRequestOutcome> {
-        let url_path = match self.settings.is_margin_trading {
+        let url_path = match self.settings.is_futures() {
             true => "/fapi/v1/openOrders",
             false => "/api/v3/openOrders",
         };
@@ -683,36 +780,6 @@ impl Binance {
 
         orders
     }
-
-    fn binance_position_to_active_position(
-        &self,
-        binance_position: BinancePosition,
-    ) -> ActivePosition {
-        let currency_pair = self
-            .get_unified_currency_pair(&binance_position.specific_currency_pair)
-            .with_expect(|| {
-                format!(
-                    "Failed to get_unified_currency_pair for {:?}",
-                    binance_position.specific_currency_pair
-                )
-            });
-
-        let side = match binance_position.position_side > dec!(0) {
-            true => OrderSide::Buy,
-            false => OrderSide::Sell,
-        };
-
-        let derivative_position = DerivativePosition::new(
-            currency_pair,
-            binance_position.position_amount,
-            Some(side),
-            dec!(0),
-            binance_position.liquidation_price,
-            binance_position.leverage,
-        );
-
-        ActivePosition::new(derivative_position)
-    }
 }
 
 fn get_order_book_side(levels: &Vec<Value>) -> Result<SortedOrderData> {
@@ -2,6 +2,7 @@ use anyhow::Result;
 use binance::binance::Binance;
 use core_tests::order::OrderProxy;
 use mmb_core::balance_manager::balance_manager::BalanceManager;
+use mmb_core::connectivity::traffic_fixtures::{TrafficFixtures, TrafficMode};
 use mmb_core::exchanges::common::*;
 use mmb_core::exchanges::events::ExchangeEvent;
 use mmb_core::exchanges::general::commission::Commission;
@@ -20,7 +21,9 @@ use tokio::sync::broadcast;
 
 use crate::binance::common::get_default_price;
 use crate::binance::common::get_min_amount;
-use crate::binance::common::{get_binance_credentials, get_timeout_manager};
+use crate::binance::common::{
+    get_binance_credentials, get_timeout_manager, get_traffic_fixtures, has_traffic_fixture,
+};
 
 pub struct BinanceBuilder {
     pub exchange: Arc<Exchange>,
@@ -30,6 +33,18 @@ pub struct BinanceBuilder {
     pub min_amount: Amount,
     pub tx: broadcast::Sender<ExchangeEvent>,
     pub rx: broadcast::Receiver<ExchangeEvent>,
+    _traffic_fixtures_guard: Option<TrafficFixturesGuard>,
+}
+
+/// Saves recorded traffic when test is finished, because exchange can outlive the test
+struct TrafficFixturesGuard(Arc<TrafficFixtures>);
+
+impl Drop for TrafficFixturesGuard {
+    fn drop(&mut self) {
+        if self.0.mode() == TrafficMode::Record {
+            self.0.save().expect("Failed to save recorded traffic");
+        }
+    }
 }
 
 impl BinanceBuilder {
@@ -40,8 +55,27 @@ impl BinanceBuilder {
         commission: Commission,
         need_to_clean_up: bool,
     ) -> Result<Self> {
+        let settings = Self::make_default_settings(exchange_account_id)?;
+
+        Self::try_new_with_settings(
+            settings,
+            exchange_account_id,
+            cancellation_token,
+            features,
+            commission,
+            need_to_clean_up,
+        )
+        .await
+    }
+
+    /// Settings with credentials and default currency pair for tests
+    pub fn make_default_settings(
+        exchange_account_id: ExchangeAccountId,
+    ) -> Result<ExchangeSettings> {
         let (api_key, secret_key) = match get_binance_credentials() {
             Ok((api_key, secret_key)) => (api_key, secret_key),
+            // Recorded traffic is replayed without real credentials
+            Err(_) if has_traffic_fixture() => ("api_key".to_string(), "secret_key".to_string()),
             Err(_) => ("".to_string(), "".to_string()),
         };
         if api_key == "" || secret_key == "" {
@@ -60,15 +94,7 @@ impl BinanceBuilder {
             currency_pair: None,
         }]);
 
-        Self::try_new_with_settings(
-            settings,
-            exchange_account_id,
-            cancellation_token,
-            features,
-            commission,
-            need_to_clean_up,
-        )
-        .await
+        Ok(settings)
     }
 
     pub async fn try_new_with_settings(
@@ -82,15 +108,23 @@ impl BinanceBuilder {
         let application_manager = ApplicationManager::new(cancellation_token.clone());
         let (tx, rx) = broadcast::channel(10);
 
-        settings.websocket_channels = vec!["depth".into(), "trade".into()];
+        if settings.websocket_channels.is_empty() {
+            settings.websocket_channels = vec!["depth".into(), "trade".into()];
+        }
+
+        let traffic_fixtures = get_traffic_fixtures();
 
-        let binance = Box::new(Binance::new(
+        let mut binance = Binance::new(
             exchange_account_id,
             settings.clone(),
             tx.clone(),
             application_manager.clone(),
             false,
-        ));
+        );
+        if let Some(traffic_fixtures) = &traffic_fixtures {
+            binance = binance.with_traffic_fixtures(traffic_fixtures.clone());
+        }
+        let binance = Box::new(binance);
 
         let hosts = binance.hosts.clone();
 
@@ -105,8 +139,9 @@ impl BinanceBuilder {
             timeout_manager,
             commission,
         );
-        exchange.clone().connect().await;
+        // Symbols are needed before connection for subscription to market data streams
         exchange.build_symbols(&settings.currency_pairs).await;
+        exchange.clone().connect().await;
 
         let currency_pair_to_symbol_converter = CurrencyPairToSymbolConverter::new(
             hashmap![ exchange_account_id => exchange.clone()  ],
@@ -126,9 +161,21 @@ impl BinanceBuilder {
 
         let currency_pair =
             get_specific_currency_pair_for_tests(&exchange, OrderProxy::default_currency_pair());
-        let default_price = get_default_price(currency_pair, &hosts, &settings.api_key).await;
-        let min_amount =
-            get_min_amount(currency_pair, &hosts, &settings.api_key, default_price).await;
+        let default_price = get_default_price(
+            currency_pair,
+            &hosts,
+            &settings.api_key,
+            traffic_fixtures.clone(),
+        )
+        .await;
+        let min_amount = get_min_amount(
+            currency_pair,
+            &hosts,
+            &settings.api_key,
+            default_price,
+            traffic_fixtures.clone(),
+        )
+        .await;
 
         Ok(Self {
             exchange,
@@ -138,6 +185,7 @@ impl BinanceBuilder {
             min_amount,
             tx,
             rx,
+            _traffic_fixtures_guard: traffic_fixtures.map(TrafficFixturesGuard),
         })
     }
 }
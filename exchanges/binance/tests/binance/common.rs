@@ -8,6 +8,7 @@ use mmb_core::exchanges::traits::ExchangeClientBuilder;
 use mmb_utils::hashmap;
 use mmb_utils::infrastructure::WithExpect;
 
+use mmb_core::connectivity::traffic_fixtures::TrafficFixtures;
 use mmb_core::exchanges::hosts::Hosts;
 use mmb_core::{
     exchanges::common::ExchangeId,
@@ -25,6 +26,7 @@ use mmb_core::{
 use mmb_utils::value_to_decimal::GetOrErr;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 pub(crate) fn get_binance_credentials() -> Result<(String, String)> {
     let api_key = std::env::var("BINANCE_API_KEY");
@@ -78,13 +80,61 @@ pub(crate) fn get_timeout_manager(exchange_account_id: ExchangeAccountId) -> Arc
     TimeoutManager::new(hashmap![exchange_account_id => request_timeout_manager])
 }
 
+/// Environment variable which enables recording of Binance traffic to test fixtures.
+/// Credentials are required for recording
+const RECORD_TRAFFIC_ENV: &str = "MMB_RECORD_TRAFFIC";
+
+// Client order ids are generated randomly for each test run
+const GENERATED_TRAFFIC_PARAMS: &[&str] = &["newClientOrderId", "origClientOrderId"];
+
+/// Fixture path is built from the name of current test thread, e.g.
+/// `binance::create_order::create_successfully` -> `tests/binance/fixtures/create_order/create_successfully.json`
+fn get_traffic_fixture_path() -> Option<PathBuf> {
+    let thread = std::thread::current();
+    let test_name = thread.name()?.strip_prefix("binance::")?;
+
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/binance/fixtures");
+    path.push(format!("{}.json", test_name.replace("::", "/")));
+
+    Some(path)
+}
+
+pub(crate) fn has_traffic_fixture() -> bool {
+    get_traffic_fixture_path().map_or(false, |path| path.exists())
+}
+
+/// Traffic is recorded if `MMB_RECORD_TRAFFIC` is set and credentials are available,
+/// otherwise recorded fixture of current test is replayed if it exists
+pub(crate) fn get_traffic_fixtures() -> Option<Arc<TrafficFixtures>> {
+    let path = get_traffic_fixture_path()?;
+
+    let traffic_fixtures =
+        if std::env::var(RECORD_TRAFFIC_ENV).is_ok() && get_binance_credentials().is_ok() {
+            TrafficFixtures::record(path)
+        } else if path.exists() {
+            TrafficFixtures::replay(&path)
+                .with_expect(|| format!("Failed to load traffic fixture {}", path.display()))
+        } else {
+            return None;
+        };
+
+    Some(Arc::new(
+        traffic_fixtures.with_generated_params(GENERATED_TRAFFIC_PARAMS),
+    ))
+}
+
 async fn send_request(
     hosts: &Hosts,
     api_key: &String,
     url_path: &str,
     http_params: &Vec<(String, String)>,
+    traffic_fixtures: Option<Arc<TrafficFixtures>>,
 ) -> String {
-    let rest_client = RestClient::new();
+    let rest_client = match traffic_fixtures {
+        Some(traffic_fixtures) => RestClient::with_traffic_fixtures(traffic_fixtures),
+        None => RestClient::new(),
+    };
 
     let full_url = rest_client::build_uri(&hosts.rest_host, url_path, http_params)
         .expect("build_uri is failed");
@@ -102,6 +152,7 @@ pub(crate) async fn get_default_price(
     currency_pair: SpecificCurrencyPair,
     hosts: &Hosts,
     api_key: &String,
+    traffic_fixtures: Option<Arc<TrafficFixtures>>,
 ) -> Price {
     #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
     struct OrderBook {
@@ -113,6 +164,7 @@ pub(crate) async fn get_default_price(
         api_key,
         "/api/v3/depth",
         &vec![("symbol".to_owned(), currency_pair.as_str().to_owned())],
+        traffic_fixtures,
     )
     .await;
 
@@ -135,12 +187,14 @@ pub(crate) async fn get_min_amount(
     hosts: &Hosts,
     api_key: &String,
     price: Price,
+    traffic_fixtures: Option<Arc<TrafficFixtures>>,
 ) -> Amount {
     let data = send_request(
         hosts,
         api_key,
         "/api/v3/exchangeInfo",
         &vec![("symbol".to_owned(), currency_pair.as_str().to_owned())],
+        traffic_fixtures,
     )
     .await;
 
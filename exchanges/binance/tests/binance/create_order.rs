@@ -2,9 +2,11 @@ use mmb_core::exchanges::common::*;
 use mmb_core::exchanges::general::features::*;
 use mmb_core::exchanges::{events::AllowedEventSourceType, general::commission::Commission};
 use mmb_core::orders::event::OrderEventType;
+use mmb_core::orders::order::{OrderOptions, OrderStatus, TimeInForce};
 use mmb_utils::cancellation_token::CancellationToken;
 use mmb_utils::logger::init_logger;
 use rust_decimal_macros::*;
+use std::time::Duration;
 
 use mmb_core::exchanges::events::ExchangeEvent;
 
@@ -127,3 +129,64 @@ async fn should_fail() {
         }
     }
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn expired_immediate_or_cancel_order() {
+    init_logger();
+
+    let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+    let binance_builder = match BinanceBuilder::try_new(
+        exchange_account_id,
+        CancellationToken::default(),
+        ExchangeFeatures::new(
+            OpenOrdersType::AllCurrencyPair,
+            RestFillsFeatures::default(),
+            OrderFeatures {
+                supports_immediate_or_cancel: true,
+                ..OrderFeatures::default()
+            },
+            OrderTradeOption::default(),
+            WebSocketOptions::default(),
+            false,
+            true,
+            AllowedEventSourceType::default(),
+            AllowedEventSourceType::default(),
+        ),
+        Commission::default(),
+        true,
+    )
+    .await
+    {
+        Ok(binance_builder) => binance_builder,
+        Err(_) => return,
+    };
+
+    // Price is below the best ask, so the order isn't filled and expires immediately
+    let mut order_proxy = OrderProxy::new(
+        exchange_account_id,
+        Some("FromExpiredImmediateOrCancelOrderTest".to_owned()),
+        CancellationToken::default(),
+        binance_builder.default_price,
+        binance_builder.min_amount,
+    );
+    order_proxy.options = OrderOptions::with_time_in_force(TimeInForce::ImmediateOrCancel);
+
+    let order_ref = order_proxy
+        .create_order(binance_builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    let _ = tokio::time::timeout(
+        Duration::from_secs(5),
+        binance_builder.exchange.clone().wait_order_finish(
+            &order_ref,
+            None,
+            CancellationToken::default(),
+        ),
+    )
+    .await
+    .expect("Expired order should be finished")
+    .expect("in test");
+
+    assert_eq!(order_ref.status(), OrderStatus::Canceled);
+}
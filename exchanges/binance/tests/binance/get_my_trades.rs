@@ -0,0 +1,63 @@
+use mmb_core::exchanges::common::*;
+use mmb_core::exchanges::events::AllowedEventSourceType;
+use mmb_core::exchanges::general::commission::Commission;
+use mmb_core::exchanges::general::exchange::RequestResult;
+use mmb_core::exchanges::general::features::*;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::logger::init_logger;
+use rust_decimal_macros::dec;
+
+use crate::binance::binance_builder::BinanceBuilder;
+use core_tests::order::OrderProxy;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn get_my_trades_successfully() {
+    init_logger();
+
+    let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+    let binance_builder = match BinanceBuilder::try_new(
+        exchange_account_id,
+        CancellationToken::default(),
+        ExchangeFeatures::new(
+            OpenOrdersType::AllCurrencyPair,
+            RestFillsFeatures::default(),
+            OrderFeatures::default(),
+            OrderTradeOption::default(),
+            WebSocketOptions::default(),
+            false,
+            true,
+            AllowedEventSourceType::default(),
+            AllowedEventSourceType::default(),
+        ),
+        Commission::default(),
+        false,
+    )
+    .await
+    {
+        Ok(binance_builder) => binance_builder,
+        Err(_) => return,
+    };
+
+    let symbol = binance_builder
+        .exchange
+        .symbols
+        .get(&OrderProxy::default_currency_pair())
+        .expect("Symbol for default currency pair should exist")
+        .clone();
+
+    let my_trades = binance_builder
+        .exchange
+        .get_my_trades(&symbol, None)
+        .await
+        .expect("in test");
+
+    match my_trades {
+        RequestResult::Success(my_trades) => {
+            for my_trade in my_trades {
+                assert!(my_trade.price > dec!(0));
+                assert!(my_trade.amount > dec!(0));
+            }
+        }
+        RequestResult::Error(error) => panic!("Unable to get my trades: {:?}", error),
+    }
+}
@@ -3,8 +3,10 @@ pub mod binance_builder;
 pub mod cancel_order;
 pub mod common;
 pub mod create_order;
+pub mod get_my_trades;
 pub mod get_open_orders;
 pub mod get_order_info;
+pub mod order_book_snapshot;
 pub mod request_symbol;
 pub mod should_reconnect_normally;
 pub mod wait_cancel_order;
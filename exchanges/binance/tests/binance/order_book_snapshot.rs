@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use mmb_core::exchanges::common::*;
+use mmb_core::exchanges::events::{AllowedEventSourceType, ExchangeEvent};
+use mmb_core::exchanges::general::commission::Commission;
+use mmb_core::exchanges::general::features::*;
+use mmb_core::order_book::event::EventType;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::logger::init_logger;
+
+use crate::binance::binance_builder::BinanceBuilder;
+use core_tests::order::OrderProxy;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn snapshot_received_successfully() {
+    init_logger();
+
+    let exchange_account_id: ExchangeAccountId = "Binance_0".parse().expect("in test");
+    let mut settings = match BinanceBuilder::make_default_settings(exchange_account_id) {
+        Ok(settings) => settings,
+        Err(_) => return,
+    };
+    settings.websocket_channels = vec!["depth20".into()];
+
+    let mut binance_builder = BinanceBuilder::try_new_with_settings(
+        settings,
+        exchange_account_id,
+        CancellationToken::default(),
+        ExchangeFeatures::new(
+            OpenOrdersType::AllCurrencyPair,
+            RestFillsFeatures::default(),
+            OrderFeatures::default(),
+            OrderTradeOption::default(),
+            WebSocketOptions::default(),
+            false,
+            true,
+            AllowedEventSourceType::default(),
+            AllowedEventSourceType::default(),
+        ),
+        Commission::default(),
+        false,
+    )
+    .await
+    .expect("in test");
+
+    let order_book_event = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match binance_builder.rx.recv().await.expect("in test") {
+                ExchangeEvent::OrderBookEvent(order_book_event) => return order_book_event,
+                _ => continue,
+            }
+        }
+    })
+    .await
+    .expect("OrderBookEvent had to be occurred");
+
+    assert_eq!(order_book_event.exchange_account_id, exchange_account_id);
+    assert_eq!(
+        order_book_event.currency_pair,
+        OrderProxy::default_currency_pair()
+    );
+    assert!(matches!(order_book_event.event_type, EventType::Snapshot));
+    assert!(!order_book_event.data.asks.is_empty());
+    assert!(!order_book_event.data.bids.is_empty());
+}
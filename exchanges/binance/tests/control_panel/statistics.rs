@@ -99,7 +99,7 @@ async fn orders_cancelled() {
     exchange_settings.secret_key = secret_key;
     let exchange_account_id = exchange_settings.exchange_account_id;
 
-    let is_margin_trading = exchange_settings.is_margin_trading;
+    let is_futures = exchange_settings.is_futures();
     let api_key = exchange_settings.api_key.clone();
 
     let init_settings = InitSettings::Directly(settings.clone());
@@ -131,15 +131,17 @@ async fn orders_cancelled() {
 
     let price = get_default_price(
         get_specific_currency_pair_for_tests(&exchange, test_currency_pair),
-        &Binance::make_hosts(is_margin_trading),
+        &Binance::make_hosts(is_futures),
         &api_key,
+        None,
     )
     .await;
     let amount = get_min_amount(
         get_specific_currency_pair_for_tests(&exchange, test_currency_pair),
-        &Binance::make_hosts(is_margin_trading),
+        &Binance::make_hosts(is_futures),
         &api_key,
         price,
+        None,
     )
     .await;
     let order = OrderProxy::new(
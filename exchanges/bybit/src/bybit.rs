@@ -0,0 +1,701 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use dashmap::DashMap;
+use hex;
+use hmac::{Hmac, Mac, NewMac};
+use itertools::Itertools;
+use mmb_utils::time::get_current_milliseconds;
+use mmb_utils::DateTime;
+use parking_lot::{Mutex, RwLock};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::Value;
+use sha2::Sha256;
+use tokio::sync::broadcast;
+
+use super::support::{BybitCoinBalance, BybitOrderInfo, BybitPosition};
+use mmb_core::exchanges::common::{Amount, Price};
+use mmb_core::exchanges::events::{
+    BalanceUpdateEvent, ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent,
+    LiquidationPriceEvent, TradeId,
+};
+use mmb_core::exchanges::general::features::{
+    BalancePositionOption, OrderFeatures, OrderTradeOption, RestFillsFeatures, RestFillsType,
+    WebSocketOptions,
+};
+use mmb_core::exchanges::hosts::Hosts;
+use mmb_core::exchanges::rest_client::RestClient;
+use mmb_core::exchanges::traits::ExchangeClientBuilderResult;
+use mmb_core::exchanges::{
+    common::CurrencyCode,
+    general::features::{ExchangeFeatures, OpenOrdersType},
+    timeouts::requests_timeout_manager_factory::RequestTimeoutArguments,
+};
+use mmb_core::exchanges::{common::CurrencyId, general::exchange::BoxExchangeClient};
+use mmb_core::exchanges::{
+    common::{CurrencyPair, ExchangeAccountId, SpecificCurrencyPair},
+    events::AllowedEventSourceType,
+};
+use mmb_core::exchanges::{general::handlers::handle_order_filled::FillEventData, rest_client};
+use mmb_core::lifecycle::application_manager::ApplicationManager;
+use mmb_core::misc::derivative_position::DerivativePosition;
+use mmb_core::orders::fill::EventSourceType;
+use mmb_core::orders::order::*;
+use mmb_core::settings::ExchangeSettings;
+use mmb_core::{exchanges::traits::ExchangeClientBuilder, orders::fill::OrderFillType};
+
+/// Connector to Bybit USDT perpetual contracts
+pub struct Bybit {
+    pub settings: ExchangeSettings,
+    pub hosts: Hosts,
+    pub id: ExchangeAccountId,
+    pub order_created_callback:
+        Mutex<Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>>,
+    pub order_cancelled_callback:
+        Mutex<Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>>,
+    pub handle_order_filled_callback: Mutex<Box<dyn FnMut(FillEventData) + Send + Sync>>,
+    pub handle_trade_callback: Mutex<
+        Box<dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync>,
+    >,
+
+    pub unified_to_specific: RwLock<HashMap<CurrencyPair, SpecificCurrencyPair>>,
+    pub specific_to_unified: RwLock<HashMap<SpecificCurrencyPair, CurrencyPair>>,
+    pub supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    // Currencies in which margin, PnL and commissions of contracts are calculated
+    pub(super) settlement_currencies: DashMap<SpecificCurrencyPair, CurrencyCode>,
+    // Currencies used for trading according to user settings
+    pub traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+    // Last known available balances, funding changes them without any message about balance
+    pub(super) wallet_balances: DashMap<CurrencyCode, Amount>,
+
+    pub(super) application_manager: Arc<ApplicationManager>,
+
+    pub(super) events_channel: broadcast::Sender<ExchangeEvent>,
+
+    pub(super) subscribe_to_market_data: bool,
+
+    pub(super) rest_client: RestClient,
+}
+
+impl Bybit {
+    pub fn new(
+        id: ExchangeAccountId,
+        settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        application_manager: Arc<ApplicationManager>,
+    ) -> Self {
+        Self {
+            id,
+            order_created_callback: Mutex::new(Box::new(|_, _, _| {})),
+            order_cancelled_callback: Mutex::new(Box::new(|_, _, _| {})),
+            handle_order_filled_callback: Mutex::new(Box::new(|_| {})),
+            handle_trade_callback: Mutex::new(Box::new(|_, _, _, _, _, _| {})),
+            unified_to_specific: Default::default(),
+            specific_to_unified: Default::default(),
+            supported_currencies: Default::default(),
+            settlement_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+            wallet_balances: Default::default(),
+            subscribe_to_market_data: settings.subscribe_to_market_data,
+            settings,
+            hosts: Self::make_hosts(),
+            events_channel,
+            application_manager,
+            rest_client: RestClient::new(),
+        }
+    }
+
+    pub fn make_hosts() -> Hosts {
+        Hosts {
+            web_socket_host: "wss://stream.bybit.com/realtime_public",
+            web_socket2_host: "wss://stream.bybit.com/realtime_private",
+            rest_host: "https://api.bybit.com",
+        }
+    }
+
+    pub(super) fn to_server_order_side(side: OrderSide) -> String {
+        match side {
+            OrderSide::Buy => "Buy".to_owned(),
+            OrderSide::Sell => "Sell".to_owned(),
+        }
+    }
+
+    pub(super) fn to_local_order_side(side: &str) -> Result<OrderSide> {
+        match side {
+            "Buy" => Ok(OrderSide::Buy),
+            "Sell" => Ok(OrderSide::Sell),
+            _ => bail!("Unexpected order side {}", side),
+        }
+    }
+
+    // According to https://bybit-exchange.github.io/docs/linear/#order-status-order_status-stop_order_status
+    fn to_local_order_status(status: &str) -> Result<OrderStatus> {
+        match status {
+            "Created" | "New" | "PartiallyFilled" | "Untriggered" | "Triggered" | "Active" => {
+                Ok(OrderStatus::Created)
+            }
+            "Filled" => Ok(OrderStatus::Completed),
+            "PendingCancel" => Ok(OrderStatus::Canceling),
+            "Cancelled" | "Rejected" | "Deactivated" => Ok(OrderStatus::Canceled),
+            _ => bail!("Unexpected order status {}", status),
+        }
+    }
+
+    pub(super) fn to_server_order_type(order_type: OrderType) -> String {
+        match order_type {
+            OrderType::Limit => "Limit".to_owned(),
+            OrderType::Market => "Market".to_owned(),
+            unexpected_variant => panic!("{:?} are not expected", unexpected_variant),
+        }
+    }
+
+    pub(super) fn to_server_time_in_force(
+        order_type: OrderType,
+        execution_type: OrderExecutionType,
+    ) -> String {
+        match (order_type, execution_type) {
+            (OrderType::Market, _) => "ImmediateOrCancel".to_owned(),
+            (_, OrderExecutionType::MakerOnly) => "PostOnly".to_owned(),
+            (_, OrderExecutionType::None) => "GoodTillCancel".to_owned(),
+        }
+    }
+
+    fn generate_signature(&self, data: String) -> Result<String> {
+        let mut hmac = Hmac::<Sha256>::new_from_slice(self.settings.secret_key.as_bytes())
+            .context("Unable to calculate hmac")?;
+        hmac.update(data.as_bytes());
+
+        Ok(hex::encode(hmac.finalize().into_bytes()))
+    }
+
+    /// Bybit signs alphabetically sorted parameters including api key and timestamp
+    pub(super) fn add_authentification_headers(
+        &self,
+        parameters: &mut rest_client::HttpParams,
+    ) -> Result<()> {
+        let time_stamp = get_current_milliseconds();
+        parameters.push(("api_key".to_owned(), self.settings.api_key.clone()));
+        parameters.push(("timestamp".to_owned(), time_stamp.to_string()));
+        parameters.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        let message_to_sign = rest_client::to_http_string(parameters);
+        let signature = self.generate_signature(message_to_sign)?;
+        parameters.push(("sign".to_owned(), signature));
+
+        Ok(())
+    }
+
+    pub(super) fn get_ws_authentification_params(
+        &self,
+        expires: u128,
+    ) -> Result<rest_client::HttpParams> {
+        let signature = self.generate_signature(format!("GET/realtime{}", expires))?;
+
+        Ok(vec![
+            ("api_key".to_owned(), self.settings.api_key.clone()),
+            ("expires".to_owned(), expires.to_string()),
+            ("signature".to_owned(), signature),
+        ])
+    }
+
+    pub fn get_unified_currency_pair(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyPair> {
+        self.specific_to_unified
+            .read()
+            .get(currency_pair)
+            .with_context(|| {
+                format!(
+                    "Not found currency pair '{:?}' in {}",
+                    currency_pair, self.id
+                )
+            })
+            .cloned()
+    }
+
+    pub(super) fn get_settlement_currency_code(
+        &self,
+        currency_pair: &SpecificCurrencyPair,
+    ) -> Result<CurrencyCode> {
+        self.settlement_currencies
+            .get(currency_pair)
+            .map(|some| *some.value())
+            .with_context(|| {
+                format!(
+                    "Not found settlement currency for '{:?}' in {}",
+                    currency_pair, self.id
+                )
+            })
+    }
+
+    pub(crate) fn get_currency_code(&self, currency_id: &CurrencyId) -> Option<CurrencyCode> {
+        self.supported_currencies
+            .get(currency_id)
+            .map(|some| *some.value())
+    }
+
+    pub(super) fn specific_order_info_to_unified(
+        &self,
+        specific: &BybitOrderInfo,
+    ) -> Result<OrderInfo> {
+        let average_fill_price = match specific.cum_exec_qty.is_zero() {
+            true => dec!(0),
+            false => specific.cum_exec_value / specific.cum_exec_qty,
+        };
+
+        let commission_currency_code =
+            self.get_settlement_currency_code(&specific.specific_currency_pair)?;
+
+        Ok(OrderInfo::new(
+            self.get_unified_currency_pair(&specific.specific_currency_pair)?,
+            specific.order_id.as_str().into(),
+            specific.order_link_id.as_str().into(),
+            Self::to_local_order_side(&specific.side)?,
+            Self::to_local_order_status(&specific.order_status)?,
+            specific.price,
+            specific.qty,
+            average_fill_price,
+            specific.cum_exec_qty,
+            Some(commission_currency_code.to_string()),
+            None,
+            Some(specific.cum_exec_fee),
+        ))
+    }
+
+    pub(super) fn handle_order_updates(&self, msg_to_log: &str, data: Value) -> Result<()> {
+        let orders: Vec<BybitOrderInfo> =
+            serde_json::from_value(data).context("Unable to parse order updates")?;
+
+        for order in orders {
+            if order.order_link_id.is_empty() {
+                log::info!(
+                    "Skipped update of order {} without client order id on {}",
+                    order.order_id,
+                    self.id
+                );
+                continue;
+            }
+
+            let client_order_id = order.order_link_id.as_str().into();
+            let exchange_order_id = order.order_id.as_str().into();
+            match order.order_status.as_str() {
+                "New" => (&self.order_created_callback).lock()(
+                    client_order_id,
+                    exchange_order_id,
+                    EventSourceType::WebSocket,
+                ),
+                "Cancelled" => (&self.order_cancelled_callback).lock()(
+                    client_order_id,
+                    exchange_order_id,
+                    EventSourceType::WebSocket,
+                ),
+                "Rejected" => {
+                    // We get notification of rejected orders from the rest responses
+                }
+                "Created" | "PartiallyFilled" | "Filled" | "PendingCancel" => {
+                    // Fills are handled by executions stream
+                }
+                status => log::error!(
+                    "Unexpected order status {} in message {}",
+                    status,
+                    msg_to_log
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn handle_executions(&self, data: &Value) -> Result<()> {
+        let executions = data
+            .as_array()
+            .ok_or(anyhow!("Unable to parse executions"))?;
+
+        for execution in executions {
+            let execution_type = execution["exec_type"]
+                .as_str()
+                .ok_or(anyhow!("Unable to parse execution type"))?;
+            let fill_type = Self::get_fill_type(execution_type)?;
+
+            if fill_type == OrderFillType::Funding {
+                // Funding isn't related to any order so it affects balances only
+                self.handle_funding(execution)?;
+                continue;
+            }
+
+            let event_data = self.prepare_data_for_fill_handler(execution, fill_type)?;
+            (&self.handle_order_filled_callback).lock()(event_data);
+        }
+
+        Ok(())
+    }
+
+    fn prepare_data_for_fill_handler(
+        &self,
+        execution: &Value,
+        fill_type: OrderFillType,
+    ) -> Result<FillEventData> {
+        let specific_currency_pair = execution["symbol"]
+            .as_str()
+            .ok_or(anyhow!("Unable to parse symbol"))?
+            .into();
+        let currency_pair = self.get_unified_currency_pair(&specific_currency_pair)?;
+        let exchange_order_id = execution["order_id"]
+            .as_str()
+            .ok_or(anyhow!("Unable to parse exchange order id"))?;
+        let client_order_id = execution["order_link_id"]
+            .as_str()
+            .ok_or(anyhow!("Unable to parse client order id"))?;
+        let trade_id = execution["exec_id"]
+            .as_str()
+            .ok_or(anyhow!("Unable to parse execution id"))?;
+        let fill_price = value_to_decimal(&execution["price"])
+            .ok_or(anyhow!("Unable to parse last filled price"))?;
+        let fill_amount = value_to_decimal(&execution["exec_qty"])
+            .ok_or(anyhow!("Unable to parse last filled amount"))?;
+        let order_amount = value_to_decimal(&execution["order_qty"])
+            .ok_or(anyhow!("Unable to parse order amount"))?;
+        let leaves_amount = value_to_decimal(&execution["leaves_qty"])
+            .ok_or(anyhow!("Unable to parse leaves amount"))?;
+        let commission_amount = value_to_decimal(&execution["exec_fee"])
+            .ok_or(anyhow!("Unable to parse commission amount"))?;
+        let is_maker = execution["is_maker"]
+            .as_bool()
+            .ok_or(anyhow!("Unable to parse trade side"))?;
+        let order_side = Self::to_local_order_side(
+            execution["side"]
+                .as_str()
+                .ok_or(anyhow!("Unable to parse order side"))?,
+        )?;
+        let fill_date = execution["trade_time"]
+            .as_str()
+            .ok_or(anyhow!("Unable to parse trade time"))?
+            .parse::<DateTime>()
+            .context("Unable to parse trade time")?;
+
+        // Liquidations and auto-deleveraging aren't created by us, so there are no client order ids
+        let client_order_id = match fill_type {
+            OrderFillType::UserTrade if !client_order_id.is_empty() => Some(client_order_id.into()),
+            _ => None,
+        };
+        let order_role = if is_maker {
+            OrderRole::Maker
+        } else {
+            OrderRole::Taker
+        };
+
+        Ok(FillEventData {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::String(trade_id.into())),
+            client_order_id,
+            exchange_order_id: exchange_order_id.into(),
+            fill_price,
+            fill_amount,
+            is_diff: true,
+            total_filled_amount: Some(order_amount - leaves_amount),
+            order_role: Some(order_role),
+            commission_currency_code: Some(
+                self.get_settlement_currency_code(&specific_currency_pair)?,
+            ),
+            commission_rate: None,
+            commission_amount: Some(commission_amount),
+            fill_type,
+            trade_currency_pair: Some(currency_pair),
+            order_side: Some(order_side),
+            order_amount: Some(order_amount),
+            fill_date: Some(fill_date),
+        })
+    }
+
+    // According to https://bybit-exchange.github.io/docs/linear/#exec-type-exec_type
+    pub(super) fn get_fill_type(raw_type: &str) -> Result<OrderFillType> {
+        match raw_type {
+            "Trade" => Ok(OrderFillType::UserTrade),
+            "BustTrade" => Ok(OrderFillType::Liquidation),
+            "AdlTrade" => Ok(OrderFillType::ClosePosition),
+            "Funding" => Ok(OrderFillType::Funding),
+            _ => bail!("Unable to map trade type {}", raw_type),
+        }
+    }
+
+    pub(super) fn handle_positions(&self, data: Value) -> Result<()> {
+        let positions: Vec<BybitPosition> =
+            serde_json::from_value(data).context("Unable to parse positions")?;
+
+        for position in positions {
+            let side = match position.get_side()? {
+                Some(side) => side,
+                None => continue,
+            };
+
+            let event = LiquidationPriceEvent::new(
+                Utc::now(),
+                self.id,
+                self.get_unified_currency_pair(&position.specific_currency_pair)?,
+                position.liquidation_price,
+                position.entry_price,
+                side,
+            );
+            self.send_event(ExchangeEvent::LiquidationPrice(event))?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_funding(&self, execution: &Value) -> Result<()> {
+        log::info!("Funding execution on {}: {}", self.id, execution);
+
+        let specific_currency_pair = execution["symbol"]
+            .as_str()
+            .ok_or(anyhow!("Unable to parse symbol"))?
+            .into();
+        let currency_code = self.get_settlement_currency_code(&specific_currency_pair)?;
+        // Positive fee is paid by us and negative one is received
+        let funding_fee = value_to_decimal(&execution["exec_fee"])
+            .ok_or(anyhow!("Unable to parse funding fee"))?;
+
+        match self.wallet_balances.get_mut(&currency_code) {
+            Some(mut balance) => *balance -= funding_fee,
+            None => {
+                log::warn!(
+                    "Balance of {} isn't received yet on {}, so funding can't be applied",
+                    currency_code,
+                    self.id
+                );
+                return Ok(());
+            }
+        }
+
+        self.send_balance_update()
+    }
+
+    pub(super) fn handle_wallet(&self, data: Value) -> Result<()> {
+        let wallet_balances: Vec<BybitCoinBalance> =
+            serde_json::from_value(data).context("Unable to parse wallet balances")?;
+
+        // USDT perpetual contracts have single USDT wallet, so messages don't contain coin
+        let currency_code = match self.get_currency_code(&"USDT".into()) {
+            Some(currency_code) => currency_code,
+            None => bail!("USDT isn't supported on {}", self.id),
+        };
+        let balances = wallet_balances
+            .into_iter()
+            .map(|wallet_balance| ExchangeBalance {
+                currency_code,
+                balance: wallet_balance.available_balance,
+            })
+            .collect_vec();
+        self.update_wallet_balances(&balances);
+
+        self.send_balance_update()
+    }
+
+    pub(super) fn update_wallet_balances(&self, balances: &[ExchangeBalance]) {
+        for balance in balances {
+            let _ = self
+                .wallet_balances
+                .insert(balance.currency_code, balance.balance);
+        }
+    }
+
+    fn send_balance_update(&self) -> Result<()> {
+        let balances = self
+            .wallet_balances
+            .iter()
+            .map(|balance| ExchangeBalance {
+                currency_code: *balance.key(),
+                balance: *balance.value(),
+            })
+            .collect_vec();
+
+        self.send_event(ExchangeEvent::BalanceUpdate(BalanceUpdateEvent {
+            exchange_account_id: self.id,
+            balances_and_positions: ExchangeBalancesAndPositions {
+                balances,
+                positions: None,
+            },
+        }))
+    }
+
+    pub(super) fn bybit_position_to_derivative(
+        &self,
+        position: &BybitPosition,
+    ) -> Result<Option<DerivativePosition>> {
+        let side = match position.get_side()? {
+            Some(side) => side,
+            None => return Ok(None),
+        };
+
+        let amount = match side {
+            OrderSide::Buy => position.size,
+            OrderSide::Sell => -position.size,
+        };
+
+        Ok(Some(DerivativePosition::new(
+            self.get_unified_currency_pair(&position.specific_currency_pair)?,
+            amount,
+            Some(side),
+            position.entry_price,
+            position.liquidation_price,
+            position.leverage,
+        )))
+    }
+
+    pub(super) fn send_event(&self, event: ExchangeEvent) -> Result<()> {
+        match self.events_channel.send(event) {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                let msg = format!("Unable to send exchange event in {}: {}", self.id, error);
+                log::error!("{}", msg);
+                self.application_manager
+                    .clone()
+                    .spawn_graceful_shutdown(msg.clone());
+                Err(anyhow!(msg))
+            }
+        }
+    }
+
+    pub(super) fn build_subscription_message(topics: Vec<String>) -> String {
+        format!(
+            r#"{{"op":"subscribe","args":[{}]}}"#,
+            topics.iter().map(|x| format!(r#""{}""#, x)).join(",")
+        )
+    }
+}
+
+/// Bybit sends numbers as json strings or as json numbers depending on endpoint
+pub(super) fn value_to_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(value) => value.parse().ok(),
+        Value::Number(value) => value.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+pub struct BybitBuilder;
+
+impl ExchangeClientBuilder for BybitBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        application_manager: Arc<ApplicationManager>,
+    ) -> ExchangeClientBuilderResult {
+        let exchange_account_id = exchange_settings.exchange_account_id;
+
+        let mut features = ExchangeFeatures::new(
+            OpenOrdersType::OneCurrencyPair,
+            RestFillsFeatures::new(RestFillsType::MyTrades),
+            OrderFeatures {
+                maker_only: true,
+                supports_get_order_info_by_client_order_id: true,
+                ..OrderFeatures::default()
+            },
+            OrderTradeOption {
+                supports_trade_time: true,
+                supports_tick_direction: true,
+                supports_my_trades_from_time: true,
+                ..OrderTradeOption::default()
+            },
+            WebSocketOptions::new(true, true, false, true),
+            false,
+            true,
+            AllowedEventSourceType::All,
+            AllowedEventSourceType::All,
+        );
+        features.balance_position_option = BalancePositionOption::IndividualRequests;
+
+        ExchangeClientBuilderResult {
+            client: Box::new(Bybit::new(
+                exchange_account_id,
+                exchange_settings,
+                events_channel.clone(),
+                application_manager,
+            )) as BoxExchangeClient,
+            features,
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        RequestTimeoutArguments::from_requests_per_minute(100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_utils::cancellation_token::CancellationToken;
+
+    fn create_bybit() -> Bybit {
+        // Credentials gotten from Bybit API example
+        let exchange_account_id: ExchangeAccountId = "Bybit_0".parse().expect("in test");
+        let settings = ExchangeSettings::new_short(
+            exchange_account_id,
+            "B2Rou0PLPpGqcU0Vu2".into(),
+            "t7T0YlFnYXk0Fx3JswQsDrViLg1Gh3DUU5Mr".into(),
+            true,
+        );
+
+        let (tx, _) = broadcast::channel(10);
+        Bybit::new(
+            exchange_account_id,
+            settings,
+            tx,
+            ApplicationManager::new(CancellationToken::default()),
+        )
+    }
+
+    #[test]
+    fn generate_signature() {
+        // All values and strings gotten from Bybit API example
+        let right_value = "670e3e4aa32b243f2dedf1dafcec2fd17a440e71b05681550416507de591d908";
+
+        let params =
+            "api_key=B2Rou0PLPpGqcU0Vu2&leverage=100&symbol=BTCUSD&timestamp=1542434791000".into();
+        let result = create_bybit().generate_signature(params).expect("in test");
+        assert_eq!(result, right_value);
+    }
+
+    #[test]
+    fn authentification_params_are_sorted() {
+        let mut params = vec![
+            ("symbol".to_owned(), "BTCUSDT".to_owned()),
+            ("leverage".to_owned(), "100".to_owned()),
+        ];
+        create_bybit()
+            .add_authentification_headers(&mut params)
+            .expect("in test");
+
+        let keys = params.iter().map(|(key, _)| key.as_str()).collect_vec();
+        assert_eq!(keys, ["api_key", "leverage", "symbol", "timestamp", "sign"]);
+    }
+
+    #[test]
+    fn websocket_authentification_params() {
+        let params = create_bybit()
+            .get_ws_authentification_params(1662350400000)
+            .expect("in test");
+
+        assert_eq!(
+            rest_client::to_http_string(&params),
+            "api_key=B2Rou0PLPpGqcU0Vu2&expires=1662350400000&signature=757fe2bba891e3f663f60f41a25ee4cd1ded5a5b49fcbc68aace24a2eedb6347"
+        );
+    }
+
+    #[test]
+    fn subscription_message() {
+        let message = Bybit::build_subscription_message(vec![
+            "orderBookL2_25.BTCUSDT".to_owned(),
+            "trade.BTCUSDT".to_owned(),
+        ]);
+
+        assert_eq!(
+            message,
+            r#"{"op":"subscribe","args":["orderBookL2_25.BTCUSDT","trade.BTCUSDT"]}"#
+        );
+    }
+}
@@ -0,0 +1,269 @@
+use super::bybit::Bybit;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use mmb_core::exchanges::common::{ActivePosition, Price};
+use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::exchanges::rest_client;
+use mmb_core::exchanges::traits::{ExchangeClient, Support};
+use mmb_core::orders::order::*;
+use mmb_core::{
+    exchanges::common::{CurrencyPair, RestRequestOutcome},
+    orders::pool::OrderRef,
+};
+use mmb_utils::DateTime;
+
+#[async_trait]
+impl ExchangeClient for Bybit {
+    async fn request_all_symbols(&self) -> Result<RestRequestOutcome> {
+        let url_path = "/v2/public/symbols";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &vec![])?;
+
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn create_order(&self, order: &OrderCreating) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(order.header.currency_pair);
+
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            (
+                "side".to_owned(),
+                Self::to_server_order_side(order.header.side),
+            ),
+            (
+                "order_type".to_owned(),
+                Self::to_server_order_type(order.header.order_type),
+            ),
+            ("qty".to_owned(), order.header.amount.to_string()),
+            (
+                "time_in_force".to_owned(),
+                Self::to_server_time_in_force(order.header.order_type, order.header.execution_type),
+            ),
+            ("reduce_only".to_owned(), "false".to_owned()),
+            ("close_on_trigger".to_owned(), "false".to_owned()),
+            (
+                "order_link_id".to_owned(),
+                order.header.client_order_id.as_str().to_owned(),
+            ),
+        ];
+
+        if order.header.order_type != OrderType::Market {
+            http_params.push(("price".to_owned(), order.price.to_string()));
+        }
+        self.add_authentification_headers(&mut http_params)?;
+
+        self.post_signed("/private/linear/order/create", &http_params)
+            .await
+    }
+
+    async fn request_cancel_order(&self, order: &OrderCancelling) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(order.header.currency_pair);
+
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            (
+                "order_id".to_owned(),
+                order.exchange_order_id.as_str().to_owned(),
+            ),
+        ];
+        self.add_authentification_headers(&mut http_params)?;
+
+        self.post_signed("/private/linear/order/cancel", &http_params)
+            .await
+    }
+
+    async fn request_replace_order(
+        &self,
+        _order: &OrderCancelling,
+        _new_order: &OrderCreating,
+    ) -> Result<RestRequestOutcome> {
+        bail!("Bybit doesn't support replacing orders")
+    }
+
+    async fn request_create_orders(&self, _orders: &[OrderCreating]) -> Result<RestRequestOutcome> {
+        bail!("Bybit doesn't support batch orders")
+    }
+
+    async fn request_cancel_orders(
+        &self,
+        _orders: &[OrderCancelling],
+    ) -> Result<RestRequestOutcome> {
+        bail!("Bybit doesn't support batch orders")
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+
+        let mut http_params = vec![(
+            "symbol".to_owned(),
+            specific_currency_pair.as_str().to_owned(),
+        )];
+        self.add_authentification_headers(&mut http_params)?;
+
+        let _cancel_order_outcome = self
+            .post_signed("/private/linear/order/cancel-all", &http_params)
+            .await;
+
+        Ok(())
+    }
+
+    async fn request_open_orders(&self) -> Result<RestRequestOutcome> {
+        bail!("Bybit doesn't support requesting open orders for all currency pairs at once")
+    }
+
+    async fn request_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(currency_pair);
+        let mut http_params = vec![(
+            "symbol".to_owned(),
+            specific_currency_pair.as_str().to_owned(),
+        )];
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/private/linear/order/search";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_order_info(&self, order: &OrderRef) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(order.currency_pair());
+
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            (
+                "order_link_id".to_owned(),
+                order.client_order_id().as_str().to_owned(),
+            ),
+        ];
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/private/linear/order/search";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_my_trades(
+        &self,
+        symbol: &Symbol,
+        last_date_time: Option<DateTime>,
+    ) -> Result<RestRequestOutcome> {
+        let specific_currency_pair = self.get_specific_currency_pair(symbol.currency_pair());
+        let mut http_params = vec![(
+            "symbol".to_owned(),
+            specific_currency_pair.as_str().to_owned(),
+        )];
+
+        if let Some(last_date_time) = last_date_time {
+            http_params.push((
+                "start_time".to_owned(),
+                last_date_time.timestamp_millis().to_string(),
+            ));
+        }
+
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/private/linear/trade/execution/list";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_get_position(&self) -> Result<RestRequestOutcome> {
+        let mut http_params = Vec::new();
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/private/linear/position/list";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_get_balance_and_position(&self) -> Result<RestRequestOutcome> {
+        bail!("Bybit doesn't support requesting balances and positions in a single request")
+    }
+
+    async fn request_get_balance(&self) -> Result<RestRequestOutcome> {
+        let mut http_params = Vec::new();
+        self.add_authentification_headers(&mut http_params)?;
+
+        let url_path = "/v2/private/wallet/balance";
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, &http_params)?;
+        self.rest_client.get(full_url, &self.settings.api_key).await
+    }
+
+    async fn request_close_position(
+        &self,
+        position: &ActivePosition,
+        price: Option<Price>,
+    ) -> Result<RestRequestOutcome> {
+        let side = match position.derivative.side {
+            Some(side) => side.change_side(),
+            None => bail!("Unable to close position {} with unknown side", position.id),
+        };
+        let specific_currency_pair =
+            self.get_specific_currency_pair(position.derivative.currency_pair);
+
+        let order_type = match price {
+            Some(_) => OrderType::Limit,
+            None => OrderType::Market,
+        };
+
+        let mut http_params = vec![
+            (
+                "symbol".to_owned(),
+                specific_currency_pair.as_str().to_owned(),
+            ),
+            ("side".to_owned(), Self::to_server_order_side(side)),
+            (
+                "order_type".to_owned(),
+                Self::to_server_order_type(order_type),
+            ),
+            (
+                "qty".to_owned(),
+                position.derivative.position.abs().to_string(),
+            ),
+            (
+                "time_in_force".to_owned(),
+                Self::to_server_time_in_force(order_type, OrderExecutionType::None),
+            ),
+            ("reduce_only".to_owned(), "true".to_owned()),
+            ("close_on_trigger".to_owned(), "true".to_owned()),
+        ];
+
+        if let Some(price) = price {
+            http_params.push(("price".to_owned(), price.to_string()));
+        }
+
+        self.add_authentification_headers(&mut http_params)?;
+
+        self.post_signed("/private/linear/order/create", &http_params)
+            .await
+    }
+}
+
+impl Bybit {
+    // Signed parameters are passed in query string, so body of request is empty
+    async fn post_signed(
+        &self,
+        url_path: &str,
+        http_params: &rest_client::HttpParams,
+    ) -> Result<RestRequestOutcome> {
+        let full_url = rest_client::build_uri(&self.hosts.rest_host, url_path, http_params)?;
+
+        self.rest_client
+            .post(full_url, &self.settings.api_key, &Vec::new())
+            .await
+    }
+}
@@ -0,0 +1,643 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_utils::{value_to_decimal::GetOrErr, DateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use super::bybit::{value_to_decimal, Bybit};
+use mmb_core::exchanges::common::{ActivePosition, ClosedPosition, SortedOrderData};
+use mmb_core::exchanges::events::{
+    ExchangeBalance, ExchangeBalancesAndPositions, ExchangeEvent, TradeId,
+};
+use mmb_core::exchanges::general::exchange::RequestResult;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::rest_client;
+use mmb_core::exchanges::{
+    common::CurrencyCode, common::CurrencyId,
+    general::handlers::handle_order_filled::FillEventData, general::symbol::Symbol,
+    traits::Support,
+};
+use mmb_core::order_book::event::{EventType, OrderBookEvent};
+use mmb_core::order_book::order_book_data::OrderBookData;
+use mmb_core::orders::order::*;
+use mmb_core::settings::ExchangeSettings;
+use mmb_core::{
+    connectivity::connectivity_manager::WebSocketRole, exchanges::general::symbol::Precision,
+};
+use mmb_core::{
+    exchanges::common::{
+        Amount, CurrencyPair, ExchangeError, ExchangeErrorType, Price, RestRequestOutcome,
+        SpecificCurrencyPair,
+    },
+    orders::fill::EventSourceType,
+};
+use mmb_utils::time::{get_current_milliseconds, u64_to_date_time};
+
+/// Lifetime of websocket authentification signature
+const WS_AUTHENTIFICATION_EXPIRATION_MS: u128 = 10_000;
+const PRIVATE_TOPICS: [&str; 4] = ["order", "execution", "position", "wallet"];
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BybitOrderInfo {
+    #[serde(rename = "symbol")]
+    pub specific_currency_pair: SpecificCurrencyPair,
+    pub order_id: String,
+    pub order_link_id: String,
+    pub side: String,
+    pub order_status: String,
+    pub price: Price,
+    pub qty: Amount,
+    pub cum_exec_qty: Amount,
+    pub cum_exec_value: Decimal,
+    pub cum_exec_fee: Decimal,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BybitPosition {
+    #[serde(rename = "symbol")]
+    pub specific_currency_pair: SpecificCurrencyPair,
+    pub side: String,
+    pub size: Amount,
+    pub entry_price: Price,
+    #[serde(rename = "liq_price")]
+    pub liquidation_price: Price,
+    pub leverage: Decimal,
+}
+
+impl BybitPosition {
+    /// Side of opened position or None if there is no position
+    pub fn get_side(&self) -> Result<Option<OrderSide>> {
+        if self.side == "None" || self.size.is_zero() {
+            return Ok(None);
+        }
+
+        Bybit::to_local_order_side(&self.side).map(Some)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub(super) struct BybitCoinBalance {
+    pub available_balance: Decimal,
+}
+
+#[async_trait]
+impl Support for Bybit {
+    fn is_rest_error_code(&self, response: &RestRequestOutcome) -> Result<(), ExchangeError> {
+        let data: Value = serde_json::from_str(&response.content)
+            .map_err(|err| ExchangeError::parsing_error(&format!("response.content: {:?}", err)))?;
+
+        let code = data["ret_code"]
+            .as_i64()
+            .ok_or_else(|| ExchangeError::parsing_error("`ret_code` field"))?;
+        if code == 0 {
+            return Ok(());
+        }
+
+        let message = data["ret_msg"]
+            .as_str()
+            .ok_or_else(|| ExchangeError::parsing_error("`ret_msg` field"))?;
+
+        Err(ExchangeError::new(
+            ExchangeErrorType::Unknown,
+            message.to_string(),
+            Some(code),
+        ))
+    }
+
+    fn get_order_id(&self, response: &RestRequestOutcome) -> Result<ExchangeOrderId> {
+        let result = get_response_result(response)?;
+        let id = result["order_id"]
+            .as_str()
+            .context("Unable to get order_id from response")?;
+        Ok(ExchangeOrderId::new(id.into()))
+    }
+
+    fn parse_batch_orders(
+        &self,
+        _response: &RestRequestOutcome,
+    ) -> Result<Vec<RequestResult<ExchangeOrderId>>> {
+        bail!("Bybit doesn't support batch orders")
+    }
+
+    // According to https://bybit-exchange.github.io/docs/linear/#t-errors
+    fn clarify_error_type(&self, error: &mut ExchangeError) {
+        let error_type = match error.code {
+            Some(10006) | Some(10018) => ExchangeErrorType::RateLimit,
+            Some(10003) | Some(10004) | Some(33004) => ExchangeErrorType::Authentication,
+            Some(20001) | Some(30032) | Some(130010) => ExchangeErrorType::OrderNotFound,
+            Some(30031) | Some(130021) | Some(130052) => ExchangeErrorType::InsufficientFunds,
+            Some(10001) | Some(130074) | Some(130075) | Some(130125) => {
+                ExchangeErrorType::InvalidOrder
+            }
+            _ => ExchangeErrorType::Unknown,
+        };
+
+        error.error_type = error_type;
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        let mut data: Value =
+            serde_json::from_str(msg).context("Unable to parse websocket message")?;
+
+        // Response to subscription or ping request
+        if let Some(success) = data.get("success") {
+            if success != true {
+                log::error!("Unsuccessful websocket request on {}: {}", self.id, msg);
+            }
+            return Ok(());
+        }
+
+        let topic = match data["topic"].as_str() {
+            Some(topic) => topic.to_owned(),
+            None => {
+                self.log_unknown_message(self.id, msg);
+                return Ok(());
+            }
+        };
+
+        // Public topics look like `trade.BTCUSDT`
+        if let Some((channel, specific_currency_pair)) = topic.split_once('.') {
+            let currency_pair = self.get_unified_currency_pair(&specific_currency_pair.into())?;
+            if channel == "trade" {
+                return self.handle_trades(currency_pair, &data["data"]);
+            }
+
+            if channel.starts_with("orderBookL2") {
+                return self.handle_order_book(currency_pair, &data);
+            }
+
+            self.log_unknown_message(self.id, msg);
+            return Ok(());
+        }
+
+        match topic.as_str() {
+            "order" => self.handle_order_updates(msg, data["data"].take()),
+            "execution" => self.handle_executions(&data["data"]),
+            "position" => self.handle_positions(data["data"].take()),
+            "wallet" => self.handle_wallet(data["data"].take()),
+            _ => {
+                self.log_unknown_message(self.id, msg);
+                Ok(())
+            }
+        }
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_order_created_callback(
+        &self,
+        callback: Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>,
+    ) {
+        *self.order_created_callback.lock() = callback;
+    }
+
+    fn set_order_cancelled_callback(
+        &self,
+        callback: Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>,
+    ) {
+        *self.order_cancelled_callback.lock() = callback;
+    }
+
+    fn set_handle_order_filled_callback(
+        &self,
+        callback: Box<dyn FnMut(FillEventData) + Send + Sync>,
+    ) {
+        *self.handle_order_filled_callback.lock() = callback;
+    }
+
+    fn set_handle_trade_callback(
+        &self,
+        callback: Box<
+            dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync,
+        >,
+    ) {
+        *self.handle_trade_callback.lock() = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.traded_specific_currencies.lock() = currencies;
+    }
+
+    fn is_websocket_enabled(&self, role: WebSocketRole) -> bool {
+        match role {
+            WebSocketRole::Main => true,
+            WebSocketRole::Secondary => {
+                !self.settings.api_key.is_empty() && !self.settings.secret_key.is_empty()
+            }
+        }
+    }
+
+    async fn create_ws_url(&self, role: WebSocketRole) -> Result<Url> {
+        let url = match role {
+            WebSocketRole::Main => self.hosts.web_socket_host.to_owned(),
+            WebSocketRole::Secondary => {
+                let expires = get_current_milliseconds() + WS_AUTHENTIFICATION_EXPIRATION_MS;
+                let params = self.get_ws_authentification_params(expires)?;
+                format!(
+                    "{}?{}",
+                    self.hosts.web_socket2_host,
+                    rest_client::to_http_string(&params)
+                )
+            }
+        };
+
+        Url::parse(&url).with_context(|| format!("Unable parse websocket {:?} uri", role))
+    }
+
+    fn get_ws_subscription_messages(&self, role: WebSocketRole) -> Result<Vec<String>> {
+        let topics = match role {
+            WebSocketRole::Main => self
+                .traded_specific_currencies
+                .lock()
+                .iter()
+                .cartesian_product(self.settings.websocket_channels.iter())
+                .map(|(currency_pair, channel)| format!("{}.{}", channel, currency_pair.as_str()))
+                .collect_vec(),
+            WebSocketRole::Secondary => PRIVATE_TOPICS.iter().map(|x| x.to_string()).collect(),
+        };
+
+        if topics.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![Self::build_subscription_message(topics)])
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        self.unified_to_specific.read()[&currency_pair]
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, message: &str) -> bool {
+        message.contains(r#""topic":"execution""#) || message.contains(r#""topic":"order""#)
+    }
+
+    fn parse_open_orders(&self, response: &RestRequestOutcome) -> Result<Vec<OrderInfo>> {
+        let result = get_response_result(response)?;
+        // Bybit returns null instead of empty array if there are no open orders
+        if result.is_null() {
+            return Ok(Vec::new());
+        }
+
+        let bybit_orders: Vec<BybitOrderInfo> = serde_json::from_value(result)
+            .context("Unable to parse response content for get_open_orders request")?;
+
+        bybit_orders
+            .iter()
+            .map(|order| self.specific_order_info_to_unified(order))
+            .try_collect()
+    }
+
+    fn parse_order_info(&self, response: &RestRequestOutcome) -> Result<OrderInfo> {
+        let specific_order: BybitOrderInfo = serde_json::from_value(get_response_result(response)?)
+            .context("Unable to parse response content for get_order_info request")?;
+
+        self.specific_order_info_to_unified(&specific_order)
+    }
+
+    fn parse_all_symbols(&self, response: &RestRequestOutcome) -> Result<Vec<Arc<Symbol>>> {
+        let result = get_response_result(response)?;
+        let symbols = result
+            .as_array()
+            .ok_or(anyhow!("Unable to get symbols array from Bybit"))?;
+
+        let mut result = Vec::new();
+        for symbol in symbols {
+            let base_currency_id = symbol
+                .get_as_str("base_currency")
+                .context("Unable to get base currency id from Bybit")?;
+            let quote_currency_id = symbol
+                .get_as_str("quote_currency")
+                .context("Unable to get quote currency id from Bybit")?;
+
+            // Only USDT perpetual contracts are supported, inverse ones have another API
+            if quote_currency_id != "USDT" {
+                continue;
+            }
+
+            let is_active = symbol["status"] == "Trading";
+            let is_derivative = true;
+            let base = base_currency_id.as_str().into();
+            let quote = quote_currency_id.as_str().into();
+
+            let specific_currency_pair = symbol.get_as_str("name")?.as_str().into();
+            let unified_currency_pair = CurrencyPair::from_codes(base, quote);
+            self.unified_to_specific
+                .write()
+                .insert(unified_currency_pair, specific_currency_pair);
+
+            self.specific_to_unified
+                .write()
+                .insert(specific_currency_pair, unified_currency_pair);
+
+            // Contracts are margined and settled in quote currency
+            let _ = self
+                .settlement_currencies
+                .insert(specific_currency_pair, quote);
+
+            let price_filter = &symbol["price_filter"];
+            let lot_size_filter = &symbol["lot_size_filter"];
+
+            let price_precision = match value_to_decimal(&price_filter["tick_size"]) {
+                Some(tick) => Precision::ByTick { tick },
+                None => bail!(
+                    "Unable to get price precision from Bybit for {:?}",
+                    specific_currency_pair
+                ),
+            };
+
+            let amount_precision = match value_to_decimal(&lot_size_filter["qty_step"]) {
+                Some(tick) => Precision::ByTick { tick },
+                None => bail!(
+                    "Unable to get amount precision from Bybit for {:?}",
+                    specific_currency_pair
+                ),
+            };
+
+            let symbol = Symbol::new(
+                is_active,
+                is_derivative,
+                base_currency_id.as_str().into(),
+                base,
+                quote_currency_id.as_str().into(),
+                quote,
+                value_to_decimal(&price_filter["min_price"]),
+                value_to_decimal(&price_filter["max_price"]),
+                value_to_decimal(&lot_size_filter["min_trading_qty"]),
+                value_to_decimal(&lot_size_filter["max_trading_qty"]),
+                None,
+                base,
+                Some(quote),
+                price_precision,
+                amount_precision,
+            );
+
+            result.push(Arc::new(symbol))
+        }
+
+        Ok(result)
+    }
+
+    fn get_balance_reservation_currency_code(
+        &self,
+        symbol: Arc<Symbol>,
+        _side: OrderSide,
+    ) -> CurrencyCode {
+        // Margin for both sides of USDT perpetual contracts is reserved in USDT
+        symbol
+            .balance_currency_code
+            .unwrap_or(symbol.quote_currency_code)
+    }
+
+    fn parse_get_my_trades(
+        &self,
+        response: &RestRequestOutcome,
+        _last_date_time: Option<DateTime>,
+    ) -> Result<Vec<OrderTrade>> {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct BybitMyTrade {
+            #[serde(rename = "symbol")]
+            specific_currency_pair: SpecificCurrencyPair,
+            order_id: String,
+            exec_id: String,
+            exec_type: String,
+            exec_price: Price,
+            exec_qty: Amount,
+            exec_fee: Amount,
+            fee_rate: Decimal,
+            last_liquidity_ind: String,
+            trade_time_ms: u64,
+        }
+
+        impl BybitMyTrade {
+            fn to_unified_order_trade(
+                &self,
+                commission_currency_code: CurrencyCode,
+            ) -> Result<OrderTrade> {
+                let order_role = match self.last_liquidity_ind.as_str() {
+                    "AddedLiquidity" => OrderRole::Maker,
+                    _ => OrderRole::Taker,
+                };
+
+                Ok(OrderTrade::new(
+                    ExchangeOrderId::from(self.order_id.as_str()),
+                    TradeId::String(self.exec_id.as_str().into()),
+                    u64_to_date_time(self.trade_time_ms),
+                    self.exec_price,
+                    self.exec_qty,
+                    order_role,
+                    commission_currency_code,
+                    Some(self.fee_rate),
+                    Some(self.exec_fee),
+                    Bybit::get_fill_type(&self.exec_type)?,
+                ))
+            }
+        }
+
+        let result = get_response_result(response)?;
+        // Bybit returns null instead of empty array if there are no trades
+        if result["data"].is_null() {
+            return Ok(Vec::new());
+        }
+
+        let my_trades: Vec<BybitMyTrade> = serde_json::from_value(result["data"].clone())
+            .context("Unable to parse response content for get_my_trades request")?;
+
+        my_trades
+            .into_iter()
+            .map(|my_trade| {
+                my_trade.to_unified_order_trade(
+                    self.get_settlement_currency_code(&my_trade.specific_currency_pair)?,
+                )
+            })
+            .collect()
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+
+    fn parse_get_position(&self, response: &RestRequestOutcome) -> Vec<ActivePosition> {
+        let result = get_response_result(response)
+            .expect("Unable to parse response content for get_active_positions_core request");
+
+        // Positions of all currency pairs are wrapped to `data` field
+        let bybit_positions: Vec<BybitPosition> = result
+            .as_array()
+            .expect("Unable to get positions array from Bybit")
+            .iter()
+            .map(|x| serde_json::from_value(x.get("data").unwrap_or(x).clone()))
+            .try_collect()
+            .expect("Unable to parse positions from Bybit");
+
+        bybit_positions
+            .iter()
+            .filter_map(|x| {
+                self.bybit_position_to_derivative(x)
+                    .expect("Unable to convert Bybit position")
+            })
+            .map(ActivePosition::new)
+            .collect_vec()
+    }
+
+    fn parse_close_position(&self, response: &RestRequestOutcome) -> Result<ClosedPosition> {
+        let bybit_order: BybitOrderInfo = serde_json::from_value(get_response_result(response)?)
+            .context("Unable to parse response content for close_position request")?;
+
+        Ok(ClosedPosition::new(
+            ExchangeOrderId::from(bybit_order.order_id.as_str()),
+            bybit_order.qty,
+        ))
+    }
+
+    fn parse_get_balance(&self, response: &RestRequestOutcome) -> ExchangeBalancesAndPositions {
+        let bybit_balances: HashMap<String, BybitCoinBalance> = serde_json::from_value(
+            get_response_result(response)
+                .expect("Unable to parse response content for get_balance request"),
+        )
+        .expect("Unable to parse balances from Bybit");
+
+        // Wallet contains all coins of account, so we skip the ones which aren't traded
+        let balances = bybit_balances
+            .iter()
+            .filter_map(|(currency_id, balance)| {
+                self.get_currency_code(&currency_id.as_str().into())
+                    .map(|currency_code| ExchangeBalance {
+                        currency_code,
+                        balance: balance.available_balance,
+                    })
+            })
+            .collect_vec();
+        self.update_wallet_balances(&balances);
+
+        // Positions are requested separately
+        ExchangeBalancesAndPositions {
+            balances,
+            positions: None,
+        }
+    }
+}
+
+impl Bybit {
+    fn handle_trades(&self, currency_pair: CurrencyPair, data: &Value) -> Result<()> {
+        let trades = data
+            .as_array()
+            .ok_or(anyhow!("Unable to parse trades in Bybit"))?;
+
+        for trade in trades {
+            let trade_id = trade
+                .get_as_str("trade_id")
+                .context("Unable to get trade id")?;
+            let price = value_to_decimal(&trade["price"]).context("Unable to get trade price")?;
+            let quantity = value_to_decimal(&trade["size"]).context("Unable to get trade size")?;
+            // Side of taker
+            let order_side = Self::to_local_order_side(&trade.get_as_str("side")?)?;
+            let datetime: u64 = trade
+                .get_as_str("trade_time_ms")
+                .context("Unable to get trade time")?
+                .parse()?;
+
+            (&self.handle_trade_callback).lock()(
+                currency_pair,
+                TradeId::String(trade_id.into()),
+                price,
+                quantity,
+                order_side,
+                u64_to_date_time(datetime),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn handle_order_book(&self, currency_pair: CurrencyPair, data: &Value) -> Result<()> {
+        if !self.subscribe_to_market_data {
+            return Ok(());
+        }
+
+        let event_id = data["cross_seq"].to_string();
+        let event_id = event_id.trim_matches('"');
+        let order_book = &data["data"];
+
+        let (event_type, order_book_data) = match data["type"].as_str() {
+            Some("snapshot") => {
+                let levels = order_book.get("order_book").unwrap_or(order_book);
+                let mut order_book_data = empty_order_book_data();
+                add_order_book_levels(&mut order_book_data, levels, false)?;
+                (EventType::Snapshot, order_book_data)
+            }
+            Some("delta") => {
+                let mut order_book_data = empty_order_book_data();
+                add_order_book_levels(&mut order_book_data, &order_book["delete"], true)?;
+                add_order_book_levels(&mut order_book_data, &order_book["update"], false)?;
+                add_order_book_levels(&mut order_book_data, &order_book["insert"], false)?;
+                (EventType::Update, order_book_data)
+            }
+            _ => bail!("Unknown order book message type in Bybit: {}", data),
+        };
+
+        let order_book_event = OrderBookEvent::new(
+            Utc::now(),
+            self.id,
+            currency_pair,
+            event_id.to_string(),
+            event_type,
+            Arc::new(order_book_data),
+        );
+
+        self.send_event(ExchangeEvent::OrderBookEvent(order_book_event))
+    }
+}
+
+fn get_response_result(response: &RestRequestOutcome) -> Result<Value> {
+    let mut data: Value =
+        serde_json::from_str(&response.content).context("Unable to parse response content")?;
+    Ok(data["result"].take())
+}
+
+fn empty_order_book_data() -> OrderBookData {
+    OrderBookData::new(SortedOrderData::new(), SortedOrderData::new())
+}
+
+/// Deleted levels have no size, so they are added with zero amount
+fn add_order_book_levels(
+    order_book_data: &mut OrderBookData,
+    levels: &Value,
+    is_deleted: bool,
+) -> Result<()> {
+    let levels = match levels.as_array() {
+        Some(levels) => levels,
+        None => return Ok(()),
+    };
+
+    for level in levels {
+        let price = value_to_decimal(&level["price"])
+            .ok_or(anyhow!("Unable parse price of order book level in Bybit"))?;
+        let amount = match is_deleted {
+            true => Decimal::ZERO,
+            false => value_to_decimal(&level["size"])
+                .ok_or(anyhow!("Unable parse amount of order book level in Bybit"))?,
+        };
+
+        let side = match Bybit::to_local_order_side(&level.get_as_str("side")?)? {
+            OrderSide::Buy => &mut order_book_data.bids,
+            OrderSide::Sell => &mut order_book_data.asks,
+        };
+        let _ = side.insert(price, amount);
+    }
+
+    Ok(())
+}
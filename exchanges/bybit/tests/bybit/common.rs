@@ -0,0 +1,67 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use bybit::bybit::Bybit;
+use hyper::StatusCode;
+use mmb_core::exchanges::common::{ExchangeAccountId, RestRequestOutcome};
+use mmb_core::exchanges::events::ExchangeEvent;
+use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::exchanges::traits::Support;
+use mmb_core::lifecycle::application_manager::ApplicationManager;
+use mmb_core::settings::ExchangeSettings;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::infrastructure::WithExpect;
+use tokio::sync::broadcast;
+
+/// Load response or message recorded from Bybit
+pub(crate) fn load_fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/bybit/fixtures")
+        .join(name);
+
+    std::fs::read_to_string(&path).with_expect(|| format!("Unable to read fixture {:?}", path))
+}
+
+pub(crate) fn rest_outcome(name: &str) -> RestRequestOutcome {
+    RestRequestOutcome {
+        content: load_fixture(name),
+        status: StatusCode::OK,
+    }
+}
+
+pub(crate) fn exchange_account_id() -> ExchangeAccountId {
+    "Bybit_0".parse().expect("in test")
+}
+
+/// Create Bybit client with metadata initialized from recorded symbols
+pub(crate) fn create_bybit() -> (Bybit, broadcast::Receiver<ExchangeEvent>, Vec<Arc<Symbol>>) {
+    let mut settings = ExchangeSettings::new_short(
+        exchange_account_id(),
+        "api_key".into(),
+        "secret_key".into(),
+        true,
+    );
+    settings.websocket_channels = vec!["orderBookL2_25".into(), "trade".into()];
+
+    let (tx, rx) = broadcast::channel(10);
+    let bybit = Bybit::new(
+        exchange_account_id(),
+        settings,
+        tx,
+        ApplicationManager::new(CancellationToken::default()),
+    );
+
+    let symbols = bybit
+        .parse_all_symbols(&rest_outcome("rest/symbols.json"))
+        .expect("in test");
+    for symbol in &symbols {
+        let _ = bybit
+            .supported_currencies
+            .insert(symbol.base_currency_id, symbol.base_currency_code);
+        let _ = bybit
+            .supported_currencies
+            .insert(symbol.quote_currency_id, symbol.quote_currency_code);
+    }
+
+    (bybit, rx, symbols)
+}
@@ -0,0 +1,3 @@
+pub mod common;
+pub mod rest_responses;
+pub mod websocket_messages;
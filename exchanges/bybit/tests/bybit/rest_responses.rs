@@ -0,0 +1,199 @@
+use mmb_core::exchanges::common::{CurrencyCode, CurrencyPair, ExchangeErrorType};
+use mmb_core::exchanges::events::TradeId;
+use mmb_core::exchanges::general::symbol::Precision;
+use mmb_core::exchanges::traits::Support;
+use mmb_core::orders::fill::OrderFillType;
+use mmb_core::orders::order::{ExchangeOrderId, OrderRole, OrderSide, OrderStatus};
+use mmb_utils::time::u64_to_date_time;
+use rust_decimal_macros::dec;
+
+use crate::bybit::common::{create_bybit, rest_outcome};
+
+fn btc_usdt() -> CurrencyPair {
+    CurrencyPair::from_codes("btc".into(), "usdt".into())
+}
+
+#[test]
+fn parse_all_symbols() {
+    let (bybit, _rx, symbols) = create_bybit();
+
+    // Inverse BTCUSD contract is skipped
+    assert_eq!(symbols.len(), 2);
+
+    let btc_usdt_symbol = &symbols[0];
+    assert_eq!(btc_usdt_symbol.currency_pair(), btc_usdt());
+    assert!(btc_usdt_symbol.is_active);
+    assert!(btc_usdt_symbol.is_derivative);
+    assert_eq!(btc_usdt_symbol.min_price, Some(dec!(0.5)));
+    assert_eq!(btc_usdt_symbol.max_price, Some(dec!(999999)));
+    assert_eq!(btc_usdt_symbol.min_amount, Some(dec!(0.001)));
+    assert_eq!(btc_usdt_symbol.max_amount, Some(dec!(100)));
+    assert_eq!(btc_usdt_symbol.amount_currency_code, "btc".into());
+    assert_eq!(btc_usdt_symbol.balance_currency_code, Some("usdt".into()));
+    assert_eq!(
+        btc_usdt_symbol.price_precision,
+        Precision::ByTick { tick: dec!(0.5) }
+    );
+    assert_eq!(
+        btc_usdt_symbol.amount_precision,
+        Precision::ByTick { tick: dec!(0.001) }
+    );
+    assert_eq!(
+        bybit.get_specific_currency_pair(btc_usdt()).as_str(),
+        "BTCUSDT"
+    );
+
+    assert!(!symbols[1].is_active);
+    assert_eq!(
+        bybit.get_balance_reservation_currency_code(symbols[1].clone(), OrderSide::Sell),
+        CurrencyCode::from("usdt")
+    );
+}
+
+#[test]
+fn get_order_id() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let response = rest_outcome("rest/create_order.json");
+    bybit.is_rest_error_code(&response).expect("in test");
+    let order_id = bybit.get_order_id(&response).expect("in test");
+
+    assert_eq!(
+        order_id,
+        ExchangeOrderId::from("e66b1d4a-3a2b-4a1c-9b1d-3f7c8a4d2e11")
+    );
+}
+
+#[test]
+fn rest_error() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let mut error = bybit
+        .is_rest_error_code(&rest_outcome("rest/insufficient_balance.json"))
+        .expect_err("in test");
+    bybit.clarify_error_type(&mut error);
+
+    assert_eq!(error.error_type, ExchangeErrorType::InsufficientFunds);
+    assert_eq!(error.code, Some(130021));
+    assert_eq!(error.message, "order cost not available");
+}
+
+#[test]
+fn parse_order_info() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let order_info = bybit
+        .parse_order_info(&rest_outcome("rest/order_info.json"))
+        .expect("in test");
+
+    assert_eq!(order_info.currency_pair, btc_usdt());
+    assert_eq!(order_info.client_order_id.as_str(), "1662350400123");
+    assert_eq!(order_info.order_side, OrderSide::Buy);
+    assert_eq!(order_info.order_status, OrderStatus::Created);
+    assert_eq!(order_info.price, dec!(19500));
+    assert_eq!(order_info.amount, dec!(0.002));
+    assert_eq!(order_info.average_fill_price, dec!(19500));
+    assert_eq!(order_info.filled_amount, dec!(0.001));
+    assert_eq!(order_info.commission_amount, Some(dec!(0.00195)));
+}
+
+#[test]
+fn parse_open_orders() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let orders = bybit
+        .parse_open_orders(&rest_outcome("rest/open_orders.json"))
+        .expect("in test");
+
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[1].client_order_id.as_str(), "1662350400456");
+    assert_eq!(orders[1].order_side, OrderSide::Sell);
+    assert_eq!(orders[1].price, dec!(20500));
+    assert_eq!(orders[1].average_fill_price, dec!(0));
+
+    let orders = bybit
+        .parse_open_orders(&rest_outcome("rest/open_orders_empty.json"))
+        .expect("in test");
+    assert!(orders.is_empty());
+}
+
+#[test]
+fn parse_my_trades_with_funding() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let trades = bybit
+        .parse_get_my_trades(&rest_outcome("rest/my_trades.json"), None)
+        .expect("in test");
+
+    assert_eq!(trades.len(), 2);
+
+    let trade = &trades[0];
+    assert_eq!(
+        trade.exchange_order_id,
+        ExchangeOrderId::from("e66b1d4a-3a2b-4a1c-9b1d-3f7c8a4d2e11")
+    );
+    assert_eq!(
+        trade.trade_id,
+        TradeId::String("3e1a9f0c-4b5d-5e6f-8a7b-9c0d1e2f3a4b".into())
+    );
+    assert_eq!(trade.datetime, u64_to_date_time(1662350405000));
+    assert_eq!(trade.price, dec!(19500));
+    assert_eq!(trade.amount, dec!(0.001));
+    assert_eq!(trade.order_role, OrderRole::Maker);
+    assert_eq!(trade.fee_currency_code, "usdt".into());
+    assert_eq!(trade.fee_amount, Some(dec!(0.00195)));
+    assert_eq!(trade.fill_type, OrderFillType::UserTrade);
+
+    assert_eq!(trades[1].order_role, OrderRole::Taker);
+    assert_eq!(trades[1].fill_type, OrderFillType::Funding);
+}
+
+#[test]
+fn parse_positions() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let positions = bybit.parse_get_position(&rest_outcome("rest/positions.json"));
+
+    // Empty ETHUSDT position is skipped
+    assert_eq!(positions.len(), 1);
+    let position = &positions[0].derivative;
+    assert_eq!(position.currency_pair, btc_usdt());
+    assert_eq!(position.position, dec!(0.001));
+    assert_eq!(position.side, Some(OrderSide::Buy));
+    assert_eq!(position.average_entry_price, dec!(19500));
+    assert_eq!(position.liquidation_price, dec!(17600));
+    assert_eq!(position.leverage, dec!(10));
+}
+
+#[test]
+fn parse_balance() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let balances_and_positions = bybit.parse_get_balance(&rest_outcome("rest/wallet_balance.json"));
+
+    assert!(balances_and_positions.positions.is_none());
+
+    // EOS isn't traded, so it's skipped
+    let mut balances = balances_and_positions
+        .balances
+        .iter()
+        .map(|x| (x.currency_code.to_string(), x.balance))
+        .collect::<Vec<_>>();
+    balances.sort();
+    assert_eq!(
+        balances,
+        [
+            ("btc".to_owned(), dec!(0.0015)),
+            ("usdt".to_owned(), dec!(998.55))
+        ]
+    );
+}
+
+#[test]
+fn parse_close_position() {
+    let (bybit, _rx, _) = create_bybit();
+
+    bybit
+        .parse_close_position(&rest_outcome("rest/close_position.json"))
+        .expect("in test");
+}
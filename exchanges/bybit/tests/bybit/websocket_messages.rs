@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use mmb_core::connectivity::connectivity_manager::WebSocketRole;
+use mmb_core::exchanges::common::{CurrencyPair, SpecificCurrencyPair};
+use mmb_core::exchanges::events::{ExchangeBalancesAndPositions, ExchangeEvent, TradeId};
+use mmb_core::exchanges::general::handlers::handle_order_filled::FillEventData;
+use mmb_core::exchanges::traits::Support;
+use mmb_core::order_book::event::EventType;
+use mmb_core::orders::fill::{EventSourceType, OrderFillType};
+use mmb_core::orders::order::{ClientOrderId, ExchangeOrderId, OrderRole, OrderSide};
+use mmb_utils::time::u64_to_date_time;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::bybit::common::{create_bybit, load_fixture, rest_outcome};
+
+fn btc_usdt() -> CurrencyPair {
+    CurrencyPair::from_codes("btc".into(), "usdt".into())
+}
+
+fn sorted_balances(
+    balances_and_positions: &ExchangeBalancesAndPositions,
+) -> Vec<(String, Decimal)> {
+    let mut balances = balances_and_positions
+        .balances
+        .iter()
+        .map(|x| (x.currency_code.to_string(), x.balance))
+        .collect::<Vec<_>>();
+    balances.sort();
+    balances
+}
+
+#[test]
+fn subscription_response_is_skipped() {
+    let (bybit, mut rx, _) = create_bybit();
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/subscription_response.json"))
+        .expect("in test");
+
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn subscription_messages() {
+    let (bybit, _rx, _) = create_bybit();
+    bybit.set_traded_specific_currencies(vec![SpecificCurrencyPair::from("BTCUSDT")]);
+
+    assert_eq!(
+        bybit
+            .get_ws_subscription_messages(WebSocketRole::Main)
+            .expect("in test"),
+        [r#"{"op":"subscribe","args":["orderBookL2_25.BTCUSDT","trade.BTCUSDT"]}"#]
+    );
+    assert_eq!(
+        bybit
+            .get_ws_subscription_messages(WebSocketRole::Secondary)
+            .expect("in test"),
+        [r#"{"op":"subscribe","args":["order","execution","position","wallet"]}"#]
+    );
+}
+
+#[test]
+fn order_book_snapshot_and_delta() {
+    let (bybit, mut rx, _) = create_bybit();
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/order_book_snapshot.json"))
+        .expect("in test");
+    bybit
+        .on_websocket_message(&load_fixture("ws/order_book_delta.json"))
+        .expect("in test");
+
+    let snapshot = match rx.try_recv().expect("in test") {
+        ExchangeEvent::OrderBookEvent(event) => event,
+        event => panic!("Unexpected event {:?}", event),
+    };
+    assert_eq!(snapshot.currency_pair, btc_usdt());
+    assert!(matches!(snapshot.event_type, EventType::Snapshot));
+    assert_eq!(
+        snapshot.data.bids.iter().collect::<Vec<_>>(),
+        [(&dec!(19499), &dec!(1.5)), (&dec!(19499.5), &dec!(0.25))]
+    );
+    assert_eq!(
+        snapshot.data.asks.iter().collect::<Vec<_>>(),
+        [(&dec!(19500), &dec!(0.75)), (&dec!(19500.5), &dec!(2))]
+    );
+
+    let delta = match rx.try_recv().expect("in test") {
+        ExchangeEvent::OrderBookEvent(event) => event,
+        event => panic!("Unexpected event {:?}", event),
+    };
+    assert!(matches!(delta.event_type, EventType::Update));
+    // Deleted levels have zero amount
+    assert_eq!(
+        delta.data.bids.iter().collect::<Vec<_>>(),
+        [(&dec!(19498.5), &dec!(3)), (&dec!(19499), &dec!(0))]
+    );
+    assert_eq!(
+        delta.data.asks.iter().collect::<Vec<_>>(),
+        [(&dec!(19500), &dec!(0.5))]
+    );
+}
+
+#[test]
+fn trades() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let trades = Arc::new(Mutex::new(Vec::new()));
+    let trades_clone = trades.clone();
+    bybit.set_handle_trade_callback(Box::new(
+        move |currency_pair, trade_id, price, amount, side, datetime| {
+            trades_clone
+                .lock()
+                .push((currency_pair, trade_id, price, amount, side, datetime))
+        },
+    ));
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/trade.json"))
+        .expect("in test");
+
+    let trades = trades.lock();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(
+        trades[0],
+        (
+            btc_usdt(),
+            TradeId::String("3e1a9f0c-4b5d-5e6f-8a7b-9c0d1e2f3a4b".into()),
+            dec!(19500),
+            dec!(0.001),
+            OrderSide::Buy,
+            u64_to_date_time(1662350405000)
+        )
+    );
+    assert_eq!(trades[1].4, OrderSide::Sell);
+}
+
+#[test]
+fn order_created_and_cancelled() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let created = Arc::new(Mutex::new(Vec::new()));
+    let created_clone = created.clone();
+    bybit.set_order_created_callback(Box::new(move |client_order_id, exchange_order_id, _| {
+        created_clone
+            .lock()
+            .push((client_order_id, exchange_order_id))
+    }));
+    let cancelled = Arc::new(Mutex::new(Vec::new()));
+    let cancelled_clone = cancelled.clone();
+    bybit.set_order_cancelled_callback(Box::new(
+        move |client_order_id, exchange_order_id, source_type| {
+            assert_eq!(source_type, EventSourceType::WebSocket);
+            cancelled_clone
+                .lock()
+                .push((client_order_id, exchange_order_id))
+        },
+    ));
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/order_new.json"))
+        .expect("in test");
+    bybit
+        .on_websocket_message(&load_fixture("ws/order_cancelled.json"))
+        .expect("in test");
+
+    assert_eq!(
+        *created.lock(),
+        [(
+            ClientOrderId::from("1662350400123"),
+            ExchangeOrderId::from("e66b1d4a-3a2b-4a1c-9b1d-3f7c8a4d2e11")
+        )]
+    );
+    // Order without client order id isn't ours
+    assert_eq!(
+        *cancelled.lock(),
+        [(
+            ClientOrderId::from("1662350400456"),
+            ExchangeOrderId::from("0b7c5a2e-8d1f-4c3b-a6e9-5f2d1c0b9a87")
+        )]
+    );
+}
+
+#[test]
+fn executions() {
+    let (bybit, _rx, _) = create_bybit();
+
+    let fills = Arc::new(Mutex::new(Vec::<FillEventData>::new()));
+    let fills_clone = fills.clone();
+    bybit.set_handle_order_filled_callback(Box::new(move |event_data| {
+        fills_clone.lock().push(event_data)
+    }));
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/execution.json"))
+        .expect("in test");
+
+    // Funding doesn't relate to orders, so only trade and liquidation are passed
+    let fills = fills.lock();
+    assert_eq!(fills.len(), 2);
+
+    let fill = &fills[0];
+    assert_eq!(fill.fill_type, OrderFillType::UserTrade);
+    assert_eq!(fill.client_order_id, Some("1662350400123".into()));
+    assert_eq!(
+        fill.exchange_order_id,
+        ExchangeOrderId::from("e66b1d4a-3a2b-4a1c-9b1d-3f7c8a4d2e11")
+    );
+    assert_eq!(
+        fill.trade_id,
+        Some(TradeId::String(
+            "3e1a9f0c-4b5d-5e6f-8a7b-9c0d1e2f3a4b".into()
+        ))
+    );
+    assert_eq!(fill.fill_price, dec!(19500));
+    assert_eq!(fill.fill_amount, dec!(0.001));
+    assert!(fill.is_diff);
+    assert_eq!(fill.total_filled_amount, Some(dec!(0.001)));
+    assert_eq!(fill.order_role, Some(OrderRole::Maker));
+    assert_eq!(fill.commission_currency_code, Some("usdt".into()));
+    assert_eq!(fill.commission_amount, Some(dec!(0.00195)));
+    assert_eq!(fill.order_side, Some(OrderSide::Buy));
+
+    let liquidation = &fills[1];
+    assert_eq!(liquidation.fill_type, OrderFillType::Liquidation);
+    assert_eq!(liquidation.client_order_id, None);
+    assert_eq!(liquidation.trade_currency_pair, Some(btc_usdt()));
+    assert_eq!(liquidation.order_side, Some(OrderSide::Sell));
+    assert_eq!(liquidation.order_amount, Some(dec!(0.001)));
+    assert_eq!(liquidation.order_role, Some(OrderRole::Taker));
+}
+
+#[test]
+fn liquidation_price() {
+    let (bybit, mut rx, _) = create_bybit();
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/position.json"))
+        .expect("in test");
+
+    let event = match rx.try_recv().expect("in test") {
+        ExchangeEvent::LiquidationPrice(event) => event,
+        event => panic!("Unexpected event {:?}", event),
+    };
+    assert_eq!(event.currency_pair, btc_usdt());
+    assert_eq!(event.liq_price, dec!(17600));
+    assert_eq!(event.entry_price, dec!(19500));
+    assert_eq!(event.side, OrderSide::Buy);
+
+    // There is no event for empty ETHUSDT position
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn wallet_balance() {
+    let (bybit, mut rx, _) = create_bybit();
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/wallet.json"))
+        .expect("in test");
+
+    let event = match rx.try_recv().expect("in test") {
+        ExchangeEvent::BalanceUpdate(event) => event,
+        event => panic!("Unexpected event {:?}", event),
+    };
+    assert_eq!(
+        sorted_balances(&event.balances_and_positions),
+        [("usdt".to_owned(), dec!(998.55))]
+    );
+}
+
+#[test]
+fn funding_changes_balance() {
+    let (bybit, mut rx, _) = create_bybit();
+    let _ = bybit.parse_get_balance(&rest_outcome("rest/wallet_balance.json"));
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/execution.json"))
+        .expect("in test");
+
+    // Funding fee is paid in settlement currency of BTCUSDT
+    let event = match rx.try_recv().expect("in test") {
+        ExchangeEvent::BalanceUpdate(event) => event,
+        event => panic!("Unexpected event {:?}", event),
+    };
+    assert_eq!(
+        sorted_balances(&event.balances_and_positions),
+        [
+            ("btc".to_owned(), dec!(0.0015)),
+            ("usdt".to_owned(), dec!(998.55) - dec!(0.00196))
+        ]
+    );
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn funding_before_balance_is_received_is_skipped() {
+    let (bybit, mut rx, _) = create_bybit();
+
+    bybit
+        .on_websocket_message(&load_fixture("ws/execution.json"))
+        .expect("in test");
+
+    assert!(rx.try_recv().is_err());
+}
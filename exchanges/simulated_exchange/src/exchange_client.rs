@@ -0,0 +1,273 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use itertools::Itertools;
+use mmb_core::exchanges::common::{
+    ActivePosition, CurrencyPair, ExchangeError, ExchangeErrorType, Price, RestRequestOutcome,
+};
+use mmb_core::exchanges::events::TradeId;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::exchanges::traits::ExchangeClient;
+use mmb_core::misc::time::get_current_time;
+use mmb_core::orders::fill::OrderFillType;
+use mmb_core::orders::order::*;
+use mmb_core::orders::pool::OrderRef;
+use mmb_utils::DateTime;
+
+use super::matching_engine::ORDER_NOT_FOUND_MSG;
+use super::simulated_exchange::SimulatedExchange;
+use super::support::{SimulatedBalance, SimulatedBatchResults, SimulatedCreatedOrder};
+
+#[async_trait]
+impl ExchangeClient for SimulatedExchange {
+    async fn request_all_symbols(&self) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let currency_pairs = self
+            .market
+            .engine
+            .lock()
+            .symbols()
+            .iter()
+            .map(|symbol| symbol.currency_pair())
+            .collect_vec();
+
+        Ok(Self::ok_response(&currency_pairs))
+    }
+
+    async fn create_order(&self, order: &OrderCreating) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let creation_result = self
+            .market
+            .engine
+            .lock()
+            .create_order(order, get_current_time());
+        match creation_result {
+            Ok((exchange_order_id, notifications)) => {
+                self.market.notify(notifications);
+                Ok(Self::ok_response(&SimulatedCreatedOrder {
+                    exchange_order_id,
+                }))
+            }
+            Err(error) => Ok(Self::error_response(error)),
+        }
+    }
+
+    async fn request_cancel_order(&self, order: &OrderCancelling) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let cancellation_result = self
+            .market
+            .engine
+            .lock()
+            .cancel_order(&order.exchange_order_id);
+        match cancellation_result {
+            Ok(notification) => {
+                self.market.notify(vec![notification]);
+                Ok(Self::ok_response(&order.exchange_order_id))
+            }
+            Err(error) => Ok(Self::error_response(error)),
+        }
+    }
+
+    async fn request_replace_order(
+        &self,
+        _order: &OrderCancelling,
+        _new_order: &OrderCreating,
+    ) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        Ok(Self::error_response(ExchangeError::new(
+            ExchangeErrorType::InvalidOrder,
+            "Replacing orders is not supported by simulated exchange".to_owned(),
+            None,
+        )))
+    }
+
+    async fn request_create_orders(&self, orders: &[OrderCreating]) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let mut notifications = Vec::new();
+        let results = {
+            let mut engine = self.market.engine.lock();
+            orders
+                .iter()
+                .map(|order| {
+                    engine.create_order(order, get_current_time()).map(
+                        |(exchange_order_id, order_notifications)| {
+                            notifications.extend(order_notifications);
+                            exchange_order_id
+                        },
+                    )
+                })
+                .collect_vec()
+        };
+        self.market.notify(notifications);
+
+        Ok(Self::ok_response(&SimulatedBatchResults { results }))
+    }
+
+    async fn request_cancel_orders(
+        &self,
+        orders: &[OrderCancelling],
+    ) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let mut notifications = Vec::new();
+        let results = {
+            let mut engine = self.market.engine.lock();
+            orders
+                .iter()
+                .map(|order| {
+                    engine
+                        .cancel_order(&order.exchange_order_id)
+                        .map(|notification| {
+                            notifications.push(notification);
+                            order.exchange_order_id.clone()
+                        })
+                })
+                .collect_vec()
+        };
+        self.market.notify(notifications);
+
+        Ok(Self::ok_response(&SimulatedBatchResults { results }))
+    }
+
+    async fn cancel_all_orders(&self, currency_pair: CurrencyPair) -> Result<()> {
+        self.market.simulate_latency().await;
+
+        let notifications = self.market.engine.lock().cancel_all_orders(currency_pair);
+        self.market.notify(notifications);
+
+        Ok(())
+    }
+
+    async fn request_open_orders(&self) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let orders = self
+            .market
+            .engine
+            .lock()
+            .open_orders(None)
+            .iter()
+            .map(|order| order.to_order_info())
+            .collect_vec();
+
+        Ok(Self::ok_response(&orders))
+    }
+
+    async fn request_open_orders_by_currency_pair(
+        &self,
+        currency_pair: CurrencyPair,
+    ) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let orders = self
+            .market
+            .engine
+            .lock()
+            .open_orders(Some(currency_pair))
+            .iter()
+            .map(|order| order.to_order_info())
+            .collect_vec();
+
+        Ok(Self::ok_response(&orders))
+    }
+
+    async fn request_order_info(&self, order: &OrderRef) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let order_info = self
+            .market
+            .engine
+            .lock()
+            .get_order_by_client_id(&order.client_order_id())
+            .map(|order| order.to_order_info());
+
+        match order_info {
+            Some(order_info) => Ok(Self::ok_response(&order_info)),
+            None => Ok(Self::error_response(ExchangeError::new(
+                ExchangeErrorType::OrderNotFound,
+                ORDER_NOT_FOUND_MSG.to_owned(),
+                None,
+            ))),
+        }
+    }
+
+    async fn request_my_trades(
+        &self,
+        symbol: &Symbol,
+        _last_date_time: Option<DateTime>,
+    ) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let trades = self
+            .market
+            .engine
+            .lock()
+            .fills(symbol.currency_pair())
+            .iter()
+            .map(|fill| {
+                OrderTrade::new(
+                    fill.exchange_order_id.clone(),
+                    TradeId::Number(fill.trade_id),
+                    fill.datetime,
+                    fill.price,
+                    fill.amount,
+                    fill.order_role,
+                    symbol.get_commission_currency_code(fill.side),
+                    None,
+                    None,
+                    OrderFillType::UserTrade,
+                )
+            })
+            .collect_vec();
+
+        Ok(Self::ok_response(&trades))
+    }
+
+    async fn request_get_position(&self) -> Result<RestRequestOutcome> {
+        // Only spot trading is simulated, so there are never any positions
+        self.market.simulate_latency().await;
+
+        Ok(Self::ok_response(&Vec::<()>::new()))
+    }
+
+    async fn request_get_balance_and_position(&self) -> Result<RestRequestOutcome> {
+        self.request_get_balance().await
+    }
+
+    async fn request_get_balance(&self) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        let balances = self
+            .market
+            .engine
+            .lock()
+            .balances()
+            .iter()
+            .map(|(currency_code, balance)| SimulatedBalance {
+                currency_code: *currency_code,
+                balance: *balance,
+            })
+            .collect_vec();
+
+        Ok(Self::ok_response(&balances))
+    }
+
+    async fn request_close_position(
+        &self,
+        _position: &ActivePosition,
+        _price: Option<Price>,
+    ) -> Result<RestRequestOutcome> {
+        self.market.simulate_latency().await;
+
+        Ok(Self::error_response(ExchangeError::new(
+            ExchangeErrorType::InvalidOrder,
+            "Positions are not supported by simulated exchange".to_owned(),
+            None,
+        )))
+    }
+}
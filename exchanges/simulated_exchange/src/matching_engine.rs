@@ -0,0 +1,814 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use itertools::Itertools;
+use mmb_core::exchanges::common::{
+    Amount, CurrencyCode, CurrencyPair, ExchangeError, ExchangeErrorType, Price, SortedOrderData,
+};
+use mmb_core::exchanges::general::symbol::{Precision, Symbol};
+use mmb_core::order_book::order_book_data::OrderBookData;
+use mmb_core::orders::order::{
+    ClientOrderId, ExchangeOrderId, OrderCreating, OrderExecutionType, OrderInfo, OrderRole,
+    OrderSide, OrderStatus, OrderType, TimeInForce,
+};
+use mmb_utils::{nothing_to_do, DateTime};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+pub const ORDER_NOT_FOUND_MSG: &str = "Order does not exist.";
+pub const INSUFFICIENT_FUNDS_MSG: &str = "Account has insufficient balance for requested action.";
+pub const PRICE_FILTER_MSG: &str = "Filter failure: PRICE_FILTER";
+pub const LOT_SIZE_MSG: &str = "Filter failure: LOT_SIZE";
+pub const MAKER_ONLY_WOULD_TAKE_MSG: &str = "Order would immediately match and take.";
+pub const NO_LIQUIDITY_MSG: &str = "There is no liquidity to execute market order.";
+
+/// Order accepted by the simulated exchange
+#[derive(Debug, Clone)]
+pub struct SimulatedOrder {
+    pub exchange_order_id: ExchangeOrderId,
+    pub client_order_id: ClientOrderId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub price: Price,
+    pub amount: Amount,
+    pub filled_amount: Amount,
+    pub filled_cost: Amount,
+    pub status: OrderStatus,
+    pub time_in_force: TimeInForce,
+}
+
+impl SimulatedOrder {
+    pub fn remaining_amount(&self) -> Amount {
+        self.amount - self.filled_amount
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.status == OrderStatus::Created
+    }
+
+    pub fn average_fill_price(&self) -> Price {
+        match self.filled_amount.is_zero() {
+            true => Decimal::ZERO,
+            false => self.filled_cost / self.filled_amount,
+        }
+    }
+
+    pub fn to_order_info(&self) -> OrderInfo {
+        OrderInfo::new(
+            self.currency_pair,
+            self.exchange_order_id.clone(),
+            self.client_order_id.clone(),
+            self.side,
+            self.status,
+            self.price,
+            self.amount,
+            self.average_fill_price(),
+            self.filled_amount,
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Checks whether order can be executed against opposite side liquidity at `price`
+    fn is_crossed_by(&self, price: Price) -> bool {
+        match self.side {
+            OrderSide::Buy => price <= self.price,
+            OrderSide::Sell => price >= self.price,
+        }
+    }
+}
+
+/// Single execution of a simulated order
+#[derive(Debug, Clone)]
+pub struct SimulatedFill {
+    pub trade_id: u64,
+    pub exchange_order_id: ExchangeOrderId,
+    pub client_order_id: ClientOrderId,
+    pub currency_pair: CurrencyPair,
+    pub side: OrderSide,
+    pub price: Price,
+    pub amount: Amount,
+    pub total_filled_amount: Amount,
+    pub order_amount: Amount,
+    pub order_role: OrderRole,
+    pub datetime: DateTime,
+}
+
+/// Things happened inside matching engine that should be reported to the exchange client user
+#[derive(Debug, Clone)]
+pub enum EngineNotification {
+    OrderCreated(ClientOrderId, ExchangeOrderId),
+    OrderFilled(SimulatedFill),
+    OrderCancelled(ClientOrderId, ExchangeOrderId),
+}
+
+/// In-process matching engine that executes own orders against externally supplied market data.
+/// Market liquidity consumed by own orders is removed from the current book until next book update.
+/// Commissions are not deducted from simulated balances.
+pub struct MatchingEngine {
+    symbols: HashMap<CurrencyPair, Arc<Symbol>>,
+    balances: HashMap<CurrencyCode, Amount>,
+    order_books: HashMap<CurrencyPair, OrderBookData>,
+    // ordered by exchange order id so resting orders are matched with time priority
+    orders: BTreeMap<u64, SimulatedOrder>,
+    fills: Vec<SimulatedFill>,
+    liquidity_share: Decimal,
+    last_order_id: u64,
+    last_trade_id: u64,
+}
+
+impl MatchingEngine {
+    pub fn new(
+        symbols: &[Arc<Symbol>],
+        balances: HashMap<CurrencyCode, Amount>,
+        liquidity_share: Decimal,
+    ) -> Self {
+        Self {
+            symbols: symbols
+                .iter()
+                .map(|symbol| (symbol.currency_pair(), symbol.clone()))
+                .collect(),
+            balances,
+            order_books: HashMap::new(),
+            orders: BTreeMap::new(),
+            fills: Vec::new(),
+            liquidity_share,
+            last_order_id: 0,
+            last_trade_id: 0,
+        }
+    }
+
+    pub fn symbols(&self) -> Vec<Arc<Symbol>> {
+        self.symbols.values().cloned().collect_vec()
+    }
+
+    pub fn balances(&self) -> &HashMap<CurrencyCode, Amount> {
+        &self.balances
+    }
+
+    pub fn set_balance(&mut self, currency_code: CurrencyCode, balance: Amount) {
+        let _ = self.balances.insert(currency_code, balance);
+    }
+
+    pub fn open_orders(&self, currency_pair: Option<CurrencyPair>) -> Vec<&SimulatedOrder> {
+        self.orders
+            .values()
+            .filter(|order| order.is_open())
+            .filter(|order| currency_pair.map_or(true, |x| x == order.currency_pair))
+            .collect_vec()
+    }
+
+    pub fn get_order_by_client_id(
+        &self,
+        client_order_id: &ClientOrderId,
+    ) -> Option<&SimulatedOrder> {
+        self.orders
+            .values()
+            .find(|order| &order.client_order_id == client_order_id)
+    }
+
+    pub fn fills(&self, currency_pair: CurrencyPair) -> Vec<&SimulatedFill> {
+        self.fills
+            .iter()
+            .filter(|fill| fill.currency_pair == currency_pair)
+            .collect_vec()
+    }
+
+    pub fn get_symbol(&self, currency_pair: CurrencyPair) -> Option<&Arc<Symbol>> {
+        self.symbols.get(&currency_pair)
+    }
+
+    /// Validates order, reserves nothing but checks available balance, matches it against
+    /// current book and leaves the rest of GTC limit order in the book
+    pub fn create_order(
+        &mut self,
+        order: &OrderCreating,
+        now: DateTime,
+    ) -> Result<(ExchangeOrderId, Vec<EngineNotification>), ExchangeError> {
+        let header = &order.header;
+        let symbol = self
+            .symbols
+            .get(&header.currency_pair)
+            .cloned()
+            .ok_or_else(|| {
+                invalid_order(format!("Unknown currency pair {}", header.currency_pair))
+            })?;
+
+        match header.options.time_in_force {
+            TimeInForce::GoodTillCancelled | TimeInForce::ImmediateOrCancel => nothing_to_do(),
+            unsupported => {
+                return Err(invalid_order(format!(
+                    "Time in force {:?} is not supported",
+                    unsupported
+                )))
+            }
+        }
+
+        Self::check_amount_filter(&symbol, header.amount)?;
+        if header.order_type != OrderType::Market {
+            Self::check_price_filter(&symbol, order.price)?;
+        }
+
+        let order_book = self.order_books.get(&header.currency_pair);
+        let opposite_top = order_book.and_then(|book| match header.side {
+            OrderSide::Buy => book.asks.keys().next().cloned(),
+            OrderSide::Sell => book.bids.keys().next_back().cloned(),
+        });
+
+        let price = match header.order_type {
+            OrderType::Market => {
+                // worst price that can be reached by market order in the current book
+                self.estimate_market_order_worst_price(
+                    header.currency_pair,
+                    header.side,
+                    header.amount,
+                )
+                .ok_or_else(|| invalid_order(NO_LIQUIDITY_MSG.to_owned()))?
+            }
+            OrderType::Limit => order.price,
+            unsupported => {
+                return Err(invalid_order(format!(
+                    "Order type {:?} is not supported",
+                    unsupported
+                )))
+            }
+        };
+
+        let would_take = opposite_top.map_or(false, |top| match header.side {
+            OrderSide::Buy => top <= price,
+            OrderSide::Sell => top >= price,
+        });
+        if header.execution_type == OrderExecutionType::MakerOnly && would_take {
+            return Err(invalid_order(MAKER_ONLY_WOULD_TAKE_MSG.to_owned()));
+        }
+
+        let (currency_code, required_amount) = match header.side {
+            OrderSide::Buy => (symbol.quote_currency_code, header.amount * price),
+            OrderSide::Sell => (symbol.base_currency_code, header.amount),
+        };
+        if self.available_balance(currency_code) < required_amount {
+            return Err(ExchangeError::new(
+                ExchangeErrorType::InsufficientFunds,
+                INSUFFICIENT_FUNDS_MSG.to_owned(),
+                None,
+            ));
+        }
+
+        self.last_order_id += 1;
+        let id = self.last_order_id;
+        let exchange_order_id = ExchangeOrderId::from(id.to_string().as_str());
+        self.orders.insert(
+            id,
+            SimulatedOrder {
+                exchange_order_id: exchange_order_id.clone(),
+                client_order_id: header.client_order_id.clone(),
+                currency_pair: header.currency_pair,
+                side: header.side,
+                order_type: header.order_type,
+                price,
+                amount: header.amount,
+                filled_amount: Decimal::ZERO,
+                filled_cost: Decimal::ZERO,
+                status: OrderStatus::Created,
+                time_in_force: header.options.time_in_force,
+            },
+        );
+
+        let mut notifications = vec![EngineNotification::OrderCreated(
+            header.client_order_id.clone(),
+            exchange_order_id.clone(),
+        )];
+
+        if would_take {
+            self.match_against_book(id, OrderRole::Taker, now, &mut notifications);
+        }
+
+        let order = &mut self.orders.get_mut(&id).expect("order was inserted above");
+        let is_immediate = order.order_type == OrderType::Market
+            || order.time_in_force == TimeInForce::ImmediateOrCancel;
+        if is_immediate && order.is_open() {
+            // unfilled rest of market and IOC orders can't stay in the book
+            order.status = OrderStatus::Canceled;
+            notifications.push(EngineNotification::OrderCancelled(
+                order.client_order_id.clone(),
+                order.exchange_order_id.clone(),
+            ));
+        }
+
+        Ok((exchange_order_id, notifications))
+    }
+
+    pub fn cancel_order(
+        &mut self,
+        exchange_order_id: &ExchangeOrderId,
+    ) -> Result<EngineNotification, ExchangeError> {
+        let order = exchange_order_id
+            .as_str()
+            .parse::<u64>()
+            .ok()
+            .and_then(|id| self.orders.get_mut(&id))
+            .ok_or_else(|| {
+                ExchangeError::new(
+                    ExchangeErrorType::OrderNotFound,
+                    ORDER_NOT_FOUND_MSG.to_owned(),
+                    None,
+                )
+            })?;
+
+        match order.status {
+            OrderStatus::Created => {
+                order.status = OrderStatus::Canceled;
+                Ok(EngineNotification::OrderCancelled(
+                    order.client_order_id.clone(),
+                    order.exchange_order_id.clone(),
+                ))
+            }
+            OrderStatus::Completed => Err(ExchangeError::new(
+                ExchangeErrorType::OrderCompleted,
+                format!("Order {} is already completed", exchange_order_id),
+                None,
+            )),
+            _ => Err(ExchangeError::new(
+                ExchangeErrorType::OrderNotFound,
+                ORDER_NOT_FOUND_MSG.to_owned(),
+                None,
+            )),
+        }
+    }
+
+    pub fn cancel_all_orders(&mut self, currency_pair: CurrencyPair) -> Vec<EngineNotification> {
+        self.orders
+            .values_mut()
+            .filter(|order| order.is_open() && order.currency_pair == currency_pair)
+            .map(|order| {
+                order.status = OrderStatus::Canceled;
+                EngineNotification::OrderCancelled(
+                    order.client_order_id.clone(),
+                    order.exchange_order_id.clone(),
+                )
+            })
+            .collect_vec()
+    }
+
+    /// Replaces market book and executes resting orders crossed by it as makers
+    pub fn update_order_book(
+        &mut self,
+        currency_pair: CurrencyPair,
+        order_book: OrderBookData,
+        now: DateTime,
+    ) -> Vec<EngineNotification> {
+        let _ = self.order_books.insert(currency_pair, order_book);
+
+        let crossed_orders = self
+            .orders
+            .iter()
+            .filter(|(_, order)| order.is_open() && order.currency_pair == currency_pair)
+            .map(|(id, _)| *id)
+            .collect_vec();
+
+        let mut notifications = Vec::new();
+        for id in crossed_orders {
+            self.match_against_book(id, OrderRole::Maker, now, &mut notifications);
+        }
+
+        notifications
+    }
+
+    /// Applies incremental changes to the known order book and fills resting orders crossed by the result.
+    /// Updates without preceding snapshot are ignored
+    pub fn apply_order_book_update(
+        &mut self,
+        currency_pair: CurrencyPair,
+        update: &OrderBookData,
+        now: DateTime,
+    ) -> Vec<EngineNotification> {
+        let mut order_book = match self.order_books.remove(&currency_pair) {
+            Some(order_book) => order_book,
+            None => return Vec::new(),
+        };
+
+        order_book.update(vec![update.clone()]);
+        self.update_order_book(currency_pair, order_book, now)
+    }
+
+    /// Executes resting orders crossed by a public trade. Trade amount limits executed amount.
+    pub fn apply_trade(
+        &mut self,
+        currency_pair: CurrencyPair,
+        price: Price,
+        amount: Amount,
+        taker_side: OrderSide,
+        now: DateTime,
+    ) -> Vec<EngineNotification> {
+        let maker_side = taker_side.change_side();
+        let mut trade_amount_left = amount * self.liquidity_share;
+
+        let ids = self
+            .orders
+            .iter()
+            .filter(|(_, order)| {
+                order.is_open()
+                    && order.currency_pair == currency_pair
+                    && order.side == maker_side
+                    && order.is_crossed_by(price)
+            })
+            .map(|(id, _)| *id)
+            .collect_vec();
+
+        let mut notifications = Vec::new();
+        for id in ids {
+            if trade_amount_left <= Decimal::ZERO {
+                break;
+            }
+
+            let order_price = self.orders[&id].price;
+            let fill_amount = self.orders[&id].remaining_amount().min(trade_amount_left);
+            trade_amount_left -= fill_amount;
+
+            notifications.push(self.fill_order(
+                id,
+                order_price,
+                fill_amount,
+                OrderRole::Maker,
+                now,
+            ));
+        }
+
+        notifications
+    }
+
+    fn match_against_book(
+        &mut self,
+        id: u64,
+        order_role: OrderRole,
+        now: DateTime,
+        notifications: &mut Vec<EngineNotification>,
+    ) {
+        let order = self.orders[&id].clone();
+        let book = match self.order_books.get_mut(&order.currency_pair) {
+            Some(book) => book,
+            None => return,
+        };
+
+        let levels = match order.side {
+            OrderSide::Buy => book.asks.iter().map(|(p, a)| (*p, *a)).collect_vec(),
+            OrderSide::Sell => book.bids.iter().rev().map(|(p, a)| (*p, *a)).collect_vec(),
+        };
+
+        let mut executions = Vec::new();
+        let mut remaining = order.remaining_amount();
+        for (level_price, level_amount) in levels {
+            if remaining <= Decimal::ZERO || !order.is_crossed_by(level_price) {
+                break;
+            }
+
+            let available = level_amount * self.liquidity_share;
+            let fill_amount = remaining.min(available);
+            if fill_amount <= Decimal::ZERO {
+                continue;
+            }
+            remaining -= fill_amount;
+
+            let book_side = match order.side {
+                OrderSide::Buy => &mut book.asks,
+                OrderSide::Sell => &mut book.bids,
+            };
+            consume_level(book_side, level_price, fill_amount / self.liquidity_share);
+
+            // resting order gets its own price, taker order gets book price
+            let fill_price = match order_role {
+                OrderRole::Maker => order.price,
+                OrderRole::Taker => level_price,
+            };
+            executions.push((fill_price, fill_amount));
+        }
+
+        for (fill_price, fill_amount) in executions {
+            notifications.push(self.fill_order(id, fill_price, fill_amount, order_role, now));
+        }
+    }
+
+    fn fill_order(
+        &mut self,
+        id: u64,
+        price: Price,
+        amount: Amount,
+        order_role: OrderRole,
+        now: DateTime,
+    ) -> EngineNotification {
+        let order = self.orders.get_mut(&id).expect("order should exist");
+        order.filled_amount += amount;
+        order.filled_cost += amount * price;
+        if order.remaining_amount() <= Decimal::ZERO {
+            order.status = OrderStatus::Completed;
+        }
+
+        let symbol = &self.symbols[&order.currency_pair];
+        let cost = amount * price;
+        let (base_delta, quote_delta) = match order.side {
+            OrderSide::Buy => (amount, -cost),
+            OrderSide::Sell => (-amount, cost),
+        };
+        *self
+            .balances
+            .entry(symbol.base_currency_code)
+            .or_insert(Decimal::ZERO) += base_delta;
+        *self
+            .balances
+            .entry(symbol.quote_currency_code)
+            .or_insert(Decimal::ZERO) += quote_delta;
+
+        self.last_trade_id += 1;
+        let fill = SimulatedFill {
+            trade_id: self.last_trade_id,
+            exchange_order_id: order.exchange_order_id.clone(),
+            client_order_id: order.client_order_id.clone(),
+            currency_pair: order.currency_pair,
+            side: order.side,
+            price,
+            amount,
+            total_filled_amount: order.filled_amount,
+            order_amount: order.amount,
+            order_role,
+            datetime: now,
+        };
+        self.fills.push(fill.clone());
+
+        EngineNotification::OrderFilled(fill)
+    }
+
+    fn available_balance(&self, currency_code: CurrencyCode) -> Amount {
+        let total = self
+            .balances
+            .get(&currency_code)
+            .cloned()
+            .unwrap_or(Decimal::ZERO);
+
+        let locked: Amount = self
+            .orders
+            .values()
+            .filter(|order| order.is_open())
+            .filter_map(|order| {
+                let order_symbol = self.symbols.get(&order.currency_pair)?;
+                match order.side {
+                    OrderSide::Buy if order_symbol.quote_currency_code == currency_code => {
+                        Some(order.remaining_amount() * order.price)
+                    }
+                    OrderSide::Sell if order_symbol.base_currency_code == currency_code => {
+                        Some(order.remaining_amount())
+                    }
+                    _ => None,
+                }
+            })
+            .sum();
+
+        total - locked
+    }
+
+    fn estimate_market_order_worst_price(
+        &self,
+        currency_pair: CurrencyPair,
+        side: OrderSide,
+        amount: Amount,
+    ) -> Option<Price> {
+        let book = self.order_books.get(&currency_pair)?;
+        let levels = match side {
+            OrderSide::Buy => book.asks.iter().collect_vec(),
+            OrderSide::Sell => book.bids.iter().rev().collect_vec(),
+        };
+
+        let mut remaining = amount;
+        let mut worst_price = None;
+        for (price, level_amount) in levels {
+            worst_price = Some(*price);
+            remaining -= *level_amount * self.liquidity_share;
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+        }
+
+        worst_price
+    }
+
+    fn check_price_filter(symbol: &Symbol, price: Price) -> Result<(), ExchangeError> {
+        let below_min = symbol.min_price.map_or(false, |min| price < min);
+        let above_max = symbol.max_price.map_or(false, |max| price > max);
+        let not_by_tick = match symbol.price_precision {
+            Precision::ByTick { tick } => !tick.is_zero() && !(price % tick).is_zero(),
+            Precision::ByMantissa { .. } => false,
+        };
+
+        if price <= Decimal::ZERO || below_min || above_max || not_by_tick {
+            return Err(invalid_order(PRICE_FILTER_MSG.to_owned()));
+        }
+
+        Ok(())
+    }
+
+    fn check_amount_filter(symbol: &Symbol, amount: Amount) -> Result<(), ExchangeError> {
+        let below_min = symbol.min_amount.map_or(false, |min| amount < min);
+        let above_max = symbol.max_amount.map_or(false, |max| amount > max);
+
+        if amount <= Decimal::ZERO || below_min || above_max {
+            return Err(invalid_order(LOT_SIZE_MSG.to_owned()));
+        }
+
+        Ok(())
+    }
+}
+
+fn consume_level(book_side: &mut SortedOrderData, price: Price, amount: Amount) {
+    if let Some(level_amount) = book_side.get_mut(&price) {
+        *level_amount -= amount;
+        if *level_amount <= dec!(0) {
+            let _ = book_side.remove(&price);
+        }
+    }
+}
+
+fn invalid_order(message: String) -> ExchangeError {
+    ExchangeError::new(ExchangeErrorType::InvalidOrder, message, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use mmb_core::exchanges::common::ExchangeAccountId;
+    use mmb_core::orders::order::{OrderHeader, OrderOptions};
+    use mmb_utils::hashmap;
+
+    fn currency_pair() -> CurrencyPair {
+        CurrencyPair::from_codes("eth".into(), "btc".into())
+    }
+
+    fn create_engine() -> MatchingEngine {
+        let symbol = Arc::new(Symbol::new(
+            true,
+            false,
+            "ETH".into(),
+            "eth".into(),
+            "BTC".into(),
+            "btc".into(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            "eth".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.1) },
+            Precision::ByTick { tick: dec!(0.1) },
+        ));
+
+        MatchingEngine::new(
+            &[symbol],
+            hashmap!["eth".into() => dec!(10), "btc".into() => dec!(10)],
+            dec!(1),
+        )
+    }
+
+    fn order_creating(side: OrderSide, price: Price, amount: Amount) -> OrderCreating {
+        order_creating_with_time_in_force(side, price, amount, TimeInForce::GoodTillCancelled)
+    }
+
+    fn order_creating_with_time_in_force(
+        side: OrderSide,
+        price: Price,
+        amount: Amount,
+        time_in_force: TimeInForce,
+    ) -> OrderCreating {
+        let exchange_account_id: ExchangeAccountId = "Simulated_0".parse().expect("in test");
+        OrderCreating {
+            header: OrderHeader::new_with_options(
+                ClientOrderId::unique_id(),
+                Utc::now(),
+                exchange_account_id,
+                currency_pair(),
+                OrderType::Limit,
+                side,
+                amount,
+                OrderExecutionType::None,
+                None,
+                None,
+                "MatchingEngineTest".to_owned(),
+                OrderOptions::with_time_in_force(time_in_force),
+            ),
+            price,
+        }
+    }
+
+    fn assert_time_in_force_rejected(time_in_force: TimeInForce) {
+        let mut engine = create_engine();
+        let _ = engine.update_order_book(
+            currency_pair(),
+            OrderBookData::new(
+                BTreeMap::from([(dec!(2), dec!(1))]),
+                BTreeMap::from([(dec!(1), dec!(1))]),
+            ),
+            Utc::now(),
+        );
+
+        let order =
+            order_creating_with_time_in_force(OrderSide::Buy, dec!(2), dec!(3), time_in_force);
+        let error = engine
+            .create_order(&order, Utc::now())
+            .expect_err("in test");
+
+        assert_eq!(error.error_type, ExchangeErrorType::InvalidOrder);
+        assert!(engine
+            .get_order_by_client_id(&order.header.client_order_id)
+            .is_none());
+        assert!(engine.fills(currency_pair()).is_empty());
+    }
+
+    #[test]
+    fn open_orders_lock_balance() {
+        let mut engine = create_engine();
+
+        let _ = engine
+            .create_order(
+                &order_creating(OrderSide::Buy, dec!(1), dec!(6)),
+                Utc::now(),
+            )
+            .expect("in test");
+        let error = engine
+            .create_order(
+                &order_creating(OrderSide::Buy, dec!(1), dec!(6)),
+                Utc::now(),
+            )
+            .expect_err("in test");
+
+        assert_eq!(error.error_type, ExchangeErrorType::InsufficientFunds);
+    }
+
+    #[test]
+    fn cancel_unfilled_rest_of_ioc_order() {
+        let mut engine = create_engine();
+        let _ = engine.update_order_book(
+            currency_pair(),
+            OrderBookData::new(
+                BTreeMap::from([(dec!(2), dec!(1))]),
+                BTreeMap::from([(dec!(1), dec!(1))]),
+            ),
+            Utc::now(),
+        );
+
+        let order = order_creating_with_time_in_force(
+            OrderSide::Buy,
+            dec!(2),
+            dec!(3),
+            TimeInForce::ImmediateOrCancel,
+        );
+        let (_, notifications) = engine.create_order(&order, Utc::now()).expect("in test");
+
+        assert!(matches!(
+            notifications.last(),
+            Some(EngineNotification::OrderCancelled(..))
+        ));
+        let order = engine
+            .get_order_by_client_id(&order.header.client_order_id)
+            .expect("in test");
+        assert_eq!(order.status, OrderStatus::Canceled);
+        assert_eq!(order.filled_amount, dec!(1));
+        assert!(engine.open_orders(None).is_empty());
+    }
+
+    #[test]
+    fn reject_fill_or_kill_order() {
+        assert_time_in_force_rejected(TimeInForce::FillOrKill);
+    }
+
+    #[test]
+    fn reject_good_till_date_order() {
+        assert_time_in_force_rejected(TimeInForce::GoodTillDate(Utc::now()));
+    }
+
+    #[test]
+    fn cancel_completed_order() {
+        let mut engine = create_engine();
+        let (exchange_order_id, _) = engine
+            .create_order(
+                &order_creating(OrderSide::Sell, dec!(1), dec!(2)),
+                Utc::now(),
+            )
+            .expect("in test");
+        let _ = engine.apply_trade(
+            currency_pair(),
+            dec!(1),
+            dec!(2),
+            OrderSide::Buy,
+            Utc::now(),
+        );
+
+        let error = engine
+            .cancel_order(&exchange_order_id)
+            .expect_err("in test");
+        assert_eq!(error.error_type, ExchangeErrorType::OrderCompleted);
+
+        let error = engine.cancel_order(&"unknown".into()).expect_err("in test");
+        assert_eq!(error.error_type, ExchangeErrorType::OrderNotFound);
+    }
+}
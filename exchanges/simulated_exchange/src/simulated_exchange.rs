@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_core::backtesting::BacktestMarket;
+use mmb_core::exchanges::common::{
+    Amount, CurrencyCode, CurrencyId, CurrencyPair, ExchangeAccountId, ExchangeError, Price,
+    RestRequestOutcome, SpecificCurrencyPair,
+};
+use mmb_core::exchanges::events::{AllowedEventSourceType, ExchangeEvent, TradeId, TradesEvent};
+use mmb_core::exchanges::general::exchange::BoxExchangeClient;
+use mmb_core::exchanges::general::features::{
+    ExchangeFeatures, OpenOrdersType, OrderFeatures, OrderTradeOption, RestFillsFeatures,
+    RestFillsType, WebSocketOptions,
+};
+use mmb_core::exchanges::general::handlers::handle_order_filled::FillEventData;
+use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::exchanges::timeouts::requests_timeout_manager_factory::RequestTimeoutArguments;
+use mmb_core::exchanges::traits::{ExchangeClientBuilder, ExchangeClientBuilderResult};
+use mmb_core::lifecycle::application_manager::ApplicationManager;
+use mmb_core::misc::time::get_current_time;
+use mmb_core::order_book::event::{EventType, OrderBookEvent};
+use mmb_core::order_book::order_book_data::OrderBookData;
+use mmb_core::orders::fill::{EventSourceType, OrderFillType};
+use mmb_core::orders::order::*;
+use mmb_core::settings::ExchangeSettings;
+use mmb_utils::DateTime;
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use super::matching_engine::{EngineNotification, MatchingEngine, SimulatedFill, SimulatedOrder};
+use super::support::SimulatedErrorResponse;
+
+/// Initial state and behaviour of the simulated exchange
+#[derive(Clone)]
+pub struct SimulatedExchangeConfig {
+    pub symbols: Vec<Arc<Symbol>>,
+    pub balances: HashMap<CurrencyCode, Amount>,
+    /// Delay before every response of the rest API
+    pub latency: Duration,
+    /// Share of every book level or public trade amount available for own orders, in range (0, 1].
+    /// Values less than 1 emulate queue position and produce partial fills
+    pub liquidity_share: Decimal,
+    /// Max count of orders in single batch create or cancel request.
+    /// Batch requests are disabled if None
+    pub max_batch_orders_count: Option<usize>,
+}
+
+impl SimulatedExchangeConfig {
+    pub fn new(symbols: Vec<Arc<Symbol>>, balances: HashMap<CurrencyCode, Amount>) -> Self {
+        Self {
+            symbols,
+            balances,
+            latency: Duration::ZERO,
+            liquidity_share: dec!(1),
+            max_batch_orders_count: None,
+        }
+    }
+}
+
+type OrderEventCallback =
+    Mutex<Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>>;
+
+/// State of the simulated exchange shared between the exchange client used by the engine and tests
+/// that drive market data
+pub struct SimulatedMarket {
+    pub(super) config: SimulatedExchangeConfig,
+    pub(super) engine: Mutex<MatchingEngine>,
+
+    exchange_account_id: Mutex<Option<ExchangeAccountId>>,
+    events_channel: Mutex<Option<broadcast::Sender<ExchangeEvent>>>,
+    last_event_id: Mutex<u64>,
+    requests_in_flight: AtomicUsize,
+
+    pub(super) order_created_callback: OrderEventCallback,
+    pub(super) order_cancelled_callback: OrderEventCallback,
+    pub(super) handle_order_filled_callback: Mutex<Box<dyn FnMut(FillEventData) + Send + Sync>>,
+    pub(super) handle_trade_callback: Mutex<
+        Box<dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync>,
+    >,
+}
+
+impl SimulatedMarket {
+    pub fn new(config: SimulatedExchangeConfig) -> Arc<Self> {
+        assert!(
+            config.liquidity_share > dec!(0) && config.liquidity_share <= dec!(1),
+            "liquidity_share should be in range (0, 1], but it is {}",
+            config.liquidity_share
+        );
+
+        let engine = MatchingEngine::new(
+            &config.symbols,
+            config.balances.clone(),
+            config.liquidity_share,
+        );
+
+        Arc::new(Self {
+            config,
+            engine: Mutex::new(engine),
+            exchange_account_id: Mutex::new(None),
+            events_channel: Mutex::new(None),
+            last_event_id: Mutex::new(0),
+            requests_in_flight: AtomicUsize::new(0),
+            order_created_callback: Mutex::new(Box::new(|_, _, _| {})),
+            order_cancelled_callback: Mutex::new(Box::new(|_, _, _| {})),
+            handle_order_filled_callback: Mutex::new(Box::new(|_| {})),
+            handle_trade_callback: Mutex::new(Box::new(|_, _, _, _, _, _| {})),
+        })
+    }
+
+    /// Replaces order book snapshot, notifies engine with `ExchangeEvent::OrderBookEvent`
+    /// and fills resting orders crossed by the new book
+    pub fn update_order_book(&self, currency_pair: CurrencyPair, order_book: OrderBookData) {
+        let now = get_current_time();
+        self.send_order_book_event(currency_pair, &order_book, now);
+
+        let notifications = self
+            .engine
+            .lock()
+            .update_order_book(currency_pair, order_book, now);
+        self.notify(notifications);
+    }
+
+    /// Publishes public trade and fills resting orders crossed by it
+    pub fn add_trade(
+        &self,
+        currency_pair: CurrencyPair,
+        price: Price,
+        amount: Amount,
+        taker_side: OrderSide,
+    ) {
+        let now = get_current_time();
+        let (trade_id, notifications) = {
+            let mut engine = self.engine.lock();
+            let notifications = engine.apply_trade(currency_pair, price, amount, taker_side, now);
+            (self.next_event_id(), notifications)
+        };
+
+        (self.handle_trade_callback.lock())(
+            currency_pair,
+            TradeId::Number(trade_id),
+            price,
+            amount,
+            taker_side,
+            now,
+        );
+        self.notify(notifications);
+    }
+
+    pub fn set_balance(&self, currency_code: CurrencyCode, balance: Amount) {
+        self.engine.lock().set_balance(currency_code, balance);
+    }
+
+    pub fn get_balance(&self, currency_code: CurrencyCode) -> Amount {
+        self.engine
+            .lock()
+            .balances()
+            .get(&currency_code)
+            .cloned()
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    pub fn open_orders(&self) -> Vec<SimulatedOrder> {
+        self.engine
+            .lock()
+            .open_orders(None)
+            .into_iter()
+            .cloned()
+            .collect_vec()
+    }
+
+    pub fn fills(&self, currency_pair: CurrencyPair) -> Vec<SimulatedFill> {
+        self.engine
+            .lock()
+            .fills(currency_pair)
+            .into_iter()
+            .cloned()
+            .collect_vec()
+    }
+
+    pub(super) fn attach(
+        &self,
+        exchange_account_id: ExchangeAccountId,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+    ) {
+        let mut attached_id = self.exchange_account_id.lock();
+        if let Some(attached_id) = *attached_id {
+            panic!(
+                "SimulatedMarket is already used by {}, so it can't be used by {}",
+                attached_id, exchange_account_id
+            );
+        }
+
+        *attached_id = Some(exchange_account_id);
+        *self.events_channel.lock() = Some(events_channel);
+    }
+
+    /// Detaches the exchange which uses the market, so the market can be used by a new exchange
+    /// instance with the same state. It allows to simulate restart of the engine
+    pub fn detach(&self) {
+        *self.exchange_account_id.lock() = None;
+        *self.events_channel.lock() = None;
+        *self.order_created_callback.lock() = Box::new(|_, _, _| {});
+        *self.order_cancelled_callback.lock() = Box::new(|_, _, _| {});
+        *self.handle_order_filled_callback.lock() = Box::new(|_| {});
+        *self.handle_trade_callback.lock() = Box::new(|_, _, _, _, _, _| {});
+    }
+
+    pub(super) async fn simulate_latency(&self) {
+        if !self.config.latency.is_zero() {
+            let _request_in_flight = RequestInFlight::new(&self.requests_in_flight);
+            tokio::time::sleep(self.config.latency).await;
+        }
+    }
+
+    /// Reports engine notifications through exchange client callbacks.
+    /// Must be called without holding engine lock because callbacks can request exchange client again
+    pub(super) fn notify(&self, notifications: Vec<EngineNotification>) {
+        for notification in notifications {
+            match notification {
+                EngineNotification::OrderCreated(client_order_id, exchange_order_id) => {
+                    (self.order_created_callback.lock())(
+                        client_order_id,
+                        exchange_order_id,
+                        EventSourceType::WebSocket,
+                    )
+                }
+                EngineNotification::OrderCancelled(client_order_id, exchange_order_id) => {
+                    (self.order_cancelled_callback.lock())(
+                        client_order_id,
+                        exchange_order_id,
+                        EventSourceType::WebSocket,
+                    )
+                }
+                EngineNotification::OrderFilled(fill) => {
+                    (self.handle_order_filled_callback.lock())(Self::to_fill_event_data(fill))
+                }
+            }
+        }
+    }
+
+    fn to_fill_event_data(fill: SimulatedFill) -> FillEventData {
+        FillEventData {
+            source_type: EventSourceType::WebSocket,
+            trade_id: Some(TradeId::Number(fill.trade_id)),
+            client_order_id: Some(fill.client_order_id),
+            exchange_order_id: fill.exchange_order_id,
+            fill_price: fill.price,
+            fill_amount: fill.amount,
+            is_diff: true,
+            total_filled_amount: Some(fill.total_filled_amount),
+            order_role: Some(fill.order_role),
+            commission_currency_code: None,
+            commission_rate: None,
+            commission_amount: None,
+            fill_type: OrderFillType::UserTrade,
+            trade_currency_pair: Some(fill.currency_pair),
+            order_side: Some(fill.side),
+            order_amount: Some(fill.order_amount),
+            fill_date: Some(fill.datetime),
+        }
+    }
+
+    fn send_order_book_event(
+        &self,
+        currency_pair: CurrencyPair,
+        order_book: &OrderBookData,
+        now: DateTime,
+    ) {
+        let exchange_account_id = match *self.exchange_account_id.lock() {
+            Some(exchange_account_id) => exchange_account_id,
+            None => return,
+        };
+
+        let event = OrderBookEvent::new(
+            now,
+            exchange_account_id,
+            currency_pair,
+            self.next_event_id().to_string(),
+            EventType::Snapshot,
+            Arc::new(order_book.clone()),
+        );
+
+        if let Some(events_channel) = self.events_channel.lock().as_ref() {
+            if let Err(error) = events_channel.send(ExchangeEvent::OrderBookEvent(event)) {
+                log::error!("Unable to send order book event: {:?}", error);
+            }
+        }
+    }
+
+    fn next_event_id(&self) -> u64 {
+        let mut last_event_id = self.last_event_id.lock();
+        *last_event_id += 1;
+        *last_event_id
+    }
+}
+
+/// Counts request as in flight until dropped, so cancelled request isn't counted forever
+struct RequestInFlight<'a>(&'a AtomicUsize);
+
+impl<'a> RequestInFlight<'a> {
+    fn new(requests_in_flight: &'a AtomicUsize) -> Self {
+        requests_in_flight.fetch_add(1, Ordering::SeqCst);
+        Self(requests_in_flight)
+    }
+}
+
+impl Drop for RequestInFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl BacktestMarket for SimulatedMarket {
+    fn handle_order_book_event(&self, event: &OrderBookEvent) {
+        if *self.exchange_account_id.lock() != Some(event.exchange_account_id) {
+            return;
+        }
+
+        let notifications = {
+            let mut engine = self.engine.lock();
+            match event.event_type {
+                EventType::Snapshot => engine.update_order_book(
+                    event.currency_pair,
+                    event.data.as_ref().clone(),
+                    event.creation_time,
+                ),
+                EventType::Update => engine.apply_order_book_update(
+                    event.currency_pair,
+                    &event.data,
+                    event.creation_time,
+                ),
+            }
+        };
+        self.notify(notifications);
+    }
+
+    fn handle_trades_event(&self, event: &TradesEvent) {
+        if *self.exchange_account_id.lock() != Some(event.exchange_account_id) {
+            return;
+        }
+
+        let notifications = {
+            let mut engine = self.engine.lock();
+            event
+                .trades
+                .iter()
+                .flat_map(|trade| {
+                    engine.apply_trade(
+                        event.currency_pair,
+                        trade.price,
+                        trade.quantity,
+                        trade.side,
+                        trade.transaction_time,
+                    )
+                })
+                .collect_vec()
+        };
+        self.notify(notifications);
+    }
+
+    fn has_requests_in_flight(&self) -> bool {
+        self.requests_in_flight.load(Ordering::SeqCst) > 0
+    }
+}
+
+pub struct SimulatedExchange {
+    pub id: ExchangeAccountId,
+    pub settings: ExchangeSettings,
+    pub market: Arc<SimulatedMarket>,
+    pub supported_currencies: DashMap<CurrencyId, CurrencyCode>,
+    pub traded_specific_currencies: Mutex<Vec<SpecificCurrencyPair>>,
+}
+
+impl SimulatedExchange {
+    pub fn new(
+        id: ExchangeAccountId,
+        settings: ExchangeSettings,
+        market: Arc<SimulatedMarket>,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+    ) -> Self {
+        market.attach(id, events_channel);
+
+        Self {
+            id,
+            settings,
+            market,
+            supported_currencies: Default::default(),
+            traded_specific_currencies: Default::default(),
+        }
+    }
+
+    pub(super) fn ok_response(content: &impl Serialize) -> RestRequestOutcome {
+        let content = serde_json::to_string(content).expect("Unable to serialize response");
+        RestRequestOutcome::new(content, hyper::StatusCode::OK)
+    }
+
+    pub(super) fn error_response(error: ExchangeError) -> RestRequestOutcome {
+        let content = serde_json::to_string(&SimulatedErrorResponse { error })
+            .expect("Unable to serialize error response");
+        RestRequestOutcome::new(content, hyper::StatusCode::BAD_REQUEST)
+    }
+
+    pub fn features(config: &SimulatedExchangeConfig) -> ExchangeFeatures {
+        ExchangeFeatures::new(
+            OpenOrdersType::AllCurrencyPair,
+            RestFillsFeatures::new(RestFillsType::MyTrades),
+            OrderFeatures {
+                supports_immediate_or_cancel: true,
+                // matching engine doesn't emulate all-or-nothing fills and expiration of orders
+                supports_fill_or_kill: false,
+                supports_good_till_date: false,
+                supports_batch_orders: config.max_batch_orders_count.is_some(),
+                max_batch_orders_count: config.max_batch_orders_count.unwrap_or_default(),
+                ..OrderFeatures::default()
+            },
+            OrderTradeOption::default(),
+            WebSocketOptions::default(),
+            false,
+            true,
+            AllowedEventSourceType::All,
+            AllowedEventSourceType::All,
+        )
+    }
+}
+
+pub struct SimulatedExchangeBuilder {
+    market: Arc<SimulatedMarket>,
+}
+
+impl SimulatedExchangeBuilder {
+    pub fn new(market: Arc<SimulatedMarket>) -> Self {
+        Self { market }
+    }
+}
+
+impl ExchangeClientBuilder for SimulatedExchangeBuilder {
+    fn create_exchange_client(
+        &self,
+        exchange_settings: ExchangeSettings,
+        events_channel: broadcast::Sender<ExchangeEvent>,
+        _application_manager: Arc<ApplicationManager>,
+    ) -> ExchangeClientBuilderResult {
+        let exchange_account_id = exchange_settings.exchange_account_id;
+
+        ExchangeClientBuilderResult {
+            client: Box::new(SimulatedExchange::new(
+                exchange_account_id,
+                exchange_settings,
+                self.market.clone(),
+                events_channel,
+            )) as BoxExchangeClient,
+            features: SimulatedExchange::features(&self.market.config),
+        }
+    }
+
+    fn get_timeout_arguments(&self) -> RequestTimeoutArguments {
+        // there are no real rate limits, but requests still go through timeout manager
+        RequestTimeoutArguments::from_requests_per_minute(6000)
+    }
+}
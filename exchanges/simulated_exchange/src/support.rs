@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use itertools::Itertools;
+use mmb_core::connectivity::connectivity_manager::WebSocketRole;
+use mmb_core::exchanges::common::{
+    ActivePosition, Amount, ClosedPosition, CurrencyCode, CurrencyId, CurrencyPair, ExchangeError,
+    Price, RestRequestOutcome, SpecificCurrencyPair,
+};
+use mmb_core::exchanges::events::{ExchangeBalance, ExchangeBalancesAndPositions, TradeId};
+use mmb_core::exchanges::general::exchange::RequestResult;
+use mmb_core::exchanges::general::handlers::handle_order_filled::FillEventData;
+use mmb_core::exchanges::general::order::get_order_trades::OrderTrade;
+use mmb_core::exchanges::general::symbol::Symbol;
+use mmb_core::exchanges::traits::Support;
+use mmb_core::orders::fill::EventSourceType;
+use mmb_core::orders::order::*;
+use mmb_core::settings::ExchangeSettings;
+use mmb_utils::DateTime;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::simulated_exchange::SimulatedExchange;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedErrorResponse {
+    pub error: ExchangeError,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedCreatedOrder {
+    pub exchange_order_id: ExchangeOrderId,
+}
+
+/// Results of batch request in the same order as orders in the request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedBatchResults {
+    pub results: Vec<Result<ExchangeOrderId, ExchangeError>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulatedBalance {
+    pub currency_code: CurrencyCode,
+    pub balance: Amount,
+}
+
+#[async_trait]
+impl Support for SimulatedExchange {
+    fn is_rest_error_code(&self, response: &RestRequestOutcome) -> Result<(), ExchangeError> {
+        if !response.content.starts_with(r#"{"error":"#) {
+            return Ok(());
+        }
+
+        let error_response: SimulatedErrorResponse = serde_json::from_str(&response.content)
+            .map_err(|err| ExchangeError::parsing_error(&format!("response.content: {:?}", err)))?;
+
+        Err(error_response.error)
+    }
+
+    fn get_order_id(&self, response: &RestRequestOutcome) -> Result<ExchangeOrderId> {
+        let created_order: SimulatedCreatedOrder =
+            serde_json::from_str(&response.content).context("Unable to parse response content")?;
+
+        Ok(created_order.exchange_order_id)
+    }
+
+    fn parse_batch_orders(
+        &self,
+        response: &RestRequestOutcome,
+    ) -> Result<Vec<RequestResult<ExchangeOrderId>>> {
+        let batch_results: SimulatedBatchResults =
+            serde_json::from_str(&response.content).context("Unable to parse response content")?;
+
+        Ok(batch_results
+            .results
+            .into_iter()
+            .map(|result| match result {
+                Ok(exchange_order_id) => RequestResult::Success(exchange_order_id),
+                Err(error) => RequestResult::Error(error),
+            })
+            .collect())
+    }
+
+    fn clarify_error_type(&self, _error: &mut ExchangeError) {
+        // Simulated exchange always reports exact error type
+    }
+
+    fn on_websocket_message(&self, msg: &str) -> Result<()> {
+        bail!(
+            "Simulated exchange has no websocket, but message received: {}",
+            msg
+        )
+    }
+
+    fn on_connecting(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_order_created_callback(
+        &self,
+        callback: Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>,
+    ) {
+        *self.market.order_created_callback.lock() = callback;
+    }
+
+    fn set_order_cancelled_callback(
+        &self,
+        callback: Box<dyn FnMut(ClientOrderId, ExchangeOrderId, EventSourceType) + Send + Sync>,
+    ) {
+        *self.market.order_cancelled_callback.lock() = callback;
+    }
+
+    fn set_handle_order_filled_callback(
+        &self,
+        callback: Box<dyn FnMut(FillEventData) + Send + Sync>,
+    ) {
+        *self.market.handle_order_filled_callback.lock() = callback;
+    }
+
+    fn set_handle_trade_callback(
+        &self,
+        callback: Box<
+            dyn FnMut(CurrencyPair, TradeId, Price, Amount, OrderSide, DateTime) + Send + Sync,
+        >,
+    ) {
+        *self.market.handle_trade_callback.lock() = callback;
+    }
+
+    fn set_traded_specific_currencies(&self, currencies: Vec<SpecificCurrencyPair>) {
+        *self.traded_specific_currencies.lock() = currencies;
+    }
+
+    fn is_websocket_enabled(&self, _role: WebSocketRole) -> bool {
+        // All events are reported directly through callbacks
+        false
+    }
+
+    async fn create_ws_url(&self, _role: WebSocketRole) -> Result<Url> {
+        bail!("Simulated exchange doesn't support websocket")
+    }
+
+    fn get_specific_currency_pair(&self, currency_pair: CurrencyPair) -> SpecificCurrencyPair {
+        currency_pair
+            .as_str()
+            .replace('/', "")
+            .to_uppercase()
+            .as_str()
+            .into()
+    }
+
+    fn get_supported_currencies(&self) -> &DashMap<CurrencyId, CurrencyCode> {
+        &self.supported_currencies
+    }
+
+    fn should_log_message(&self, _message: &str) -> bool {
+        false
+    }
+
+    fn parse_open_orders(&self, response: &RestRequestOutcome) -> Result<Vec<OrderInfo>> {
+        serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_open_orders request")
+    }
+
+    fn parse_order_info(&self, response: &RestRequestOutcome) -> Result<OrderInfo> {
+        serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_order_info request")
+    }
+
+    fn parse_all_symbols(&self, response: &RestRequestOutcome) -> Result<Vec<Arc<Symbol>>> {
+        let currency_pairs: Vec<CurrencyPair> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_all_symbols request")?;
+
+        let engine = self.market.engine.lock();
+        currency_pairs
+            .into_iter()
+            .map(|currency_pair| {
+                engine
+                    .get_symbol(currency_pair)
+                    .cloned()
+                    .with_context(|| format!("Unknown currency pair {}", currency_pair))
+            })
+            .try_collect()
+    }
+
+    fn parse_get_my_trades(
+        &self,
+        response: &RestRequestOutcome,
+        last_date_time: Option<DateTime>,
+    ) -> Result<Vec<OrderTrade>> {
+        let trades: Vec<OrderTrade> = serde_json::from_str(&response.content)
+            .context("Unable to parse response content for get_my_trades request")?;
+
+        Ok(trades
+            .into_iter()
+            .filter(|trade| last_date_time.map_or(true, |last| trade.datetime > last))
+            .collect_vec())
+    }
+
+    fn get_settings(&self) -> &ExchangeSettings {
+        &self.settings
+    }
+
+    fn parse_get_position(&self, _response: &RestRequestOutcome) -> Vec<ActivePosition> {
+        Vec::new()
+    }
+
+    fn parse_close_position(&self, _response: &RestRequestOutcome) -> Result<ClosedPosition> {
+        bail!("Positions are not supported by simulated exchange")
+    }
+
+    fn parse_get_balance(&self, response: &RestRequestOutcome) -> ExchangeBalancesAndPositions {
+        let balances: Vec<SimulatedBalance> = serde_json::from_str(&response.content)
+            .expect("Unable to parse response content for get_balance request");
+
+        ExchangeBalancesAndPositions {
+            balances: balances
+                .into_iter()
+                .map(|x| ExchangeBalance {
+                    currency_code: x.currency_code,
+                    balance: x.balance,
+                })
+                .collect_vec(),
+            positions: None,
+        }
+    }
+}
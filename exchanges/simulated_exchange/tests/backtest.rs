@@ -0,0 +1,103 @@
+//! Backtest replaces engine time with virtual time globally, so it is kept in a separate test binary
+
+use chrono::Duration;
+use core_tests::backtest::{
+    cnd_btc, exchange_account_id, exchange_settings, order_book, start_time, symbol, trade,
+    write_market_data_file, BacktestStrategySettings,
+};
+use example::strategies::example_strategy::ExampleStrategy;
+use mmb_core::exchanges::common::TradePlaceAccount;
+use mmb_core::lifecycle::launcher::{launch_backtest, BacktestSettings, EngineBuildConfig};
+use mmb_core::orders::order::{OrderRole, OrderSide};
+use mmb_core::settings::{AppSettings, BaseStrategySettings, CoreSettings};
+use mmb_utils::hashmap;
+use rust_decimal_macros::dec;
+use simulated_exchange::simulated_exchange::{
+    SimulatedExchangeBuilder, SimulatedExchangeConfig, SimulatedMarket,
+};
+
+#[test]
+fn strategy_earns_spread_on_replayed_trades() {
+    let start = start_time();
+    let trade_place = TradePlaceAccount::new(exchange_account_id(), cnd_btc());
+    let market_data_path = write_market_data_file(
+        "simulated_exchange_backtest",
+        &[
+            order_book(start, trade_place),
+            trade(
+                start + Duration::seconds(1),
+                trade_place,
+                1,
+                OrderSide::Sell,
+            ),
+            order_book(start + Duration::seconds(2), trade_place),
+            trade(start + Duration::seconds(3), trade_place, 2, OrderSide::Buy),
+            order_book(start + Duration::seconds(4), trade_place),
+        ],
+    );
+
+    let mut config = SimulatedExchangeConfig::new(
+        vec![symbol("cnd", "CND")],
+        hashmap!["cnd".into() => dec!(100), "btc".into() => dec!(100)],
+    );
+    // Orders of both sides are created and cancelled by batch requests
+    config.max_batch_orders_count = Some(2);
+    // Next market event is replayed only after responses delayed by latency are handled
+    config.latency = std::time::Duration::from_millis(50);
+    let market = SimulatedMarket::new(config);
+    let build_config = EngineBuildConfig {
+        supported_exchange_clients: hashmap![
+            exchange_account_id().exchange_id => Box::new(SimulatedExchangeBuilder::new(market.clone())) as _
+        ],
+    };
+
+    let settings = AppSettings {
+        strategy: BacktestStrategySettings {
+            max_amount: dec!(1),
+        },
+        core: CoreSettings {
+            exchanges: vec![exchange_settings(exchange_account_id(), &["cnd"])],
+            ..Default::default()
+        },
+    };
+
+    let report = launch_backtest(
+        &build_config,
+        settings,
+        BacktestSettings {
+            market_data_paths: vec![market_data_path.clone()],
+            market: market.clone(),
+            sample_interval: Duration::seconds(1),
+        },
+        |settings, ctx| {
+            Box::new(ExampleStrategy::new(
+                settings.strategy.exchange_account_id(),
+                settings.strategy.currency_pair(),
+                dec!(0.1),
+                ctx,
+            ))
+        },
+    )
+    .expect("Backtest failed");
+
+    let _ = std::fs::remove_file(market_data_path);
+
+    assert_eq!(report.market_events_count, 5);
+    assert_eq!(report.finished_at, start + Duration::seconds(4));
+    assert_eq!(report.trade_places.len(), 1);
+
+    let trade_place_report = &report.trade_places[0];
+    assert_eq!(trade_place_report.fills_count_by_side(OrderSide::Buy), 1);
+    assert_eq!(trade_place_report.fills_count_by_side(OrderSide::Sell), 1);
+    assert!(trade_place_report
+        .fills
+        .iter()
+        .all(|fill| fill.role == OrderRole::Maker));
+    assert_eq!(trade_place_report.samples.len(), 5);
+
+    let final_sample = trade_place_report.final_sample().expect("in test");
+    assert_eq!(final_sample.position, dec!(0));
+    assert_eq!(final_sample.quote_balance_change, dec!(0.2));
+    assert_eq!(trade_place_report.final_pnl(), Some(dec!(0.2)));
+    assert_eq!(market.get_balance("btc".into()), dec!(100.2));
+}
@@ -0,0 +1,300 @@
+//! Backtest replaces engine time with virtual time globally, so it is kept in a separate test binary
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Duration;
+use core_tests::backtest::{
+    cnd_btc, exchange_account_id, exchange_settings, order_book, order_book_with_spread_offset,
+    start_time, symbol, trade, write_market_data_file, BacktestStrategySettings,
+};
+use mmb_core::backtesting::market_data::RecordedMarketEvent;
+use mmb_core::backtesting::BacktestMarket;
+use mmb_core::disposition_execution::{
+    PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
+};
+use mmb_core::exchanges::common::{Amount, ExchangeAccountId, TradePlaceAccount};
+use mmb_core::exchanges::events::TradesEvent;
+use mmb_core::explanation::{Explanation, WithExplanation};
+use mmb_core::lifecycle::launcher::{launch_backtest, BacktestSettings, EngineBuildConfig};
+use mmb_core::order_book::event::OrderBookEvent;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::{OrderRole, OrderSide, OrderSnapshot};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::{AppSettings, CoreSettings};
+use mmb_core::statistic_service::HedgeStatistic;
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_core::strategies::hedger::{Hedger, HedgerSettings};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::{hashmap, DateTime};
+use parking_lot::Mutex;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use simulated_exchange::simulated_exchange::{
+    SimulatedExchangeBuilder, SimulatedExchangeConfig, SimulatedMarket,
+};
+
+/// Quotes top of the book on maker exchange and hedges its fills on hedge exchange
+struct HedgedStrategy {
+    hedger: Arc<Hedger>,
+}
+
+impl HedgedStrategy {
+    fn trade_place() -> TradePlaceAccount {
+        TradePlaceAccount::new(maker_exchange_account_id(), cnd_btc())
+    }
+
+    fn configuration_descriptor() -> ConfigurationDescriptor {
+        ConfigurationDescriptor::new("HedgedStrategy".into(), "hedger_backtest".into())
+    }
+
+    fn calc_trading_context_by_side(
+        side: OrderSide,
+        max_amount: Amount,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &Explanation,
+    ) -> Option<TradingContextBySide> {
+        let price = local_snapshots_service
+            .get_snapshot(Self::trade_place().trade_place())?
+            .get_top(side)?
+            .0;
+
+        Some(TradingContextBySide {
+            max_amount,
+            estimating: vec![WithExplanation {
+                value: Some(TradeCycle {
+                    order_role: OrderRole::Maker,
+                    strategy_name: "HedgedStrategy".to_owned(),
+                    disposition: TradeDisposition::new(
+                        Self::trade_place(),
+                        side,
+                        price,
+                        max_amount,
+                    ),
+                }),
+                explanation: explanation.clone(),
+            }],
+        })
+    }
+}
+
+impl DispositionStrategy for HedgedStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        max_amount: Decimal,
+        _now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        let calc_by_side = |side| {
+            Self::calc_trading_context_by_side(
+                side,
+                max_amount,
+                local_snapshots_service,
+                explanation,
+            )
+        };
+
+        Some(TradingContext::new(
+            Self::trade_place(),
+            calc_by_side(OrderSide::Buy)?,
+            calc_by_side(OrderSide::Sell)?,
+        ))
+    }
+
+    fn handle_order_fill(
+        &self,
+        cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        self.hedger
+            .handle_order_fill(cloned_order, cancellation_token)
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        Self::configuration_descriptor()
+    }
+}
+
+/// Passes replayed market data to markets of both exchanges
+struct CombinedMarket {
+    markets: Vec<Arc<SimulatedMarket>>,
+}
+
+impl BacktestMarket for CombinedMarket {
+    fn handle_order_book_event(&self, event: &OrderBookEvent) {
+        self.markets
+            .iter()
+            .for_each(|market| market.handle_order_book_event(event));
+    }
+
+    fn handle_trades_event(&self, event: &TradesEvent) {
+        self.markets
+            .iter()
+            .for_each(|market| market.handle_trades_event(event));
+    }
+
+    fn has_requests_in_flight(&self) -> bool {
+        self.markets
+            .iter()
+            .any(|market| market.has_requests_in_flight())
+    }
+}
+
+fn maker_exchange_account_id() -> ExchangeAccountId {
+    exchange_account_id()
+}
+
+fn hedge_exchange_account_id() -> ExchangeAccountId {
+    "SimulatedHedge_0".parse().expect("in test")
+}
+
+fn order_books(time: DateTime) -> Vec<RecordedMarketEvent> {
+    vec![
+        order_book(time, HedgedStrategy::trade_place()),
+        // hedge exchange has tighter spread
+        order_book_with_spread_offset(
+            time,
+            TradePlaceAccount::new(hedge_exchange_account_id(), cnd_btc()),
+            dec!(0.05),
+        ),
+    ]
+}
+
+fn market() -> Arc<SimulatedMarket> {
+    SimulatedMarket::new(SimulatedExchangeConfig::new(
+        vec![symbol("cnd", "CND")],
+        hashmap![
+            "cnd".into() => dec!(100),
+            "btc".into() => dec!(100)
+        ],
+    ))
+}
+
+#[test]
+fn maker_fills_are_hedged_on_other_exchange() {
+    let start = start_time();
+    let second = |seconds| start + Duration::seconds(seconds);
+    let mut events = order_books(start);
+    events.push(trade(
+        second(1),
+        HedgedStrategy::trade_place(),
+        1,
+        OrderSide::Sell,
+    ));
+    events.extend(order_books(second(2)));
+    events.push(trade(
+        second(3),
+        HedgedStrategy::trade_place(),
+        2,
+        OrderSide::Buy,
+    ));
+    events.extend(order_books(second(4)));
+    let market_data_path = write_market_data_file("simulated_exchange_hedger_backtest", &events);
+
+    let maker_market = market();
+    let hedge_market = market();
+    let build_config = EngineBuildConfig {
+        supported_exchange_clients: hashmap![
+            maker_exchange_account_id().exchange_id => Box::new(SimulatedExchangeBuilder::new(maker_market.clone())) as _,
+            hedge_exchange_account_id().exchange_id => Box::new(SimulatedExchangeBuilder::new(hedge_market.clone())) as _
+        ],
+    };
+
+    let settings = AppSettings {
+        strategy: BacktestStrategySettings {
+            max_amount: dec!(1),
+        },
+        core: CoreSettings {
+            exchanges: vec![
+                exchange_settings(maker_exchange_account_id(), &["cnd"]),
+                exchange_settings(hedge_exchange_account_id(), &["cnd"]),
+            ],
+            ..Default::default()
+        },
+    };
+
+    let hedger = Mutex::new(None);
+    let report = launch_backtest(
+        &build_config,
+        settings,
+        BacktestSettings {
+            market_data_paths: vec![market_data_path.clone()],
+            market: Arc::new(CombinedMarket {
+                markets: vec![maker_market.clone(), hedge_market.clone()],
+            }),
+            sample_interval: Duration::seconds(1),
+        },
+        |_settings, ctx| {
+            let strategy_hedger = Hedger::new(
+                HedgerSettings {
+                    exchange_account_id: hedge_exchange_account_id(),
+                    max_slippage: dec!(5),
+                    slippage_step: dec!(1),
+                    max_attempts: 3,
+                    retry_delay_ms: 100,
+                    order_finish_timeout_ms: 1000,
+                },
+                HedgedStrategy::configuration_descriptor(),
+                ctx.clone(),
+            );
+            *hedger.lock() = Some((strategy_hedger.clone(), ctx));
+
+            Box::new(HedgedStrategy {
+                hedger: strategy_hedger,
+            })
+        },
+    )
+    .expect("Backtest failed");
+
+    let _ = std::fs::remove_file(market_data_path);
+
+    let maker_report = report
+        .trade_place(
+            "HedgedStrategy",
+            TradePlaceAccount::new(maker_exchange_account_id(), cnd_btc()),
+        )
+        .expect("in test");
+    assert_eq!(maker_report.fills_count_by_side(OrderSide::Buy), 1);
+    assert_eq!(maker_report.fills_count_by_side(OrderSide::Sell), 1);
+
+    let hedge_report = report
+        .trade_place(
+            "Hedger",
+            TradePlaceAccount::new(hedge_exchange_account_id(), cnd_btc()),
+        )
+        .expect("in test");
+    assert_eq!(hedge_report.fills_count_by_side(OrderSide::Buy), 1);
+    assert_eq!(hedge_report.fills_count_by_side(OrderSide::Sell), 1);
+
+    // maker bought at 0.9 and sold at 1.1, hedge sold at 0.95 and bought at 1.05
+    assert_eq!(maker_market.get_balance("cnd".into()), dec!(100));
+    assert_eq!(maker_market.get_balance("btc".into()), dec!(100.2));
+    assert_eq!(hedge_market.get_balance("cnd".into()), dec!(100));
+    assert_eq!(hedge_market.get_balance("btc".into()), dec!(99.9));
+
+    let (hedger, ctx) = hedger.lock().take().expect("strategy should be built");
+    assert_eq!(hedger.unhedged_exposure("cnd".into()), dec!(0));
+
+    let hedge_statistic = ctx
+        .statistic_service
+        .get_hedge_statistic(TradePlaceAccount::new(
+            hedge_exchange_account_id(),
+            cnd_btc(),
+        ))
+        .expect("hedge statistic should be registered");
+    assert_eq!(
+        hedge_statistic,
+        HedgeStatistic {
+            hedges_count: 2,
+            failed_hedges_count: 0,
+            hedged_amount: dec!(2),
+            summary_latency_ms: hedge_statistic.summary_latency_ms,
+            max_latency_ms: hedge_statistic.max_latency_ms,
+            // hedges were executed 0.05 better than maker fills
+            summary_slippage: dec!(-0.1),
+        }
+    );
+}
@@ -0,0 +1 @@
+pub mod simulated_exchange;
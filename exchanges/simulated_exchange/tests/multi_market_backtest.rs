@@ -0,0 +1,123 @@
+//! Backtest replaces engine time with virtual time globally, so it is kept in a separate test binary
+
+use chrono::Duration;
+use core_tests::backtest::{
+    cnd_btc, eth_btc, exchange_account_id, exchange_settings, order_book, start_time, symbol,
+    trade, write_market_data_file, TopOfBookStrategy,
+};
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, TradePlaceAccount};
+use mmb_core::lifecycle::launcher::{launch_backtest, BacktestSettings, EngineBuildConfig};
+use mmb_core::orders::order::OrderSide;
+use mmb_core::settings::{AppSettings, BaseStrategySettings, CoreSettings};
+use mmb_utils::hashmap;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use simulated_exchange::simulated_exchange::{
+    SimulatedExchangeBuilder, SimulatedExchangeConfig, SimulatedMarket,
+};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+struct MultiMarketStrategySettings {
+    max_amount: Amount,
+}
+
+impl BaseStrategySettings for MultiMarketStrategySettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        exchange_account_id()
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        cnd_btc()
+    }
+
+    fn max_amount(&self) -> Amount {
+        self.max_amount
+    }
+
+    fn trade_places(&self) -> Vec<TradePlaceAccount> {
+        vec![
+            TradePlaceAccount::new(exchange_account_id(), cnd_btc()),
+            TradePlaceAccount::new(exchange_account_id(), eth_btc()),
+        ]
+    }
+}
+
+#[test]
+fn strategy_trades_on_several_currency_pairs() {
+    let start = start_time();
+    let second = |seconds| start + Duration::seconds(seconds);
+    let cnd_btc_place = TradePlaceAccount::new(exchange_account_id(), cnd_btc());
+    let eth_btc_place = TradePlaceAccount::new(exchange_account_id(), eth_btc());
+    let market_data_path = write_market_data_file(
+        "simulated_exchange_multi_market_backtest",
+        &[
+            order_book(start, cnd_btc_place),
+            order_book(start, eth_btc_place),
+            trade(second(1), cnd_btc_place, 1, OrderSide::Sell),
+            trade(second(1), eth_btc_place, 2, OrderSide::Sell),
+            order_book(second(2), cnd_btc_place),
+            order_book(second(2), eth_btc_place),
+            trade(second(3), cnd_btc_place, 3, OrderSide::Buy),
+            trade(second(3), eth_btc_place, 4, OrderSide::Buy),
+            order_book(second(4), cnd_btc_place),
+            order_book(second(4), eth_btc_place),
+        ],
+    );
+
+    let market = SimulatedMarket::new(SimulatedExchangeConfig::new(
+        vec![symbol("cnd", "CND"), symbol("eth", "ETH")],
+        hashmap![
+            "cnd".into() => dec!(100),
+            "eth".into() => dec!(100),
+            "btc".into() => dec!(100)
+        ],
+    ));
+    let build_config = EngineBuildConfig {
+        supported_exchange_clients: hashmap![
+            exchange_account_id().exchange_id => Box::new(SimulatedExchangeBuilder::new(market.clone())) as _
+        ],
+    };
+
+    let settings = AppSettings {
+        strategy: MultiMarketStrategySettings {
+            max_amount: dec!(1),
+        },
+        core: CoreSettings {
+            exchanges: vec![exchange_settings(exchange_account_id(), &["cnd", "eth"])],
+            ..Default::default()
+        },
+    };
+
+    let report = launch_backtest(
+        &build_config,
+        settings,
+        BacktestSettings {
+            market_data_paths: vec![market_data_path.clone()],
+            market: market.clone(),
+            sample_interval: Duration::seconds(1),
+        },
+        |settings, _ctx| {
+            Box::new(TopOfBookStrategy::new(
+                "TopOfBookStrategy",
+                settings.strategy.trade_places(),
+            ))
+        },
+    )
+    .expect("Backtest failed");
+
+    let _ = std::fs::remove_file(market_data_path);
+
+    for trade_place in [cnd_btc_place, eth_btc_place] {
+        let trade_place_report = report
+            .trade_place("TopOfBookStrategy", trade_place)
+            .expect("in test");
+        assert_eq!(trade_place_report.fills_count_by_side(OrderSide::Buy), 1);
+        assert_eq!(trade_place_report.fills_count_by_side(OrderSide::Sell), 1);
+        assert_eq!(trade_place_report.final_pnl(), Some(dec!(0.2)));
+    }
+
+    // both currency pairs earned spread in shared quote currency
+    assert_eq!(market.get_balance("cnd".into()), dec!(100));
+    assert_eq!(market.get_balance("eth".into()), dec!(100));
+    assert_eq!(market.get_balance("btc".into()), dec!(100.4));
+}
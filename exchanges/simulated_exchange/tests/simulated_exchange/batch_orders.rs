@@ -0,0 +1,161 @@
+use core_tests::order::OrderProxy;
+use mmb_core::exchanges::common::ExchangeErrorType;
+use mmb_core::exchanges::timeouts::requests_timeout_manager::RequestGroupId;
+use mmb_core::orders::order::{OrderCreating, OrderStatus};
+use mmb_core::orders::pool::OrderRef;
+use mmb_utils::cancellation_token::CancellationToken;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::simulated_exchange::simulated_builder::SimulatedBuilder;
+
+async fn builder_with_batch_orders() -> SimulatedBuilder {
+    let mut config = SimulatedBuilder::default_config();
+    config.max_batch_orders_count = Some(2);
+    SimulatedBuilder::try_new_with_config(config).await
+}
+
+fn order_creating(builder: &SimulatedBuilder, amount: Decimal) -> OrderCreating {
+    let order_proxy = OrderProxy::new(
+        SimulatedBuilder::exchange_account_id(),
+        Some("FromBatchOrdersTest".to_owned()),
+        CancellationToken::default(),
+        builder.default_price,
+        amount,
+    );
+
+    OrderCreating {
+        header: order_proxy.make_header(),
+        price: order_proxy.price,
+    }
+}
+
+async fn create_orders(builder: &SimulatedBuilder, count: usize) -> Vec<OrderRef> {
+    let orders = (0..count)
+        .map(|_| order_creating(builder, builder.min_amount))
+        .collect::<Vec<_>>();
+
+    builder
+        .exchange
+        .batch_create_orders(&orders, CancellationToken::default())
+        .await
+        .into_iter()
+        .map(|(_, result)| result.expect("Create order failed with error"))
+        .collect()
+}
+
+fn without_request_groups(orders: &[OrderRef]) -> Vec<(OrderRef, Option<RequestGroupId>)> {
+    orders.iter().map(|order| (order.clone(), None)).collect()
+}
+
+#[tokio::test]
+async fn create_orders_by_batches() {
+    let builder = builder_with_batch_orders().await;
+    // 1000 * 0.5 btc is more than 100 btc on balance
+    let orders = vec![
+        order_creating(&builder, builder.min_amount),
+        order_creating(&builder, dec!(1000)),
+        order_creating(&builder, builder.min_amount),
+    ];
+
+    let results = builder
+        .exchange
+        .batch_create_orders(&orders, CancellationToken::default())
+        .await;
+
+    assert_eq!(results.len(), orders.len());
+    for ((client_order_id, _), order) in results.iter().zip(&orders) {
+        assert_eq!(client_order_id, &order.header.client_order_id);
+    }
+
+    let created_order = results[0].1.as_ref().expect("in test");
+    assert_eq!(created_order.status(), OrderStatus::Created);
+    assert!(results[2].1.is_ok());
+
+    assert!(results[1].1.is_err());
+    let failed_order = builder
+        .exchange
+        .orders
+        .cache_by_client_id
+        .get(&orders[1].header.client_order_id)
+        .expect("in test")
+        .clone();
+    failed_order.fn_ref(|order| {
+        assert_eq!(order.props.status, OrderStatus::FailedToCreate);
+        assert_eq!(
+            order.internal_props.last_creation_error_type,
+            Some(ExchangeErrorType::InsufficientFunds)
+        );
+    });
+
+    assert_eq!(builder.market.open_orders().len(), 2);
+}
+
+#[tokio::test]
+async fn cancel_orders_by_batches() {
+    let builder = builder_with_batch_orders().await;
+    let orders = create_orders(&builder, 3).await;
+    assert_eq!(builder.market.open_orders().len(), 3);
+
+    let results = builder
+        .exchange
+        .batch_cancel_orders(
+            &without_request_groups(&orders),
+            CancellationToken::default(),
+        )
+        .await;
+
+    assert_eq!(results.len(), orders.len());
+    for ((client_order_id, result), order) in results.iter().zip(&orders) {
+        assert_eq!(client_order_id, &order.client_order_id());
+        assert!(result.is_ok());
+        assert_eq!(order.status(), OrderStatus::Canceled);
+    }
+    assert!(builder.market.open_orders().is_empty());
+}
+
+#[tokio::test]
+async fn cancel_already_cancelled_order_by_batch() {
+    let builder = builder_with_batch_orders().await;
+    let orders = create_orders(&builder, 2).await;
+    builder
+        .exchange
+        .batch_cancel_orders(
+            &without_request_groups(&orders[..1]),
+            CancellationToken::default(),
+        )
+        .await;
+
+    let results = builder
+        .exchange
+        .batch_cancel_orders(
+            &without_request_groups(&orders),
+            CancellationToken::default(),
+        )
+        .await;
+
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+    assert!(orders
+        .iter()
+        .all(|order| order.status() == OrderStatus::Canceled));
+    assert!(builder.market.open_orders().is_empty());
+}
+
+#[tokio::test]
+async fn batch_orders_fall_back_to_single_requests() {
+    let builder = SimulatedBuilder::try_new().await;
+
+    let orders = create_orders(&builder, 3).await;
+    assert_eq!(builder.market.open_orders().len(), 3);
+
+    let results = builder
+        .exchange
+        .batch_cancel_orders(
+            &without_request_groups(&orders),
+            CancellationToken::default(),
+        )
+        .await;
+
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+    assert!(builder.market.open_orders().is_empty());
+}
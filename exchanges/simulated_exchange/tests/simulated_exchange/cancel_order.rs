@@ -0,0 +1,65 @@
+use core_tests::order::OrderProxy;
+use mmb_core::orders::event::OrderEventType;
+use mmb_core::orders::order::OrderStatus;
+use mmb_utils::cancellation_token::CancellationToken;
+
+use crate::simulated_exchange::simulated_builder::SimulatedBuilder;
+
+#[tokio::test]
+async fn cancelled_successfully() {
+    let mut builder = SimulatedBuilder::try_new().await;
+    let order_proxy = OrderProxy::new(
+        SimulatedBuilder::exchange_account_id(),
+        Some("FromCancelledSuccessfullyTest".to_owned()),
+        CancellationToken::default(),
+        builder.default_price,
+        builder.min_amount,
+    );
+
+    let order_ref = order_proxy
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    order_proxy
+        .cancel_order_or_fail(&order_ref, builder.exchange.clone())
+        .await;
+
+    assert_eq!(order_ref.status(), OrderStatus::Canceled);
+    assert!(builder.market.open_orders().is_empty());
+
+    assert!(matches!(
+        builder.recv_order_event().await.event_type,
+        OrderEventType::CreateOrderSucceeded
+    ));
+    assert!(matches!(
+        builder.recv_order_event().await.event_type,
+        OrderEventType::CancelOrderSucceeded
+    ));
+}
+
+#[tokio::test]
+async fn cancel_opened_orders() {
+    let builder = SimulatedBuilder::try_new().await;
+    for _ in 0..3 {
+        OrderProxy::new(
+            SimulatedBuilder::exchange_account_id(),
+            Some("FromCancelOpenedOrdersTest".to_owned()),
+            CancellationToken::default(),
+            builder.default_price,
+            builder.min_amount,
+        )
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+    }
+    assert_eq!(builder.market.open_orders().len(), 3);
+
+    builder
+        .exchange
+        .clone()
+        .cancel_opened_orders(CancellationToken::default(), true)
+        .await;
+
+    assert!(builder.market.open_orders().is_empty());
+}
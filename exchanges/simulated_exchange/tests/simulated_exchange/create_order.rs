@@ -0,0 +1,125 @@
+use core_tests::order::OrderProxy;
+use mmb_core::exchanges::common::ExchangeErrorType;
+use mmb_core::orders::event::OrderEventType;
+use mmb_core::orders::order::{OrderExecutionType, OrderSide, OrderStatus, OrderType};
+use mmb_utils::cancellation_token::CancellationToken;
+use rust_decimal_macros::dec;
+
+use crate::simulated_exchange::simulated_builder::SimulatedBuilder;
+
+fn order_proxy(builder: &SimulatedBuilder) -> OrderProxy {
+    OrderProxy::new(
+        SimulatedBuilder::exchange_account_id(),
+        Some("FromSimulatedExchangeTest".to_owned()),
+        CancellationToken::default(),
+        builder.default_price,
+        builder.min_amount,
+    )
+}
+
+#[tokio::test]
+async fn create_successfully() {
+    let mut builder = SimulatedBuilder::try_new().await;
+    let order_proxy = order_proxy(&builder);
+
+    let order_ref = order_proxy
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    let order_event = builder.recv_order_event().await;
+    assert!(matches!(
+        order_event.event_type,
+        OrderEventType::CreateOrderSucceeded
+    ));
+    assert_eq!(order_ref.status(), OrderStatus::Created);
+
+    let open_orders = builder.market.open_orders();
+    assert_eq!(open_orders.len(), 1);
+    assert_eq!(open_orders[0].client_order_id, order_proxy.client_order_id);
+}
+
+#[tokio::test]
+async fn should_fail_on_insufficient_funds() {
+    let mut builder = SimulatedBuilder::try_new().await;
+    let mut order_proxy = order_proxy(&builder);
+    // 1000 * 0.5 btc is more than 100 btc on balance
+    order_proxy.amount = dec!(1000);
+
+    let result = order_proxy.create_order(builder.exchange.clone()).await;
+
+    assert!(result.is_err());
+    let order_event = builder.recv_order_event().await;
+    assert!(matches!(
+        order_event.event_type,
+        OrderEventType::CreateOrderFailed
+    ));
+    order_event.order.fn_ref(|order| {
+        assert_eq!(order.props.status, OrderStatus::FailedToCreate);
+        assert_eq!(
+            order.internal_props.last_creation_error_type,
+            Some(ExchangeErrorType::InsufficientFunds)
+        );
+    });
+}
+
+#[tokio::test]
+async fn should_fail_on_price_filter() {
+    let mut builder = SimulatedBuilder::try_new().await;
+    let mut order_proxy = order_proxy(&builder);
+    // price tick is 0.01
+    order_proxy.price = dec!(0.505);
+
+    let result = order_proxy.create_order(builder.exchange.clone()).await;
+
+    assert!(result.is_err());
+    let order_event = builder.recv_order_event().await;
+    order_event.order.fn_ref(|order| {
+        assert_eq!(
+            order.internal_props.last_creation_error_type,
+            Some(ExchangeErrorType::InvalidOrder)
+        );
+    });
+    assert!(builder.market.open_orders().is_empty());
+}
+
+#[tokio::test]
+async fn should_fail_maker_only_order_crossing_the_book() {
+    let builder = SimulatedBuilder::try_new().await;
+    let mut order_proxy = order_proxy(&builder);
+    order_proxy.price = dec!(1.1);
+    order_proxy.execution_type = OrderExecutionType::MakerOnly;
+
+    let result = order_proxy.create_order(builder.exchange.clone()).await;
+
+    assert!(result.is_err());
+    assert!(builder.market.fills(order_proxy.currency_pair).is_empty());
+}
+
+#[tokio::test]
+async fn market_order_takes_liquidity_from_several_levels() {
+    let builder = SimulatedBuilder::try_new().await;
+    let mut order_proxy = order_proxy(&builder);
+    order_proxy.order_type = OrderType::Market;
+    order_proxy.side = OrderSide::Buy;
+    order_proxy.amount = dec!(7);
+
+    let order_ref = order_proxy
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    assert_eq!(order_ref.status(), OrderStatus::Completed);
+    assert_eq!(order_ref.filled_amount(), dec!(7));
+
+    let fills = builder.market.fills(order_proxy.currency_pair);
+    assert_eq!(fills.len(), 2);
+    assert_eq!((fills[0].price, fills[0].amount), (dec!(1.1), dec!(5)));
+    assert_eq!((fills[1].price, fills[1].amount), (dec!(1.2), dec!(2)));
+
+    assert_eq!(builder.market.get_balance("cnd".into()), dec!(107));
+    assert_eq!(
+        builder.market.get_balance("btc".into()),
+        dec!(100) - dec!(7.9)
+    );
+}
@@ -0,0 +1,114 @@
+use core_tests::order::OrderProxy;
+use mmb_core::orders::order::OrderRole;
+use mmb_core::orders::order::{OrderSide, OrderStatus};
+use mmb_utils::cancellation_token::CancellationToken;
+use rust_decimal_macros::dec;
+use std::time::Duration;
+
+use mmb_core::order_book::order_book_data::OrderBookData;
+
+use crate::simulated_exchange::simulated_builder::SimulatedBuilder;
+
+fn resting_sell_order() -> OrderProxy {
+    let mut order_proxy = OrderProxy::new(
+        SimulatedBuilder::exchange_account_id(),
+        Some("FromSimulatedFillsTest".to_owned()),
+        CancellationToken::default(),
+        dec!(1.5),
+        dec!(4),
+    );
+    order_proxy.side = OrderSide::Sell;
+    order_proxy
+}
+
+#[tokio::test]
+async fn order_partially_filled_by_public_trade() {
+    let builder = SimulatedBuilder::try_new().await;
+    let order_proxy = resting_sell_order();
+    let order_ref = order_proxy
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    builder.market.add_trade(
+        order_proxy.currency_pair,
+        dec!(1.6),
+        dec!(1),
+        OrderSide::Buy,
+    );
+
+    assert_eq!(order_ref.status(), OrderStatus::Created);
+    assert_eq!(order_ref.filled_amount(), dec!(1));
+    let fills = builder.market.fills(order_proxy.currency_pair);
+    assert_eq!(fills[0].order_role, OrderRole::Maker);
+
+    builder.market.add_trade(
+        order_proxy.currency_pair,
+        dec!(1.5),
+        dec!(10),
+        OrderSide::Buy,
+    );
+
+    assert_eq!(order_ref.status(), OrderStatus::Completed);
+    assert_eq!(order_ref.filled_amount(), dec!(4));
+    assert_eq!(builder.market.get_balance("btc".into()), dec!(106));
+}
+
+#[tokio::test]
+async fn order_filled_when_book_crosses_it() {
+    let builder = SimulatedBuilder::try_new().await;
+    let order_proxy = resting_sell_order();
+    let order_ref = order_proxy
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    builder.market.update_order_book(
+        order_proxy.currency_pair,
+        OrderBookData::new(
+            [(dec!(1.7), dec!(5))].into(),
+            [(dec!(1.6), dec!(3)), (dec!(1.4), dec!(5))].into(),
+        ),
+    );
+
+    // only bids with price not less than order price can be matched
+    assert_eq!(order_ref.status(), OrderStatus::Created);
+    assert_eq!(order_ref.filled_amount(), dec!(3));
+    let fills = builder.market.fills(order_proxy.currency_pair);
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].price, dec!(1.5));
+}
+
+#[tokio::test]
+async fn liquidity_share_produces_partial_fills() {
+    let mut config = SimulatedBuilder::default_config();
+    config.liquidity_share = dec!(0.5);
+    let builder = SimulatedBuilder::try_new_with_config(config).await;
+
+    let mut order_proxy = resting_sell_order();
+    order_proxy.price = dec!(0.9);
+    let order_ref = order_proxy
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    // only half of 5 at best bid is available for own orders, 0.8 is lower than order price
+    assert_eq!(order_ref.filled_amount(), dec!(2.5));
+    assert_eq!(order_ref.status(), OrderStatus::Created);
+}
+
+#[tokio::test]
+async fn responses_are_delayed_by_latency() {
+    let mut config = SimulatedBuilder::default_config();
+    config.latency = Duration::from_millis(200);
+    let builder = SimulatedBuilder::try_new_with_config(config).await;
+    let order_proxy = resting_sell_order();
+
+    let started_at = std::time::Instant::now();
+    order_proxy
+        .create_order(builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+
+    assert!(started_at.elapsed() >= Duration::from_millis(200));
+}
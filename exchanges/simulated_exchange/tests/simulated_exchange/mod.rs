@@ -0,0 +1,6 @@
+pub mod batch_orders;
+pub mod cancel_order;
+pub mod create_order;
+pub mod fills;
+pub mod restore_orders;
+pub mod simulated_builder;
@@ -0,0 +1,173 @@
+use std::path::PathBuf;
+
+use core_tests::order::OrderProxy;
+use mmb_core::misc::reserve_parameters::ReserveParameters;
+use mmb_core::orders::journal::{FileOrdersJournal, OrdersJournal, OrdersJournalSettings};
+use mmb_core::orders::order::{OrderSide, OrderStatus};
+use mmb_core::orders::pool::OrderRef;
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_utils::cancellation_token::CancellationToken;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::simulated_exchange::simulated_builder::SimulatedBuilder;
+
+fn sell_order(price: Decimal) -> OrderProxy {
+    let mut order_proxy = OrderProxy::new(
+        SimulatedBuilder::exchange_account_id(),
+        Some("FromSimulatedRestoreTest".to_owned()),
+        CancellationToken::default(),
+        price,
+        dec!(1),
+    );
+    order_proxy.side = OrderSide::Sell;
+    order_proxy
+}
+
+fn journal_path() -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "mmb_simulated_orders_journal_{}.jsonl",
+        std::process::id()
+    ))
+}
+
+fn get_order(builder: &SimulatedBuilder, order_proxy: &OrderProxy) -> OrderRef {
+    builder
+        .exchange
+        .orders
+        .cache_by_client_id
+        .get(&order_proxy.client_order_id)
+        .expect("Order should be restored")
+        .clone()
+}
+
+#[tokio::test]
+async fn restore_orders_after_restart() {
+    let path = journal_path();
+    let _ = std::fs::remove_file(&path);
+    let journal = FileOrdersJournal::open(&OrdersJournalSettings {
+        path: path.clone(),
+        compaction_records_count: 1_000,
+    })
+    .expect("in test");
+
+    let builder = SimulatedBuilder::try_new().await;
+    builder.exchange.setup_orders_journal(journal.clone());
+    let market = builder.market.clone();
+
+    let balances = builder
+        .exchange
+        .get_balance(CancellationToken::default())
+        .await
+        .expect("in test");
+    builder
+        .balance_manager
+        .lock()
+        .update_exchange_balance(SimulatedBuilder::exchange_account_id(), &balances)
+        .expect("in test");
+
+    // configuration key isn't derived from exchange account and currency pair of the order
+    let configuration_descriptor =
+        ConfigurationDescriptor::new("FromSimulatedRestoreTest".into(), "all_markets".into());
+    let mut open_order = sell_order(dec!(1.5));
+    let reservation_id = builder
+        .balance_manager
+        .lock()
+        .try_reserve(
+            &ReserveParameters::new(
+                configuration_descriptor,
+                SimulatedBuilder::exchange_account_id(),
+                SimulatedBuilder::default_symbol(),
+                OrderSide::Sell,
+                open_order.price,
+                open_order.amount,
+            ),
+            &mut None,
+        )
+        .expect("in test");
+    builder.balance_manager.lock().approve_reservation(
+        reservation_id,
+        &open_order.client_order_id,
+        open_order.amount,
+    );
+    open_order.reservation_id = Some(reservation_id);
+    let filled_order = sell_order(dec!(1.4));
+    for order_proxy in [&open_order, &filled_order] {
+        let _ = order_proxy
+            .create_order(builder.exchange.clone())
+            .await
+            .expect("Create order failed with error");
+    }
+
+    // engine is stopped, but the order is filled on the exchange
+    journal.flush().await.expect("in test");
+    market.detach();
+    market.add_trade(
+        filled_order.currency_pair,
+        dec!(1.4),
+        dec!(10),
+        OrderSide::Buy,
+    );
+
+    // order created by another instance without journal
+    let other_builder = SimulatedBuilder::try_new_with_market(market.clone()).await;
+    let orphan_order = sell_order(dec!(1.6));
+    let _ = orphan_order
+        .create_order(other_builder.exchange.clone())
+        .await
+        .expect("Create order failed with error");
+    market.detach();
+
+    let restarted_builder = SimulatedBuilder::try_new_with_market(market.clone()).await;
+    restarted_builder
+        .exchange
+        .setup_orders_journal(journal.clone());
+    restarted_builder
+        .exchange
+        .restore_orders(journal.load_orders().expect("in test"))
+        .await
+        .expect("Restore orders failed with error");
+
+    journal.flush().await.expect("in test");
+    let journaled_orders = journal.load_orders().expect("in test");
+    let _ = std::fs::remove_file(&path);
+
+    let restored_open_order = get_order(&restarted_builder, &open_order);
+    assert_eq!(restored_open_order.status(), OrderStatus::Created);
+    assert!(restored_open_order.exchange_order_id().is_some());
+    let restored_reservation = restarted_builder
+        .balance_manager
+        .lock()
+        .get_reservation(reservation_id)
+        .cloned()
+        .expect("Reservation of not finished order should be restored");
+    assert!(restored_reservation
+        .approved_parts
+        .contains_key(&open_order.client_order_id));
+    assert_eq!(
+        restored_reservation.configuration_descriptor,
+        configuration_descriptor
+    );
+
+    let restored_filled_order = get_order(&restarted_builder, &filled_order);
+    assert_eq!(restored_filled_order.status(), OrderStatus::Completed);
+    // fill missed while the engine was stopped is requested from the exchange
+    assert_eq!(restored_filled_order.filled_amount(), dec!(1));
+    assert_eq!(restored_filled_order.get_fills().0.len(), 1);
+
+    let restored_orphan_order = get_order(&restarted_builder, &orphan_order);
+    assert_eq!(restored_orphan_order.status(), OrderStatus::Created);
+
+    let not_finished = &restarted_builder.exchange.orders.not_finished;
+    assert_eq!(not_finished.len(), 2);
+    assert!(!not_finished.contains_key(&filled_order.client_order_id));
+
+    // reconciled state is journaled, so it survives the next restart
+    assert_eq!(journaled_orders.len(), 3);
+    assert!(journaled_orders
+        .iter()
+        .any(
+            |order| order.header.client_order_id == filled_order.client_order_id
+                && order.props.status == OrderStatus::Completed
+        ));
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use core_tests::order::OrderProxy;
+use mmb_core::balance_manager::balance_manager::BalanceManager;
+use mmb_core::exchanges::common::*;
+use mmb_core::exchanges::events::ExchangeEvent;
+use mmb_core::exchanges::general::currency_pair_to_symbol_converter::CurrencyPairToSymbolConverter;
+use mmb_core::exchanges::general::exchange::Exchange;
+use mmb_core::exchanges::general::exchange_creation::{create_exchange, create_timeout_manager};
+use mmb_core::exchanges::general::symbol::{Precision, Symbol};
+use mmb_core::lifecycle::application_manager::ApplicationManager;
+use mmb_core::lifecycle::launcher::EngineBuildConfig;
+use mmb_core::order_book::order_book_data::OrderBookData;
+use mmb_core::orders::event::OrderEvent;
+use mmb_core::settings::{CoreSettings, CurrencyPairSetting, ExchangeSettings};
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::hashmap;
+use parking_lot::Mutex;
+use rust_decimal_macros::dec;
+use simulated_exchange::simulated_exchange::{
+    SimulatedExchangeBuilder, SimulatedExchangeConfig, SimulatedMarket,
+};
+use tokio::sync::broadcast;
+
+pub struct SimulatedBuilder {
+    pub exchange: Arc<Exchange>,
+    pub market: Arc<SimulatedMarket>,
+    pub balance_manager: Arc<Mutex<BalanceManager>>,
+    pub default_price: Price,
+    pub min_amount: Amount,
+    pub rx: broadcast::Receiver<ExchangeEvent>,
+}
+
+impl SimulatedBuilder {
+    pub fn exchange_account_id() -> ExchangeAccountId {
+        "Simulated_0".parse().expect("in test")
+    }
+
+    /// Symbol for `OrderProxy::default_currency_pair()` with price tick 0.01 and amount tick 0.1
+    pub fn default_symbol() -> Arc<Symbol> {
+        Arc::new(Symbol::new(
+            true,
+            false,
+            "CND".into(),
+            "cnd".into(),
+            "BTC".into(),
+            "btc".into(),
+            Some(dec!(0.01)),
+            Some(dec!(1000)),
+            Some(dec!(0.1)),
+            Some(dec!(10000)),
+            None,
+            "cnd".into(),
+            None,
+            Precision::ByTick { tick: dec!(0.01) },
+            Precision::ByTick { tick: dec!(0.1) },
+        ))
+    }
+
+    pub fn default_config() -> SimulatedExchangeConfig {
+        SimulatedExchangeConfig::new(
+            vec![Self::default_symbol()],
+            hashmap!["cnd".into() => dec!(100), "btc".into() => dec!(100)],
+        )
+    }
+
+    pub async fn try_new() -> Self {
+        Self::try_new_with_config(Self::default_config()).await
+    }
+
+    pub async fn try_new_with_config(config: SimulatedExchangeConfig) -> Self {
+        let builder = Self::try_new_with_market(SimulatedMarket::new(config)).await;
+
+        builder.market.update_order_book(
+            OrderProxy::default_currency_pair(),
+            OrderBookData::new(
+                [(dec!(1.1), dec!(5)), (dec!(1.2), dec!(5))].into(),
+                [(dec!(0.9), dec!(5)), (dec!(0.8), dec!(5))].into(),
+            ),
+        );
+
+        builder
+    }
+
+    /// Creates exchange for existing market without changing market state
+    pub async fn try_new_with_market(market: Arc<SimulatedMarket>) -> Self {
+        let exchange_account_id = Self::exchange_account_id();
+        let application_manager = ApplicationManager::new(CancellationToken::default());
+        let (tx, rx) = broadcast::channel(100);
+
+        let build_config = EngineBuildConfig {
+            supported_exchange_clients: hashmap![
+                exchange_account_id.exchange_id => Box::new(SimulatedExchangeBuilder::new(market.clone())) as _
+            ],
+        };
+
+        let mut settings =
+            ExchangeSettings::new_short(exchange_account_id, "".to_owned(), "".to_owned(), false);
+        settings.currency_pairs = Some(vec![CurrencyPairSetting {
+            base: "cnd".into(),
+            quote: "btc".into(),
+            currency_pair: None,
+        }]);
+
+        let core_settings = CoreSettings {
+            exchanges: vec![settings.clone()],
+            ..Default::default()
+        };
+        let timeout_manager = create_timeout_manager(&core_settings, &build_config);
+
+        let exchange = create_exchange(
+            &settings,
+            &build_config,
+            tx,
+            application_manager,
+            timeout_manager,
+        )
+        .await;
+
+        let currency_pair_to_symbol_converter =
+            CurrencyPairToSymbolConverter::new(hashmap![ exchange_account_id => exchange.clone() ]);
+        let balance_manager = BalanceManager::new(currency_pair_to_symbol_converter);
+        exchange.setup_balance_manager(balance_manager.clone());
+
+        Self {
+            exchange,
+            market,
+            balance_manager,
+            default_price: dec!(0.5),
+            min_amount: dec!(1),
+            rx,
+        }
+    }
+
+    /// Waits next order event skipping all other events
+    pub async fn recv_order_event(&mut self) -> OrderEvent {
+        loop {
+            let event = tokio::time::timeout(Duration::from_secs(5), self.rx.recv())
+                .await
+                .expect("Order event was not received in time")
+                .expect("Unable to receive event");
+
+            if let ExchangeEvent::OrderEvent(order_event) = event {
+                return order_event;
+            }
+        }
+    }
+}
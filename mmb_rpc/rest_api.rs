@@ -1,11 +1,29 @@
 use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
 
 #[cfg(unix)]
 pub static IPC_ADDRESS: &str = "/tmp/mmb_core.ipc";
 #[cfg(windows)]
 pub static IPC_ADDRESS: &str = r#"\\.\pipe\mmb_core"#;
 
+/// Filter of trading state requests. Not specified fields don't filter anything
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradePlaceFilter {
+    /// Exchange account id in format `Binance_0`
+    pub exchange_account_id: Option<String>,
+    /// Currency pair in format `btc/usdt`
+    pub currency_pair: Option<String>,
+}
+
+/// Filter of strategies PnL request. Not specified fields don't filter anything
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PnlFilter {
+    /// Strategy name
+    pub service_name: Option<String>,
+    pub service_configuration_key: Option<String>,
+}
+
 #[rpc]
 pub trait MmbRpc {
     #[rpc(name = "health")]
@@ -22,12 +40,45 @@ pub trait MmbRpc {
 
     #[rpc(name = "stats")]
     fn stats(&self) -> Result<String>;
+
+    #[rpc(name = "strategies")]
+    fn strategies(&self) -> Result<String>;
+
+    #[rpc(name = "start_strategy")]
+    fn start_strategy(&self, strategy_name: String) -> Result<String>;
+
+    #[rpc(name = "stop_strategy")]
+    fn stop_strategy(&self, strategy_name: String) -> Result<String>;
+
+    #[rpc(name = "kill_switch")]
+    fn kill_switch(&self, flatten: bool) -> Result<String>;
+
+    #[rpc(name = "kill_switch_report")]
+    fn kill_switch_report(&self) -> Result<String>;
+
+    #[rpc(name = "orders")]
+    fn orders(&self, filter: TradePlaceFilter) -> Result<String>;
+
+    #[rpc(name = "balances")]
+    fn balances(&self, filter: TradePlaceFilter) -> Result<String>;
+
+    #[rpc(name = "reservations")]
+    fn reservations(&self, filter: TradePlaceFilter) -> Result<String>;
+
+    #[rpc(name = "positions")]
+    fn positions(&self, filter: TradePlaceFilter) -> Result<String>;
+
+    #[rpc(name = "pnl")]
+    fn pnl(&self, filter: PnlFilter) -> Result<String>;
 }
 
 pub enum ErrorCode {
     StopperIsNone = 1,
     UnableToSendSignal = 2,
     FailedToSaveNewConfig = 3,
+    StrategyNotFound = 4,
+    FailedToSerializeResponse = 5,
+    KillSwitchIsAlreadyActivated = 6,
 }
 
 pub fn server_side_error(code: ErrorCode) -> Error {
@@ -35,6 +86,9 @@ pub fn server_side_error(code: ErrorCode) -> Error {
         ErrorCode::StopperIsNone => "Server stopper is none",
         ErrorCode::UnableToSendSignal => "Unable to send signal",
         ErrorCode::FailedToSaveNewConfig => "Failed to save new config",
+        ErrorCode::StrategyNotFound => "Strategy not found",
+        ErrorCode::FailedToSerializeResponse => "Failed to serialize response",
+        ErrorCode::KillSwitchIsAlreadyActivated => "Kill switch is already activated",
     };
     log::error!("Rest API error: {}", reason);
     Error::new(jsonrpc_core::ErrorCode::ServerError(code as i64))
@@ -102,6 +102,11 @@ macro_rules! impl_u64_id {
                 let new_id = paste::paste! { [<$type:snake:upper _ID>] }.fetch_add(1, Ordering::AcqRel);
                 $type(new_id)
             }
+
+            /// Moves generator past this ID, so ID restored from storage isn't generated again
+            pub fn mark_as_used(&self) {
+                paste::paste! { [<$type:snake:upper _ID>] }.fetch_max(self.0 + 1, Ordering::AcqRel);
+            }
         }
 
         impl Display for $type {
@@ -0,0 +1,176 @@
+use std::collections::VecDeque;
+
+use chrono::Duration;
+use mmb_core::exchanges::common::{Amount, Price};
+use mmb_utils::DateTime;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// Estimates variance of price per second from trades prices during the sliding window
+pub struct VolatilityEstimator {
+    window: Duration,
+    prices: VecDeque<(DateTime, Price)>,
+}
+
+impl VolatilityEstimator {
+    pub fn new(window: Duration) -> Self {
+        VolatilityEstimator {
+            window,
+            prices: VecDeque::new(),
+        }
+    }
+
+    pub fn add_trade(&mut self, time: DateTime, price: Price) {
+        self.prices.push_back((time, price));
+        remove_outdated(&mut self.prices, self.window, time);
+    }
+
+    pub fn trades_count(&self) -> usize {
+        self.prices.len()
+    }
+
+    /// Sum of squared price changes divided by duration of observed period in seconds
+    pub fn variance_per_second(&self) -> Option<Decimal> {
+        let (first_time, _) = self.prices.front()?;
+        let (last_time, _) = self.prices.back()?;
+        let period_ms = (*last_time - *first_time).num_milliseconds();
+        if period_ms <= 0 {
+            return None;
+        }
+
+        let squared_changes_sum: Decimal = self
+            .prices
+            .iter()
+            .zip(self.prices.iter().skip(1))
+            .map(|((_, previous), (_, current))| {
+                let change = current - previous;
+                change * change
+            })
+            .sum();
+
+        Some(squared_changes_sum * dec!(1000) / Decimal::from(period_ms))
+    }
+}
+
+/// Intensity of market orders reaching the depth `delta` from mid price
+/// modeled as `lambda(delta) = A * exp(-k * delta)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderArrivalIntensity {
+    /// Market orders count per second (A)
+    pub arrival_rate: Decimal,
+    /// Mean depth of market orders from mid price which is equal to `1 / k`
+    pub mean_depth: Price,
+}
+
+/// Estimates order arrival intensity from trades during the sliding window.
+/// Depth of trades is exponentially distributed for the intensity model,
+/// so maximum likelihood estimation of `1 / k` is the mean depth of trades
+pub struct OrderArrivalEstimator {
+    window: Duration,
+    depths: VecDeque<(DateTime, Price)>,
+}
+
+impl OrderArrivalEstimator {
+    pub fn new(window: Duration) -> Self {
+        OrderArrivalEstimator {
+            window,
+            depths: VecDeque::new(),
+        }
+    }
+
+    pub fn add_trade(&mut self, time: DateTime, price: Price, mid_price: Price) {
+        self.depths.push_back((time, (price - mid_price).abs()));
+        remove_outdated(&mut self.depths, self.window, time);
+    }
+
+    pub fn intensity(&self) -> Option<OrderArrivalIntensity> {
+        if self.depths.is_empty() {
+            return None;
+        }
+
+        let trades_count = Decimal::from(self.depths.len());
+        let depths_sum: Amount = self.depths.iter().map(|(_, depth)| depth).sum();
+        let window_secs = Decimal::from(self.window.num_milliseconds()) / dec!(1000);
+
+        Some(OrderArrivalIntensity {
+            arrival_rate: trades_count / window_secs,
+            mean_depth: depths_sum / trades_count,
+        })
+    }
+}
+
+fn remove_outdated<T>(values: &mut VecDeque<(DateTime, T)>, window: Duration, now: DateTime) {
+    while let Some((time, _)) = values.front() {
+        if *time + window >= now {
+            break;
+        }
+
+        let _ = values.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn time(seconds: i64) -> DateTime {
+        Utc.with_ymd_and_hms(2021, 9, 20, 0, 0, 0)
+            .single()
+            .expect("in test")
+            + Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn variance_is_not_estimated_by_single_trade() {
+        let mut estimator = VolatilityEstimator::new(Duration::seconds(60));
+
+        estimator.add_trade(time(0), dec!(1));
+
+        assert_eq!(estimator.variance_per_second(), None);
+    }
+
+    #[test]
+    fn variance_per_second() {
+        let mut estimator = VolatilityEstimator::new(Duration::seconds(60));
+
+        estimator.add_trade(time(0), dec!(1));
+        estimator.add_trade(time(2), dec!(1.2));
+        estimator.add_trade(time(4), dec!(1.1));
+        estimator.add_trade(time(8), dec!(1.3));
+
+        // (0.04 + 0.01 + 0.04) / 8
+        assert_eq!(estimator.variance_per_second(), Some(dec!(0.01125)));
+    }
+
+    #[test]
+    fn outdated_trades_are_not_used_for_variance() {
+        let mut estimator = VolatilityEstimator::new(Duration::seconds(5));
+
+        estimator.add_trade(time(0), dec!(2));
+        estimator.add_trade(time(4), dec!(1));
+        estimator.add_trade(time(8), dec!(1.2));
+
+        assert_eq!(estimator.trades_count(), 2);
+        assert_eq!(estimator.variance_per_second(), Some(dec!(0.01)));
+    }
+
+    #[test]
+    fn order_arrival_intensity() {
+        let mut estimator = OrderArrivalEstimator::new(Duration::seconds(10));
+
+        estimator.add_trade(time(0), dec!(1.1), dec!(1));
+        estimator.add_trade(time(1), dec!(0.8), dec!(1));
+        estimator.add_trade(time(2), dec!(1), dec!(1));
+        estimator.add_trade(time(3), dec!(1.3), dec!(1.1));
+
+        assert_eq!(
+            estimator.intensity(),
+            Some(OrderArrivalIntensity {
+                arrival_rate: dec!(0.4),
+                mean_depth: dec!(0.125),
+            })
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use binance::binance::BinanceBuilder;
+use mmb_core::config::{CONFIG_PATH, CREDENTIALS_PATH};
+use mmb_core::exchanges::traits::ExchangeClientBuilder;
+use mmb_core::lifecycle::launcher::{launch_trading_engine, EngineBuildConfig, InitSettings};
+
+use avellaneda_stoikov::settings::AvellanedaStoikovSettings;
+use avellaneda_stoikov::strategy::AvellanedaStoikovStrategy;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let engine_config =
+        EngineBuildConfig::standard(Box::new(BinanceBuilder) as Box<dyn ExchangeClientBuilder>);
+
+    let init_settings = InitSettings::<AvellanedaStoikovSettings>::Load {
+        config_path: CONFIG_PATH.to_owned(),
+        credentials_path: CREDENTIALS_PATH.to_owned(),
+    };
+
+    let engine = launch_trading_engine(&engine_config, init_settings, |settings, ctx| {
+        Box::new(
+            AvellanedaStoikovStrategy::new(settings.strategy.clone(), ctx)
+                .expect("Invalid AvellanedaStoikov strategy settings"),
+        )
+    })
+    .await?;
+
+    if let Some(engine) = engine {
+        engine.run().await;
+    }
+
+    Ok(())
+}
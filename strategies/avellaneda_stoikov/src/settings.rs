@@ -0,0 +1,57 @@
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId};
+use mmb_core::exchanges::general::commission::Percent;
+use mmb_core::settings::{BaseStrategySettings, CurrencyPairSetting};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct AvellanedaStoikovSettings {
+    pub exchange_account_id: ExchangeAccountId,
+    pub currency_pair: CurrencyPairSetting,
+    /// Max amount of all orders on each side. It is split equally between price levels
+    pub max_amount: Amount,
+    /// Balance budget of strategy in amount currency
+    #[serde(default)]
+    pub amount_limit: Option<Amount>,
+    /// Risk aversion (gamma). The bigger it is the more quotes are skewed
+    /// to get rid of inventory and the wider the spread is
+    pub risk_aversion: Decimal,
+    /// Time left till the end of trading session (T - t). Strategy is trading continuously,
+    /// so it is a constant horizon during which inventory is supposed to be held
+    pub time_horizon_secs: u64,
+    /// Trades during the window are used for estimating volatility and order arrival intensity
+    pub estimation_window_secs: u64,
+    /// Trades count required in the estimation window before quoting
+    pub min_trades_count: usize,
+    /// Base currency amount strategy tends to hold. Inventory is measured relatively to it
+    #[serde(default)]
+    pub target_inventory: Amount,
+    /// Count of price levels on each side
+    pub levels_count: usize,
+    /// Distance between neighbouring price levels in percent of reservation price
+    pub level_step: Percent,
+    /// Spread is never narrower than this percent of mid price
+    pub min_spread: Percent,
+}
+
+impl BaseStrategySettings for AvellanedaStoikovSettings {
+    fn exchange_account_id(&self) -> ExchangeAccountId {
+        self.exchange_account_id
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        CurrencyPair::from_codes(self.currency_pair.base, self.currency_pair.quote)
+    }
+
+    fn max_amount(&self) -> Amount {
+        self.max_amount
+    }
+
+    fn amount_limit(&self) -> Option<Amount> {
+        self.amount_limit
+    }
+
+    fn price_slots_count(&self) -> usize {
+        self.levels_count
+    }
+}
@@ -0,0 +1,519 @@
+use std::sync::Arc;
+
+use anyhow::{ensure, Result};
+use chrono::Duration;
+use mmb_core::disposition_execution::{
+    PriceSlot, TradeCycle, TradeDisposition, TradingContext, TradingContextBySide,
+};
+use mmb_core::exchanges::common::{Amount, CurrencyPair, ExchangeAccountId, Price};
+use mmb_core::exchanges::common::{TradePlace, TradePlaceAccount};
+use mmb_core::exchanges::events::TradesEvent;
+use mmb_core::exchanges::general::symbol::{Round, Symbol};
+use mmb_core::explanation::{Explanation, WithExplanation};
+use mmb_core::lifecycle::trading_engine::EngineContext;
+use mmb_core::math::ConvertPercentToRate;
+use mmb_core::order_book::local_snapshot_service::LocalSnapshotsService;
+use mmb_core::orders::order::{OrderRole, OrderSide, OrderSnapshot};
+use mmb_core::service_configuration::configuration_descriptor::ConfigurationDescriptor;
+use mmb_core::settings::BaseStrategySettings;
+use mmb_core::strategies::disposition_strategy::DispositionStrategy;
+use mmb_utils::cancellation_token::CancellationToken;
+use mmb_utils::DateTime;
+use rust_decimal::{Decimal, MathematicalOps};
+use rust_decimal_macros::dec;
+
+use crate::estimators::{OrderArrivalEstimator, VolatilityEstimator};
+use crate::settings::AvellanedaStoikovSettings;
+
+/// Prices of the best quotes according to Avellaneda-Stoikov model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quotes {
+    /// Mid price adjusted by inventory. Strategy is indifferent to buy or sell at this price
+    pub reservation_price: Price,
+    /// Half of optimal spread between bid and ask
+    pub half_spread: Price,
+}
+
+/// Calculates reservation price `r = s - q * gamma * sigma^2 * (T - t)`
+/// and optimal spread `gamma * sigma^2 * (T - t) + 2 / gamma * ln(1 + gamma / k)`
+pub fn calculate_quotes(
+    mid_price: Price,
+    inventory: Decimal,
+    variance_per_second: Decimal,
+    mean_depth: Price,
+    risk_aversion: Decimal,
+    time_horizon_secs: Decimal,
+) -> Quotes {
+    let inventory_risk = risk_aversion * variance_per_second * time_horizon_secs;
+    let reservation_price = mid_price - inventory * inventory_risk;
+
+    // `1 / k` is used instead of `k` so trades exactly at mid price don't lead to division by zero
+    let liquidity_spread = dec!(2) / risk_aversion * (dec!(1) + risk_aversion * mean_depth).ln();
+    let spread = inventory_risk + liquidity_spread;
+
+    Quotes {
+        reservation_price,
+        half_spread: spread / dec!(2),
+    }
+}
+
+/// Inventory-aware market making strategy by M. Avellaneda and S. Stoikov
+/// "High-frequency trading in a limit order book".
+/// Quotes are placed around reservation price which is skewed against inventory,
+/// spread depends on volatility and order arrival intensity estimated from trades stream
+pub struct AvellanedaStoikovStrategy {
+    settings: AvellanedaStoikovSettings,
+    engine_context: Arc<EngineContext>,
+    configuration_descriptor: ConfigurationDescriptor,
+    volatility_estimator: VolatilityEstimator,
+    order_arrival_estimator: OrderArrivalEstimator,
+    last_mid_price: Option<Price>,
+}
+
+impl AvellanedaStoikovStrategy {
+    pub fn new(
+        settings: AvellanedaStoikovSettings,
+        engine_context: Arc<EngineContext>,
+    ) -> Result<Self> {
+        Self::validate_settings(&settings)?;
+
+        let configuration_descriptor = ConfigurationDescriptor::new(
+            Self::strategy_name().into(),
+            (settings.exchange_account_id.to_string() + ";" + settings.currency_pair().as_str())
+                .as_str()
+                .into(),
+        );
+
+        let estimation_window = Duration::seconds(settings.estimation_window_secs as i64);
+
+        Ok(AvellanedaStoikovStrategy {
+            settings,
+            engine_context,
+            configuration_descriptor,
+            volatility_estimator: VolatilityEstimator::new(estimation_window),
+            order_arrival_estimator: OrderArrivalEstimator::new(estimation_window),
+            last_mid_price: None,
+        })
+    }
+
+    fn validate_settings(settings: &AvellanedaStoikovSettings) -> Result<()> {
+        ensure!(
+            settings.risk_aversion > dec!(0),
+            "Risk aversion should be positive, but it is {}",
+            settings.risk_aversion
+        );
+        ensure!(
+            settings.levels_count > 0,
+            "Strategy should quote at least one price level"
+        );
+
+        Ok(())
+    }
+
+    fn strategy_name() -> &'static str {
+        "AvellanedaStoikov"
+    }
+
+    fn currency_pair(&self) -> CurrencyPair {
+        self.settings.currency_pair()
+    }
+
+    fn trade_place_account(&self) -> TradePlaceAccount {
+        TradePlaceAccount::new(self.settings.exchange_account_id, self.currency_pair())
+    }
+
+    fn trade_place(&self) -> TradePlace {
+        self.trade_place_account().trade_place()
+    }
+
+    fn symbol(&self) -> Option<Arc<Symbol>> {
+        self.engine_context
+            .exchanges
+            .get(&self.settings.exchange_account_id)?
+            .symbols
+            .get(&self.currency_pair())
+            .map(|symbol| symbol.clone())
+    }
+
+    /// Deviation of base currency balance from target inventory measured in max amounts,
+    /// so risk aversion doesn't depend on order size
+    fn calculate_inventory(
+        &self,
+        symbol: Arc<Symbol>,
+        mid_price: Price,
+        explanation: &mut Explanation,
+    ) -> Decimal {
+        let base_balance = self
+            .engine_context
+            .balance_manager
+            .lock()
+            .get_balance_with_reserved_amounts(
+                self.configuration_descriptor,
+                self.settings.exchange_account_id,
+                symbol.clone(),
+                symbol.base_currency_code(),
+                mid_price,
+            )
+            .unwrap_or_default();
+
+        let inventory = (base_balance - self.settings.target_inventory) / self.settings.max_amount;
+        explanation.add_reason(format!(
+            "base balance {} target inventory {} inventory {}",
+            base_balance, self.settings.target_inventory, inventory
+        ));
+
+        inventory
+    }
+
+    fn calculate_quotes(
+        &self,
+        mid_price: Price,
+        symbol: Arc<Symbol>,
+        explanation: &mut Explanation,
+    ) -> Option<Quotes> {
+        let trades_count = self.volatility_estimator.trades_count();
+        if trades_count < self.settings.min_trades_count {
+            explanation.add_reason(format!(
+                "not enough trades for estimation: {} of {}",
+                trades_count, self.settings.min_trades_count
+            ));
+            return None;
+        }
+
+        let variance = self.volatility_estimator.variance_per_second()?;
+        let intensity = self.order_arrival_estimator.intensity()?;
+        explanation.add_reason(format!(
+            "variance per second {} order arrival rate {} mean depth of trades {}",
+            variance, intensity.arrival_rate, intensity.mean_depth
+        ));
+
+        let inventory = self.calculate_inventory(symbol, mid_price, explanation);
+        let mut quotes = calculate_quotes(
+            mid_price,
+            inventory,
+            variance,
+            intensity.mean_depth,
+            self.settings.risk_aversion,
+            Decimal::from(self.settings.time_horizon_secs),
+        );
+        explanation.add_reason(format!(
+            "mid price {} reservation price {} optimal half spread {}",
+            mid_price, quotes.reservation_price, quotes.half_spread
+        ));
+
+        let min_half_spread = mid_price * self.settings.min_spread.percent_to_rate() / dec!(2);
+        if quotes.half_spread < min_half_spread {
+            quotes.half_spread = min_half_spread;
+            explanation.add_reason(format!(
+                "half spread changed to {} because of min spread {}%",
+                min_half_spread, self.settings.min_spread
+            ));
+        }
+
+        Some(quotes)
+    }
+
+    fn calculate_level_price(
+        &self,
+        side: OrderSide,
+        level_index: usize,
+        quotes: Quotes,
+        top_prices: (Price, Price),
+        symbol: &Symbol,
+        explanation: &mut Explanation,
+    ) -> Price {
+        let (top_bid, top_ask) = top_prices;
+        let level_offset = quotes.reservation_price
+            * self.settings.level_step.percent_to_rate()
+            * Decimal::from(level_index);
+        let offset = quotes.half_spread + level_offset;
+
+        // price is moved to the top of the book if it crosses opposite side,
+        // so strategy stays maker when inventory skews reservation price too much
+        match side {
+            OrderSide::Buy => {
+                let price = symbol.price_round(quotes.reservation_price - offset, Round::Floor);
+                if price >= top_ask {
+                    explanation.add_reason(format!(
+                        "level {} price {} crosses top ask {}, top bid is used",
+                        level_index, price, top_ask
+                    ));
+                    return top_bid;
+                }
+                price
+            }
+            OrderSide::Sell => {
+                let price = symbol.price_round(quotes.reservation_price + offset, Round::Ceiling);
+                if price <= top_bid {
+                    explanation.add_reason(format!(
+                        "level {} price {} crosses top bid {}, top ask is used",
+                        level_index, price, top_bid
+                    ));
+                    return top_ask;
+                }
+                price
+            }
+        }
+    }
+
+    fn calc_trading_context_by_side(
+        &self,
+        side: OrderSide,
+        max_amount: Amount,
+        quotes: Quotes,
+        top_prices: (Price, Price),
+        symbol: Arc<Symbol>,
+        mut explanation: Explanation,
+    ) -> TradingContextBySide {
+        let level_amount = symbol.amount_round(
+            max_amount / Decimal::from(self.settings.levels_count),
+            Round::Floor,
+        );
+
+        let mut explanation_by_balance = Some(explanation.clone());
+        let mut available_amount = self
+            .engine_context
+            .balance_manager
+            .lock()
+            .get_leveraged_balance_in_amount_currency_code(
+                self.configuration_descriptor,
+                side,
+                self.settings.exchange_account_id,
+                symbol.clone(),
+                quotes.reservation_price,
+                &mut explanation_by_balance,
+            )
+            .unwrap_or_default();
+        if let Some(explanation_by_balance) = explanation_by_balance {
+            explanation = explanation_by_balance;
+        }
+        explanation.add_reason(format!(
+            "{} level amount {} available balance in amount currency {}",
+            side, level_amount, available_amount
+        ));
+
+        let estimating = (0..self.settings.levels_count)
+            .map(|level_index| {
+                let mut explanation = explanation.clone();
+                let price = self.calculate_level_price(
+                    side,
+                    level_index,
+                    quotes,
+                    top_prices,
+                    &symbol,
+                    &mut explanation,
+                );
+
+                let amount = symbol.amount_round(level_amount.min(available_amount), Round::Floor);
+                if amount <= dec!(0) {
+                    explanation.add_reason(format!(
+                        "level {} is not quoted because balance isn't enough",
+                        level_index
+                    ));
+                    return WithExplanation {
+                        value: None,
+                        explanation,
+                    };
+                }
+                available_amount -= amount;
+
+                explanation.add_reason(format!(
+                    "level {} price {} amount {}",
+                    level_index, price, amount
+                ));
+
+                WithExplanation {
+                    value: Some(TradeCycle {
+                        order_role: OrderRole::Maker,
+                        strategy_name: Self::strategy_name().to_string(),
+                        disposition: TradeDisposition::new(
+                            self.trade_place_account(),
+                            side,
+                            price,
+                            amount,
+                        ),
+                    }),
+                    explanation,
+                }
+            })
+            .collect();
+
+        TradingContextBySide {
+            max_amount,
+            estimating,
+        }
+    }
+}
+
+impl DispositionStrategy for AvellanedaStoikovStrategy {
+    fn calculate_trading_context(
+        &mut self,
+        max_amount: Decimal,
+        _now: DateTime,
+        local_snapshots_service: &LocalSnapshotsService,
+        explanation: &mut Explanation,
+    ) -> Option<TradingContext> {
+        let snapshot = local_snapshots_service.get_snapshot(self.trade_place())?;
+        let top_ask = snapshot.get_top_ask()?.0;
+        let top_bid = snapshot.get_top_bid()?.0;
+
+        let mid_price = (top_bid + top_ask) / dec!(2);
+        self.last_mid_price = Some(mid_price);
+
+        let symbol = self.symbol()?;
+        let quotes = self.calculate_quotes(mid_price, symbol.clone(), explanation)?;
+
+        let calc_by_side = |side| {
+            self.calc_trading_context_by_side(
+                side,
+                max_amount,
+                quotes,
+                (top_bid, top_ask),
+                symbol.clone(),
+                explanation.clone(),
+            )
+        };
+        let buy_trading_ctx = calc_by_side(OrderSide::Buy);
+        let sell_trading_ctx = calc_by_side(OrderSide::Sell);
+
+        Some(TradingContext::new(
+            self.trade_place_account(),
+            buy_trading_ctx,
+            sell_trading_ctx,
+        ))
+    }
+
+    fn handle_order_fill(
+        &self,
+        _cloned_order: &Arc<OrderSnapshot>,
+        _price_slot: &PriceSlot,
+        _target_eai: ExchangeAccountId,
+        _cancellation_token: CancellationToken,
+    ) -> Result<()> {
+        // inventory is taken from balances on the next trading context calculation
+        Ok(())
+    }
+
+    fn handle_trades_event(&mut self, trades_event: &TradesEvent) {
+        if trades_event.exchange_account_id != self.settings.exchange_account_id
+            || trades_event.currency_pair != self.currency_pair()
+        {
+            return;
+        }
+
+        for trade in &trades_event.trades {
+            self.volatility_estimator
+                .add_trade(trade.transaction_time, trade.price);
+
+            // depth of trades can't be estimated before order book is received
+            if let Some(mid_price) = self.last_mid_price {
+                self.order_arrival_estimator.add_trade(
+                    trade.transaction_time,
+                    trade.price,
+                    mid_price,
+                );
+            }
+        }
+    }
+
+    fn configuration_descriptor(&self) -> ConfigurationDescriptor {
+        self.configuration_descriptor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mmb_core::settings::CurrencyPairSetting;
+
+    fn settings() -> AvellanedaStoikovSettings {
+        AvellanedaStoikovSettings {
+            exchange_account_id: "Binance_0".parse().expect("in test"),
+            currency_pair: CurrencyPairSetting {
+                base: "btc".into(),
+                quote: "usdt".into(),
+                currency_pair: None,
+            },
+            max_amount: dec!(1),
+            amount_limit: None,
+            risk_aversion: dec!(1),
+            time_horizon_secs: 10,
+            estimation_window_secs: 60,
+            min_trades_count: 3,
+            target_inventory: dec!(0),
+            levels_count: 2,
+            level_step: dec!(5),
+            min_spread: dec!(0),
+        }
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        assert!(AvellanedaStoikovStrategy::validate_settings(&settings()).is_ok());
+
+        let mut settings_without_risk_aversion = settings();
+        settings_without_risk_aversion.risk_aversion = dec!(0);
+        assert!(
+            AvellanedaStoikovStrategy::validate_settings(&settings_without_risk_aversion).is_err()
+        );
+
+        let mut settings_without_levels = settings();
+        settings_without_levels.levels_count = 0;
+        assert!(AvellanedaStoikovStrategy::validate_settings(&settings_without_levels).is_err());
+    }
+
+    #[test]
+    fn quotes_without_inventory_are_around_mid_price() {
+        let quotes = calculate_quotes(dec!(100), dec!(0), dec!(0.01), dec!(0), dec!(1), dec!(10));
+
+        assert_eq!(
+            quotes,
+            Quotes {
+                reservation_price: dec!(100),
+                half_spread: dec!(0.05),
+            }
+        );
+    }
+
+    #[test]
+    fn reservation_price_is_skewed_against_inventory() {
+        let long = calculate_quotes(dec!(100), dec!(2), dec!(0.01), dec!(0), dec!(1), dec!(10));
+        let short = calculate_quotes(dec!(100), dec!(-1), dec!(0.01), dec!(0), dec!(1), dec!(10));
+
+        assert_eq!(long.reservation_price, dec!(99.8));
+        assert_eq!(short.reservation_price, dec!(100.1));
+        assert_eq!(long.half_spread, short.half_spread);
+    }
+
+    #[test]
+    fn spread_is_wider_for_less_intensive_order_arrival() {
+        let intensive =
+            calculate_quotes(dec!(100), dec!(0), dec!(0.01), dec!(0.1), dec!(1), dec!(10));
+        let sparse = calculate_quotes(dec!(100), dec!(0), dec!(0.01), dec!(0.5), dec!(1), dec!(10));
+
+        assert!(intensive.half_spread > dec!(0.05));
+        assert!(sparse.half_spread > intensive.half_spread);
+    }
+
+    #[test]
+    fn spread_is_wider_for_higher_risk_aversion() {
+        let low = calculate_quotes(
+            dec!(100),
+            dec!(0),
+            dec!(0.01),
+            dec!(0.1),
+            dec!(0.1),
+            dec!(10),
+        );
+        let high = calculate_quotes(
+            dec!(100),
+            dec!(0),
+            dec!(0.01),
+            dec!(0.1),
+            dec!(10),
+            dec!(10),
+        );
+
+        assert!(high.half_spread > low.half_spread);
+    }
+}
@@ -0,0 +1,122 @@
+//! Backtest replaces engine time with virtual time globally, so it is kept in a separate test binary
+
+use avellaneda_stoikov::settings::AvellanedaStoikovSettings;
+use avellaneda_stoikov::strategy::AvellanedaStoikovStrategy;
+use chrono::Duration;
+use core_tests::backtest::{
+    cnd_btc, currency_pair_setting, exchange_account_id, exchange_settings, order_book, start_time,
+    symbol, trade_by_price, write_market_data_file,
+};
+use mmb_core::exchanges::common::TradePlaceAccount;
+use mmb_core::lifecycle::launcher::{launch_backtest, BacktestSettings, EngineBuildConfig};
+use mmb_core::orders::order::OrderSide;
+use mmb_core::settings::{AppSettings, CoreSettings};
+use mmb_utils::hashmap;
+use rust_decimal_macros::dec;
+use simulated_exchange::simulated_exchange::{
+    SimulatedExchangeBuilder, SimulatedExchangeConfig, SimulatedMarket,
+};
+
+fn strategy_settings() -> AvellanedaStoikovSettings {
+    AvellanedaStoikovSettings {
+        exchange_account_id: exchange_account_id(),
+        currency_pair: currency_pair_setting("cnd"),
+        max_amount: dec!(2),
+        amount_limit: None,
+        risk_aversion: dec!(1),
+        time_horizon_secs: 10,
+        estimation_window_secs: 60,
+        min_trades_count: 3,
+        target_inventory: dec!(100),
+        levels_count: 2,
+        level_step: dec!(5),
+        min_spread: dec!(0),
+    }
+}
+
+#[test]
+fn quotes_are_skewed_against_inventory() {
+    let start = start_time();
+    let second = |seconds| start + Duration::seconds(seconds);
+    let trade_place = TradePlaceAccount::new(exchange_account_id(), cnd_btc());
+    let trade = |time, trade_id, price, taker_side| {
+        trade_by_price(time, trade_place, trade_id, price, taker_side)
+    };
+    let market_data_path = write_market_data_file(
+        "avellaneda_stoikov_backtest",
+        &[
+            order_book(start, trade_place),
+            // trades for estimation of volatility and order arrival intensity
+            trade(second(1), 1, dec!(1), OrderSide::Buy),
+            trade(second(2), 2, dec!(1.02), OrderSide::Buy),
+            trade(second(3), 3, dec!(1), OrderSide::Sell),
+            order_book(second(4), trade_place),
+            // fills both bid levels
+            trade(second(5), 4, dec!(0.93), OrderSide::Sell),
+            order_book(second(6), trade_place),
+            order_book(second(7), trade_place),
+            // fills both ask levels which are moved lower because of bought inventory
+            trade(second(8), 5, dec!(1.08), OrderSide::Buy),
+            order_book(second(9), trade_place),
+        ],
+    );
+
+    let market = SimulatedMarket::new(SimulatedExchangeConfig::new(
+        vec![symbol("cnd", "CND")],
+        hashmap![
+            "cnd".into() => dec!(100),
+            "btc".into() => dec!(100)
+        ],
+    ));
+    let build_config = EngineBuildConfig {
+        supported_exchange_clients: hashmap![
+            exchange_account_id().exchange_id => Box::new(SimulatedExchangeBuilder::new(market.clone())) as _
+        ],
+    };
+
+    let settings = AppSettings {
+        strategy: strategy_settings(),
+        core: CoreSettings {
+            exchanges: vec![exchange_settings(exchange_account_id(), &["cnd"])],
+            ..Default::default()
+        },
+    };
+
+    let report = launch_backtest(
+        &build_config,
+        settings,
+        BacktestSettings {
+            market_data_paths: vec![market_data_path.clone()],
+            market: market.clone(),
+            sample_interval: Duration::seconds(1),
+        },
+        |settings, ctx| {
+            Box::new(
+                AvellanedaStoikovStrategy::new(settings.strategy.clone(), ctx).expect("in test"),
+            )
+        },
+    )
+    .expect("Backtest failed");
+
+    let _ = std::fs::remove_file(market_data_path);
+
+    assert_eq!(report.trade_places.len(), 1);
+    let fills = report.trade_places[0]
+        .fills
+        .iter()
+        .map(|fill| (fill.side, fill.price, fill.amount))
+        .collect::<Vec<_>>();
+    // without inventory ask levels would be 1.03 and 1.08
+    assert_eq!(
+        fills,
+        vec![
+            (OrderSide::Buy, dec!(0.99), dec!(1)),
+            (OrderSide::Buy, dec!(0.94), dec!(1)),
+            (OrderSide::Sell, dec!(1.02), dec!(1)),
+            (OrderSide::Sell, dec!(1.07), dec!(1)),
+        ]
+    );
+
+    assert_eq!(market.get_balance("cnd".into()), dec!(100));
+    assert_eq!(market.get_balance("btc".into()), dec!(100.16));
+}